@@ -0,0 +1,10 @@
+-- Remove duplicate follows, keeping the earliest row per user/scholar pair
+DELETE f1 FROM `tbl_user_scholar_follows` f1
+JOIN `tbl_user_scholar_follows` f2
+  ON f1.user_id = f2.user_id
+ AND f1.scholar_id = f2.scholar_id
+ AND f1.id > f2.id;
+
+-- A user can follow a scholar at most once
+ALTER TABLE `tbl_user_scholar_follows`
+ADD UNIQUE INDEX `uniq_user_scholar_follow` (`user_id`, `scholar_id`);
@@ -0,0 +1,33 @@
+-- Enforce uniqueness in the database so concurrent inserts cannot slip past
+-- the application-level duplicate checks. Existing duplicates are resolved
+-- first so the indexes can be built.
+
+-- Remove duplicate accounts, keeping the earliest row per email
+DELETE u1 FROM `tbl_users` u1
+JOIN `tbl_users` u2
+  ON u1.email = u2.email
+ AND u1.id > u2.id;
+
+ALTER TABLE `tbl_users`
+ADD UNIQUE INDEX `uniq_user_email` (`email`);
+
+-- Scholars and books own files, so duplicates are renamed rather than removed:
+-- the earliest row keeps the slug and later ones get their id appended
+UPDATE `tbl_scholars` s1
+JOIN `tbl_scholars` s2
+  ON s1.slug = s2.slug
+ AND s1.id > s2.id
+SET s1.slug = CONCAT(s1.slug, '-', s1.id);
+
+ALTER TABLE `tbl_scholars`
+ADD UNIQUE INDEX `uniq_scholar_slug` (`slug`);
+
+UPDATE `tbl_books` b1
+JOIN `tbl_books` b2
+  ON b1.scholar_id = b2.scholar_id
+ AND b1.slug = b2.slug
+ AND b1.id > b2.id
+SET b1.slug = CONCAT(b1.slug, '-', b1.id);
+
+ALTER TABLE `tbl_books`
+ADD UNIQUE INDEX `uniq_book_scholar_slug` (`scholar_id`, `slug`);
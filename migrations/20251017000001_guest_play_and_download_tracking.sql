@@ -0,0 +1,12 @@
+-- Allow plays and downloads without an account. Guest rows have no user_id
+-- and are keyed by the client-supplied anonymous id plus the request IP.
+ALTER TABLE `tbl_play_history`
+MODIFY COLUMN `user_id` INT NULL,
+ADD COLUMN `anonymous_id` VARCHAR(64) NULL AFTER `user_id`,
+ADD COLUMN `client_ip` VARCHAR(45) NULL AFTER `device_type`,
+ADD INDEX `idx_play_history_anonymous_id` (`anonymous_id`);
+
+ALTER TABLE `tbl_download_logs`
+MODIFY COLUMN `user_id` INT NULL,
+ADD COLUMN `anonymous_id` VARCHAR(64) NULL AFTER `user_id`,
+ADD INDEX `idx_download_logs_anonymous_id` (`anonymous_id`);
@@ -0,0 +1,10 @@
+-- Row versions for optimistic concurrency: an update carrying a stale
+-- version is rejected instead of overwriting a concurrent edit.
+ALTER TABLE `tbl_scholars`
+ADD COLUMN `version` INT NOT NULL DEFAULT 1;
+
+ALTER TABLE `tbl_books`
+ADD COLUMN `version` INT NOT NULL DEFAULT 1;
+
+ALTER TABLE `tbl_files`
+ADD COLUMN `version` INT NOT NULL DEFAULT 1;
@@ -0,0 +1,3 @@
+-- Set by the integrity scan when a file's audio is not on disk, cleared when it reappears
+ALTER TABLE `tbl_files`
+ADD COLUMN `missing_since` DATETIME NULL DEFAULT NULL;
@@ -0,0 +1,11 @@
+-- Per-user notification toggles; a missing row means everything is enabled
+CREATE TABLE IF NOT EXISTS `tbl_notification_prefs` (
+  `user_id` INT NOT NULL,
+  `new_uploads_from_followed` TINYINT(1) NOT NULL DEFAULT 1,
+  `comment_replies` TINYINT(1) NOT NULL DEFAULT 1,
+  `likes_on_my_uploads` TINYINT(1) NOT NULL DEFAULT 1,
+  `subscription_reminders` TINYINT(1) NOT NULL DEFAULT 1,
+  `updated_at` DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`user_id`),
+  CONSTRAINT `fk_notification_prefs_user` FOREIGN KEY (`user_id`) REFERENCES `tbl_users` (`id`) ON DELETE CASCADE
+);
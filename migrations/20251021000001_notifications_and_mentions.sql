@@ -0,0 +1,18 @@
+-- In-app notifications, e.g. replies to and mentions in comments
+CREATE TABLE IF NOT EXISTS `tbl_notifications` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `user_id` INT NOT NULL,
+  `actor_id` INT NULL,
+  `kind` VARCHAR(32) NOT NULL,
+  `file_id` INT NULL,
+  `comment_id` INT NULL,
+  `is_read` TINYINT(1) NOT NULL DEFAULT 0,
+  `created_at` DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  KEY `idx_notifications_user_created` (`user_id`, `created_at`),
+  CONSTRAINT `fk_notifications_user` FOREIGN KEY (`user_id`) REFERENCES `tbl_users` (`id`) ON DELETE CASCADE
+);
+
+-- Comma-separated ids of users resolved from @mentions in the comment
+ALTER TABLE `tbl_file_comments`
+ADD COLUMN `mentioned_user_ids` VARCHAR(255) NULL DEFAULT NULL;
@@ -0,0 +1,10 @@
+-- Books a user has listened to the end of every file of
+CREATE TABLE IF NOT EXISTS `tbl_book_completions` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `user_id` INT NOT NULL,
+  `book_id` INT NOT NULL,
+  `completed_at` DATETIME NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uniq_book_completion` (`user_id`, `book_id`),
+  CONSTRAINT `fk_book_completions_user` FOREIGN KEY (`user_id`) REFERENCES `tbl_users` (`id`) ON DELETE CASCADE
+);
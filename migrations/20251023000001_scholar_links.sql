@@ -0,0 +1,13 @@
+-- External profiles for a scholar (YouTube channel, website, Telegram, ...)
+CREATE TABLE IF NOT EXISTS `tbl_scholar_links` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `scholar_id` INT NOT NULL,
+  `link_type` VARCHAR(32) NOT NULL,
+  `url` VARCHAR(500) NOT NULL,
+  `created_by` INT NOT NULL,
+  `created_at` DATETIME NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uniq_scholar_link` (`scholar_id`, `url`),
+  KEY `idx_scholar_links_scholar` (`scholar_id`),
+  CONSTRAINT `fk_scholar_links_scholar` FOREIGN KEY (`scholar_id`) REFERENCES `tbl_scholars` (`id`) ON DELETE CASCADE
+);
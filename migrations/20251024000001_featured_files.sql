@@ -0,0 +1,13 @@
+-- Curated pool for the featured file of the day. A row with a `feature_date`
+-- pins that file for the date; rows without one form the rotating pool
+CREATE TABLE IF NOT EXISTS `tbl_featured_files` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `file_id` INT NOT NULL,
+  `feature_date` DATE NULL,
+  `created_by` INT NOT NULL,
+  `created_at` DATETIME NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uniq_featured_date` (`feature_date`),
+  KEY `idx_featured_file` (`file_id`),
+  CONSTRAINT `fk_featured_files_file` FOREIGN KEY (`file_id`) REFERENCES `tbl_files` (`id`) ON DELETE CASCADE
+);
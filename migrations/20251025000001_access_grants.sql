@@ -0,0 +1,13 @@
+-- Book- and file-level grants. `tbl_access` stays the broad, per-scholar
+-- grant; a row here only covers the single book or file it names
+CREATE TABLE IF NOT EXISTS `tbl_access_grants` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `user_id` INT NOT NULL,
+  `resource_type` ENUM('book', 'file') NOT NULL,
+  `resource_id` INT NOT NULL,
+  `created_by` INT NOT NULL,
+  `created_at` DATETIME NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uniq_access_grant` (`user_id`, `resource_type`, `resource_id`),
+  KEY `idx_access_grants_resource` (`resource_type`, `resource_id`)
+);
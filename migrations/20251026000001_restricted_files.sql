@@ -0,0 +1,5 @@
+-- Restricted files stay reachable by direct link but are left out of
+-- anonymous listings and search; new and existing files default to unrestricted
+ALTER TABLE `tbl_files`
+ADD COLUMN `restricted` BOOLEAN NOT NULL DEFAULT FALSE,
+ADD INDEX `idx_files_restricted` (`restricted`);
@@ -0,0 +1,18 @@
+-- IANA zone name (e.g. `Africa/Lagos`); days such as the listening goal's
+-- reset at the user's local midnight. NULL means UTC
+ALTER TABLE `tbl_users`
+ADD COLUMN `timezone` VARCHAR(64) NULL DEFAULT NULL;
+
+-- Optional daily listening goal; `last_met_on` is the local date the goal was
+-- last reached, so the congratulation is sent once per day
+CREATE TABLE IF NOT EXISTS `tbl_listening_goals` (
+  `user_id` INT NOT NULL,
+  `daily_minutes` INT NOT NULL,
+  `last_met_on` DATE NULL DEFAULT NULL,
+  `updated_at` DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`user_id`),
+  CONSTRAINT `fk_listening_goals_user` FOREIGN KEY (`user_id`) REFERENCES `tbl_users` (`id`) ON DELETE CASCADE
+);
+
+ALTER TABLE `tbl_notification_prefs`
+ADD COLUMN `listening_goals` TINYINT(1) NOT NULL DEFAULT 1;
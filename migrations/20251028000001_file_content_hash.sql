@@ -0,0 +1,4 @@
+-- SHA-256 of the audio, used to skip duplicates when importing a legacy library
+ALTER TABLE `tbl_files`
+ADD COLUMN `content_hash` CHAR(64) NULL DEFAULT NULL,
+ADD INDEX `idx_files_content_hash` (`content_hash`);
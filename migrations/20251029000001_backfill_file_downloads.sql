@@ -0,0 +1,11 @@
+-- `tbl_files.downloads` is the source of truth for download totals.
+-- Raise it to the download log where the log has more; counts from before the
+-- log existed are kept, so the counter is never lowered.
+UPDATE `tbl_files` f
+JOIN (
+    SELECT `file_id`, COUNT(*) AS `total`
+    FROM `tbl_download_logs`
+    GROUP BY `file_id`
+) d ON d.`file_id` = f.`id`
+SET f.`downloads` = d.`total`
+WHERE f.`downloads` < d.`total`;
@@ -0,0 +1,17 @@
+-- Audit trail of admins acting as a user for support. A row is written when
+-- the impersonation token is issued and closed when the session is ended
+CREATE TABLE IF NOT EXISTS `tbl_impersonation_sessions` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `session_id` CHAR(36) NOT NULL,
+  `admin_id` INT NOT NULL,
+  `user_id` INT NOT NULL,
+  `reason` VARCHAR(255) NULL,
+  `client_ip` VARCHAR(45) NULL,
+  `started_at` DATETIME NOT NULL,
+  `expires_at` DATETIME NOT NULL,
+  `ended_at` DATETIME NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uniq_impersonation_session` (`session_id`),
+  KEY `idx_impersonation_admin` (`admin_id`),
+  KEY `idx_impersonation_user` (`user_id`)
+);
@@ -0,0 +1,4 @@
+-- When the user last opened their following feed; files from followed
+-- scholars newer than this count as new
+ALTER TABLE `tbl_users`
+ADD COLUMN `follows_last_seen` TIMESTAMP NULL DEFAULT NULL;
@@ -0,0 +1,5 @@
+-- Playlist deletion used to leave its `tbl_playlist_files` rows behind.
+-- Remove the ones whose playlist is already gone.
+DELETE pf FROM `tbl_playlist_files` pf
+LEFT JOIN `tbl_playlists` p ON p.`id` = pf.`playlist_id`
+WHERE p.`id` IS NULL;
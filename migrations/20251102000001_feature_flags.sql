@@ -0,0 +1,9 @@
+-- Runtime feature toggles. Only flags an admin has set are stored; a flag
+-- without a row uses the default compiled into the server
+CREATE TABLE IF NOT EXISTS `tbl_feature_flags` (
+  `flag_key` VARCHAR(64) NOT NULL,
+  `enabled` TINYINT(1) NOT NULL,
+  `updated_by` INT NULL,
+  `updated_at` DATETIME NOT NULL,
+  PRIMARY KEY (`flag_key`)
+);
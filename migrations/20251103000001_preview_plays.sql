@@ -0,0 +1,12 @@
+-- Preview clips are logged apart from tbl_play_history so they never count
+-- as plays in stats, trending or listening goals
+CREATE TABLE IF NOT EXISTS `tbl_preview_plays` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `file_id` INT NOT NULL,
+  `user_id` INT NULL,
+  `anonymous_id` VARCHAR(64) NULL,
+  `client_ip` VARCHAR(45) NULL,
+  `played_at` DATETIME NOT NULL,
+  PRIMARY KEY (`id`),
+  INDEX `idx_preview_plays_file_played` (`file_id`, `played_at`)
+);
@@ -0,0 +1,14 @@
+-- A user finishing a file, at most once per listening session. Sessions are
+-- client-supplied ids, or the UTC date when the client sends none
+CREATE TABLE IF NOT EXISTS `tbl_file_completions` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `user_id` INT NOT NULL,
+  `file_id` INT NOT NULL,
+  `session_key` VARCHAR(64) NOT NULL,
+  `position_seconds` INT NOT NULL,
+  `duration_seconds` INT NOT NULL,
+  `completed_at` DATETIME NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uniq_file_completions_session` (`user_id`, `file_id`, `session_key`),
+  INDEX `idx_file_completions_file_completed` (`file_id`, `completed_at`)
+);
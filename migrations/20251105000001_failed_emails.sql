@@ -0,0 +1,16 @@
+-- Emails the background worker could not send, kept so an admin can re-send
+-- them once SMTP is fixed. `payload` is the serialized email and may hold a
+-- one-time code, so it is never returned by the API
+CREATE TABLE IF NOT EXISTS `tbl_failed_emails` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `email_kind` VARCHAR(50) NOT NULL,
+  `recipient` VARCHAR(255) NOT NULL,
+  `payload` TEXT NOT NULL,
+  `last_error` TEXT NOT NULL,
+  `attempts` INT NOT NULL DEFAULT 1,
+  `created_at` DATETIME NOT NULL,
+  `last_attempt_at` DATETIME NOT NULL,
+  `retried_at` DATETIME NULL,
+  PRIMARY KEY (`id`),
+  INDEX `idx_failed_emails_created` (`created_at`)
+);
@@ -0,0 +1,10 @@
+-- Admin-curated featured scholars, shown in `sort_order` independently of
+-- the priority ordering used by the scholar listings
+CREATE TABLE IF NOT EXISTS `tbl_featured_scholars` (
+  `scholar_id` INT NOT NULL,
+  `sort_order` INT NOT NULL,
+  `created_by` INT NULL,
+  `created_at` DATETIME NOT NULL,
+  PRIMARY KEY (`scholar_id`),
+  KEY `idx_featured_scholars_order` (`sort_order`)
+);
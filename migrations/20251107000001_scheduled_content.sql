@@ -0,0 +1,21 @@
+-- Files and books scheduled to surface on a date in the platform timezone.
+-- `publish_at` (UTC) hides content from listings until it is reached
+ALTER TABLE `tbl_files`
+ADD COLUMN `publish_at` DATETIME NULL DEFAULT NULL;
+
+ALTER TABLE `tbl_books`
+ADD COLUMN `publish_at` DATETIME NULL DEFAULT NULL;
+
+CREATE TABLE IF NOT EXISTS `tbl_scheduled_content` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `content_type` VARCHAR(10) NOT NULL,
+  `content_id` INT NOT NULL,
+  `surface_on` DATE NOT NULL,
+  `note` VARCHAR(255) NULL,
+  `hide_until_surfaced` BOOLEAN NOT NULL DEFAULT FALSE,
+  `created_by` INT NULL,
+  `created_at` DATETIME NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uq_scheduled_content` (`content_type`, `content_id`, `surface_on`),
+  KEY `idx_scheduled_content_date` (`surface_on`)
+);
@@ -0,0 +1,3 @@
+-- Reports the server raises itself (e.g. missing_file) have no reporting user
+ALTER TABLE `tbl_file_reports`
+MODIFY COLUMN `user_id` INT NULL;
@@ -0,0 +1,19 @@
+-- One pending report per reporter and file, enforced by a unique key so two
+-- concurrent submissions cannot both insert. Server-raised reports have no
+-- user and share reporter 0. Older pending duplicates are dismissed first.
+UPDATE `tbl_file_reports` r
+JOIN (
+    SELECT `file_id`, COALESCE(`user_id`, 0) AS `reporter`, MAX(`id`) AS `keep_id`
+    FROM `tbl_file_reports`
+    WHERE `status` = 'pending'
+    GROUP BY `file_id`, COALESCE(`user_id`, 0)
+    HAVING COUNT(*) > 1
+) d ON d.`file_id` = r.`file_id` AND d.`reporter` = COALESCE(r.`user_id`, 0)
+SET r.`status` = 'dismissed', r.`admin_notes` = 'Duplicate of a newer pending report'
+WHERE r.`status` = 'pending' AND r.`id` <> d.`keep_id`;
+
+ALTER TABLE `tbl_file_reports`
+ADD COLUMN `pending_key` VARCHAR(32) GENERATED ALWAYS AS (
+    IF(`status` = 'pending', CONCAT(`file_id`, ':', COALESCE(`user_id`, 0)), NULL)
+) STORED,
+ADD UNIQUE KEY `uniq_pending_file_report` (`pending_key`);
@@ -0,0 +1,36 @@
+-- Unique @mention handles. Existing users get their lowercased name without
+-- spaces; names that clash get their id appended so every handle resolves
+ALTER TABLE `tbl_users`
+ADD COLUMN `handle` VARCHAR(64) NULL DEFAULT NULL AFTER `name`;
+
+UPDATE `tbl_users` u
+JOIN (
+  SELECT id, base, COUNT(*) OVER (PARTITION BY base) AS holders
+  FROM (
+    SELECT id, COALESCE(NULLIF(REGEXP_REPLACE(LEFT(LOWER(REGEXP_REPLACE(name, '[^[:alnum:]_.-]', '')), 48), '[.-]+$', ''), ''), 'user') AS base
+    FROM `tbl_users`
+  ) raw
+) h ON h.id = u.id
+SET u.handle = IF(h.holders = 1, h.base, CONCAT(h.base, '_', u.id));
+
+ALTER TABLE `tbl_users`
+ADD UNIQUE KEY `uniq_users_handle` (`handle`);
+
+-- Users mentioned in a comment, replacing the comma-separated column
+CREATE TABLE IF NOT EXISTS `tbl_comment_mentions` (
+  `comment_id` INT NOT NULL,
+  `user_id` INT NOT NULL,
+  PRIMARY KEY (`comment_id`, `user_id`),
+  KEY `idx_comment_mentions_user` (`user_id`),
+  CONSTRAINT `fk_comment_mentions_comment` FOREIGN KEY (`comment_id`) REFERENCES `tbl_file_comments` (`id`) ON DELETE CASCADE,
+  CONSTRAINT `fk_comment_mentions_user` FOREIGN KEY (`user_id`) REFERENCES `tbl_users` (`id`) ON DELETE CASCADE
+);
+
+INSERT IGNORE INTO `tbl_comment_mentions` (`comment_id`, `user_id`)
+SELECT c.id, u.id
+FROM `tbl_file_comments` c
+JOIN `tbl_users` u ON FIND_IN_SET(u.id, c.mentioned_user_ids)
+WHERE c.mentioned_user_ids IS NOT NULL;
+
+ALTER TABLE `tbl_file_comments`
+DROP COLUMN `mentioned_user_ids`;
@@ -0,0 +1,9 @@
+-- Book progress reads only tbl_file_completions, so copy in completions that
+-- were recorded as 'Complete' plays before the table existed, one per day
+INSERT IGNORE INTO `tbl_file_completions`
+  (`user_id`, `file_id`, `session_key`, `position_seconds`, `duration_seconds`, `completed_at`)
+SELECT ph.user_id, ph.file_id, CONCAT('day:', DATE(ph.played_at)),
+       COALESCE(MAX(ph.play_position), 0), COALESCE(MAX(ph.total_duration), 0), MIN(ph.played_at)
+FROM `tbl_play_history` ph
+WHERE ph.play_action = 'Complete' AND ph.user_id IS NOT NULL
+GROUP BY ph.user_id, ph.file_id, DATE(ph.played_at);
@@ -0,0 +1,24 @@
+-- One place that decides whether scheduled content is visible: a file is
+-- hidden while its own or its book's `publish_at` (UTC) is in the future.
+-- Book-only listings pass NULL for the file side
+CREATE FUNCTION `is_published`(`file_publish_at` DATETIME, `book_publish_at` DATETIME)
+RETURNS BOOLEAN
+NOT DETERMINISTIC
+NO SQL
+RETURN (`file_publish_at` IS NULL OR `file_publish_at` <= UTC_TIMESTAMP())
+    AND (`book_publish_at` IS NULL OR `book_publish_at` <= UTC_TIMESTAMP());
+
+-- The publish_at a schedule set, so deleting it clears exactly that value
+-- even if the platform timezone has changed since
+ALTER TABLE `tbl_scheduled_content`
+ADD COLUMN `publish_at` DATETIME NULL DEFAULT NULL AFTER `hide_until_surfaced`;
+
+UPDATE `tbl_scheduled_content` sc
+JOIN `tbl_files` f ON sc.`content_type` = 'file' AND f.`id` = sc.`content_id`
+SET sc.`publish_at` = f.`publish_at`
+WHERE sc.`hide_until_surfaced` = TRUE;
+
+UPDATE `tbl_scheduled_content` sc
+JOIN `tbl_books` b ON sc.`content_type` = 'book' AND b.`id` = sc.`content_id`
+SET sc.`publish_at` = b.`publish_at`
+WHERE sc.`hide_until_surfaced` = TRUE;
@@ -0,0 +1,3 @@
+-- Rejected comments are kept so their authors can see the moderation outcome;
+-- a held comment with `rejected_at` set is out of the moderation queue
+ALTER TABLE tbl_file_comments ADD COLUMN rejected_at DATETIME NULL DEFAULT NULL;
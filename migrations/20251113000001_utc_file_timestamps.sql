@@ -0,0 +1,15 @@
+-- Connections now pin the session time zone to UTC. `tbl_files.date` and
+-- `created_at` are DATETIME columns that were filled with NOW() in the
+-- server's own time zone, so shift them to UTC by that zone's offset.
+-- Every other timestamp was either bound from `Utc::now()` by the app or is a
+-- TIMESTAMP column, which MySQL stores in UTC and converts per session, so
+-- those need no conversion. On a server already running in UTC the offset is
+-- zero and this is a no-op.
+SET time_zone = @@global.time_zone;
+SET @server_utc_offset_minutes = TIMESTAMPDIFF(MINUTE, UTC_TIMESTAMP(), NOW());
+SET time_zone = '+00:00';
+
+UPDATE `tbl_files`
+SET `date` = `date` - INTERVAL @server_utc_offset_minutes MINUTE,
+    `created_at` = `created_at` - INTERVAL @server_utc_offset_minutes MINUTE
+WHERE @server_utc_offset_minutes <> 0;
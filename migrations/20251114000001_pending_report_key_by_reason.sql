@@ -0,0 +1,17 @@
+-- Server-raised reports have no user, so keying them by user alone let one
+-- pending server report on a file block every other kind. Key them by reason
+-- instead: at most one pending report per user and file, and one per server
+-- reason and file.
+ALTER TABLE `tbl_file_reports`
+DROP INDEX `uniq_pending_file_report`,
+DROP COLUMN `pending_key`;
+
+ALTER TABLE `tbl_file_reports`
+ADD COLUMN `pending_key` VARCHAR(128) GENERATED ALWAYS AS (
+    IF(
+        `status` = 'pending',
+        CONCAT(`file_id`, ':', COALESCE(CONCAT('user:', `user_id`), CONCAT('server:', `reason`))),
+        NULL
+    )
+) STORED,
+ADD UNIQUE KEY `uniq_pending_file_report` (`pending_key`);
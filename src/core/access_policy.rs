@@ -0,0 +1,570 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use actix_web::{web, Error, HttpMessage, ResponseError};
+use futures_util::future::LocalBoxFuture;
+use jsonwebtoken::{decode, DecodingKey, Validation};
+
+use crate::core::config::AccessPolicyConfig;
+use crate::core::jwt_auth::{impersonation_session_key, JwtClaims};
+use crate::core::{AppConfig, AppError, RedisHelper};
+
+/// Prefix every API route is mounted under
+const API_PREFIX: &str = "/api/v1";
+
+/// Later API versions mount the same routes under their own prefix and share
+/// the v1 policy rows
+const VERSIONED_API_PREFIXES: &[&str] = &[API_PREFIX, "/api/v2"];
+
+/// The only write an impersonation token may make
+const END_IMPERSONATION_ROUTE: (&str, &str) = ("POST", "/auth/admin/impersonate/end");
+
+/// Who may call a route
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RouteAccess {
+    /// Anyone; a token is ignored
+    Public,
+    /// Anyone; a valid token identifies the caller and personalizes the response
+    OptionalAuth,
+    /// Any signed-in user
+    Authenticated,
+    /// Admins and managers; per-scholar access is still checked by the handler
+    Staff,
+    Admin,
+}
+
+use RouteAccess::*;
+
+/// The access matrix: method and route pattern (relative to the `/api/vN` prefix)
+/// for every endpoint; v2 routes share their v1 row. A route missing from this
+/// table requires authentication, so a new endpoint is never public by accident;
+/// add it here when registering it.
+/// Handlers keep their finer checks (ownership, per-scholar access).
+pub const ROUTE_POLICIES: &[(&str, &str, RouteAccess)] = &[
+    // Utility
+    ("GET", "/health_check", Public),
+    ("GET", "/states", Public),
+    ("GET", "/settings", Public),
+    ("GET", "/stats/public", Public),
+    ("GET", "/search", Public),
+    ("GET", "/share/file/{}", Public),
+    ("GET", "/share/book/{}", Public),
+    ("GET", "/share/scholar/{}", Public),
+    ("GET", "/scheduled/today", OptionalAuth),
+    // Auth and account
+    ("POST", "/auth/register", Public),
+    ("POST", "/auth/login", Public),
+    ("POST", "/auth/refresh-token", Public),
+    ("POST", "/auth/logout", Public),
+    ("POST", "/auth/forgot-password", Public),
+    ("POST", "/auth/reset-password", Public),
+    ("GET", "/auth/profile", Authenticated),
+    ("PUT", "/auth/profile", Authenticated),
+    ("POST", "/auth/change-password", Authenticated),
+    ("POST", "/auth/change-email", Authenticated),
+    ("POST", "/auth/change-email/confirm", Authenticated),
+    ("DELETE", "/auth/deactivate", Authenticated),
+    ("GET", "/auth/permissions", Authenticated),
+    ("POST", "/auth/access/grant", Staff),
+    ("POST", "/auth/access/revoke", Staff),
+    ("GET", "/auth/access/all", Admin),
+    ("POST", "/auth/admin/impersonate/end", Authenticated),
+    ("POST", "/auth/admin/impersonate/{}", Admin),
+    ("GET", "/auth/notification-preferences", Authenticated),
+    ("PUT", "/auth/notification-preferences", Authenticated),
+    ("GET", "/auth/notifications", Authenticated),
+    ("GET", "/auth/my-activity", Authenticated),
+    // Scholars
+    ("GET", "/scholars", Public),
+    ("GET", "/scholars/state/{}", Public),
+    ("GET", "/scholars/filter", Public),
+    ("GET", "/scholars/dropdown", Public),
+    ("GET", "/scholars/trending", Public),
+    ("GET", "/scholars/featured", Public),
+    ("PUT", "/scholars/featured", Admin),
+    ("GET", "/scholars/{}", OptionalAuth),
+    ("GET", "/scholars/{}/home", OptionalAuth),
+    ("GET", "/scholars/{}/statistics", Public),
+    ("GET", "/scholars/{}/top-files", OptionalAuth),
+    ("GET", "/scholars/{}/files/search", OptionalAuth),
+    ("GET", "/scholars/{}/can-manage", Authenticated),
+    ("GET", "/scholars/{}/catalog", OptionalAuth),
+    ("GET", "/scholars/{}/books", Public),
+    ("GET", "/scholars/{}/report.csv", Staff),
+    ("POST", "/scholars", Admin),
+    ("PUT", "/scholars/{}", Admin),
+    ("DELETE", "/scholars/{}", Admin),
+    ("POST", "/scholars/{}/links", Admin),
+    ("DELETE", "/scholars/{}/links/{}", Admin),
+    ("POST", "/scholars/{}/follow", Authenticated),
+    ("PUT", "/scholars/{}/follow", Authenticated),
+    ("DELETE", "/scholars/{}/follow", Authenticated),
+    ("GET", "/scholars/my-follows", Authenticated),
+    ("GET", "/scholars/{}/follow-status", Authenticated),
+    ("GET", "/follows/new-count", Authenticated),
+    ("POST", "/follows/mark-seen", Authenticated),
+    // Books
+    ("GET", "/books/dropdown", Public),
+    ("GET", "/books/{}", OptionalAuth),
+    ("GET", "/books/{}/statistics", Public),
+    ("GET", "/books/{}/files", OptionalAuth),
+    ("GET", "/books/{}/play-all", Public),
+    ("GET", "/books/{}/offline-manifest", Authenticated),
+    ("GET", "/books/{}/files/search", OptionalAuth),
+    ("GET", "/books/{}/progress", Authenticated),
+    ("GET", "/books/{}/siblings", Public),
+    ("GET", "/books/{}/can-manage", Authenticated),
+    ("POST", "/books/{}/upload", Authenticated),
+    ("POST", "/books", Authenticated),
+    ("PUT", "/books/{}", Authenticated),
+    ("DELETE", "/books/{}", Authenticated),
+    // Files
+    ("GET", "/files/recent", OptionalAuth),
+    ("GET", "/files/featured-today", OptionalAuth),
+    ("POST", "/files/featured", Admin),
+    ("PUT", "/files/{}/restriction", Admin),
+    ("GET", "/files/{}/view", Public),
+    ("GET", "/files/{}/analytics", Authenticated),
+    ("GET", "/files/{}/related", Public),
+    ("GET", "/files/{}/suggestions", OptionalAuth),
+    ("GET", "/files/{}/next", OptionalAuth),
+    ("GET", "/files/{}/likes", Public),
+    ("GET", "/files/{}/comments", Public),
+    ("GET", "/files/{}/download-stats", Public),
+    ("GET", "/files/{}/download", Authenticated),
+    ("POST", "/files/download-zip", Authenticated),
+    ("GET", "/files/{}/preview", OptionalAuth),
+    ("POST", "/files/{}/track-download", OptionalAuth),
+    ("PUT", "/files/{}", Authenticated),
+    ("DELETE", "/files/{}", Authenticated),
+    ("POST", "/files/reports", Authenticated),
+    ("GET", "/files/admin/reports/pending", Staff),
+    ("PUT", "/files/admin/reports/{}/resolve", Staff),
+    ("POST", "/files/likes", Authenticated),
+    ("DELETE", "/files/{}/likes", Authenticated),
+    ("GET", "/files/{}/like-status", Authenticated),
+    ("GET", "/files/{}/in-playlists", Authenticated),
+    ("POST", "/files/comments", Authenticated),
+    ("PUT", "/files/comments/{}", Authenticated),
+    ("DELETE", "/files/comments/{}", Authenticated),
+    ("GET", "/files/admin/comments/pending", Staff),
+    ("PUT", "/files/admin/comments/{}/approve", Staff),
+    ("PUT", "/files/admin/comments/{}/reject", Staff),
+    ("GET", "/files/my-downloads", Authenticated),
+    ("GET", "/files/my-downloads/details", Authenticated),
+    ("GET", "/files/my-likes", Authenticated),
+    ("GET", "/files/my-comments", Authenticated),
+    // Subscriptions
+    ("GET", "/subscriptions/plans", Public),
+    ("GET", "/subscriptions/my-subscriptions", Authenticated),
+    ("GET", "/subscriptions/status", Authenticated),
+    ("GET", "/subscriptions/active", Authenticated),
+    ("POST", "/subscriptions/subscribe", Authenticated),
+    ("GET", "/subscriptions/{}/payment-instructions", Authenticated),
+    ("POST", "/subscriptions/{}/resend-instructions", Authenticated),
+    ("GET", "/subscriptions/admin/pending", Admin),
+    ("PUT", "/subscriptions/admin/verify/{}", Admin),
+    ("POST", "/subscriptions/admin/verify-batch", Admin),
+    // Also called by the scheduler with `X-Cron-Token` instead of a JWT; the handler checks both
+    ("POST", "/subscriptions/admin/expire-now", OptionalAuth),
+    // Play history; guests may record plays when guest tracking is on
+    ("POST", "/play-history", OptionalAuth),
+    ("POST", "/play-history/sync", Authenticated),
+    ("POST", "/play-history/{}/complete", Authenticated),
+    ("GET", "/play-history", Authenticated),
+    ("GET", "/play-history/most-played", Authenticated),
+    ("DELETE", "/play-history/", Authenticated),
+    ("GET", "/play-history/files/{}/play-stats", Public),
+    ("PUT", "/play-history/goal", Authenticated),
+    ("GET", "/play-history/goal/progress", Authenticated),
+    // Playlists; private playlists are checked against the caller by the handler
+    ("GET", "/playlists/public", Public),
+    ("GET", "/playlists/{}", OptionalAuth),
+    ("GET", "/playlists/{}/files", OptionalAuth),
+    ("GET", "/playlists/{}/full", OptionalAuth),
+    ("POST", "/playlists", Authenticated),
+    ("GET", "/playlists", Authenticated),
+    ("PUT", "/playlists/{}", Authenticated),
+    ("DELETE", "/playlists/{}", Authenticated),
+    ("POST", "/playlists/{}/files", Authenticated),
+    ("DELETE", "/playlists/{}/files/{}", Authenticated),
+    // Admin
+    ("POST", "/admin/maintenance/recompute-counters", Admin),
+    ("POST", "/admin/maintenance/prune-play-history", Admin),
+    ("POST", "/admin/maintenance/integrity-scan", Admin),
+    ("POST", "/admin/maintenance/backfill-content-hashes", Admin),
+    ("POST", "/admin/import/scan", Admin),
+    ("GET", "/admin/log-level", Admin),
+    ("PUT", "/admin/log-level", Admin),
+    ("GET", "/admin/flags", Admin),
+    ("PUT", "/admin/flags/{}", Admin),
+    ("GET", "/admin/emails/failed", Admin),
+    ("POST", "/admin/emails/failed/{}/retry", Admin),
+    ("GET", "/admin/schedules", Admin),
+    ("POST", "/admin/schedules", Admin),
+    ("DELETE", "/admin/schedules/{}", Admin),
+];
+
+/// Middleware enforcing `ROUTE_POLICIES` before any handler runs. Valid claims
+/// are stored in the request extensions, where the `JwtClaims` extractor picks them up.
+pub struct AccessPolicy;
+
+impl<S, B> Transform<S, ServiceRequest> for AccessPolicy
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AccessPolicyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessPolicyMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct AccessPolicyMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessPolicyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let config = match req.app_data::<web::Data<AppConfig>>() {
+                Some(config) => config.clone(),
+                None => return service.call(req).await.map(|res| res.map_into_left_body()),
+            };
+
+            // CORS preflights carry no credentials
+            if req.method() == Method::OPTIONS {
+                return service.call(req).await.map(|res| res.map_into_left_body());
+            }
+
+            let access = route_access(&req, &config);
+            let claims = bearer_claims(&req, &config);
+
+            if let Err(error) = check_access(access, claims.as_ref()) {
+                tracing::warn!(
+                    "Denied {} {} ({:?}): {}",
+                    req.method(),
+                    req.path(),
+                    access,
+                    error
+                );
+                let (http_req, _) = req.into_parts();
+                return Ok(ServiceResponse::new(http_req, error.error_response())
+                    .map_into_right_body());
+            }
+
+            if let Some(Ok(claims)) = claims {
+                if let Err(error) = check_impersonation(&req, &claims).await {
+                    tracing::warn!(
+                        "Denied {} {} to impersonation session {:?}: {}",
+                        req.method(),
+                        req.path(),
+                        claims.impersonation_id,
+                        error
+                    );
+                    let (http_req, _) = req.into_parts();
+                    return Ok(ServiceResponse::new(http_req, error.error_response())
+                        .map_into_right_body());
+                }
+                req.extensions_mut().insert(claims);
+            }
+
+            service.call(req).await.map(|res| res.map_into_left_body())
+        })
+    }
+}
+
+/// Access level for the request, after applying the configured anonymous allowlist
+fn route_access(req: &ServiceRequest, config: &AppConfig) -> RouteAccess {
+    let path = req.path();
+    let static_prefix = format!("{}/static/", API_PREFIX);
+    if let Some(rest) = path.strip_prefix(&static_prefix) {
+        return if rest.starts_with("images/") || config.app_paths.public_audio {
+            Public
+        } else {
+            Authenticated
+        };
+    }
+
+    // Unrouted paths fall through to the router's 404
+    let Some(pattern) = relative_pattern(req) else {
+        return Public;
+    };
+
+    let access = ROUTE_POLICIES
+        .iter()
+        .find(|(method, route, _)| req.method().as_str() == *method && *route == pattern)
+        .map(|(_, _, access)| *access)
+        .unwrap_or_else(|| {
+            tracing::warn!("No access policy for {} {}; requiring auth", req.method(), pattern);
+            Authenticated
+        });
+
+    restrict_anonymous(access, &pattern, &config.access_policy)
+}
+
+/// The matched route pattern relative to its `/api/vN` prefix, with params normalized
+fn relative_pattern(req: &ServiceRequest) -> Option<String> {
+    let pattern = req.match_pattern()?;
+    let relative = VERSIONED_API_PREFIXES
+        .iter()
+        .find_map(|prefix| pattern.strip_prefix(prefix))
+        .unwrap_or(pattern.as_str());
+    Some(normalize_pattern(relative))
+}
+
+/// With an allowlist configured, anonymous access is limited to the listed patterns.
+/// The allowlist only narrows the matrix: it never opens a protected route
+fn restrict_anonymous(access: RouteAccess, pattern: &str, policy: &AccessPolicyConfig) -> RouteAccess {
+    if policy.anonymous_allowlist.is_empty() || !matches!(access, Public | OptionalAuth) {
+        return access;
+    }
+
+    let allowed = policy
+        .anonymous_allowlist
+        .iter()
+        .any(|entry| normalize_pattern(entry.trim()) == pattern);
+    if allowed {
+        access
+    } else {
+        Authenticated
+    }
+}
+
+/// `None` without a bearer token, otherwise whether it decoded to valid claims
+fn bearer_claims(req: &ServiceRequest, config: &AppConfig) -> Option<Result<JwtClaims, AppError>> {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))?;
+
+    Some(
+        decode::<JwtClaims>(
+            token,
+            &DecodingKey::from_secret(config.get_jwt_secret().as_ref()),
+            &Validation::default(),
+        )
+        .map(|data| data.claims)
+        .map_err(|_| AppError::unauthorized("Invalid token")),
+    )
+}
+
+fn check_access(
+    access: RouteAccess,
+    claims: Option<&Result<JwtClaims, AppError>>,
+) -> Result<(), AppError> {
+    let claims = match (access, claims) {
+        // An invalid token on an optional route is treated as anonymous, as before
+        (Public | OptionalAuth, _) => return Ok(()),
+        (_, None) => return Err(AppError::unauthorized("Authentication required")),
+        (_, Some(Err(_))) => return Err(AppError::unauthorized("Invalid token")),
+        (_, Some(Ok(claims))) => claims,
+    };
+
+    match access {
+        Staff if claims.role != "admin" && claims.role != "manager" => {
+            Err(AppError::forbidden_error("Access denied. Manager or admin role required."))
+        }
+        Admin if claims.role != "admin" => {
+            Err(AppError::forbidden_error("Access denied. Admin role required."))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Impersonation tokens may only read, apart from ending their own session,
+/// and stop working as soon as that session is ended
+async fn check_impersonation(req: &ServiceRequest, claims: &JwtClaims) -> Result<(), AppError> {
+    let Some(admin_id) = claims.impersonated_by else {
+        return Ok(());
+    };
+
+    let (end_method, end_route) = END_IMPERSONATION_ROUTE;
+    let ends_session = req.method().as_str() == end_method
+        && relative_pattern(req).as_deref() == Some(end_route);
+    if !matches!(*req.method(), Method::GET | Method::HEAD) && !ends_session {
+        return Err(AppError::forbidden_error("Impersonation tokens are read-only"));
+    }
+
+    let session_id = claims
+        .impersonation_id
+        .as_deref()
+        .ok_or_else(|| AppError::unauthorized("Invalid token"))?;
+    let redis = req
+        .app_data::<web::Data<RedisHelper>>()
+        .ok_or_else(|| AppError::internal_error("Redis is not configured"))?;
+
+    let session_admin: Option<i32> = redis
+        .get_secure(&impersonation_session_key(session_id))
+        .await?;
+    if session_admin != Some(admin_id) {
+        return Err(AppError::unauthorized("Impersonation session has ended"));
+    }
+
+    Ok(())
+}
+
+/// Replace every `{param}` segment with `{}` so patterns compare regardless of param names
+fn normalize_pattern(pattern: &str) -> String {
+    let mut normalized = String::with_capacity(pattern.len());
+    let mut depth = 0;
+    for c in pattern.chars() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    normalized.push_str("{}");
+                }
+                depth += 1;
+            }
+            '}' => depth -= 1,
+            _ if depth == 0 => normalized.push(c),
+            _ => {}
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::jwt_auth::generate_jwt_token;
+    use actix_web::{test as actix_test, App, HttpRequest, HttpResponse};
+
+    async fn caller(req: HttpRequest) -> HttpResponse {
+        let caller = if req.extensions().get::<JwtClaims>().is_some() { "user" } else { "anonymous" };
+        HttpResponse::Ok().body(caller)
+    }
+
+    fn bearer(config: &AppConfig) -> String {
+        let claims = JwtClaims {
+            sub: "7".to_string(),
+            email: "listener@example.com".to_string(),
+            role: "user".to_string(),
+            exp: (chrono::Utc::now().timestamp() + 3600) as usize,
+            impersonated_by: None,
+            impersonation_id: None,
+        };
+        format!("Bearer {}", generate_jwt_token(&claims, config).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn policy_rejects_anonymous_calls_to_authenticated_routes_and_passes_optional_ones() {
+        let mut config = AppConfig::new().expect("local configuration");
+        config.access_policy.anonymous_allowlist.clear();
+        let token = bearer(&config);
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .wrap(AccessPolicy)
+                .service(
+                    web::scope(API_PREFIX)
+                        // Authenticated in the matrix
+                        .route("/play-history", web::get().to(caller))
+                        // OptionalAuth in the matrix
+                        .route("/playlists/{playlist_id}", web::get().to(caller)),
+                ),
+        )
+        .await;
+
+        let anonymous = actix_test::TestRequest::get().uri("/api/v1/play-history").to_request();
+        let response = actix_test::call_service(&app, anonymous).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let signed_in = actix_test::TestRequest::get()
+            .uri("/api/v1/play-history")
+            .insert_header((header::AUTHORIZATION, token.clone()))
+            .to_request();
+        assert_eq!(actix_test::call_and_read_body(&app, signed_in).await, web::Bytes::from_static(b"user"));
+
+        let anonymous = actix_test::TestRequest::get().uri("/api/v1/playlists/3").to_request();
+        assert_eq!(actix_test::call_and_read_body(&app, anonymous).await, web::Bytes::from_static(b"anonymous"));
+
+        let signed_in = actix_test::TestRequest::get()
+            .uri("/api/v1/playlists/3")
+            .insert_header((header::AUTHORIZATION, token))
+            .to_request();
+        assert_eq!(actix_test::call_and_read_body(&app, signed_in).await, web::Bytes::from_static(b"user"));
+    }
+
+    #[actix_web::test]
+    async fn previews_are_open_while_the_full_stream_stays_gated() {
+        let mut config = AppConfig::new().expect("local configuration");
+        config.access_policy.anonymous_allowlist.clear();
+        config.app_paths.public_audio = false;
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .wrap(AccessPolicy)
+                .service(
+                    web::scope(API_PREFIX)
+                        .route("/files/{file_id}/preview", web::get().to(caller))
+                        .route("/static/audio/{location:.*}", web::get().to(caller)),
+                ),
+        )
+        .await;
+
+        let preview = actix_test::TestRequest::get().uri("/api/v1/files/3/preview").to_request();
+        assert_eq!(actix_test::call_and_read_body(&app, preview).await, web::Bytes::from_static(b"anonymous"));
+
+        let stream = actix_test::TestRequest::get().uri("/api/v1/static/audio/lecture.mp3").to_request();
+        let response = actix_test::call_service(&app, stream).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn staff_and_admin_rows_check_the_token_role() {
+        let claims = |role: &str| {
+            Some(Ok::<_, AppError>(JwtClaims {
+                sub: "1".to_string(),
+                email: "staff@example.com".to_string(),
+                role: role.to_string(),
+                exp: 0,
+                impersonated_by: None,
+                impersonation_id: None,
+            }))
+        };
+        assert!(check_access(Admin, claims("admin").as_ref()).is_ok());
+        assert!(check_access(Admin, claims("manager").as_ref()).is_err());
+        assert!(check_access(Staff, claims("manager").as_ref()).is_ok());
+        assert!(check_access(Staff, claims("user").as_ref()).is_err());
+        assert!(check_access(OptionalAuth, Some(&Err(AppError::unauthorized("Invalid token")))).is_ok());
+    }
+
+    #[test]
+    fn normalize_pattern_ignores_param_names() {
+        assert_eq!(normalize_pattern("/files/{file_id}/related"), "/files/{}/related");
+        assert_eq!(normalize_pattern("/files/{id}/related"), normalize_pattern("/files/{file_id}/related"));
+        assert_eq!(normalize_pattern("/books/{a}/files/{b}"), "/books/{}/files/{}");
+        assert_eq!(normalize_pattern("/health"), "/health");
+    }
+
+    #[test]
+    fn normalize_pattern_handles_nested_regex_params() {
+        assert_eq!(normalize_pattern(r"/day/{date:\d{4}-\d{2}}"), "/day/{}");
+    }
+}
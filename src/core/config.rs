@@ -1,5 +1,7 @@
 use secrecy::{ExposeSecret, Secret};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use bigdecimal::BigDecimal;
+use std::collections::HashMap;
 
 use sqlx::mysql::MySqlConnectOptions;
 use sqlx::ConnectOptions;
@@ -12,6 +14,51 @@ pub struct AppConfig {
     pub jwt_auth_config: JwtAuthConfig,
     pub smtp: SmtpConfig,
     pub app_paths: AppPaths,
+    #[serde(default)]
+    pub request_timeouts: RequestTimeoutConfig,
+    #[serde(default)]
+    pub uploads: UploadConfig,
+    #[serde(default)]
+    pub password_hashing: PasswordHashingConfig,
+    #[serde(default)]
+    pub play_history_retention: PlayHistoryRetentionConfig,
+    #[serde(default)]
+    pub payments: PaymentConfig,
+    #[serde(default)]
+    pub subscriptions: SubscriptionConfig,
+    #[serde(default)]
+    pub onboarding: OnboardingConfig,
+    #[serde(default)]
+    pub guest_tracking: GuestTrackingConfig,
+    #[serde(default)]
+    pub comment_filter: CommentFilterConfig,
+    #[serde(default)]
+    pub comments: CommentConfig,
+    #[serde(default)]
+    pub access_policy: AccessPolicyConfig,
+    #[serde(default)]
+    pub featured_files: FeaturedFileConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub proxies: ProxyConfig,
+    #[serde(default)]
+    pub imports: ImportConfig,
+    #[serde(default)]
+    pub trending: TrendingConfig,
+    #[serde(default)]
+    pub previews: PreviewConfig,
+    #[serde(default)]
+    pub failed_emails: FailedEmailConfig,
+    #[serde(default)]
+    pub currencies: CurrencyConfig,
+    #[serde(default)]
+    pub audio_serving: AudioServingConfig,
+    #[serde(default)]
+    pub scheduling: SchedulingConfig,
+    /// Filled from `images_dir` at startup by the image index job
+    #[serde(skip)]
+    pub image_index: crate::core::ImageIndex,
 }
 
 impl AppConfig {
@@ -48,6 +95,9 @@ pub struct SunnahWebServer {
     pub port: u16,
     pub host: String,
     pub base_url: String,
+    /// CDN origin used for media URLs; API URLs always stay on `base_url`
+    #[serde(default)]
+    pub media_base_url: Option<String>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -57,14 +107,710 @@ pub struct AppPaths {
     pub static_audio: String,
     pub images_dir: String,
     pub uploads_dir: String,
+    #[serde(default = "default_shard_by_date")]
+    pub shard_by_date: bool,
+    /// Name downloads after the file's display name instead of its stored name
+    #[serde(default = "default_humanize_download_names")]
+    pub humanize_download_names: bool,
+    #[serde(default = "default_scholar_image")]
+    pub default_scholar_image: String,
+    #[serde(default = "default_book_image")]
+    pub default_book_image: String,
+    /// Served in place of any image that is empty or missing from `images_dir`
+    #[serde(default = "default_placeholder_image")]
+    pub placeholder_image: String,
+    /// Serve `/static/audio` without authentication, as it always was. Set to
+    /// false to require a login (and the file's access rules) for audio
+    #[serde(default = "default_public_audio")]
+    pub public_audio: bool,
+}
+
+impl AppPaths {
+    /// Warn at startup about configured default images that are not on disk
+    pub fn verify_default_images(&self) {
+        for image in [
+            &self.default_scholar_image,
+            &self.default_book_image,
+            &self.placeholder_image,
+        ] {
+            let path = std::path::Path::new(&self.images_dir).join(image);
+            if !path.is_file() {
+                tracing::warn!("Default image {} not found at {}", image, path.display());
+            }
+        }
+    }
+}
+
+fn default_shard_by_date() -> bool {
+    true
+}
+
+fn default_humanize_download_names() -> bool {
+    true
+}
+
+fn default_public_audio() -> bool {
+    true
+}
+
+fn default_scholar_image() -> String {
+    "scholar.jpg".to_string()
+}
+
+fn default_book_image() -> String {
+    "book.jpg".to_string()
+}
+
+fn default_placeholder_image() -> String {
+    "placeholder.jpg".to_string()
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RequestTimeoutConfig {
+    #[serde(default = "default_request_timeout_seconds")]
+    pub default_seconds: u64,
+    /// Per route group overrides keyed by scope name, e.g. `scholars: 20`
+    #[serde(default)]
+    pub groups: HashMap<String, u64>,
+}
+
+impl Default for RequestTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            default_seconds: default_request_timeout_seconds(),
+            groups: HashMap::new(),
+        }
+    }
+}
+
+impl RequestTimeoutConfig {
+    /// Get the timeout for a route group, falling back to the default
+    pub fn for_group(&self, group: &str) -> std::time::Duration {
+        let seconds = self.groups.get(group).copied().unwrap_or(self.default_seconds);
+        std::time::Duration::from_secs(seconds)
+    }
+}
+
+fn default_request_timeout_seconds() -> u64 {
+    30
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UploadConfig {
+    /// Audio shorter than this is treated as empty or corrupt
+    #[serde(default = "default_min_duration_seconds")]
+    pub min_duration_seconds: u64,
+    /// Files smaller than this cannot hold meaningful audio
+    #[serde(default = "default_min_file_bytes")]
+    pub min_file_bytes: usize,
+    /// Files larger than this are rejected before their metadata is parsed,
+    /// since parsing reads the whole file into memory
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            min_duration_seconds: default_min_duration_seconds(),
+            min_file_bytes: default_min_file_bytes(),
+            max_file_bytes: default_max_file_bytes(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SubscriptionConfig {
+    /// Days an active subscription stays active after its end_date
+    #[serde(default)]
+    pub grace_days: i64,
+    /// Email users when their subscription expires
+    #[serde(default)]
+    pub send_expiry_emails: bool,
+    /// Shared secret a scheduler sends in `X-Cron-Token` to run expiry without an admin login
+    #[serde(default)]
+    pub cron_token: Option<Secret<String>>,
+}
+
+/// What happens to a comment containing a blocked word
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentFilterMode {
+    #[default]
+    Off,
+    Reject, // Refuse the comment with a validation error
+    Mask,   // Replace each blocked word with asterisks
+    Hold,   // Keep the comment unapproved until a moderator reviews it
+}
+
+/// Keyword filter for comments. Words match whole words, ignoring case and
+/// Arabic diacritics, so one entry covers its vowelled spellings
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CommentFilterConfig {
+    #[serde(default)]
+    pub mode: CommentFilterMode,
+    #[serde(default)]
+    pub words: Vec<String>,
+}
+
+/// Length bounds, in characters after trimming, and the per-user creation rate for comments
+#[derive(Deserialize, Clone, Debug)]
+pub struct CommentConfig {
+    #[serde(default = "default_comment_min_length")]
+    pub min_length: usize,
+    #[serde(default = "default_comment_max_length")]
+    pub max_length: usize,
+    /// Comments a user may post per `rate_window_seconds`
+    #[serde(default = "default_comment_rate_limit")]
+    pub rate_limit: u64,
+    #[serde(default = "default_comment_rate_window_seconds")]
+    pub rate_window_seconds: u64,
+}
+
+impl Default for CommentConfig {
+    fn default() -> Self {
+        Self {
+            min_length: default_comment_min_length(),
+            max_length: default_comment_max_length(),
+            rate_limit: default_comment_rate_limit(),
+            rate_window_seconds: default_comment_rate_window_seconds(),
+        }
+    }
+}
+
+fn default_comment_min_length() -> usize {
+    1
+}
+
+fn default_comment_max_length() -> usize {
+    2000
+}
+
+fn default_comment_rate_limit() -> u64 {
+    5
+}
+
+fn default_comment_rate_window_seconds() -> u64 {
+    60
+}
+
+/// Narrows anonymous access below the built-in matrix in `core::access_policy`
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct AccessPolicyConfig {
+    /// Route patterns relative to `/api/v1` (e.g. `/scholars/{id}`) that anonymous
+    /// callers may reach. Empty keeps every public and optional-auth route open
+    #[serde(default)]
+    pub anonymous_allowlist: Vec<String>,
+}
+
+/// Server-side directories an admin may bulk import MP3s from
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ImportConfig {
+    /// Imports are refused for any directory outside these. Empty disables imports
+    #[serde(default)]
+    pub allowed_dirs: Vec<String>,
+}
+
+/// Reverse proxies whose `X-Forwarded-For` is believed when working out the
+/// client IP; requests from any other peer are identified by their socket address
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ProxyConfig {
+    /// CIDR blocks or single addresses, e.g. `10.0.0.0/8` or `127.0.0.1`.
+    /// Empty trusts no proxy
+    #[serde(default)]
+    pub trusted: Vec<String>,
+}
+
+/// Record plays and downloads from clients without an account, keyed by their anonymous client id
+#[derive(Deserialize, Clone, Debug)]
+pub struct GuestTrackingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Guest events one client id may record per `rate_window_seconds`
+    #[serde(default = "default_guest_client_rate_limit")]
+    pub client_rate_limit: u64,
+    /// Guest events one IP may record per `rate_window_seconds`; higher than
+    /// the per-client limit since many clients can share an address
+    #[serde(default = "default_guest_ip_rate_limit")]
+    pub ip_rate_limit: u64,
+    #[serde(default = "default_guest_rate_window_seconds")]
+    pub rate_window_seconds: u64,
+}
+
+impl Default for GuestTrackingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_rate_limit: default_guest_client_rate_limit(),
+            ip_rate_limit: default_guest_ip_rate_limit(),
+            rate_window_seconds: default_guest_rate_window_seconds(),
+        }
+    }
+}
+
+fn default_guest_client_rate_limit() -> u64 {
+    120
+}
+
+fn default_guest_ip_rate_limit() -> u64 {
+    600
+}
+
+fn default_guest_rate_window_seconds() -> u64 {
+    3600
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct OnboardingConfig {
+    /// Email new users a welcome message after registration
+    #[serde(default = "default_send_welcome_email")]
+    pub send_welcome_email: bool,
+}
+
+impl Default for OnboardingConfig {
+    fn default() -> Self {
+        Self {
+            send_welcome_email: default_send_welcome_email(),
+        }
+    }
+}
+
+fn default_send_welcome_email() -> bool {
+    true
+}
+
+/// Where subscribers send manual payments, shown in payment instructions
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct PaymentConfig {
+    #[serde(default)]
+    pub channels: Vec<PaymentChannel>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PaymentChannel {
+    pub name: String,
+    pub account_name: Option<String>,
+    pub account_number: Option<String>,
+    pub bank_name: Option<String>,
+    pub instructions: Option<String>,
+}
+
+/// Log output and retention. `level` is the startup filter in `RUST_LOG`
+/// directive form; the `RUST_LOG` env var overrides it, and admins can change
+/// it at runtime. Logs roll daily into `{directory}/{file_prefix}.YYYY-MM-DD`
+#[derive(Deserialize, Clone, Debug)]
+pub struct LoggingConfig {
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    #[serde(default = "default_log_directory")]
+    pub directory: String,
+    #[serde(default = "default_log_file_prefix")]
+    pub file_prefix: String,
+    /// Rolled files older than this are deleted; 0 keeps them regardless of age
+    #[serde(default = "default_log_max_age_days")]
+    pub max_age_days: u64,
+    /// Oldest rolled files are deleted until the logs fit; 0 disables the cap
+    #[serde(default)]
+    pub max_total_mb: u64,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_log_level(),
+            directory: default_log_directory(),
+            file_prefix: default_log_file_prefix(),
+            max_age_days: default_log_max_age_days(),
+            max_total_mb: 0,
+        }
+    }
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_directory() -> String {
+    "/var/tmp/log/sunnah_audio".to_string()
+}
+
+fn default_log_file_prefix() -> String {
+    "app".to_string()
+}
+
+fn default_log_max_age_days() -> u64 {
+    30
+}
+
+/// Featured file of the day; without a curated pool the pick rotates
+/// through the `top_pool_size` most downloaded files
+#[derive(Deserialize, Clone, Debug)]
+pub struct FeaturedFileConfig {
+    #[serde(default = "default_featured_top_pool_size")]
+    pub top_pool_size: i64,
+}
+
+impl Default for FeaturedFileConfig {
+    fn default() -> Self {
+        Self {
+            top_pool_size: default_featured_top_pool_size(),
+        }
+    }
+}
+
+fn default_featured_top_pool_size() -> i64 {
+    50
+}
+
+/// Trending scholars rank recent activity as
+/// `plays * play_weight + downloads * download_weight + new_followers * follow_weight`
+#[derive(Deserialize, Clone, Debug)]
+pub struct TrendingConfig {
+    /// Window used when the request does not name one
+    #[serde(default = "default_trending_window_days")]
+    pub default_window_days: i64,
+    #[serde(default = "default_trending_max_window_days")]
+    pub max_window_days: i64,
+    #[serde(default = "default_trending_play_weight")]
+    pub play_weight: f64,
+    #[serde(default = "default_trending_download_weight")]
+    pub download_weight: f64,
+    #[serde(default = "default_trending_follow_weight")]
+    pub follow_weight: f64,
+    #[serde(default = "default_trending_cache_ttl_seconds")]
+    pub cache_ttl_seconds: u64,
+}
+
+impl Default for TrendingConfig {
+    fn default() -> Self {
+        Self {
+            default_window_days: default_trending_window_days(),
+            max_window_days: default_trending_max_window_days(),
+            play_weight: default_trending_play_weight(),
+            download_weight: default_trending_download_weight(),
+            follow_weight: default_trending_follow_weight(),
+            cache_ttl_seconds: default_trending_cache_ttl_seconds(),
+        }
+    }
+}
+
+impl TrendingConfig {
+    /// A scholar's trend score; the trending query orders by the same sum
+    pub fn score(&self, plays: i64, downloads: i64, new_followers: i64) -> f64 {
+        plays as f64 * self.play_weight
+            + downloads as f64 * self.download_weight
+            + new_followers as f64 * self.follow_weight
+    }
+}
+
+fn default_trending_window_days() -> i64 {
+    7
+}
+
+fn default_trending_max_window_days() -> i64 {
+    90
+}
+
+fn default_trending_play_weight() -> f64 {
+    1.0
+}
+
+fn default_trending_download_weight() -> f64 {
+    2.0
+}
+
+fn default_trending_follow_weight() -> f64 {
+    5.0
+}
+
+fn default_trending_cache_ttl_seconds() -> u64 {
+    10 * 60
+}
+
+/// Clips served by `GET /files/{id}/preview`; 0 seconds disables previews
+#[derive(Deserialize, Clone, Debug)]
+pub struct PreviewConfig {
+    #[serde(default = "default_preview_seconds")]
+    pub seconds: u32,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            seconds: default_preview_seconds(),
+        }
+    }
+}
+
+fn default_preview_seconds() -> u32 {
+    30
+}
+
+/// How old a stored failed email may be and still be re-sent, and how long it is kept
+#[derive(Deserialize, Clone, Debug)]
+pub struct FailedEmailConfig {
+    #[serde(default = "default_failed_email_retry_max_age_minutes")]
+    pub retry_max_age_minutes: i64,
+    /// Stored failures older than this are deleted; 0 keeps them
+    #[serde(default = "default_failed_email_retention_days")]
+    pub retention_days: i64,
+}
+
+impl Default for FailedEmailConfig {
+    fn default() -> Self {
+        Self {
+            retry_max_age_minutes: default_failed_email_retry_max_age_minutes(),
+            retention_days: default_failed_email_retention_days(),
+        }
+    }
+}
+
+fn default_failed_email_retry_max_age_minutes() -> i64 {
+    3 * 24 * 60
+}
+
+fn default_failed_email_retention_days() -> i64 {
+    30
+}
+
+/// Headers on audio served by the download and stream routes
+#[derive(Deserialize, Clone, Debug)]
+pub struct AudioServingConfig {
+    /// Set `Content-Type` from the file's leading bytes instead of its extension
+    #[serde(default = "default_detect_audio_content_type")]
+    pub detect_content_type: bool,
+    /// `Cache-Control: private, max-age=...`; 0 sends `no-cache`
+    #[serde(default = "default_audio_cache_max_age_seconds")]
+    pub cache_max_age_seconds: u64,
+}
+
+impl Default for AudioServingConfig {
+    fn default() -> Self {
+        Self {
+            detect_content_type: default_detect_audio_content_type(),
+            cache_max_age_seconds: default_audio_cache_max_age_seconds(),
+        }
+    }
+}
+
+fn default_detect_audio_content_type() -> bool {
+    true
+}
+
+fn default_audio_cache_max_age_seconds() -> u64 {
+    24 * 3600
+}
+
+/// Scheduled content surfaces by calendar day in the platform timezone
+#[derive(Deserialize, Clone, Debug)]
+pub struct SchedulingConfig {
+    /// IANA name, e.g. `Africa/Lagos`; an unknown name fails config loading
+    #[serde(
+        default = "default_platform_timezone",
+        deserialize_with = "deserialize_timezone"
+    )]
+    pub timezone: String,
+}
+
+impl Default for SchedulingConfig {
+    fn default() -> Self {
+        Self {
+            timezone: default_platform_timezone(),
+        }
+    }
+}
+
+fn default_platform_timezone() -> String {
+    "Africa/Lagos".to_string()
+}
+
+fn deserialize_timezone<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let timezone = String::deserialize(deserializer)?;
+    timezone
+        .parse::<chrono_tz::Tz>()
+        .map_err(|_| serde::de::Error::custom(format!("unknown timezone '{}'", timezone)))?;
+    Ok(timezone)
+}
+
+impl SchedulingConfig {
+    pub fn tz(&self) -> chrono_tz::Tz {
+        // Checked when the config is loaded
+        self.timezone.parse().unwrap_or(chrono_tz::UTC)
+    }
+
+    /// The current date in the platform timezone
+    pub fn today(&self) -> chrono::NaiveDate {
+        self.date_at(chrono::Utc::now())
+    }
+
+    /// The date in the platform timezone at `instant`
+    pub fn date_at(&self, instant: chrono::DateTime<chrono::Utc>) -> chrono::NaiveDate {
+        instant.with_timezone(&self.tz()).date_naive()
+    }
+
+    /// UTC instant at which `date` starts in the platform timezone
+    pub fn start_of_day_utc(&self, date: chrono::NaiveDate) -> chrono::NaiveDateTime {
+        use chrono::TimeZone;
+
+        let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+        self.tz()
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|start| start.naive_utc())
+            // Midnight skipped by a DST change; UTC midnight is close enough
+            .unwrap_or(midnight)
+    }
+}
+
+/// Display-only conversion of plan prices. Amounts are never stored converted
+#[derive(Deserialize, Clone, Debug)]
+pub struct CurrencyConfig {
+    /// Currency every rate is quoted against
+    #[serde(default = "default_base_currency")]
+    pub base: String,
+    /// Units of each currency per one unit of `base`, e.g. `XOF: "1.45"`
+    #[serde(default)]
+    pub rates: HashMap<String, BigDecimal>,
+    /// Label shown after an amount instead of the code, e.g. `XOF: CFA`
+    #[serde(default)]
+    pub display_names: HashMap<String, String>,
+}
+
+impl Default for CurrencyConfig {
+    fn default() -> Self {
+        Self {
+            base: default_base_currency(),
+            rates: HashMap::new(),
+            display_names: HashMap::new(),
+        }
+    }
+}
+
+fn default_base_currency() -> String {
+    "NGN".to_string()
+}
+
+impl CurrencyConfig {
+    /// Units of `currency` per one unit of `base`; None for an unknown currency
+    pub fn rate(&self, currency: &str) -> Option<BigDecimal> {
+        if currency.eq_ignore_ascii_case(&self.base) {
+            return Some(BigDecimal::from(1));
+        }
+        self.rates
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(currency))
+            .map(|(_, rate)| rate.clone())
+            .filter(|rate| *rate > BigDecimal::from(0))
+    }
+
+    /// `amount` in `from` expressed in `to`; None if either has no rate
+    pub fn convert(&self, amount: &BigDecimal, from: &str, to: &str) -> Option<BigDecimal> {
+        Some(amount / self.rate(from)? * self.rate(to)?)
+    }
+
+    pub fn display_name<'a>(&'a self, currency: &'a str) -> &'a str {
+        self.display_names
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(currency))
+            .map(|(_, name)| name.as_str())
+            .unwrap_or(currency)
+    }
+}
+
+/// How much play history is kept per user; a value of 0 disables that limit.
+/// Both are off unless configured, so no history is pruned by default
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct PlayHistoryRetentionConfig {
+    #[serde(default)]
+    pub max_entries_per_user: i64,
+    #[serde(default)]
+    pub max_age_days: i64,
+}
+
+impl PlayHistoryRetentionConfig {
+    /// Days of plays that survive pruning; None while age pruning is off
+    pub fn history_window_days(&self) -> Option<i64> {
+        (self.max_age_days > 0).then_some(self.max_age_days)
+    }
+}
+
+/// Argon2id cost parameters for password hashes; raising them upgrades
+/// existing hashes the next time each user logs in
+#[derive(Deserialize, Clone, Debug)]
+pub struct PasswordHashingConfig {
+    #[serde(default = "default_hash_memory_kib")]
+    pub memory_kib: u32,
+    #[serde(default = "default_hash_iterations")]
+    pub iterations: u32,
+    #[serde(default = "default_hash_parallelism")]
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashingConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: default_hash_memory_kib(),
+            iterations: default_hash_iterations(),
+            parallelism: default_hash_parallelism(),
+        }
+    }
+}
+
+// Defaults match argon2's own defaults so existing hashes are not rehashed needlessly
+fn default_hash_memory_kib() -> u32 {
+    argon2::Params::DEFAULT_M_COST
+}
+
+fn default_hash_iterations() -> u32 {
+    argon2::Params::DEFAULT_T_COST
+}
+
+fn default_hash_parallelism() -> u32 {
+    argon2::Params::DEFAULT_P_COST
+}
+
+fn default_min_duration_seconds() -> u64 {
+    1
+}
+
+fn default_min_file_bytes() -> usize {
+    1024
+}
+
+fn default_max_file_bytes() -> u64 {
+    512 * 1024 * 1024
 }
 
 impl AppConfig {
-    /// Get the full URL for an image file
+    /// Base URL for media (images, uploads, audio): the CDN when configured, else the app host
+    pub fn media_base_url(&self) -> &str {
+        self.sunnah_audio_server_config
+            .media_base_url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+            .unwrap_or(&self.sunnah_audio_server_config.base_url)
+    }
+
+    /// Get the full URL for an image file, or the placeholder when the image is empty, unsafe or not in the image index
     pub fn get_image_url(&self, filename: &str) -> String {
+        let filename = filename.trim();
+        let filename = if !crate::core::is_safe_storage_location(filename)
+            || !self.image_index.contains(filename)
+        {
+            self.app_paths.placeholder_image.as_str()
+        } else {
+            filename
+        };
+
         format!(
             "{}{}/{}",
-            self.sunnah_audio_server_config.base_url, self.app_paths.static_images, filename
+            self.media_base_url(),
+            self.app_paths.static_images,
+            filename
         )
     }
 
@@ -72,7 +818,9 @@ impl AppConfig {
     pub fn get_upload_url(&self, filename: &str) -> String {
         format!(
             "{}{}/{}",
-            self.sunnah_audio_server_config.base_url, self.app_paths.static_uploads, filename
+            self.media_base_url(),
+            self.app_paths.static_uploads,
+            filename
         )
     }
 
@@ -80,7 +828,18 @@ impl AppConfig {
     pub fn get_audio_url(&self, filename: &str) -> String {
         format!(
             "{}{}/{}",
-            self.sunnah_audio_server_config.base_url, self.app_paths.static_audio, filename
+            self.media_base_url(),
+            self.app_paths.static_audio,
+            filename
+        )
+    }
+
+    /// API URL that downloads a file; always on `base_url`, never the CDN
+    pub fn get_download_url(&self, file_id: i32) -> String {
+        format!(
+            "{}/api/v1/files/{}/download",
+            self.sunnah_audio_server_config.base_url.trim_end_matches('/'),
+            file_id
         )
     }
 
@@ -95,6 +854,13 @@ pub struct RedisConfig {
     pub host: String,
     pub port: String,
     pub password: Option<String>,
+    /// Allow requests when Redis is down instead of rejecting them at rate limits
+    #[serde(default = "default_rate_limit_fail_open")]
+    pub rate_limit_fail_open: bool,
+}
+
+fn default_rate_limit_fail_open() -> bool {
+    true
 }
 
 impl RedisConfig {
@@ -142,13 +908,16 @@ pub struct MySqlConfig {
 }
 
 impl MySqlConfig {
+    /// Timestamps are stored and returned in UTC; the session time zone is pinned so that
+    /// `UTC_DATE()`/`NOW()` agree with `Utc::now()` on the app side. Convert to local time at the client.
     pub fn connect(&self) -> MySqlConnectOptions {
         let options = MySqlConnectOptions::new()
             .host(&self.host)
             .username(&self.username)
             .password(self.password.expose_secret())
             .port(self.port)
-            .database(&self.database_name);
+            .database(&self.database_name)
+            .timezone(Some(String::from("+00:00")));
 
         options.log_statements(tracing::log::LevelFilter::Trace)
     }
@@ -201,3 +970,126 @@ impl TryFrom<String> for Environment {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn currencies() -> CurrencyConfig {
+        CurrencyConfig {
+            base: "NGN".to_string(),
+            rates: HashMap::from([
+                ("XOF".to_string(), BigDecimal::from_str("0.4").unwrap()),
+                ("BAD".to_string(), BigDecimal::from(0)),
+            ]),
+            display_names: HashMap::from([("XOF".to_string(), "CFA".to_string())]),
+        }
+    }
+
+    #[test]
+    fn currency_convert_goes_through_the_base() {
+        let currencies = currencies();
+        let price = BigDecimal::from(5000);
+        assert_eq!(currencies.convert(&price, "NGN", "xof"), Some(BigDecimal::from(2000)));
+        assert_eq!(currencies.convert(&BigDecimal::from(2000), "XOF", "NGN"), Some(price.clone()));
+        assert_eq!(currencies.convert(&price, "NGN", "NGN"), Some(price.clone()));
+    }
+
+    #[test]
+    fn currency_convert_needs_a_positive_rate_for_both_sides() {
+        let currencies = currencies();
+        let price = BigDecimal::from(100);
+        assert_eq!(currencies.convert(&price, "NGN", "USD"), None);
+        assert_eq!(currencies.convert(&price, "BAD", "NGN"), None);
+        assert_eq!(currencies.display_name("xof"), "CFA");
+        assert_eq!(currencies.display_name("NGN"), "NGN");
+    }
+
+    #[test]
+    fn play_history_is_not_pruned_unless_configured() {
+        let retention = PlayHistoryRetentionConfig::default();
+        assert_eq!(retention.max_entries_per_user, 0);
+        assert_eq!(retention.max_age_days, 0);
+        assert_eq!(retention.history_window_days(), None);
+
+        let retention = PlayHistoryRetentionConfig {
+            max_entries_per_user: 0,
+            max_age_days: 90,
+        };
+        assert_eq!(retention.history_window_days(), Some(90));
+    }
+
+    fn scheduling(timezone: &str) -> SchedulingConfig {
+        SchedulingConfig {
+            timezone: timezone.to_string(),
+        }
+    }
+
+    fn utc(value: &str) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&chrono::Utc)
+    }
+
+    fn date(value: &str) -> chrono::NaiveDate {
+        chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn scheduling_dates_follow_the_platform_timezone() {
+        let lagos = scheduling("Africa/Lagos");
+        // 23:30 UTC is already the next day in Lagos (UTC+1)
+        assert_eq!(lagos.date_at(utc("2025-03-01T23:30:00Z")), date("2025-03-02"));
+        assert_eq!(lagos.date_at(utc("2025-03-01T22:59:59Z")), date("2025-03-01"));
+        assert_eq!(lagos.today(), lagos.date_at(chrono::Utc::now()));
+    }
+
+    #[test]
+    fn start_of_day_utc_converts_local_midnight() {
+        let lagos = scheduling("Africa/Lagos");
+        assert_eq!(
+            lagos.start_of_day_utc(date("2025-03-02")),
+            utc("2025-03-01T23:00:00Z").naive_utc()
+        );
+
+        // Follows daylight saving time
+        let new_york = scheduling("America/New_York");
+        assert_eq!(
+            new_york.start_of_day_utc(date("2025-01-15")),
+            utc("2025-01-15T05:00:00Z").naive_utc()
+        );
+        assert_eq!(
+            new_york.start_of_day_utc(date("2025-07-15")),
+            utc("2025-07-15T04:00:00Z").naive_utc()
+        );
+    }
+
+    #[test]
+    fn scheduling_config_rejects_unknown_timezones() {
+        let config: SchedulingConfig =
+            serde_json::from_value(serde_json::json!({ "timezone": "Africa/Lagos" })).unwrap();
+        assert_eq!(config.tz(), chrono_tz::Africa::Lagos);
+
+        let config: SchedulingConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(config.timezone, "Africa/Lagos");
+
+        assert!(serde_json::from_value::<SchedulingConfig>(serde_json::json!({ "timezone": "Mars/Olympus" })).is_err());
+    }
+
+    #[test]
+    fn the_more_played_scholar_trends_higher() {
+        let trending = TrendingConfig::default();
+
+        let busy = trending.score(40, 3, 1);
+        let quiet = trending.score(10, 3, 1);
+        assert!(busy > quiet);
+        assert_eq!(busy, 40.0 + 3.0 * 2.0 + 5.0);
+
+        // Weights come from config, so followers can be made to outweigh plays
+        let followers_first = TrendingConfig {
+            play_weight: 0.1,
+            follow_weight: 50.0,
+            ..TrendingConfig::default()
+        };
+        assert!(followers_first.score(10, 0, 1) > followers_first.score(40, 0, 0));
+    }
+}
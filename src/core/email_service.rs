@@ -1,7 +1,9 @@
 use crate::core::config::SmtpConfig;
-use crate::core::AppError;
+use crate::core::{html_escape, spawn_blocking_with_tracing, AppError};
+use crate::models::subscriptions::PaymentInstructions;
 use lettre::message::{header::ContentType, Mailbox};
 use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::response::Response;
 use lettre::{Message, SmtpTransport, Transport};
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
@@ -12,6 +14,71 @@ use tokio::sync::mpsc;
 pub enum EmailType {
     Otp { to_email: String, otp: String },
     PasswordResetConfirmation { to_email: String },
+    EmailChangeVerification { to_email: String, otp: String },
+    EmailChangeNotice { to_email: String, new_email: String },
+    PaymentInstructions { to_email: String, instructions: PaymentInstructions },
+    SubscriptionActivated { to_email: String, plan_name: String, end_date: String },
+    SubscriptionExpired { to_email: String, plan_name: String },
+    Welcome { to_email: String, name: String },
+}
+
+impl EmailType {
+    /// Stable name used when a failed email is stored
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EmailType::Otp { .. } => "otp",
+            EmailType::PasswordResetConfirmation { .. } => "password_reset_confirmation",
+            EmailType::EmailChangeVerification { .. } => "email_change_verification",
+            EmailType::EmailChangeNotice { .. } => "email_change_notice",
+            EmailType::PaymentInstructions { .. } => "payment_instructions",
+            EmailType::SubscriptionActivated { .. } => "subscription_activated",
+            EmailType::SubscriptionExpired { .. } => "subscription_expired",
+            EmailType::Welcome { .. } => "welcome",
+        }
+    }
+
+    pub fn recipient(&self) -> &str {
+        match self {
+            EmailType::Otp { to_email, .. }
+            | EmailType::PasswordResetConfirmation { to_email }
+            | EmailType::EmailChangeVerification { to_email, .. }
+            | EmailType::EmailChangeNotice { to_email, .. }
+            | EmailType::PaymentInstructions { to_email, .. }
+            | EmailType::SubscriptionActivated { to_email, .. }
+            | EmailType::SubscriptionExpired { to_email, .. }
+            | EmailType::Welcome { to_email, .. } => to_email,
+        }
+    }
+
+    /// Carries a one-time code that expires within minutes
+    pub fn has_one_time_code(&self) -> bool {
+        matches!(self, EmailType::Otp { .. } | EmailType::EmailChangeVerification { .. })
+    }
+
+    /// The same email with any one-time code blanked, for storing outside the
+    /// flow that issued it
+    pub fn redacted(&self) -> Self {
+        match self {
+            EmailType::Otp { to_email, .. } => EmailType::Otp {
+                to_email: to_email.clone(),
+                otp: String::new(),
+            },
+            EmailType::EmailChangeVerification { to_email, .. } => {
+                EmailType::EmailChangeVerification {
+                    to_email: to_email.clone(),
+                    otp: String::new(),
+                }
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+/// An email the background worker gave up on, passed to whoever stores failures
+#[derive(Debug)]
+pub struct FailedEmail {
+    pub email_type: EmailType,
+    pub error: String,
 }
 
 #[derive(Debug, Clone)]
@@ -27,13 +94,28 @@ pub struct EmailService {
 
 impl EmailService {
     pub fn new(smtp_config: SmtpConfig) -> Self {
+        Self::with_failure_sink(smtp_config, None)
+    }
+
+    /// Like `new`, but emails the worker fails to send are also handed to `failures`
+    pub fn with_failure_sink(
+        smtp_config: SmtpConfig,
+        failures: Option<mpsc::UnboundedSender<FailedEmail>>,
+    ) -> Self {
         let (sender, mut receiver) = mpsc::unbounded_channel::<EmailTask>();
 
         // Spawn background email processor
         tokio::spawn(async move {
             while let Some(task) = receiver.recv().await {
+                let email_type = task.email_type.clone();
                 if let Err(e) = Self::process_email_task(task).await {
                     tracing::error!("Failed to process email task: {}", e);
+                    if let Some(failures) = &failures {
+                        let error = e.cause.clone().unwrap_or_else(|| e.to_string());
+                        if failures.send(FailedEmail { email_type, error }).is_err() {
+                            tracing::warn!("Failed email could not be recorded, the recorder has stopped");
+                        }
+                    }
                 }
             }
         });
@@ -44,6 +126,13 @@ impl EmailService {
         }
     }
 
+    /// A service without a worker, so tests can inspect what gets queued
+    #[cfg(test)]
+    pub(crate) fn with_receiver(smtp_config: SmtpConfig) -> (Self, mpsc::UnboundedReceiver<EmailTask>) {
+        let (sender, receiver) = mpsc::unbounded_channel::<EmailTask>();
+        (Self { smtp_config, sender }, receiver)
+    }
+
     fn create_smtp_transport(smtp_config: &SmtpConfig) -> Result<SmtpTransport, AppError> {
         let credentials = Credentials::new(
             smtp_config.username.clone(),
@@ -73,6 +162,18 @@ impl EmailService {
         Ok(mailer)
     }
 
+    // SmtpTransport talks to the server synchronously, so the send runs on the
+    // blocking pool instead of holding up a runtime worker
+    async fn deliver(
+        smtp_config: &SmtpConfig,
+        email: Message,
+    ) -> Result<Result<Response, lettre::transport::smtp::Error>, AppError> {
+        let mailer = Self::create_smtp_transport(smtp_config)?;
+        spawn_blocking_with_tracing(move || mailer.send(&email))
+            .await
+            .map_err(|e| AppError::internal_error(format!("Email task failed: {}", e)))
+    }
+
     // Send OTP email in background - returns immediately
     pub async fn send_otp_email(&self, to_email: &str, otp: &str) -> Result<(), AppError> {
         let task = EmailTask {
@@ -111,6 +212,115 @@ impl EmailService {
         Ok(())
     }
 
+    // Send the code confirming a new email address in background - returns immediately
+    pub async fn send_email_change_verification(
+        &self,
+        to_email: &str,
+        otp: &str,
+    ) -> Result<(), AppError> {
+        self.queue(EmailType::EmailChangeVerification {
+            to_email: to_email.to_string(),
+            otp: otp.to_string(),
+        })?;
+
+        tracing::info!("Email change verification queued for background sending to: {}", to_email);
+        Ok(())
+    }
+
+    // Warn the current address about an email change attempt in background - returns immediately
+    pub async fn send_email_change_notice(
+        &self,
+        to_email: &str,
+        new_email: &str,
+    ) -> Result<(), AppError> {
+        self.queue(EmailType::EmailChangeNotice {
+            to_email: to_email.to_string(),
+            new_email: new_email.to_string(),
+        })?;
+
+        tracing::info!("Email change notice queued for background sending to: {}", to_email);
+        Ok(())
+    }
+
+    // Send payment instructions for a pending subscription in background - returns immediately
+    pub async fn send_payment_instructions(
+        &self,
+        to_email: &str,
+        instructions: &PaymentInstructions,
+    ) -> Result<(), AppError> {
+        self.queue(EmailType::PaymentInstructions {
+            to_email: to_email.to_string(),
+            instructions: instructions.clone(),
+        })?;
+
+        tracing::info!("Payment instructions queued for background sending to: {}", to_email);
+        Ok(())
+    }
+
+    // Tell a subscriber their payment was verified in background - returns immediately
+    pub async fn send_subscription_activated(
+        &self,
+        to_email: &str,
+        plan_name: &str,
+        end_date: &str,
+    ) -> Result<(), AppError> {
+        self.queue(EmailType::SubscriptionActivated {
+            to_email: to_email.to_string(),
+            plan_name: plan_name.to_string(),
+            end_date: end_date.to_string(),
+        })?;
+
+        tracing::info!("Subscription activation email queued for background sending to: {}", to_email);
+        Ok(())
+    }
+
+    // Tell a subscriber their subscription has expired in background - returns immediately
+    pub async fn send_subscription_expired(
+        &self,
+        to_email: &str,
+        plan_name: &str,
+    ) -> Result<(), AppError> {
+        self.queue(EmailType::SubscriptionExpired {
+            to_email: to_email.to_string(),
+            plan_name: plan_name.to_string(),
+        })?;
+
+        tracing::info!("Subscription expiry email queued for background sending to: {}", to_email);
+        Ok(())
+    }
+
+    // Welcome a newly registered user in background - returns immediately
+    pub async fn send_welcome_email(&self, to_email: &str, name: &str) -> Result<(), AppError> {
+        self.queue(EmailType::Welcome {
+            to_email: to_email.to_string(),
+            name: name.to_string(),
+        })?;
+
+        tracing::info!("Welcome email queued for background sending to: {}", to_email);
+        Ok(())
+    }
+
+    /// Send an email right away instead of queueing it, so the caller learns
+    /// whether it went out. Used to re-send stored failures
+    pub async fn send_now(&self, email_type: EmailType) -> Result<(), AppError> {
+        Self::process_email_task(EmailTask {
+            email_type,
+            smtp_config: self.smtp_config.clone(),
+        })
+        .await
+    }
+
+    fn queue(&self, email_type: EmailType) -> Result<(), AppError> {
+        let task = EmailTask {
+            email_type,
+            smtp_config: self.smtp_config.clone(),
+        };
+
+        self.sender
+            .send(task)
+            .map_err(|_| AppError::internal_error("Failed to queue email for sending"))
+    }
+
     // Background email processor
     async fn process_email_task(task: EmailTask) -> Result<(), AppError> {
         match task.email_type {
@@ -120,6 +330,99 @@ impl EmailService {
             EmailType::PasswordResetConfirmation { to_email } => {
                 Self::send_confirmation_email_sync(&task.smtp_config, &to_email).await
             }
+            EmailType::EmailChangeVerification { to_email, otp } => {
+                Self::send_html_email_sync(
+                    &task.smtp_config,
+                    &to_email,
+                    "Confirm Your New Email - Muryar Sunnah",
+                    Self::create_email_change_verification_body(&otp),
+                )
+                .await
+            }
+            EmailType::EmailChangeNotice { to_email, new_email } => {
+                Self::send_html_email_sync(
+                    &task.smtp_config,
+                    &to_email,
+                    "Email Change Requested - Muryar Sunnah",
+                    Self::create_email_change_notice_body(&new_email),
+                )
+                .await
+            }
+            EmailType::PaymentInstructions { to_email, instructions } => {
+                Self::send_html_email_sync(
+                    &task.smtp_config,
+                    &to_email,
+                    "Payment Instructions - Muryar Sunnah",
+                    Self::create_payment_instructions_body(&instructions),
+                )
+                .await
+            }
+            EmailType::SubscriptionActivated { to_email, plan_name, end_date } => {
+                Self::send_html_email_sync(
+                    &task.smtp_config,
+                    &to_email,
+                    "Subscription Activated - Muryar Sunnah",
+                    Self::create_subscription_activated_body(&plan_name, &end_date),
+                )
+                .await
+            }
+            EmailType::SubscriptionExpired { to_email, plan_name } => {
+                Self::send_html_email_sync(
+                    &task.smtp_config,
+                    &to_email,
+                    "Subscription Expired - Muryar Sunnah",
+                    Self::create_subscription_expired_body(&plan_name),
+                )
+                .await
+            }
+            EmailType::Welcome { to_email, name } => {
+                Self::send_html_email_sync(
+                    &task.smtp_config,
+                    &to_email,
+                    "Welcome to Muryar Sunnah",
+                    Self::create_welcome_body(&name),
+                )
+                .await
+            }
+        }
+    }
+
+    // Synchronous sending of a prepared HTML email (for background processing)
+    async fn send_html_email_sync(
+        smtp_config: &SmtpConfig,
+        to_email: &str,
+        subject: &str,
+        body: String,
+    ) -> Result<(), AppError> {
+        let from_mailbox = Mailbox::from_str(&format!(
+            "{} <{}>",
+            smtp_config.from_name, smtp_config.from_email
+        ))
+        .map_err(|e| AppError::internal_error(format!("Invalid from email: {}", e)))?;
+
+        let to_mailbox = Mailbox::from_str(to_email)
+            .map_err(|e| AppError::internal_error(format!("Invalid to email: {}", e)))?;
+
+        let email = Message::builder()
+            .from(from_mailbox)
+            .to(to_mailbox)
+            .subject(subject)
+            .header(ContentType::TEXT_HTML)
+            .body(body)
+            .map_err(|e| AppError::internal_error(format!("Failed to build email: {}", e)))?;
+
+        match Self::deliver(smtp_config, email).await? {
+            Ok(_) => {
+                tracing::info!("✅ Email '{}' sent successfully to: {}", subject, to_email);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("❌ Failed to send email '{}' to {}: {}", subject, to_email, e);
+                Err(AppError::internal_error(format!(
+                    "Failed to send email: {}",
+                    e
+                )))
+            }
         }
     }
 
@@ -149,9 +452,7 @@ impl EmailService {
             .body(body)
             .map_err(|e| AppError::internal_error(format!("Failed to build email: {}", e)))?;
 
-        let mailer = Self::create_smtp_transport(smtp_config)?;
-
-        match mailer.send(&email) {
+        match Self::deliver(smtp_config, email).await? {
             Ok(_) => {
                 tracing::info!("✅ OTP email sent successfully to: {}", to_email);
                 Ok(())
@@ -191,9 +492,7 @@ impl EmailService {
             .body(body)
             .map_err(|e| AppError::internal_error(format!("Failed to build email: {}", e)))?;
 
-        let mailer = Self::create_smtp_transport(smtp_config)?;
-
-        match mailer.send(&email) {
+        match Self::deliver(smtp_config, email).await? {
             Ok(_) => {
                 tracing::info!(
                     "✅ Password reset confirmation email sent successfully to: {}",
@@ -424,4 +723,201 @@ impl EmailService {
 </html>
 "#.to_string()
     }
+
+    fn create_email_change_verification_body(otp: &str) -> String {
+        format!(
+            r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Confirm Your New Email</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <h2 style="color: #2c5530;">🎧 Muryar Sunnah</h2>
+    <p>Assalamu Alaikum,</p>
+    <p>A request was made to use this address for a Muryar Sunnah account. Enter the code below to confirm the change:</p>
+    <p style="font-size: 32px; font-weight: bold; color: #2c5530; letter-spacing: 8px;">{}</p>
+    <p><small>This code will expire in 30 minutes. If you did not request this, you can ignore this email.</small></p>
+    <p style="font-size: 12px; color: #666;">This is an automated message from Muryar Sunnah. Please do not reply to this email.</p>
+</body>
+</html>
+"#,
+            otp
+        )
+    }
+
+    fn create_email_change_notice_body(new_email: &str) -> String {
+        format!(
+            r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Email Change Requested</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <h2 style="color: #2c5530;">🎧 Muryar Sunnah</h2>
+    <p>Assalamu Alaikum,</p>
+    <p>A request was made to change the email on your Muryar Sunnah account to <strong>{}</strong>.</p>
+    <p>Your email will only change once the new address is confirmed. If you did not make this request, please change your password and contact our support team immediately.</p>
+    <p style="font-size: 12px; color: #666;">This is an automated message from Muryar Sunnah. Please do not reply to this email.</p>
+</body>
+</html>
+"#,
+            html_escape(new_email)
+        )
+    }
+
+    fn create_payment_instructions_body(instructions: &PaymentInstructions) -> String {
+        let channels: String = instructions
+            .channels
+            .iter()
+            .map(|channel| {
+                let details: Vec<String> = [
+                    channel.bank_name.as_ref().map(|v| format!("Bank: {}", html_escape(v))),
+                    channel
+                        .account_name
+                        .as_ref()
+                        .map(|v| format!("Account name: {}", html_escape(v))),
+                    channel
+                        .account_number
+                        .as_ref()
+                        .map(|v| format!("Account number: {}", html_escape(v))),
+                    channel.instructions.as_deref().map(html_escape),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                format!(
+                    "<li><strong>{}</strong><br>{}</li>",
+                    html_escape(&channel.name),
+                    details.join("<br>")
+                )
+            })
+            .collect();
+
+        format!(
+            r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Payment Instructions</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <h2 style="color: #2c5530;">🎧 Muryar Sunnah</h2>
+    <p>Assalamu Alaikum,</p>
+    <p>To activate your <strong>{}</strong> subscription, please pay <strong>{} {}</strong>{}.</p>
+    <ul>{}</ul>
+    <p>Your subscription will be activated once an administrator verifies the payment.</p>
+    <p style="font-size: 12px; color: #666;">This is an automated message from Muryar Sunnah. Please do not reply to this email.</p>
+</body>
+</html>
+"#,
+            html_escape(&instructions.plan_name),
+            instructions.amount,
+            html_escape(&instructions.currency),
+            instructions
+                .transaction_reference
+                .as_ref()
+                .map(|reference| {
+                    format!(" using the reference <strong>{}</strong>", html_escape(reference))
+                })
+                .unwrap_or_default(),
+            channels
+        )
+    }
+
+    fn create_subscription_activated_body(plan_name: &str, end_date: &str) -> String {
+        format!(
+            r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Subscription Activated</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <h2 style="color: #2c5530;">🎧 Muryar Sunnah</h2>
+    <p>Assalamu Alaikum,</p>
+    <p>Your payment has been verified and your <strong>{}</strong> subscription is now active until <strong>{}</strong>.</p>
+    <p>Jazakallahu khairan for supporting Muryar Sunnah.</p>
+    <p style="font-size: 12px; color: #666;">This is an automated message from Muryar Sunnah. Please do not reply to this email.</p>
+</body>
+</html>
+"#,
+            html_escape(plan_name),
+            html_escape(end_date)
+        )
+    }
+
+    fn create_subscription_expired_body(plan_name: &str) -> String {
+        format!(
+            r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Subscription Expired</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <h2 style="color: #2c5530;">🎧 Muryar Sunnah</h2>
+    <p>Assalamu Alaikum,</p>
+    <p>Your <strong>{}</strong> subscription has expired. You can renew it at any time from the app to continue enjoying subscriber benefits.</p>
+    <p style="font-size: 12px; color: #666;">This is an automated message from Muryar Sunnah. Please do not reply to this email.</p>
+</body>
+</html>
+"#,
+            html_escape(plan_name)
+        )
+    }
+
+    fn create_welcome_body(name: &str) -> String {
+        format!(
+            r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Welcome to Muryar Sunnah</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <h2 style="color: #2c5530;">🎧 Muryar Sunnah</h2>
+    <p>Assalamu Alaikum {},</p>
+    <p>Welcome to Muryar Sunnah, and jazakallahu khairan for joining us.</p>
+    <h3 style="color: #2c5530;">Getting started</h3>
+    <ul>
+        <li>Follow your favourite scholars to see their new lectures first.</li>
+        <li>Like files and build playlists to keep your lectures organised.</li>
+        <li>Your listening position is saved, so you can continue any book where you stopped.</li>
+        <li>Download lectures to listen offline.</li>
+    </ul>
+    <p style="font-size: 12px; color: #666;">This is an automated message from Muryar Sunnah. Please do not reply to this email.</p>
+</body>
+</html>
+"#,
+            html_escape(name)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activation_email_escapes_plan_and_date() {
+        let body = EmailService::create_subscription_activated_body("<b>Gold</b>", "2026 & on");
+        assert!(body.contains("&lt;b&gt;Gold&lt;/b&gt;"));
+        assert!(body.contains("2026 &amp; on"));
+        assert!(!body.contains("<b>Gold</b>"));
+    }
+
+    #[test]
+    fn expiry_email_escapes_the_plan_name() {
+        let body = EmailService::create_subscription_expired_body("Gold <script>alert(1)</script>");
+        assert!(body.contains("Gold &lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!body.contains("<script>"));
+    }
 }
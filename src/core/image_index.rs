@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Paths of the files under `images_dir`, relative to it, so image URLs are
+/// resolved without touching the disk on the request path.
+/// Clones share the same set.
+#[derive(Clone, Default)]
+pub struct ImageIndex {
+    names: Arc<RwLock<HashSet<String>>>,
+}
+
+impl ImageIndex {
+    pub fn contains(&self, name: &str) -> bool {
+        self.names
+            .read()
+            .map(|names| names.contains(name))
+            .unwrap_or(false)
+    }
+
+    /// Record an image written after the last scan
+    pub fn insert(&self, name: &str) {
+        if let Ok(mut names) = self.names.write() {
+            names.insert(name.to_string());
+        }
+    }
+
+    pub fn replace(&self, scanned: HashSet<String>) {
+        if let Ok(mut names) = self.names.write() {
+            *names = scanned;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.read().map(|names| names.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for ImageIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageIndex").field("len", &self.len()).finish()
+    }
+}
+
+/// Walk `dir` and collect every file path relative to it with `/` separators,
+/// matching how image locations are stored (`2025/11/book_x.jpg` when sharded).
+/// Blocking; run it on the blocking pool.
+pub fn scan_images(dir: &Path) -> std::io::Result<HashSet<String>> {
+    let mut names = HashSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let entries = match std::fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+
+        for entry in entries {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() {
+                if let Some(relative) = path.strip_prefix(dir).ok().and_then(|p| p.to_str()) {
+                    names.insert(relative.replace('\\', "/"));
+                }
+            }
+        }
+    }
+
+    Ok(names)
+}
@@ -25,6 +25,12 @@ pub struct JwtClaims {
     pub email: String,
     pub role: String,
     pub exp: usize, // expiration time
+    /// Admin acting as `sub`; set only on impersonation tokens, which are read-only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<i32>,
+    /// Impersonation session the token belongs to; ending the session revokes it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonation_id: Option<String>,
 }
 
 #[derive(Debug)]
@@ -103,6 +109,11 @@ impl FromRequest for JwtMiddleware {
     }
 }
 
+/// Redis key that keeps an impersonation session alive; it holds the admin's id
+pub fn impersonation_session_key(session_id: &str) -> String {
+    format!("impersonation:{}", session_id)
+}
+
 pub fn generate_jwt_token(claims: &JwtClaims, config: &AppConfig) -> Result<String, AppError> {
     let header = Header::default();
     let encoding_key = EncodingKey::from_secret(config.get_jwt_secret().as_ref());
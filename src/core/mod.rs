@@ -5,6 +5,9 @@ mod telementry;
 pub mod redis_helper;
 pub mod email_service;
 pub mod utils;
+pub mod request_timeout;
+pub mod access_policy;
+pub mod image_index;
 
 pub use self::config::AppConfig;
 pub use responses::*;
@@ -12,4 +15,7 @@ pub use telementry::*;
 pub use redis_helper::*;
 pub use email_service::EmailService;
 pub use utils::*;
+pub use request_timeout::RequestTimeout;
+pub use access_policy::AccessPolicy;
+pub use image_index::ImageIndex;
 //pub use jwt_auth::;
@@ -1,10 +1,86 @@
 use actix_web::web;
 use redis::AsyncCommands;
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::OnceCell;
 
+use super::AppError;
+
+/// Thin JSON wrapper over Redis.
+///
+/// The raw `get`/`set` methods surface every failure. Callers should prefer the
+/// policy methods below, which decide what a Redis outage means for them:
+/// - `get_or_load`: cache, an outage is a miss and the value comes from the loader;
+///   concurrent misses on one key share a single load
+/// - `check_rate_limit`: allows (fails open) or rejects per `redis.rate_limit_fail_open`
+/// - `set_secure`/`get_secure`: OTPs and tokens, an outage fails the request with a 503
 pub struct RedisHelper {
     client: web::Data<redis::Client>,
+    rate_limit_fail_open: bool,
+    /// Loads currently running in `get_or_load`, keyed by cache key. Holds the
+    /// serialized value so every waiter can deserialize its own copy
+    in_flight: InFlight,
+}
+
+type InFlight = Mutex<HashMap<String, Arc<OnceCell<String>>>>;
+
+/// One caller's membership in a `get_or_load` flight. Dropping it, on success,
+/// error or cancellation alike, removes the map entry once the value is loaded
+/// or no other caller is still waiting on it, so failed loads never leave one behind
+struct Flight<'a> {
+    in_flight: &'a InFlight,
+    key: String,
+    cell: Arc<OnceCell<String>>,
+}
+
+impl<'a> Flight<'a> {
+    fn join(in_flight: &'a InFlight, key: &str) -> Self {
+        let mut map = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        let cell = map.entry(key.to_string()).or_default().clone();
+        Flight {
+            in_flight,
+            key: key.to_string(),
+            cell,
+        }
+    }
+}
+
+impl Drop for Flight<'_> {
+    fn drop(&mut self) {
+        let mut map = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        // Joins clone under this lock, so the count is exact: the map's copy and ours
+        let last_member = Arc::strong_count(&self.cell) == 2;
+        if map
+            .get(&self.key)
+            .is_some_and(|current| Arc::ptr_eq(current, &self.cell))
+            && (self.cell.initialized() || last_member)
+        {
+            map.remove(&self.key);
+        }
+    }
+}
+
+/// Outcome of `check_rate_limit`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimit {
+    Allowed,
+    /// Over the limit until the window key expires
+    Limited { retry_after: Duration },
+}
+
+impl RateLimit {
+    /// `Err(too_many_requests)` with `message` when limited
+    pub fn check(self, message: &str) -> Result<(), AppError> {
+        match self {
+            RateLimit::Allowed => Ok(()),
+            RateLimit::Limited { retry_after } => {
+                Err(AppError::too_many_requests(retry_after).with_message(message))
+            }
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -17,9 +93,23 @@ pub enum RedisError {
     KeyNotFound,
 }
 
+impl RedisError {
+    /// Redis could not be reached, as opposed to a missing key or a bad payload
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, RedisError::ConnectionError(_))
+    }
+}
+
+const REDIS_UNAVAILABLE_MESSAGE: &str =
+    "This service is temporarily unavailable. Please try again in a few minutes";
+
 impl RedisHelper {
-    pub fn new(client: web::Data<redis::Client>) -> Self {
-        Self { client }
+    pub fn new(client: web::Data<redis::Client>, rate_limit_fail_open: bool) -> Self {
+        Self {
+            client,
+            rate_limit_fail_open,
+            in_flight: Mutex::new(HashMap::new()),
+        }
     }
 
     async fn get_conn(&self) -> Result<redis::aio::Connection, RedisError> {
@@ -65,6 +155,139 @@ impl RedisHelper {
         Ok(exists)
     }
 
+    /// Read `key` from the cache, or run `load` and cache its result for `ttl`.
+    /// Redis failures never fail the request; they only cost a database read.
+    ///
+    /// Concurrent misses on the same key are coalesced: one caller runs `load`
+    /// and the others wait for its value instead of repeating the query. A failed
+    /// load is not shared, the next waiter runs its own
+    pub async fn get_or_load<T, F, Fut>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        load: F,
+    ) -> Result<T, AppError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        match self.get::<T>(key).await {
+            Ok(value) => return Ok(value),
+            Err(RedisError::KeyNotFound) => {}
+            Err(e) => tracing::warn!("Cache read for {} failed, loading from source: {}", key, e),
+        }
+
+        let flight = Flight::join(&self.in_flight, key);
+        let loaded = flight
+            .cell
+            .get_or_try_init(|| async {
+                let value = load().await?;
+                if let Err(e) = self.set(key, &value, Some(ttl)).await {
+                    tracing::warn!("Cache write for {} failed: {}", key, e);
+                }
+                serde_json::to_string(&value).map_err(AppError::internal_error)
+            })
+            .await
+            .cloned();
+        // The value is in the cache by now, so later misses start a fresh flight
+        // only once it expires
+        drop(flight);
+
+        serde_json::from_str(&loaded?).map_err(AppError::internal_error)
+    }
+
+    /// Count a hit against `key` and report whether it is within `limit` per `window`.
+    /// When it isn't, `retry_after` is the time left on the window's key
+    pub async fn check_rate_limit(
+        &self,
+        key: &str,
+        limit: u64,
+        window: Duration,
+    ) -> Result<RateLimit, AppError> {
+        match self.increment_window(key, limit, window).await {
+            Ok(None) => Ok(RateLimit::Allowed),
+            Ok(Some(retry_after)) => Ok(RateLimit::Limited { retry_after }),
+            Err(e) if self.rate_limit_fail_open => {
+                tracing::warn!("Rate limit check for {} skipped, Redis error: {}", key, e);
+                Ok(RateLimit::Allowed)
+            }
+            Err(e) => {
+                tracing::error!("Rate limit check for {} failed: {}", key, e);
+                Err(AppError::service_unavailable(REDIS_UNAVAILABLE_MESSAGE))
+            }
+        }
+    }
+
+    /// None while within `limit`, otherwise the key's remaining TTL.
+    /// The window key is created with its expiry and incremented in one
+    /// MULTI/EXEC, so a dropped connection can't leave a counter that never expires
+    async fn increment_window(
+        &self,
+        key: &str,
+        limit: u64,
+        window: Duration,
+    ) -> Result<Option<Duration>, RedisError> {
+        let mut conn = self.get_conn().await?;
+        let (count, ttl): (u64, i64) = redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(key)
+            .arg(0)
+            .arg("EX")
+            .arg(window.as_secs())
+            .arg("NX")
+            .ignore()
+            .incr(key, 1)
+            .ttl(key)
+            .query_async(&mut conn)
+            .await?;
+        if count <= limit {
+            return Ok(None);
+        }
+
+        // -1 (no expiry) only for a counter left by an older, non-atomic
+        // version; give it a full window rather than telling the client 0
+        if ttl > 0 {
+            Ok(Some(Duration::from_secs(ttl as u64)))
+        } else {
+            if ttl == -1 {
+                conn.expire::<_, ()>(key, window.as_secs() as usize).await?;
+            }
+            Ok(Some(window))
+        }
+    }
+
+    /// Store a security-sensitive value; an outage fails the request
+    pub async fn set_secure<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry: Option<Duration>,
+    ) -> Result<(), AppError> {
+        self.set(key, value, expiry).await.map_err(|e| {
+            tracing::error!("Failed to store {}: {}", key, e);
+            AppError::service_unavailable(REDIS_UNAVAILABLE_MESSAGE)
+        })
+    }
+
+    /// Read a security-sensitive value. Missing or unreadable values are `None`;
+    /// an outage fails the request rather than looking like an expired code
+    pub async fn get_secure<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, AppError> {
+        match self.get(key).await {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.is_unavailable() => {
+                tracing::error!("Failed to read {}: {}", key, e);
+                Err(AppError::service_unavailable(REDIS_UNAVAILABLE_MESSAGE))
+            }
+            Err(RedisError::KeyNotFound) => Ok(None),
+            Err(e) => {
+                tracing::warn!("Discarding unreadable value at {}: {}", key, e);
+                Ok(None)
+            }
+        }
+    }
+
     pub async fn rpop<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, RedisError> {
         let mut conn = self.get_conn().await?;
         let value: Option<String> = conn.rpop(key, None).await?;
@@ -121,3 +344,96 @@ impl RedisHelper {
     //     Ok(result)
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::AppErrorType;
+
+    fn in_flight() -> InFlight {
+        Mutex::new(HashMap::new())
+    }
+
+    fn is_tracked(in_flight: &InFlight, key: &str) -> bool {
+        in_flight.lock().unwrap().contains_key(key)
+    }
+
+    #[test]
+    fn a_failed_or_cancelled_leader_leaves_no_entry() {
+        let in_flight = in_flight();
+        let leader = Flight::join(&in_flight, "scholars:1");
+        assert!(is_tracked(&in_flight, "scholars:1"));
+
+        // Dropped without a value, as after an error or a cancelled request
+        drop(leader);
+        assert!(!is_tracked(&in_flight, "scholars:1"));
+    }
+
+    #[test]
+    fn waiters_keep_the_flight_until_the_last_one_leaves() {
+        let in_flight = in_flight();
+        let leader = Flight::join(&in_flight, "books:2");
+        let waiter = Flight::join(&in_flight, "books:2");
+        assert!(Arc::ptr_eq(&leader.cell, &waiter.cell));
+
+        drop(leader);
+        assert!(is_tracked(&in_flight, "books:2"));
+        drop(waiter);
+        assert!(!is_tracked(&in_flight, "books:2"));
+    }
+
+    #[test]
+    fn a_loaded_flight_is_removed_by_its_first_leaver() {
+        let in_flight = in_flight();
+        let leader = Flight::join(&in_flight, "files:3");
+        let waiter = Flight::join(&in_flight, "files:3");
+        leader.cell.set("{}".to_string()).unwrap();
+
+        drop(leader);
+        assert!(!is_tracked(&in_flight, "files:3"));
+        drop(waiter);
+        assert!(!is_tracked(&in_flight, "files:3"));
+    }
+
+    /// Nothing listens on port 1, so every command fails to connect
+    fn unreachable_redis(rate_limit_fail_open: bool) -> RedisHelper {
+        let client = redis::Client::open("redis://127.0.0.1:1/").unwrap();
+        RedisHelper::new(web::Data::new(client), rate_limit_fail_open)
+    }
+
+    #[actix_web::test]
+    async fn cache_reads_fall_back_to_the_database_during_an_outage() {
+        let redis = unreachable_redis(true);
+
+        let value: Vec<i32> = redis
+            .get_or_load("scholars:1", Duration::from_secs(60), || async { Ok(vec![1, 2, 3]) })
+            .await
+            .unwrap();
+
+        assert_eq!(value, vec![1, 2, 3]);
+        assert!(!is_tracked(&redis.in_flight, "scholars:1"));
+    }
+
+    #[actix_web::test]
+    async fn rate_limits_follow_the_fail_open_setting_during_an_outage() {
+        let window = Duration::from_secs(60);
+
+        let open = unreachable_redis(true);
+        assert_eq!(open.check_rate_limit("rl:login:1", 5, window).await.unwrap(), RateLimit::Allowed);
+
+        let closed = unreachable_redis(false);
+        let error = closed.check_rate_limit("rl:login:1", 5, window).await.unwrap_err();
+        assert_eq!(error.error_type, AppErrorType::ServiceUnavailable);
+    }
+
+    #[actix_web::test]
+    async fn otp_storage_fails_closed_during_an_outage() {
+        let redis = unreachable_redis(true);
+
+        let stored = redis.set_secure("otp:user@example.com", &"123456", Some(Duration::from_secs(600))).await;
+        assert_eq!(stored.unwrap_err().error_type, AppErrorType::ServiceUnavailable);
+
+        let read = redis.get_secure::<String>("otp:user@example.com").await;
+        assert_eq!(read.unwrap_err().error_type, AppErrorType::ServiceUnavailable);
+    }
+}
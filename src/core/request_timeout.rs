@@ -0,0 +1,86 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::time::Duration;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, ResponseError};
+use futures_util::future::LocalBoxFuture;
+
+use crate::core::{AppError, AppErrorType};
+
+/// Middleware that aborts a handler running longer than `duration` and answers 504.
+/// Routes that stream bodies or run long jobs are registered outside it (see `routes::mod`).
+pub struct RequestTimeout {
+    duration: Duration,
+}
+
+impl RequestTimeout {
+    pub fn new(duration: Duration) -> Self {
+        Self { duration }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTimeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestTimeoutMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTimeoutMiddleware {
+            service: Rc::new(service),
+            duration: self.duration,
+        }))
+    }
+}
+
+pub struct RequestTimeoutMiddleware<S> {
+    service: Rc<S>,
+    duration: Duration,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let duration = self.duration;
+
+        Box::pin(async move {
+            let http_req = req.request().clone();
+            match tokio::time::timeout(duration, service.call(req)).await {
+                Ok(res) => res.map(|res| res.map_into_left_body()),
+                Err(_) => {
+                    tracing::error!(
+                        "Request {} {} timed out after {:?}",
+                        http_req.method(),
+                        http_req.path(),
+                        duration
+                    );
+
+                    let error = AppError {
+                        message: Some("The request took too long to complete".to_string()),
+                        cause: Some(format!("handler exceeded {:?}", duration)),
+                        error_type: AppErrorType::TimeoutError,
+                    };
+
+                    Ok(ServiceResponse::new(http_req, error.error_response()).map_into_right_body())
+                }
+            }
+        })
+    }
+}
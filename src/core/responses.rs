@@ -1,9 +1,12 @@
+use actix_web::error::{InternalError, JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::http::header::RETRY_AFTER;
 use actix_web::http::StatusCode;
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
 use anyhow::Error;
 use redis::RedisError;
 use serde::Serialize;
 use std::fmt::{Display, Formatter};
+use std::time::Duration;
 
 #[derive(Debug, PartialEq)]
 pub enum AppErrorType {
@@ -23,6 +26,12 @@ pub enum AppErrorType {
     ForbiddenError,
     HashingFailed,
     ConflictError,
+    TimeoutError,
+    /// `retry_after_seconds` becomes the `Retry-After` header and body field
+    RateLimitError { retry_after_seconds: u64 },
+    ServiceUnavailable,
+    /// Signed in, but the feature needs an active subscription
+    SubscriptionRequired,
 }
 
 #[derive(Debug, PartialEq)]
@@ -35,9 +44,104 @@ pub struct AppError {
 #[derive(Serialize)]
 pub struct AppErrorResponse {
     pub success: bool,
+    /// Stable machine-readable code from `error_codes`; clients branch on this, not the message
+    pub code: String,
     pub message: String,
 }
 
+/// 409 body for a stale `version` on update; carries the record as it is now
+/// so the client can merge or re-apply its edit
+#[derive(Serialize)]
+pub struct VersionConflictResponse<T> {
+    pub success: bool,
+    pub code: String,
+    pub message: String,
+    pub current: T,
+}
+
+/// 429 body: the usual error envelope plus `retry_after_seconds`, which matches
+/// the `Retry-After` header
+#[derive(Serialize)]
+pub struct RateLimitedResponse {
+    #[serde(flatten)]
+    pub error: AppErrorResponse,
+    pub retry_after_seconds: u64,
+}
+
+/// Machine-readable error codes returned in `AppErrorResponse.code`.
+///
+/// Every `AppErrorType` maps to one of the generic codes below. Handlers that
+/// answer with an `AppErrorResponse` directly use a more specific code where a
+/// client is expected to react differently (e.g. `EMAIL_TAKEN` vs `CONFLICT`).
+/// Codes are part of the API contract: add new ones, never rename existing ones.
+pub mod error_codes {
+    // Generic codes, one per `AppErrorType`
+    pub const NOT_FOUND: &str = "NOT_FOUND";
+    pub const DATABASE_ERROR: &str = "DATABASE_ERROR";
+    pub const UNAUTHORIZED: &str = "UNAUTHORIZED";
+    pub const INVALID_JSON: &str = "INVALID_JSON";
+    pub const INVALID_QUERY: &str = "INVALID_QUERY";
+    pub const INVALID_PATH: &str = "INVALID_PATH";
+    pub const BAD_REQUEST: &str = "BAD_REQUEST";
+    pub const VALIDATION_FAILED: &str = "VALIDATION_FAILED";
+    pub const UPSTREAM_ERROR: &str = "UPSTREAM_ERROR";
+    pub const NETWORK_ERROR: &str = "NETWORK_ERROR";
+    pub const CACHE_ERROR: &str = "CACHE_ERROR";
+    pub const INTERNAL_ERROR: &str = "INTERNAL_ERROR";
+    pub const SERIALIZATION_ERROR: &str = "SERIALIZATION_ERROR";
+    pub const FORBIDDEN: &str = "FORBIDDEN";
+    pub const HASHING_FAILED: &str = "HASHING_FAILED";
+    pub const CONFLICT: &str = "CONFLICT";
+    pub const TIMEOUT: &str = "TIMEOUT";
+    pub const RATE_LIMITED: &str = "RATE_LIMITED";
+    pub const SERVICE_UNAVAILABLE: &str = "SERVICE_UNAVAILABLE";
+    pub const SUBSCRIPTION_REQUIRED: &str = "SUBSCRIPTION_REQUIRED";
+
+    // Specific codes
+    pub const EMAIL_TAKEN: &str = "EMAIL_TAKEN";
+    pub const INVALID_EMAIL: &str = "INVALID_EMAIL";
+    pub const WEAK_PASSWORD: &str = "WEAK_PASSWORD";
+    pub const INVALID_CREDENTIALS: &str = "INVALID_CREDENTIALS";
+    pub const INCORRECT_PASSWORD: &str = "INCORRECT_PASSWORD";
+    pub const INVALID_REFRESH_TOKEN: &str = "INVALID_REFRESH_TOKEN";
+    pub const EMAIL_UNCHANGED: &str = "EMAIL_UNCHANGED";
+    pub const INVALID_CODE: &str = "INVALID_CODE";
+    pub const CODE_EXPIRED: &str = "CODE_EXPIRED";
+    pub const ADMIN_REQUIRED: &str = "ADMIN_REQUIRED";
+    pub const SUBSCRIPTION_PENDING: &str = "SUBSCRIPTION_PENDING";
+    pub const INVALID_STATUS: &str = "INVALID_STATUS";
+    pub const PATH_MISMATCH: &str = "PATH_MISMATCH";
+    pub const VERSION_CONFLICT: &str = "VERSION_CONFLICT";
+}
+
+impl AppErrorType {
+    /// Generic machine-readable code for this error type
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppErrorType::NotFoundError => error_codes::NOT_FOUND,
+            AppErrorType::DbError => error_codes::DATABASE_ERROR,
+            AppErrorType::AuthError => error_codes::UNAUTHORIZED,
+            AppErrorType::JsonDeserializationError
+            | AppErrorType::JsonSerializationError
+            | AppErrorType::JsonParseError => error_codes::INVALID_JSON,
+            AppErrorType::BadRequest => error_codes::BAD_REQUEST,
+            AppErrorType::PayloadValidationError => error_codes::VALIDATION_FAILED,
+            AppErrorType::ApiError { .. } => error_codes::UPSTREAM_ERROR,
+            AppErrorType::NetworkError => error_codes::NETWORK_ERROR,
+            AppErrorType::CacheError => error_codes::CACHE_ERROR,
+            AppErrorType::InternalServerError => error_codes::INTERNAL_ERROR,
+            AppErrorType::SerializationError => error_codes::SERIALIZATION_ERROR,
+            AppErrorType::ForbiddenError => error_codes::FORBIDDEN,
+            AppErrorType::HashingFailed => error_codes::HASHING_FAILED,
+            AppErrorType::ConflictError => error_codes::CONFLICT,
+            AppErrorType::TimeoutError => error_codes::TIMEOUT,
+            AppErrorType::RateLimitError { .. } => error_codes::RATE_LIMITED,
+            AppErrorType::ServiceUnavailable => error_codes::SERVICE_UNAVAILABLE,
+            AppErrorType::SubscriptionRequired => error_codes::SUBSCRIPTION_REQUIRED,
+        }
+    }
+}
+
 impl AppError {
     pub fn message(&self) -> String {
         match &*self {
@@ -63,6 +167,14 @@ impl AppError {
         }
     }
 
+    pub fn not_found(error: impl ToString) -> AppError {
+        AppError {
+            cause: None,
+            error_type: AppErrorType::NotFoundError,
+            message: Some(error.to_string()),
+        }
+    }
+
     pub fn forbidden_error(error: impl ToString) -> AppError {
         AppError {
             cause: Some(error.to_string()),
@@ -102,6 +214,65 @@ impl AppError {
             message: Some(error.to_string()),
         }
     }
+
+    /// 429 telling the client to wait `retry_after` (rounded up to whole seconds)
+    pub fn too_many_requests(retry_after: Duration) -> AppError {
+        let retry_after_seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        AppError {
+            cause: None,
+            error_type: AppErrorType::RateLimitError {
+                retry_after_seconds: retry_after_seconds.max(1),
+            },
+            message: Some("Too many requests. Please try again later".to_string()),
+        }
+    }
+
+    /// Replace the client-facing message, keeping the type and cause
+    pub fn with_message(mut self, message: impl ToString) -> AppError {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    pub fn service_unavailable(error: impl ToString) -> AppError {
+        AppError {
+            cause: Some(error.to_string()),
+            error_type: AppErrorType::ServiceUnavailable,
+            message: Some(error.to_string()),
+        }
+    }
+
+    pub fn subscription_required(message: impl ToString) -> AppError {
+        AppError {
+            cause: None,
+            error_type: AppErrorType::SubscriptionRequired,
+            message: Some(message.to_string()),
+        }
+    }
+
+    /// Like `db_error`, but reports a unique index violation as a conflict with `message`
+    pub fn db_error_or_conflict(error: sqlx::Error, message: impl ToString) -> AppError {
+        match &error {
+            sqlx::Error::Database(db_error) if db_error.is_unique_violation() => AppError {
+                cause: Some(error.to_string()),
+                error_type: AppErrorType::ConflictError,
+                message: Some(message.to_string()),
+            },
+            _ => AppError::db_error_or_retry(error),
+        }
+    }
+
+    /// Like `db_error`, but reports a deadlock (SQLSTATE 40001) as a conflict the
+    /// client can retry; MySQL has already rolled the transaction back
+    pub fn db_error_or_retry(error: sqlx::Error) -> AppError {
+        match &error {
+            sqlx::Error::Database(db_error) if db_error.code().as_deref() == Some("40001") => AppError {
+                cause: Some(error.to_string()),
+                error_type: AppErrorType::ConflictError,
+                message: Some("The request conflicted with a concurrent change; please retry".to_string()),
+            },
+            _ => AppError::db_error(error),
+        }
+    }
 }
 
 impl From<anyhow::Error> for AppError {
@@ -168,17 +339,62 @@ impl ResponseError for AppError {
             AppErrorType::ForbiddenError => StatusCode::FORBIDDEN,
             AppErrorType::HashingFailed => StatusCode::BAD_GATEWAY,
             AppErrorType::ConflictError => StatusCode::CONFLICT,
+            AppErrorType::TimeoutError => StatusCode::GATEWAY_TIMEOUT,
+            AppErrorType::RateLimitError { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppErrorType::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            AppErrorType::SubscriptionRequired => StatusCode::PAYMENT_REQUIRED,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
+        if let AppErrorType::RateLimitError { retry_after_seconds } = self.error_type {
+            return HttpResponse::build(self.status_code())
+                .insert_header((RETRY_AFTER, retry_after_seconds.to_string()))
+                .json(RateLimitedResponse {
+                    error: AppErrorResponse {
+                        success: false,
+                        code: self.error_type.code().to_string(),
+                        message: self.message(),
+                    },
+                    retry_after_seconds,
+                });
+        }
+
         HttpResponse::build(self.status_code()).json(AppErrorResponse {
             success: false,
+            code: self.error_type.code().to_string(),
             message: self.message(),
         })
     }
 }
 
+/// Extractor error handlers, installed through `JsonConfig`/`QueryConfig`/`PathConfig`
+/// so a malformed body, query string or path segment gets the `AppErrorResponse`
+/// envelope instead of Actix's plain-text body. The status code is Actix's own
+pub fn json_error_handler(error: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    extractor_error(error, error_codes::INVALID_JSON, "Invalid JSON body")
+}
+
+pub fn query_error_handler(error: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    extractor_error(error, error_codes::INVALID_QUERY, "Invalid query parameters")
+}
+
+pub fn path_error_handler(error: PathError, _req: &HttpRequest) -> actix_web::Error {
+    extractor_error(error, error_codes::INVALID_PATH, "Invalid path parameter")
+}
+
+fn extractor_error<E>(error: E, code: &str, context: &str) -> actix_web::Error
+where
+    E: ResponseError + 'static,
+{
+    let response = HttpResponse::build(error.status_code()).json(AppErrorResponse {
+        success: false,
+        code: code.to_string(),
+        message: format!("{}: {}", context, error),
+    });
+    InternalError::from_response(error, response).into()
+}
+
 #[derive(Serialize)]
 pub struct AppSuccessResponse<T> {
     pub success: bool,
@@ -187,3 +403,101 @@ pub struct AppSuccessResponse<T> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pagination: Option<crate::models::pagination::PaginationMeta>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn error_body(error: AppError) -> (StatusCode, serde_json::Value) {
+        let response = error.error_response();
+        let status = response.status();
+        let bytes = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn subscription_required_carries_its_code_and_message() {
+        let (status, body) =
+            error_body(AppError::subscription_required("An active subscription is required")).await;
+
+        assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["code"], error_codes::SUBSCRIPTION_REQUIRED);
+        assert_eq!(body["message"], "An active subscription is required");
+    }
+
+    #[actix_web::test]
+    async fn rate_limited_body_is_the_error_envelope_with_the_wait() {
+        let error = AppError::too_many_requests(Duration::from_secs(42)).with_message("Slow down");
+        let response = error.error_response();
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "42");
+
+        let (status, body) = error_body(error).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "success": false,
+                "code": error_codes::RATE_LIMITED,
+                "message": "Slow down",
+                "retry_after_seconds": 42
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn generic_errors_map_to_their_type_code() {
+        let (status, body) = error_body(AppError::not_found("Book not found")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], error_codes::NOT_FOUND);
+        assert_eq!(body["message"], "Book not found");
+
+        let (status, body) = error_body(AppError::forbidden_error("Access denied")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], error_codes::FORBIDDEN);
+    }
+
+    #[derive(serde::Deserialize)]
+    struct LoginBody {
+        #[allow(dead_code)]
+        email: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct PageQuery {
+        #[allow(dead_code)]
+        page: i32,
+    }
+
+    #[actix_web::test]
+    async fn malformed_input_gets_the_error_envelope() {
+        use actix_web::{test as actix_test, web, App};
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .route("/login", web::post().to(|_: web::Json<LoginBody>| async { HttpResponse::Ok().finish() }))
+                .route("/files", web::get().to(|_: web::Query<PageQuery>| async { HttpResponse::Ok().finish() })),
+        )
+        .await;
+
+        let request = actix_test::TestRequest::post()
+            .uri("/login")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{\"email\": ")
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = actix_test::read_body_json(response).await;
+        assert_eq!(body["success"], false);
+        assert_eq!(body["code"], error_codes::INVALID_JSON);
+        assert!(body["message"].as_str().unwrap().starts_with("Invalid JSON body: "));
+
+        let request = actix_test::TestRequest::get().uri("/files?page=first").to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = actix_test::read_body_json(response).await;
+        assert_eq!(body["code"], error_codes::INVALID_QUERY);
+    }
+}
@@ -4,25 +4,48 @@ use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
 use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
-use tracing_subscriber::{EnvFilter, Registry};
+use tracing_subscriber::{reload, EnvFilter, Registry};
 
+/// Swaps the subscriber's filter while the server runs (see `PUT /admin/log-level`)
+#[derive(Clone)]
+pub struct LogFilterHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogFilterHandle {
+    /// The active filter in `RUST_LOG` directive form
+    pub fn current(&self) -> Option<String> {
+        self.handle.with_current(|filter| filter.to_string()).ok()
+    }
+
+    /// Replace the active filter with `directives`, e.g. `info,sunnah_audio::db=debug`
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.handle.reload(filter).map_err(|e| e.to_string())
+    }
+}
+
+/// `RUST_LOG` wins over `env_filter` (the configured default) when it is set
 pub fn get_subscriber<Sink>(
     name: String,
     env_filter: String,
     sink: Sink,
-) -> impl Subscriber + Send + Sync
+) -> (impl Subscriber + Send + Sync, LogFilterHandle)
 where
     Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
 {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
+    let (filter_layer, handle) = reload::Layer::new(env_filter);
 
     let formatting_layer = BunyanFormattingLayer::new(name, sink);
 
-    Registry::default()
-        .with(env_filter)
+    let subscriber = Registry::default()
+        .with(filter_layer)
         .with(JsonStorageLayer)
-        .with(formatting_layer)
+        .with(formatting_layer);
+
+    (subscriber, LogFilterHandle { handle })
 }
 
 pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) {
@@ -1,5 +1,5 @@
 use crate::core::AppError;
-use crate::models::access::{ScholarAccess, UserAccess, UserPermissions};
+use crate::models::access::{AccessTarget, ContentAccess, ScholarAccess, UserAccess, UserPermissions};
 use sqlx::MySqlPool;
 
 
@@ -38,9 +38,43 @@ pub async fn fetch_user_permissions(
         })
         .collect();
 
+    let accessible_books = sqlx::query!(
+        r#"
+        SELECT b.id, b.name
+        FROM tbl_access_grants g
+        JOIN tbl_books b ON g.resource_id = b.id
+        WHERE g.user_id = ? AND g.resource_type = 'book' AND b.status = 'active'
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .into_iter()
+    .map(|row| ContentAccess { id: row.id, name: row.name })
+    .collect();
+
+    let accessible_files = sqlx::query!(
+        r#"
+        SELECT f.id, f.name
+        FROM tbl_access_grants g
+        JOIN tbl_files f ON g.resource_id = f.id
+        WHERE g.user_id = ? AND g.resource_type = 'file' AND f.status = 'active'
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .into_iter()
+    .map(|row| ContentAccess { id: row.id, name: row.name })
+    .collect();
+
     Ok(UserPermissions {
         user_id,
         accessible_scholars,
+        accessible_books,
+        accessible_files,
         role: user_role,
     })
 }
@@ -62,6 +96,100 @@ pub async fn check_user_access_to_scholar(
     Ok(count > 0)
 }
 
+/// Scholar-level access to the book's scholar, or a grant on the book itself
+pub async fn check_user_access_to_book(
+    pool: &MySqlPool,
+    user_id: i32,
+    book_id: i32,
+) -> Result<bool, AppError> {
+    let Some(scholar_id) =
+        sqlx::query_scalar!("SELECT scholar_id FROM tbl_books WHERE id = ?", book_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(AppError::db_error)?
+    else {
+        return Ok(false);
+    };
+
+    Ok(fetch_user_grants(pool, user_id)
+        .await?
+        .iter()
+        .any(|grant| grant.covers_book(scholar_id, book_id)))
+}
+
+/// Scholar-level access to the file's scholar; book and file grants don't count
+pub async fn check_user_access_to_file_scholar(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_id: i32,
+) -> Result<bool, AppError> {
+    let count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)
+        FROM tbl_files f
+        JOIN tbl_access a ON a.scholar_id = f.scholar
+        WHERE f.id = ? AND a.user_id = ?
+        "#,
+        file_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(count > 0)
+}
+
+/// Access to the file's scholar or book, or a grant on the file itself
+pub async fn check_user_access_to_file(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_id: i32,
+) -> Result<bool, AppError> {
+    let Some(file) = sqlx::query!("SELECT scholar, book FROM tbl_files WHERE id = ?", file_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::db_error)?
+    else {
+        return Ok(false);
+    };
+
+    Ok(fetch_user_grants(pool, user_id)
+        .await?
+        .iter()
+        .any(|grant| grant.covers_file(file.scholar, file.book, file_id)))
+}
+
+/// Every scholar, book and file grant held by `user_id`
+async fn fetch_user_grants(pool: &MySqlPool, user_id: i32) -> Result<Vec<AccessTarget>, AppError> {
+    let scholar_ids: Vec<i32> =
+        sqlx::query_scalar!("SELECT scholar_id FROM tbl_access WHERE user_id = ?", user_id)
+            .fetch_all(pool)
+            .await
+            .map_err(AppError::db_error)?;
+
+    let content = sqlx::query!(
+        "SELECT resource_type, resource_id FROM tbl_access_grants WHERE user_id = ?",
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(scholar_ids
+        .into_iter()
+        .map(AccessTarget::Scholar)
+        .chain(content.into_iter().filter_map(|grant| {
+            match grant.resource_type.as_str() {
+                "book" => Some(AccessTarget::Book(grant.resource_id)),
+                "file" => Some(AccessTarget::File(grant.resource_id)),
+                _ => None,
+            }
+        }))
+        .collect())
+}
+
+
 pub async fn grant_user_access(
     pool: &MySqlPool,
     user_id: i32,
@@ -149,4 +277,52 @@ pub async fn fetch_all_user_accesses(
         .collect();
 
     Ok(accesses)
-}
\ No newline at end of file
+}
+
+/// Grant `user_id` a single book or file (`resource_type` is `book` or `file`)
+pub async fn grant_user_content_access(
+    pool: &MySqlPool,
+    user_id: i32,
+    resource_type: &str,
+    resource_id: i32,
+    created_by: i32,
+) -> Result<(), AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_access_grants (user_id, resource_type, resource_id, created_by, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE created_by = VALUES(created_by), created_at = VALUES(created_at)
+        "#,
+        user_id,
+        resource_type,
+        resource_id,
+        created_by,
+        now
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+pub async fn revoke_user_content_access(
+    pool: &MySqlPool,
+    user_id: i32,
+    resource_type: &str,
+    resource_id: i32,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "DELETE FROM tbl_access_grants WHERE user_id = ? AND resource_type = ? AND resource_id = ?",
+        user_id,
+        resource_type,
+        resource_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use crate::core::AppError;
+use crate::models::activity::ActivityItem;
+use chrono::{NaiveDateTime, Utc};
+use sqlx::MySqlPool;
+
+/// Activity older than this is left out of the timeline
+pub const ACTIVITY_WINDOW_DAYS: i64 = 90;
+
+struct ActivityRow {
+    kind: String,
+    occurred_at: NaiveDateTime,
+    file_id: Option<i64>,
+    file_name: Option<String>,
+    comment_id: Option<i64>,
+    playlist_id: Option<i64>,
+    playlist_name: Option<String>,
+}
+
+fn activity_item(row: ActivityRow) -> ActivityItem {
+    ActivityItem {
+        kind: row.kind,
+        occurred_at: row.occurred_at,
+        file_id: row.file_id.map(|id| id as i32),
+        file_name: row.file_name,
+        comment_id: row.comment_id.map(|id| id as i32),
+        playlist_id: row.playlist_id.map(|id| id as i32),
+        playlist_name: row.playlist_name,
+    }
+}
+
+/// A user's plays, downloads, likes, comments and created playlists, newest
+/// first. Plays are collapsed to one entry per file per day, since a single
+/// listen records several progress rows
+pub async fn get_user_activity(
+    pool: &MySqlPool,
+    user_id: i32,
+    limit: i32,
+    offset: i32,
+) -> Result<(Vec<ActivityItem>, i64), AppError> {
+    let since = (Utc::now() - chrono::Duration::days(ACTIVITY_WINDOW_DAYS)).naive_utc();
+
+    let rows = sqlx::query_as!(
+        ActivityRow,
+        r#"
+        SELECT
+            a.kind AS "kind!: String",
+            a.occurred_at AS "occurred_at!: NaiveDateTime",
+            a.file_id AS "file_id: i64",
+            f.name AS "file_name?: String",
+            a.comment_id AS "comment_id: i64",
+            a.playlist_id AS "playlist_id: i64",
+            p.name AS "playlist_name?: String"
+        FROM (
+            SELECT 'played' AS kind, MAX(played_at) AS occurred_at, file_id,
+                   CAST(NULL AS SIGNED) AS comment_id, CAST(NULL AS SIGNED) AS playlist_id
+            FROM tbl_play_history
+            WHERE user_id = ? AND played_at >= ?
+            GROUP BY file_id, DATE(played_at)
+            UNION ALL
+            SELECT 'downloaded', downloaded_at, file_id, NULL, NULL
+            FROM tbl_download_logs
+            WHERE user_id = ? AND downloaded_at >= ?
+            UNION ALL
+            SELECT 'liked', created_at, file_id, NULL, NULL
+            FROM tbl_file_likes
+            WHERE user_id = ? AND created_at >= ?
+            UNION ALL
+            SELECT 'commented', created_at, file_id, id, NULL
+            FROM tbl_file_comments
+            WHERE user_id = ? AND created_at >= ?
+            UNION ALL
+            SELECT 'playlist_created', created_at, NULL, NULL, id
+            FROM tbl_playlists
+            WHERE user_id = ? AND created_at >= ?
+        ) a
+        LEFT JOIN tbl_files f ON f.id = a.file_id AND f.status = 'active'
+        LEFT JOIN tbl_playlists p ON p.id = a.playlist_id
+        ORDER BY a.occurred_at DESC, a.kind, a.file_id DESC, a.playlist_id DESC
+        LIMIT ? OFFSET ?
+        "#,
+        user_id,
+        since,
+        user_id,
+        since,
+        user_id,
+        since,
+        user_id,
+        since,
+        user_id,
+        since,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let total: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM (
+                SELECT 1 FROM tbl_play_history
+                WHERE user_id = ? AND played_at >= ?
+                GROUP BY file_id, DATE(played_at)
+            ) plays)
+            + (SELECT COUNT(*) FROM tbl_download_logs WHERE user_id = ? AND downloaded_at >= ?)
+            + (SELECT COUNT(*) FROM tbl_file_likes WHERE user_id = ? AND created_at >= ?)
+            + (SELECT COUNT(*) FROM tbl_file_comments WHERE user_id = ? AND created_at >= ?)
+            + (SELECT COUNT(*) FROM tbl_playlists WHERE user_id = ? AND created_at >= ?)
+            AS "total!: i64"
+        "#,
+        user_id,
+        since,
+        user_id,
+        since,
+        user_id,
+        since,
+        user_id,
+        since,
+        user_id,
+        since
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let items = rows.into_iter().map(activity_item).collect();
+
+    Ok((items, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn row(kind: &str, hour: u32, file_id: Option<i64>, playlist_id: Option<i64>) -> ActivityRow {
+        ActivityRow {
+            kind: kind.to_string(),
+            occurred_at: NaiveDate::from_ymd_opt(2025, 3, 1)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+            file_id,
+            file_name: file_id.map(|id| format!("Lesson {id}")),
+            comment_id: (kind == "commented").then_some(11),
+            playlist_id,
+            playlist_name: playlist_id.map(|_| "Ramadan".to_string()),
+        }
+    }
+
+    #[test]
+    fn timeline_keeps_each_kind_in_time_order_with_its_context() {
+        // One of each kind, newest first as the query orders them
+        let rows = vec![
+            row("playlist_created", 14, None, Some(3)),
+            row("commented", 13, Some(5), None),
+            row("liked", 12, Some(5), None),
+            row("downloaded", 11, Some(4), None),
+            row("played", 10, Some(4), None),
+        ];
+
+        let items: Vec<ActivityItem> = rows.into_iter().map(activity_item).collect();
+
+        assert_eq!(
+            items.iter().map(|item| item.kind.as_str()).collect::<Vec<_>>(),
+            vec!["playlist_created", "commented", "liked", "downloaded", "played"]
+        );
+        assert!(items.windows(2).all(|pair| pair[0].occurred_at > pair[1].occurred_at));
+
+        assert_eq!(items[0].playlist_id, Some(3));
+        assert_eq!(items[0].playlist_name.as_deref(), Some("Ramadan"));
+        assert_eq!(items[0].file_id, None);
+        assert_eq!(items[1].comment_id, Some(11));
+        assert_eq!(items[1].file_name.as_deref(), Some("Lesson 5"));
+        assert_eq!(items[4].file_id, Some(4));
+        assert_eq!(items[4].comment_id, None);
+    }
+}
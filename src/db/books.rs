@@ -1,15 +1,54 @@
-use crate::core::{AppConfig, AppError};
-use crate::models::books::{Book, BookDetails, BookSearchResult, BookStatistics};
+use crate::core::{search_like_pattern, unique_slug, AppConfig, AppError};
+use crate::models::books::{
+    Book, BookDetails, BookProgress, BookSearchResult, BookSiblings, BookStatistics, CompletedBook,
+    SiblingBook,
+};
 use crate::models::pagination::PaginationQuery;
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use sqlx::MySqlPool;
 
+/// Fails with "Book not found" unless an active book has this id. Returns the
+/// book's scholar, which most callers need next for an access check.
+/// List queries use it to tell an empty book (200 with no items) from a missing one (404)
+pub async fn assert_book_active(pool: &MySqlPool, book_id: i32) -> Result<i32, AppError> {
+    sqlx::query_scalar!(
+        "SELECT scholar_id FROM tbl_books WHERE id = ? AND status = 'active'",
+        book_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)
+    .and_then(active_book_found)
+}
+
+// `scholar_id` is only returned for an active book
+fn active_book_found(scholar_id: Option<i32>) -> Result<i32, AppError> {
+    scholar_id.ok_or_else(|| AppError::not_found("Book not found"))
+}
+
+/// Like `assert_book_active`, but a book that isn't published yet is not found either
+pub async fn assert_book_published(pool: &MySqlPool, book_id: i32) -> Result<i32, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT scholar_id FROM tbl_books
+        WHERE id = ? AND status = 'active' AND is_published(NULL, publish_at)
+        "#,
+        book_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)
+    .and_then(active_book_found)
+}
+
 pub async fn fetch_books_by_scholar(
     pool: &MySqlPool,
     config: &AppConfig,
     scholar_id: i32,
     pagination: &PaginationQuery,
 ) -> Result<(Vec<Book>, i64), AppError> {
+    crate::db::scholars::assert_scholar_active(pool, scholar_id).await?;
+
     let raw_books = sqlx::query!(
         r#"
         SELECT
@@ -18,12 +57,13 @@ pub async fn fetch_books_by_scholar(
             b.image,
             b.created_at,
             b.created_by,
-            COUNT(DISTINCT f.id) as files_count,
-            COUNT(DISTINCT dl.id) as downloads
+            COUNT(f.id) as files_count,
+            CAST(COALESCE(SUM(f.downloads), 0) AS SIGNED) as "downloads!: i64"
         FROM tbl_books b
         LEFT JOIN tbl_files f ON b.id = f.book AND f.status = 'active'
-        LEFT JOIN tbl_download_logs dl ON f.id = dl.file_id
+            AND is_published(f.publish_at, NULL)
         WHERE b.scholar_id = ? AND b.status = 'active'
+        AND is_published(NULL, b.publish_at)
         GROUP BY b.id, b.name, b.image, b.created_at, b.created_by
         LIMIT ? OFFSET ?
         "#,
@@ -50,7 +90,11 @@ pub async fn fetch_books_by_scholar(
         .collect();
 
     let total_count: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM tbl_books WHERE scholar_id = ? AND status = 'active'",
+        r#"
+        SELECT COUNT(*) FROM tbl_books
+        WHERE scholar_id = ? AND status = 'active'
+        AND is_published(NULL, publish_at)
+        "#,
         scholar_id
     )
     .fetch_one(pool)
@@ -67,6 +111,9 @@ pub async fn search_books(
     page: i32,
     per_page: i32,
 ) -> Result<(Vec<BookSearchResult>, i64), AppError> {
+    let Some(pattern) = search_like_pattern(search_term) else {
+        return Ok((Vec::new(), 0));
+    };
     let offset = (page - 1) * per_page;
 
     let raw_books = sqlx::query!(
@@ -79,10 +126,11 @@ pub async fn search_books(
         FROM tbl_books b
         JOIN tbl_scholars s ON b.scholar_id = s.id
         WHERE (b.name LIKE ? OR b.about LIKE ?) AND b.status = 'active' AND s.status = 'active'
+        AND is_published(NULL, b.publish_at)
         LIMIT ? OFFSET ?
         "#,
-        format!("%{}%", search_term),
-        format!("%{}%", search_term),
+        &pattern,
+        &pattern,
         per_page,
         offset
     )
@@ -98,6 +146,7 @@ pub async fn search_books(
             name: Some(row.name),
             image: Some(config.get_image_url(&row.image)),
             scholar_name: Some(row.scholar_name),
+            highlight: None,
         })
         .collect();
 
@@ -107,9 +156,10 @@ pub async fn search_books(
         FROM tbl_books b
         JOIN tbl_scholars s ON b.scholar_id = s.id
         WHERE (b.name LIKE ? OR b.about LIKE ?) AND b.status = 'active' AND s.status = 'active'
+        AND is_published(NULL, b.publish_at)
         "#,
-        format!("%{}%", search_term),
-        format!("%{}%", search_term)
+        &pattern,
+        &pattern
     )
     .fetch_one(pool)
     .await
@@ -117,27 +167,33 @@ pub async fn search_books(
 
     Ok((books, total_count))
 }
+/// A book that isn't published yet is not found unless `include_unpublished`
+/// is set, as it is for the people editing it
 pub async fn get_book_details(
     pool: &MySqlPool,
     config: &AppConfig,
     book_id: i32,
     user_id: Option<i32>,
+    include_unpublished: bool,
 ) -> Result<BookDetails, AppError> {
     // Get basic book information with scholar details
     let book_row = sqlx::query!(
         r#"
         SELECT 
             b.id, b.name, b.about, b.scholar_id, b.image, b.created_at, b.updated_at, b.created_by,
-            s.name as scholar_name
+            b.version, s.name as scholar_name
         FROM tbl_books b
         JOIN tbl_scholars s ON b.scholar_id = s.id
         WHERE b.id = ? AND b.status = 'active' AND s.status = 'active'
+        AND (? OR is_published(NULL, b.publish_at))
         "#,
-        book_id
+        book_id,
+        include_unpublished
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Book not found"))?;
 
     // Get statistics
     let statistics = get_book_statistics(pool, book_id).await?;
@@ -159,18 +215,114 @@ pub async fn get_book_details(
         created_at: Utc::now().naive_utc(), // Using current time as placeholder
         updated_at: Utc::now().naive_utc(), // Using current time as placeholder
         created_by: book_row.created_by,
+        version: book_row.version,
         statistics,
         has_access,
     })
 }
 
+struct CatalogBookRow {
+    id: i32,
+    name: String,
+    image: String,
+    created_at: NaiveDateTime,
+}
+
+/// Neighbours of a book within its scholar's catalog, which is ordered by
+/// `(created_at, id)`. The book itself may be missing from it while unpublished
+fn book_siblings(
+    config: &AppConfig,
+    book_id: i32,
+    scholar_id: i32,
+    created_at: NaiveDateTime,
+    catalog: Vec<CatalogBookRow>,
+) -> BookSiblings {
+    let key = (created_at, book_id);
+    let total_books = catalog.len() as i64;
+    let position = catalog
+        .iter()
+        .filter(|row| (row.created_at, row.id) <= key)
+        .count() as i64;
+    let sibling = |row: &CatalogBookRow| SiblingBook {
+        id: row.id,
+        name: row.name.clone(),
+        image: config.get_image_url(&row.image),
+    };
+
+    BookSiblings {
+        book_id,
+        scholar_id,
+        previous: catalog
+            .iter()
+            .rev()
+            .find(|row| (row.created_at, row.id) < key)
+            .map(sibling),
+        next: catalog
+            .iter()
+            .find(|row| (row.created_at, row.id) > key)
+            .map(sibling),
+        position,
+        total_books,
+    }
+}
+
+/// Previous and next active books by the same scholar, ordered by creation
+/// date with the id breaking ties so the order is stable
+pub async fn get_book_siblings(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    book_id: i32,
+) -> Result<BookSiblings, AppError> {
+    let book = sqlx::query!(
+        r#"
+        SELECT b.scholar_id, b.created_at
+        FROM tbl_books b
+        JOIN tbl_scholars s ON b.scholar_id = s.id
+        WHERE b.id = ? AND b.status = 'active' AND s.status = 'active'
+        "#,
+        book_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Book not found"))?;
+
+    let catalog = sqlx::query_as!(
+        CatalogBookRow,
+        r#"
+        SELECT id, name, image, created_at
+        FROM tbl_books
+        WHERE scholar_id = ? AND status = 'active'
+          AND is_published(NULL, publish_at)
+        ORDER BY created_at ASC, id ASC
+        "#,
+        book.scholar_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(book_siblings(
+        config,
+        book_id,
+        book.scholar_id,
+        book.created_at,
+        catalog,
+    ))
+}
+
 pub async fn get_book_statistics(
     pool: &MySqlPool,
     book_id: i32,
 ) -> Result<BookStatistics, AppError> {
-    // Get total files
+    // Only published files count, the same ones the book's listing shows
     let total_files: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM tbl_files WHERE book = ? AND status = 'active'",
+        r#"
+        SELECT COUNT(*) FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
         book_id
     )
     .fetch_one(pool)
@@ -180,10 +332,11 @@ pub async fn get_book_statistics(
     // Get total downloads
     let total_downloads: i64 = sqlx::query_scalar!(
         r#"
-        SELECT COUNT(*) 
-        FROM tbl_download_logs dl
-        JOIN tbl_files f ON dl.file_id = f.id
+        SELECT CAST(COALESCE(SUM(f.downloads), 0) AS SIGNED) AS "total!: i64"
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         WHERE f.book = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
         "#,
         book_id
     )
@@ -197,7 +350,9 @@ pub async fn get_book_statistics(
         SELECT COUNT(*) 
         FROM tbl_play_history ph
         JOIN tbl_files f ON ph.file_id = f.id
+        JOIN tbl_books b ON f.book = b.id
         WHERE f.book = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
         "#,
         book_id
     )
@@ -211,7 +366,9 @@ pub async fn get_book_statistics(
         SELECT COUNT(*) 
         FROM tbl_file_likes fl
         JOIN tbl_files f ON fl.file_id = f.id
+        JOIN tbl_books b ON f.book = b.id
         WHERE f.book = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
         "#,
         book_id
     )
@@ -272,6 +429,7 @@ pub async fn get_books_dropdown(
             FROM tbl_books b
             JOIN tbl_scholars s ON b.scholar_id = s.id
             WHERE b.scholar_id = ? AND b.status = 'active' AND s.status = 'active'
+            AND is_published(NULL, b.publish_at)
             ORDER BY b.name
             "#,
             sid
@@ -287,6 +445,7 @@ pub async fn get_books_dropdown(
             FROM tbl_books b
             JOIN tbl_scholars s ON b.scholar_id = s.id
             WHERE b.status = 'active' AND s.status = 'active'
+            AND is_published(NULL, b.publish_at)
             ORDER BY s.name, b.name
             "#
         )
@@ -298,14 +457,48 @@ pub async fn get_books_dropdown(
     Ok(books)
 }
 
+/// Insert a book under an active scholar with `base_slug`, or the first free
+/// `base_slug-N` among that scholar's books. Returns the new id and its slug
 pub async fn create_book(
     pool: &MySqlPool,
     request: &crate::models::books::CreateBookRequest,
-    slug_value: &str,
+    base_slug: &str,
     user_id: i32,
-) -> Result<i32, AppError> {
+    default_image: &str,
+) -> Result<(i32, String), AppError> {
     let now = Utc::now().naive_utc();
 
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    // Holds the scholar so it can't be deleted before the book is in
+    let scholar_exists = sqlx::query_scalar!(
+        "SELECT id FROM tbl_scholars WHERE id = ? AND status = 'active' FOR SHARE",
+        request.scholar_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::db_error_or_retry)?;
+
+    if scholar_exists.is_none() {
+        return Err(AppError::not_found("Scholar not found"));
+    }
+
+    // Locks the matching slug range so a concurrent create waits for this one
+    let taken = sqlx::query_scalar!(
+        r#"
+        SELECT slug AS "slug!" FROM tbl_books
+        WHERE scholar_id = ? AND (slug = ? OR slug LIKE CONCAT(?, '-%'))
+        FOR UPDATE
+        "#,
+        request.scholar_id,
+        base_slug,
+        base_slug
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(AppError::db_error_or_retry)?;
+    let slug_value = unique_slug(base_slug, &taken);
+
     let result = sqlx::query!(
         r#"
         INSERT INTO tbl_books (name, about, scholar_id, image, slug, status, created_by, created_at, updated_at)
@@ -314,17 +507,24 @@ pub async fn create_book(
         request.name,
         request.about,
         request.scholar_id,
-        request.image.as_deref().unwrap_or("book.jpg"),
+        request.image.as_deref().unwrap_or(default_image),
         slug_value,
         user_id,
         now,
         now
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(|e| {
+        AppError::db_error_or_conflict(
+            e,
+            format!("A book with the slug '{}' already exists for this scholar", slug_value),
+        )
+    })?;
+
+    tx.commit().await.map_err(AppError::db_error_or_retry)?;
 
-    Ok(result.last_insert_id() as i32)
+    Ok((result.last_insert_id() as i32, slug_value))
 }
 
 pub async fn update_book(
@@ -334,6 +534,28 @@ pub async fn update_book(
 ) -> Result<(), AppError> {
     let now = Utc::now().naive_utc();
 
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    // Bump the version first; with an expected version this doubles as the precondition
+    let bumped = match request.version {
+        Some(expected) => sqlx::query!(
+            "UPDATE tbl_books SET version = version + 1 WHERE id = ? AND status = 'active' AND version = ?",
+            book_id,
+            expected
+        )
+        .execute(&mut *tx)
+        .await,
+        None => sqlx::query!(
+            "UPDATE tbl_books SET version = version + 1 WHERE id = ? AND status = 'active'",
+            book_id
+        )
+        .execute(&mut *tx)
+        .await,
+    }
+    .map_err(AppError::db_error)?;
+
+    crate::core::check_version_bump(bumped.rows_affected(), request.version, "Book")?;
+
     // Update each field individually if provided
     if let Some(ref name) = request.name {
         sqlx::query!(
@@ -342,7 +564,7 @@ pub async fn update_book(
             now,
             book_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -354,7 +576,7 @@ pub async fn update_book(
             now,
             book_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -366,7 +588,7 @@ pub async fn update_book(
             now,
             book_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -378,35 +600,14 @@ pub async fn update_book(
             now,
             book_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
 
-    Ok(())
-}
-
-pub async fn check_duplicate_book(
-    pool: &MySqlPool,
-    name: &str,
-    scholar_id: i32,
-    slug_value: &str,
-) -> Result<Option<String>, AppError> {
-    let existing = sqlx::query!(
-        r#"
-        SELECT name FROM tbl_books 
-        WHERE (name = ? OR slug = ?) AND scholar_id = ?
-        LIMIT 1
-        "#,
-        name,
-        slug_value,
-        scholar_id
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(AppError::db_error)?;
+    tx.commit().await.map_err(AppError::db_error)?;
 
-    Ok(existing.map(|b| b.name))
+    Ok(())
 }
 
 pub async fn delete_book(
@@ -426,3 +627,246 @@ pub async fn delete_book(
 
     Ok(())
 }
+
+// A user's progress through a book's published files, counting a file once it
+// has a completion event. Read-only: `tbl_book_completions` is kept in step by
+// `sync_book_completion_for_file` when a completion is written
+pub async fn get_book_progress(
+    pool: &MySqlPool,
+    user_id: i32,
+    book_id: i32,
+) -> Result<BookProgress, AppError> {
+    assert_book_active(pool, book_id).await?;
+
+    let (total_files, completed_files) = count_book_progress(pool, user_id, book_id).await?;
+    let is_completed = total_files > 0 && completed_files >= total_files;
+
+    let completed_at = if is_completed {
+        sqlx::query_scalar!(
+            "SELECT completed_at FROM tbl_book_completions WHERE user_id = ? AND book_id = ?",
+            user_id,
+            book_id
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::db_error)?
+    } else {
+        None
+    };
+
+    let percentage = if total_files > 0 {
+        (completed_files * 1000 / total_files) as f64 / 10.0
+    } else {
+        0.0
+    };
+
+    Ok(BookProgress {
+        book_id,
+        total_files,
+        completed_files,
+        percentage,
+        is_completed,
+        completed_at,
+    })
+}
+
+// Published files in the book, and how many of them the user has completed
+async fn count_book_progress(
+    pool: &MySqlPool,
+    user_id: i32,
+    book_id: i32,
+) -> Result<(i64, i64), AppError> {
+    let total_files: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
+        book_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let completed_files: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
+        AND EXISTS (
+            SELECT 1 FROM tbl_file_completions fc
+            WHERE fc.user_id = ? AND fc.file_id = f.id
+        )
+        "#,
+        book_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok((total_files, completed_files))
+}
+
+// Re-evaluate the completion of the book a file belongs to after a completion
+// event: the book is recorded the first time every file is done and dropped
+// again once it has an unfinished file
+pub async fn sync_book_completion_for_file(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_id: i32,
+) -> Result<(), AppError> {
+    let book_id = sqlx::query_scalar!(
+        "SELECT book FROM tbl_files WHERE id = ? AND status = 'active'",
+        file_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let Some(book_id) = book_id else {
+        return Ok(());
+    };
+
+    let (total_files, completed_files) = count_book_progress(pool, user_id, book_id).await?;
+    if total_files > 0 && completed_files >= total_files {
+        sqlx::query!(
+            "INSERT IGNORE INTO tbl_book_completions (user_id, book_id, completed_at) VALUES (?, ?, ?)",
+            user_id,
+            book_id,
+            Utc::now().naive_utc()
+        )
+        .execute(pool)
+        .await
+        .map_err(AppError::db_error)?;
+    } else {
+        sqlx::query!(
+            "DELETE FROM tbl_book_completions WHERE user_id = ? AND book_id = ?",
+            user_id,
+            book_id
+        )
+        .execute(pool)
+        .await
+        .map_err(AppError::db_error)?;
+    }
+    Ok(())
+}
+
+// Books the user completed that are still complete; a book that gained files since
+// is left out here and its row removed by the next completion written in it
+pub async fn get_completed_books(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    user_id: i32,
+) -> Result<Vec<CompletedBook>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.book_id, b.name as book_name, b.image as book_image,
+               s.name as scholar_name, c.completed_at
+        FROM tbl_book_completions c
+        JOIN tbl_books b ON c.book_id = b.id
+        JOIN tbl_scholars s ON b.scholar_id = s.id
+        WHERE c.user_id = ? AND b.status = 'active'
+        AND NOT EXISTS (
+            SELECT 1
+            FROM tbl_files f
+            WHERE f.book = c.book_id AND f.status = 'active'
+            AND is_published(f.publish_at, b.publish_at)
+            AND NOT EXISTS (
+                SELECT 1
+                FROM tbl_file_completions fc
+                WHERE fc.user_id = c.user_id AND fc.file_id = f.id
+            )
+        )
+        ORDER BY c.completed_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| CompletedBook {
+            book_id: row.book_id,
+            book_name: row.book_name,
+            book_image: config.get_image_url(&row.book_image),
+            scholar_name: row.scholar_name,
+            completed_at: row.completed_at,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn assert_book_active_is_not_found_for_an_inactive_book() {
+        let err = active_book_found(None).unwrap_err();
+        assert_eq!(err.error_type, crate::core::AppErrorType::NotFoundError);
+        assert_eq!(err.message.as_deref(), Some("Book not found"));
+
+        assert_eq!(active_book_found(Some(4)).unwrap(), 4);
+    }
+
+    fn catalog_book(id: i32, day: u32) -> CatalogBookRow {
+        CatalogBookRow {
+            id,
+            name: format!("Book {id}"),
+            image: format!("book-{id}.jpg"),
+            created_at: created_on(day),
+        }
+    }
+
+    fn created_on(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, day)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+    }
+
+    fn catalog() -> Vec<CatalogBookRow> {
+        vec![catalog_book(10, 1), catalog_book(11, 2), catalog_book(12, 3)]
+    }
+
+    #[test]
+    fn middle_book_has_both_neighbours() {
+        let config = AppConfig::new().expect("local configuration");
+        let siblings = book_siblings(&config, 11, 4, created_on(2), catalog());
+
+        assert_eq!(siblings.previous.as_ref().map(|b| b.id), Some(10));
+        assert_eq!(siblings.next.as_ref().map(|b| b.id), Some(12));
+        assert_eq!(siblings.position, 2);
+        assert_eq!(siblings.total_books, 3);
+        assert_eq!(
+            siblings.next.map(|b| b.image),
+            Some(config.get_image_url("book-12.jpg"))
+        );
+    }
+
+    #[test]
+    fn last_book_has_no_next() {
+        let config = AppConfig::new().expect("local configuration");
+        let siblings = book_siblings(&config, 12, 4, created_on(3), catalog());
+
+        assert_eq!(siblings.previous.map(|b| b.id), Some(11));
+        assert!(siblings.next.is_none());
+        assert_eq!(siblings.position, 3);
+    }
+
+    #[test]
+    fn books_created_together_are_ordered_by_id() {
+        let config = AppConfig::new().expect("local configuration");
+        let catalog = vec![catalog_book(20, 5), catalog_book(21, 5), catalog_book(22, 5)];
+        let siblings = book_siblings(&config, 21, 4, created_on(5), catalog);
+
+        assert_eq!(siblings.previous.map(|b| b.id), Some(20));
+        assert_eq!(siblings.next.map(|b| b.id), Some(22));
+    }
+}
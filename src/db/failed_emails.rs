@@ -0,0 +1,128 @@
+use crate::core::email_service::EmailType;
+use crate::core::AppError;
+use crate::models::failed_emails::{FailedEmailRecord, FailedEmailSummary};
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+/// Store an email the worker gave up on. One-time codes are blanked first, so
+/// such an email is kept for the record but can't be re-sent
+pub async fn record_failed_email(
+    pool: &MySqlPool,
+    email_type: &EmailType,
+    error: &str,
+) -> Result<(), AppError> {
+    let payload = serde_json::to_string(&email_type.redacted()).map_err(AppError::internal_error)?;
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_failed_emails
+        (email_kind, recipient, payload, last_error, attempts, created_at, last_attempt_at)
+        VALUES (?, ?, ?, ?, 1, ?, ?)
+        "#,
+        email_type.kind(),
+        email_type.recipient(),
+        payload,
+        error,
+        now,
+        now
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+/// Failed emails, newest first, with the total count
+pub async fn list_failed_emails(
+    pool: &MySqlPool,
+    limit: i32,
+    offset: i32,
+) -> Result<(Vec<FailedEmailSummary>, i64), AppError> {
+    let emails = sqlx::query_as!(
+        FailedEmailSummary,
+        r#"
+        SELECT id, email_kind, recipient, last_error, attempts, created_at, last_attempt_at, retried_at
+        FROM tbl_failed_emails
+        ORDER BY created_at DESC, id DESC
+        LIMIT ? OFFSET ?
+        "#,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let total: i64 = sqlx::query_scalar!("SELECT COUNT(*) FROM tbl_failed_emails")
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok((emails, total))
+}
+
+pub async fn get_failed_email(pool: &MySqlPool, id: i32) -> Result<FailedEmailRecord, AppError> {
+    sqlx::query_as!(
+        FailedEmailRecord,
+        "SELECT id, payload, created_at, retried_at FROM tbl_failed_emails WHERE id = ?",
+        id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Failed email not found"))
+}
+
+/// Claim a failed email for re-sending before it is sent, so concurrent
+/// retries can't both send it; false when another retry got there first
+pub async fn claim_failed_email(pool: &MySqlPool, id: i32) -> Result<bool, AppError> {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query!(
+        r#"
+        UPDATE tbl_failed_emails
+        SET retried_at = ?, last_attempt_at = ?, attempts = attempts + 1
+        WHERE id = ? AND retried_at IS NULL
+        "#,
+        now,
+        now,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Release a claim whose re-send failed, so the email can be retried again
+pub async fn release_failed_email_claim(
+    pool: &MySqlPool,
+    id: i32,
+    error: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE tbl_failed_emails SET retried_at = NULL, last_error = ? WHERE id = ?",
+        error,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+/// Delete stored failures older than `retention_days`
+pub async fn prune_failed_emails(pool: &MySqlPool, retention_days: i64) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM tbl_failed_emails WHERE created_at < UTC_TIMESTAMP() - INTERVAL ? DAY",
+        retention_days
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,168 @@
+use crate::core::{AppError, RedisHelper};
+use crate::models::feature_flags::{FeatureFlag, FeatureFlagOverride, FeatureFlagState};
+use chrono::Utc;
+use sqlx::MySqlPool;
+use std::time::Duration;
+
+const FEATURE_FLAGS_CACHE_KEY: &str = "cache:feature_flags";
+// Short, so instances that missed the invalidation catch up quickly
+const FEATURE_FLAGS_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Every known flag with its current value, cached in Redis
+pub async fn load_feature_flags(
+    pool: &MySqlPool,
+    redis_service: &RedisHelper,
+) -> Result<Vec<FeatureFlagState>, AppError> {
+    redis_service
+        .get_or_load(FEATURE_FLAGS_CACHE_KEY, FEATURE_FLAGS_CACHE_TTL, || async {
+            let overrides = fetch_flag_overrides(pool).await?;
+            Ok(flag_states(&overrides))
+        })
+        .await
+}
+
+/// Like `load_feature_flags`, but falls back to every flag's default when the
+/// flags can't be read, for callers that must not fail because of them
+pub async fn load_feature_flags_or_defaults(
+    pool: &MySqlPool,
+    redis_service: &RedisHelper,
+) -> Vec<FeatureFlagState> {
+    match load_feature_flags(pool, redis_service).await {
+        Ok(flags) => flags,
+        Err(e) => {
+            tracing::warn!("Failed to load feature flags, using defaults: {:?}", e);
+            flag_states(&[])
+        }
+    }
+}
+
+/// Whether `flag` is on. If the flags can't be read the flag's default is
+/// used, so a database hiccup never flips a feature
+pub async fn is_feature_enabled(
+    pool: &MySqlPool,
+    redis_service: &RedisHelper,
+    flag: FeatureFlag,
+) -> bool {
+    flag_enabled(&load_feature_flags_or_defaults(pool, redis_service).await, flag)
+}
+
+pub async fn invalidate_feature_flags(redis_service: &RedisHelper) {
+    if let Err(e) = redis_service.delete(FEATURE_FLAGS_CACHE_KEY).await {
+        tracing::warn!("Failed to invalidate feature flag cache: {}", e);
+    }
+}
+
+/// Every known flag, with stored overrides applied over the defaults
+pub(crate) fn flag_states(overrides: &[FeatureFlagOverride]) -> Vec<FeatureFlagState> {
+    FeatureFlag::ALL
+        .iter()
+        .map(|flag| {
+            let stored = overrides.iter().find(|o| o.flag_key == flag.key());
+            FeatureFlagState {
+                key: flag.key().to_string(),
+                enabled: stored.map_or(flag.default_enabled(), |o| o.enabled),
+                default_enabled: flag.default_enabled(),
+                is_public: flag.is_public(),
+                updated_by: stored.and_then(|o| o.updated_by),
+                updated_at: stored.map(|o| o.updated_at),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn flag_enabled(states: &[FeatureFlagState], flag: FeatureFlag) -> bool {
+    states
+        .iter()
+        .find(|state| state.key == flag.key())
+        .map_or(flag.default_enabled(), |state| state.enabled)
+}
+
+pub async fn fetch_flag_overrides(pool: &MySqlPool) -> Result<Vec<FeatureFlagOverride>, AppError> {
+    let rows = sqlx::query!(
+        "SELECT flag_key, enabled, updated_by, updated_at FROM tbl_feature_flags"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| FeatureFlagOverride {
+            flag_key: row.flag_key,
+            enabled: row.enabled != 0,
+            updated_by: row.updated_by,
+            updated_at: row.updated_at,
+        })
+        .collect())
+}
+
+pub async fn set_flag(
+    pool: &MySqlPool,
+    flag_key: &str,
+    enabled: bool,
+    user_id: i32,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_feature_flags (flag_key, enabled, updated_by, updated_at)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            enabled = VALUES(enabled),
+            updated_by = VALUES(updated_by),
+            updated_at = VALUES(updated_at)
+        "#,
+        flag_key,
+        enabled,
+        user_id,
+        Utc::now().naive_utc()
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(flag: FeatureFlag, enabled: bool) -> FeatureFlagOverride {
+        FeatureFlagOverride {
+            flag_key: flag.key().to_string(),
+            enabled,
+            updated_by: Some(1),
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn flags_start_off() {
+        let states = flag_states(&[]);
+        assert_eq!(states.len(), FeatureFlag::ALL.len());
+        for flag in FeatureFlag::ALL {
+            assert!(!flag_enabled(&states, flag), "{} should default to off", flag.key());
+        }
+        assert!(states.iter().all(|state| state.updated_by.is_none()));
+    }
+
+    #[test]
+    fn toggling_a_flag_changes_only_that_flag() {
+        let states = flag_states(&[stored(FeatureFlag::Recommendations, true)]);
+        assert!(flag_enabled(&states, FeatureFlag::Recommendations));
+        assert!(!flag_enabled(&states, FeatureFlag::GuestTracking));
+        assert!(!flag_enabled(&states, FeatureFlag::ModerationMode));
+
+        let states = flag_states(&[stored(FeatureFlag::Recommendations, false)]);
+        assert!(!flag_enabled(&states, FeatureFlag::Recommendations));
+    }
+
+    #[test]
+    fn unknown_stored_keys_are_ignored() {
+        let mut retired = stored(FeatureFlag::ModerationMode, true);
+        retired.flag_key = "retired_flag".to_string();
+        let states = flag_states(&[retired]);
+        assert!(states.iter().all(|state| state.key != "retired_flag"));
+        assert!(!flag_enabled(&states, FeatureFlag::ModerationMode));
+    }
+}
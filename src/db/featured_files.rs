@@ -0,0 +1,168 @@
+use crate::core::{AppConfig, AppError};
+use chrono::{Datelike, NaiveDate, Utc};
+use sqlx::MySqlPool;
+
+/// The file pinned for `date`, if it is still active
+pub async fn fetch_pinned_file_id(pool: &MySqlPool, date: NaiveDate) -> Result<Option<i32>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT ff.file_id
+        FROM tbl_featured_files ff
+        JOIN tbl_files f ON f.id = ff.file_id
+        JOIN tbl_books b ON f.book = b.id
+        WHERE ff.feature_date = ? AND f.status = 'active' AND f.restricted = FALSE
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
+        date
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
+/// Active files in the curated pool, in a stable order
+pub async fn fetch_curated_file_ids(pool: &MySqlPool) -> Result<Vec<i32>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT ff.file_id
+        FROM tbl_featured_files ff
+        JOIN tbl_files f ON f.id = ff.file_id
+        JOIN tbl_books b ON f.book = b.id
+        WHERE ff.feature_date IS NULL AND f.status = 'active' AND f.restricted = FALSE
+        AND is_published(f.publish_at, b.publish_at)
+        ORDER BY ff.file_id
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
+pub async fn fetch_top_file_ids(pool: &MySqlPool, limit: i64) -> Result<Vec<i32>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT f.id
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.status = 'active' AND f.restricted = FALSE
+        AND is_published(f.publish_at, b.publish_at)
+        ORDER BY f.downloads DESC, f.id
+        LIMIT ?
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
+/// The featured file for `date`: the admin pin if there is one, otherwise a
+/// pick from the curated pool (or the top files when the pool is empty) that
+/// only depends on the date
+pub async fn pick_featured_file_id(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    date: NaiveDate,
+) -> Result<Option<i32>, AppError> {
+    if let Some(file_id) = fetch_pinned_file_id(pool, date).await? {
+        return Ok(Some(file_id));
+    }
+
+    let mut candidates = fetch_curated_file_ids(pool).await?;
+    if candidates.is_empty() {
+        candidates = fetch_top_file_ids(pool, config.featured_files.top_pool_size.max(1)).await?;
+    }
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(candidates[daily_index(date, candidates.len())]))
+}
+
+// Spread consecutive days across the pool instead of stepping through it in order
+fn daily_index(date: NaiveDate, len: usize) -> usize {
+    let seed = (date.num_days_from_ce() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    ((seed >> 32) % len as u64) as usize
+}
+
+/// Pin `file_id` for `date`, replacing any earlier pin for that date
+pub async fn pin_featured_file(
+    pool: &MySqlPool,
+    file_id: i32,
+    date: NaiveDate,
+    user_id: i32,
+) -> Result<(), AppError> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_featured_files (file_id, feature_date, created_by, created_at)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            file_id = VALUES(file_id),
+            created_by = VALUES(created_by),
+            created_at = VALUES(created_at)
+        "#,
+        file_id,
+        date,
+        user_id,
+        now
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+/// Add `file_id` to the curated pool; a file already in the pool is left as is
+pub async fn add_to_featured_pool(pool: &MySqlPool, file_id: i32, user_id: i32) -> Result<(), AppError> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_featured_files (file_id, feature_date, created_by, created_at)
+        SELECT ?, NULL, ?, ?
+        FROM DUAL
+        WHERE NOT EXISTS (
+            SELECT 1 FROM tbl_featured_files WHERE file_id = ? AND feature_date IS NULL
+        )
+        "#,
+        file_id,
+        user_id,
+        now,
+        file_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_index_is_stable_and_in_range() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert_eq!(daily_index(date, 7), daily_index(date, 7));
+        assert_eq!(daily_index(date, 1), 0);
+
+        for offset in 0..60 {
+            let day = date + chrono::Duration::days(offset);
+            assert!(daily_index(day, 5) < 5);
+        }
+    }
+
+    #[test]
+    fn daily_index_does_not_step_through_the_pool() {
+        let start = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let picks: Vec<usize> = (0..10)
+            .map(|offset| daily_index(start + chrono::Duration::days(offset), 10))
+            .collect();
+        let sequential = picks.windows(2).all(|pair| pair[1] == (pair[0] + 1) % 10);
+        assert!(!sequential);
+    }
+}
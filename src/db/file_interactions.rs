@@ -1,22 +1,26 @@
-use crate::core::AppError;
+use crate::core::{AppConfig, AppError};
 use crate::models::file_interactions::{
-    FileReport, CreateReportRequest, ResolveReportRequest,
-    FileLike, LikeFileRequest,
-    FileComment, CreateCommentRequest, UpdateCommentRequest, CommentResponse,
-    DownloadLog, DownloadStats
+    FileReport, CreateReportRequest, ResolveReportRequest, PendingFileReport, PendingReportsQuery,
+    FileLike, LikeFileRequest, LikeFileResponse,
+    FileComment, CreateCommentRequest, UpdateCommentRequest, CommentResponse, CommentStatus,
+    MyCommentEntry, PendingComment,
+    DownloadLog, DownloadStats, DownloadHistoryEntry
 };
-use sqlx::MySqlPool;
+use sqlx::{MySqlConnection, MySqlPool};
 use chrono::{DateTime, Utc};
 
 // File Reports
+/// Report a file. While the user already has a pending report on it, the
+/// unique pending key rejects the insert and that report is returned instead;
+/// the flag is true only when a new report was created
 pub async fn create_file_report(
     pool: &MySqlPool,
     user_id: i32,
     request: &CreateReportRequest,
-) -> Result<FileReport, AppError> {
+) -> Result<(FileReport, bool), AppError> {
     let now = Utc::now().naive_utc();
 
-    let result = sqlx::query!(
+    let inserted = sqlx::query!(
         r#"
         INSERT INTO tbl_file_reports (user_id, file_id, reason, description, status, created_at)
         VALUES (?, ?, ?, ?, 'pending', ?)
@@ -28,11 +32,87 @@ pub async fn create_file_report(
         now
     )
     .execute(pool)
+    .await;
+
+    match inserted {
+        Ok(result) => Ok((get_file_report_by_id(pool, result.last_insert_id() as i32).await?, true)),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            let existing = get_pending_report_by_user(pool, user_id, request.file_id)
+                .await?
+                .ok_or_else(|| AppError::internal_error("Pending report vanished after a duplicate insert"))?;
+            Ok((existing, false))
+        }
+        Err(e) => Err(AppError::db_error(e)),
+    }
+}
+
+// A user's report on a file that moderators have not resolved yet
+pub async fn get_pending_report_by_user(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_id: i32,
+) -> Result<Option<FileReport>, AppError> {
+    let report_id = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM tbl_file_reports
+        WHERE user_id = ? AND file_id = ? AND status = 'pending'
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        user_id,
+        file_id
+    )
+    .fetch_optional(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    let report_id = result.last_insert_id() as i32;
-    get_file_report_by_id(pool, report_id).await
+    match report_id {
+        Some(id) => Ok(Some(get_file_report_by_id(pool, id).await?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn count_recent_reports_by_user(
+    pool: &MySqlPool,
+    user_id: i32,
+    window_minutes: i64,
+) -> Result<i64, AppError> {
+    let count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) FROM tbl_file_reports
+        WHERE user_id = ? AND created_at >= UTC_TIMESTAMP() - INTERVAL ? MINUTE
+        "#,
+        user_id,
+        window_minutes
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(count)
+}
+
+/// Seconds until the oldest report in the window ages out and frees a slot
+pub async fn report_window_retry_after(
+    pool: &MySqlPool,
+    user_id: i32,
+    window_minutes: i64,
+) -> Result<u64, AppError> {
+    let seconds: Option<i64> = sqlx::query_scalar!(
+        r#"
+        SELECT TIMESTAMPDIFF(SECOND, UTC_TIMESTAMP(), MIN(created_at) + INTERVAL ? MINUTE)
+        FROM tbl_file_reports
+        WHERE user_id = ? AND created_at >= UTC_TIMESTAMP() - INTERVAL ? MINUTE
+        "#,
+        window_minutes,
+        user_id,
+        window_minutes
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(seconds.unwrap_or(window_minutes * 60).max(1) as u64)
 }
 
 pub async fn get_file_report_by_id(
@@ -94,23 +174,69 @@ pub async fn resolve_file_report(
     get_file_report_by_id(pool, report_id).await
 }
 
+struct PendingReportRow {
+    id: i32,
+    user_id: Option<i32>,
+    file_id: i32,
+    reason: String,
+    description: Option<String>,
+    status: String,
+    admin_notes: Option<String>,
+    resolved_by: Option<i32>,
+    created_at: DateTime<Utc>,
+    resolved_at: Option<DateTime<Utc>>,
+}
+
+fn pending_report(now: DateTime<Utc>, row: PendingReportRow) -> PendingFileReport {
+    PendingFileReport {
+        age_hours: (now - row.created_at).num_hours().max(0),
+        report: FileReport {
+            id: row.id,
+            user_id: row.user_id,
+            file_id: row.file_id,
+            reason: row.reason,
+            description: row.description,
+            status: row.status,
+            admin_notes: row.admin_notes,
+            resolved_by: row.resolved_by,
+            created_at: row.created_at.naive_utc(),
+            resolved_at: row.resolved_at.map(|dt| dt.naive_utc()),
+        },
+    }
+}
+
 pub async fn get_pending_reports(
     pool: &MySqlPool,
+    query: &PendingReportsQuery,
     limit: Option<i32>,
     offset: Option<i32>,
-) -> Result<Vec<FileReport>, AppError> {
+) -> Result<(Vec<PendingFileReport>, i64), AppError> {
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
+    let sort = query.sort.as_deref().unwrap_or("oldest");
+    let reason = query.reason.as_deref();
 
-    let rows = sqlx::query!(
+    // Oldest first by default so reports that have waited longest surface
+    let rows = sqlx::query_as!(
+        PendingReportRow,
         r#"
         SELECT id, user_id, file_id, reason, description, status, 
                admin_notes, resolved_by, created_at, resolved_at
         FROM tbl_file_reports
         WHERE status = 'pending'
-        ORDER BY created_at DESC
+          AND (? IS NULL OR reason = ?)
+          AND (? IS NULL OR file_id = ?)
+        ORDER BY
+            CASE WHEN ? = 'newest' THEN created_at END DESC,
+            created_at ASC,
+            id ASC
         LIMIT ? OFFSET ?
         "#,
+        reason,
+        reason,
+        query.file_id,
+        query.file_id,
+        sort,
         limit,
         offset
     )
@@ -118,48 +244,60 @@ pub async fn get_pending_reports(
     .await
     .map_err(AppError::db_error)?;
 
-    let reports = rows
-        .into_iter()
-        .map(|row| FileReport {
-            id: row.id,
-            user_id: row.user_id,
-            file_id: row.file_id,
-            reason: row.reason,
-            description: row.description,
-            status: row.status,
-            admin_notes: row.admin_notes,
-            resolved_by: row.resolved_by,
-            created_at: row.created_at.naive_utc(),
-            resolved_at: row.resolved_at.map(|dt: DateTime<Utc>| dt.naive_utc()),
-        })
-        .collect();
+    let now = Utc::now();
+    let reports = rows.into_iter().map(|row| pending_report(now, row)).collect();
+
+    let total_count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) FROM tbl_file_reports
+        WHERE status = 'pending'
+          AND (? IS NULL OR reason = ?)
+          AND (? IS NULL OR file_id = ?)
+        "#,
+        reason,
+        reason,
+        query.file_id,
+        query.file_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
 
-    Ok(reports)
+    Ok((reports, total_count))
 }
 
 // File Likes
+/// Like a file. Liking again is a no-op that keeps the original `created_at`,
+/// so "my likes" stays ordered by when each file was first liked
 pub async fn like_file(
     pool: &MySqlPool,
     user_id: i32,
     request: &LikeFileRequest,
-) -> Result<FileLike, AppError> {
+) -> Result<LikeFileResponse, AppError> {
     let now = Utc::now().naive_utc();
 
-    let _result = sqlx::query!(
+    let inserted = sqlx::query!(
         r#"
         INSERT INTO tbl_file_likes (user_id, file_id, created_at)
         VALUES (?, ?, ?)
-        ON DUPLICATE KEY UPDATE created_at = VALUES(created_at)
         "#,
         user_id,
         request.file_id,
         now
     )
     .execute(pool)
-    .await
-    .map_err(AppError::db_error)?;
+    .await;
 
-    get_file_like(pool, user_id, request.file_id).await
+    let is_new = match inserted {
+        Ok(_) => true,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => false,
+        Err(e) => return Err(AppError::db_error(e)),
+    };
+
+    Ok(LikeFileResponse {
+        like: get_file_like(pool, user_id, request.file_id).await?,
+        is_new,
+    })
 }
 
 pub async fn unlike_file(
@@ -234,33 +372,83 @@ pub async fn is_file_liked_by_user(
 }
 
 // File Comments
+// A held comment is inserted unapproved, so it is never visible before moderation
 pub async fn create_file_comment(
     pool: &MySqlPool,
     user_id: i32,
     request: &CreateCommentRequest,
+    mentioned_user_ids: &[i32],
+    held: bool,
 ) -> Result<FileComment, AppError> {
     let now = Utc::now().naive_utc();
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
 
     let result = sqlx::query!(
         r#"
-        INSERT INTO tbl_file_comments (user_id, file_id, parent_id, comment, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?)
+        INSERT INTO tbl_file_comments (user_id, file_id, parent_id, comment, is_approved, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         "#,
         user_id,
         request.file_id,
         request.parent_id,
         request.comment,
+        !held,
         now,
         now
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
     .map_err(AppError::db_error)?;
 
     let comment_id = result.last_insert_id() as i32;
+    replace_comment_mentions(&mut *tx, comment_id, mentioned_user_ids).await?;
+    tx.commit().await.map_err(AppError::db_error)?;
+
     get_file_comment_by_id(pool, comment_id).await
 }
 
+async fn replace_comment_mentions(
+    conn: &mut MySqlConnection,
+    comment_id: i32,
+    mentioned_user_ids: &[i32],
+) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM tbl_comment_mentions WHERE comment_id = ?", comment_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::db_error)?;
+
+    for &user_id in mentioned_user_ids {
+        sqlx::query!(
+            "INSERT IGNORE INTO tbl_comment_mentions (comment_id, user_id) VALUES (?, ?)",
+            comment_id,
+            user_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::db_error)?;
+    }
+
+    Ok(())
+}
+
+async fn get_comment_mentions(pool: &MySqlPool, comment_id: i32) -> Result<Vec<i32>, AppError> {
+    sqlx::query_scalar!(
+        "SELECT user_id FROM tbl_comment_mentions WHERE comment_id = ? ORDER BY user_id",
+        comment_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
+/// File a comment belongs to, None if there is no such comment
+pub async fn get_comment_file_id(pool: &MySqlPool, comment_id: i32) -> Result<Option<i32>, AppError> {
+    sqlx::query_scalar!("SELECT file_id FROM tbl_file_comments WHERE id = ?", comment_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::db_error)
+}
+
 pub async fn get_file_comment_by_id(
     pool: &MySqlPool,
     comment_id: i32,
@@ -276,6 +464,7 @@ pub async fn get_file_comment_by_id(
     .fetch_one(pool)
     .await
     .map_err(AppError::db_error)?;
+    let mentioned_user_ids = get_comment_mentions(pool, comment_id).await?;
 
     Ok(FileComment {
         id: row.id,
@@ -284,6 +473,7 @@ pub async fn get_file_comment_by_id(
         parent_id: row.parent_id,
         comment: row.comment,
         is_approved: row.is_approved.unwrap_or(0) != 0,
+        mentioned_user_ids,
         created_at: row.created_at.naive_utc(),
         updated_at: row.updated_at.naive_utc(),
     })
@@ -293,11 +483,15 @@ pub async fn get_file_comments(
     pool: &MySqlPool,
     file_id: i32,
 ) -> Result<Vec<CommentResponse>, AppError> {
+    if !crate::db::files::file_exists(pool, file_id).await? {
+        return Err(AppError::not_found("File not found"));
+    }
+
     let rows = sqlx::query!(
         r#"
         SELECT 
-            c.id, c.parent_id, c.comment, c.is_approved, c.created_at, c.updated_at,
-            u.name as user_name
+            c.id, c.parent_id, c.comment, c.is_approved,
+            c.created_at, c.updated_at, u.name as user_name
         FROM tbl_file_comments c
         JOIN tbl_users u ON c.user_id = u.id
         WHERE c.file_id = ? AND c.is_approved = 1
@@ -309,6 +503,25 @@ pub async fn get_file_comments(
     .await
     .map_err(AppError::db_error)?;
 
+    let mention_rows = sqlx::query!(
+        r#"
+        SELECT m.comment_id, m.user_id
+        FROM tbl_comment_mentions m
+        JOIN tbl_file_comments c ON c.id = m.comment_id
+        WHERE c.file_id = ? AND c.is_approved = 1
+        ORDER BY m.comment_id, m.user_id
+        "#,
+        file_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let mut mentions: std::collections::HashMap<i32, Vec<i32>> = std::collections::HashMap::new();
+    for row in mention_rows {
+        mentions.entry(row.comment_id).or_default().push(row.user_id);
+    }
+
     // Build nested comment structure
     let mut comments_map: std::collections::HashMap<i32, CommentResponse> = std::collections::HashMap::new();
     let mut root_comments = Vec::new();
@@ -320,6 +533,7 @@ pub async fn get_file_comments(
             parent_id: row.parent_id,
             comment: row.comment,
             is_approved: row.is_approved.unwrap_or(0) != 0,
+            mentioned_user_ids: mentions.remove(&row.id).unwrap_or_default(),
             created_at: row.created_at.naive_utc(),
             updated_at: row.updated_at.naive_utc(),
             replies: Vec::new(),
@@ -340,28 +554,204 @@ pub async fn get_file_comments(
     Ok(root_comments)
 }
 
+fn comment_status(is_approved: bool, is_rejected: bool) -> CommentStatus {
+    if is_approved {
+        CommentStatus::Approved
+    } else if is_rejected {
+        CommentStatus::Rejected
+    } else {
+        CommentStatus::PendingModeration
+    }
+}
+
+/// Every comment the user wrote, newest first, including ones held for moderation
+/// and ones a moderator rejected
+pub async fn get_user_comments(
+    pool: &MySqlPool,
+    user_id: i32,
+    limit: i32,
+    offset: i32,
+) -> Result<(Vec<MyCommentEntry>, i64), AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            c.id, c.file_id, c.parent_id, c.comment, c.is_approved,
+            c.rejected_at AS "rejected_at?: DateTime<Utc>", c.created_at, c.updated_at,
+            f.name as file_name, f.status as file_status,
+            s.id as scholar_id, s.name as scholar_name
+        FROM tbl_file_comments c
+        LEFT JOIN tbl_files f ON c.file_id = f.id
+        LEFT JOIN tbl_scholars s ON f.scholar = s.id
+        WHERE c.user_id = ?
+        ORDER BY c.created_at DESC, c.id DESC
+        LIMIT ? OFFSET ?
+        "#,
+        user_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let entries = rows
+        .into_iter()
+        .map(|row| {
+            let is_file_available = row.file_status.as_deref() == Some("active");
+            let status = comment_status(row.is_approved.unwrap_or(0) != 0, row.rejected_at.is_some());
+
+            MyCommentEntry {
+                id: row.id,
+                file_id: row.file_id,
+                parent_id: row.parent_id,
+                comment: row.comment,
+                status,
+                created_at: row.created_at.naive_utc(),
+                updated_at: row.updated_at.naive_utc(),
+                is_file_available,
+                file_name: row.file_name.filter(|_| is_file_available),
+                scholar_id: row.scholar_id.filter(|_| is_file_available),
+                scholar_name: row.scholar_name.filter(|_| is_file_available),
+            }
+        })
+        .collect();
+
+    let total_count: i64 = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM tbl_file_comments WHERE user_id = ?",
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok((entries, total_count))
+}
+
+// A held edit unapproves the comment in the same statement; a clean edit keeps its approval state
 pub async fn update_file_comment(
     pool: &MySqlPool,
     comment_id: i32,
     user_id: i32,
     request: &UpdateCommentRequest,
+    mentioned_user_ids: &[i32],
+    held: bool,
 ) -> Result<FileComment, AppError> {
     let now = Utc::now().naive_utc();
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
 
     sqlx::query!(
-        "UPDATE tbl_file_comments SET comment = ?, updated_at = ? WHERE id = ? AND user_id = ?",
+        r#"
+        UPDATE tbl_file_comments
+        SET comment = ?, is_approved = IF(?, 0, is_approved), updated_at = ?
+        WHERE id = ? AND user_id = ?
+        "#,
         request.comment,
+        held,
         now,
         comment_id,
         user_id
     )
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::db_error)?;
+
+    replace_comment_mentions(&mut *tx, comment_id, mentioned_user_ids).await?;
+    tx.commit().await.map_err(AppError::db_error)?;
+
+    get_file_comment_by_id(pool, comment_id).await
+}
+
+// Comments held for moderation, oldest first so the longest-waiting surface
+pub async fn get_pending_comments(
+    pool: &MySqlPool,
+    limit: i32,
+    offset: i32,
+) -> Result<(Vec<PendingComment>, i64), AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.id, c.file_id, f.name as file_name, c.user_id, u.name as user_name,
+               c.parent_id, c.comment, c.created_at, c.updated_at
+        FROM tbl_file_comments c
+        JOIN tbl_users u ON c.user_id = u.id
+        JOIN tbl_files f ON c.file_id = f.id
+        WHERE c.is_approved = 0 AND c.rejected_at IS NULL
+        ORDER BY c.updated_at ASC, c.id ASC
+        LIMIT ? OFFSET ?
+        "#,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let comments = rows
+        .into_iter()
+        .map(|row| PendingComment {
+            id: row.id,
+            file_id: row.file_id,
+            file_name: row.file_name,
+            user_id: row.user_id,
+            user_name: row.user_name,
+            parent_id: row.parent_id,
+            comment: row.comment,
+            created_at: row.created_at.naive_utc(),
+            updated_at: row.updated_at.naive_utc(),
+        })
+        .collect();
+
+    let total_count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)
+        FROM tbl_file_comments c
+        JOIN tbl_users u ON c.user_id = u.id
+        JOIN tbl_files f ON c.file_id = f.id
+        WHERE c.is_approved = 0 AND c.rejected_at IS NULL
+        "#
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok((comments, total_count))
+}
+
+// Publish a held comment. Fails with not found unless the comment is awaiting moderation
+pub async fn approve_comment(pool: &MySqlPool, comment_id: i32) -> Result<FileComment, AppError> {
+    let result = sqlx::query!(
+        "UPDATE tbl_file_comments SET is_approved = 1 WHERE id = ? AND is_approved = 0 AND rejected_at IS NULL",
+        comment_id
+    )
     .execute(pool)
     .await
     .map_err(AppError::db_error)?;
 
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Pending comment not found"));
+    }
+
     get_file_comment_by_id(pool, comment_id).await
 }
 
+// Take a held comment out of the queue; it stays hidden and its author sees it
+// as rejected. Fails with not found unless the comment is awaiting moderation
+pub async fn reject_comment(pool: &MySqlPool, comment_id: i32) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        "UPDATE tbl_file_comments SET rejected_at = ? WHERE id = ? AND is_approved = 0 AND rejected_at IS NULL",
+        Utc::now(),
+        comment_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Pending comment not found"));
+    }
+
+    Ok(())
+}
+
 pub async fn delete_file_comment(
     pool: &MySqlPool,
     comment_id: i32,
@@ -382,7 +772,8 @@ pub async fn delete_file_comment(
 // Download Logs
 pub async fn log_file_download(
     pool: &MySqlPool,
-    user_id: i32,
+    user_id: Option<i32>,
+    anonymous_id: Option<&str>,
     subscription_id: Option<i32>,
     file_id: i32,
     download_ip: Option<String>,
@@ -392,10 +783,11 @@ pub async fn log_file_download(
 
     let result = sqlx::query!(
         r#"
-        INSERT INTO tbl_download_logs (user_id, subscription_id, file_id, download_ip, user_agent, downloaded_at)
-        VALUES (?, ?, ?, ?, ?, ?)
+        INSERT INTO tbl_download_logs (user_id, anonymous_id, subscription_id, file_id, download_ip, user_agent, downloaded_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         "#,
         user_id,
+        anonymous_id,
         subscription_id,
         file_id,
         download_ip,
@@ -406,7 +798,8 @@ pub async fn log_file_download(
     .await
     .map_err(AppError::db_error)?;
 
-    // Increment total downloads counter for the file
+    // `tbl_files.downloads` is the source of truth for download totals, so every
+    // logged download must bump it; `recompute_counters` repairs any drift
     sqlx::query!(
         r#"UPDATE tbl_files SET downloads = downloads + 1 WHERE id = ?"#,
         file_id
@@ -425,7 +818,7 @@ pub async fn get_download_log_by_id(
 ) -> Result<DownloadLog, AppError> {
     let row = sqlx::query!(
         r#"
-        SELECT id, user_id, subscription_id, file_id, download_ip, user_agent, downloaded_at
+        SELECT id, user_id, anonymous_id, subscription_id, file_id, download_ip, user_agent, downloaded_at
         FROM tbl_download_logs
         WHERE id = ?
         "#,
@@ -437,7 +830,8 @@ pub async fn get_download_log_by_id(
 
     Ok(DownloadLog {
         id: row.id,
-        user_id: row.user_id.unwrap_or(0),
+        user_id: row.user_id,
+        anonymous_id: row.anonymous_id,
         subscription_id: row.subscription_id,
         file_id: row.file_id,
         download_ip: row.download_ip,
@@ -450,16 +844,23 @@ pub async fn get_file_download_stats(
     pool: &MySqlPool,
     file_id: i32,
 ) -> Result<DownloadStats, AppError> {
+    if !crate::db::files::file_exists(pool, file_id).await? {
+        return Err(AppError::not_found("File not found"));
+    }
+
+    // The total comes from the `tbl_files.downloads` counter like every listing;
+    // the log only supplies the breakdowns
     let row = sqlx::query!(
         r#"
         SELECT 
-            COUNT(*) as total_downloads,
+            (SELECT CAST(downloads AS SIGNED) FROM tbl_files WHERE id = ?) as "total_downloads!: i64",
             COUNT(DISTINCT user_id) as unique_users,
-            COUNT(CASE WHEN DATE(downloaded_at) = CURDATE() THEN 1 END) as downloads_today,
-            COUNT(CASE WHEN YEAR(downloaded_at) = YEAR(CURDATE()) AND MONTH(downloaded_at) = MONTH(CURDATE()) THEN 1 END) as downloads_this_month
+            COUNT(CASE WHEN DATE(downloaded_at) = UTC_DATE() THEN 1 END) as downloads_today,
+            COUNT(CASE WHEN YEAR(downloaded_at) = YEAR(UTC_DATE()) AND MONTH(downloaded_at) = MONTH(UTC_DATE()) THEN 1 END) as downloads_this_month
         FROM tbl_download_logs
         WHERE file_id = ?
         "#,
+        file_id,
         file_id
     )
     .fetch_one(pool)
@@ -479,13 +880,13 @@ pub async fn get_user_download_history(
     user_id: i32,
     limit: Option<i32>,
     offset: Option<i32>,
-) -> Result<Vec<DownloadLog>, AppError> {
+) -> Result<(Vec<DownloadLog>, i64), AppError> {
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
 
     let rows = sqlx::query!(
         r#"
-        SELECT id, user_id, subscription_id, file_id, download_ip, user_agent, downloaded_at
+        SELECT id, user_id, anonymous_id, subscription_id, file_id, download_ip, user_agent, downloaded_at
         FROM tbl_download_logs
         WHERE user_id = ?
         ORDER BY downloaded_at DESC
@@ -503,7 +904,8 @@ pub async fn get_user_download_history(
         .into_iter()
         .map(|row| DownloadLog {
             id: row.id,
-            user_id: row.user_id.unwrap_or(0),
+            user_id: row.user_id,
+            anonymous_id: row.anonymous_id,
             subscription_id: row.subscription_id,
             file_id: row.file_id,
             download_ip: row.download_ip,
@@ -512,5 +914,182 @@ pub async fn get_user_download_history(
         })
         .collect();
 
-    Ok(logs)
-}
\ No newline at end of file
+    let total_count: i64 = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM tbl_download_logs WHERE user_id = ?",
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok((logs, total_count))
+}
+
+struct DownloadHistoryRow {
+    id: i32,
+    file_id: i32,
+    downloaded_at: DateTime<Utc>,
+    file_name: Option<String>,
+    location: Option<String>,
+    file_status: Option<String>,
+    scholar_id: Option<i32>,
+    scholar_name: Option<String>,
+    book_id: Option<i32>,
+    book_name: Option<String>,
+}
+
+// Files no longer active become tombstones with only the log's own fields
+fn download_history_entry(config: &AppConfig, row: DownloadHistoryRow) -> DownloadHistoryEntry {
+    let is_available = row.file_status.as_deref() == Some("active");
+    if !is_available {
+        return DownloadHistoryEntry {
+            id: row.id,
+            file_id: row.file_id,
+            downloaded_at: row.downloaded_at.naive_utc(),
+            is_available,
+            file_name: None,
+            file_url: None,
+            scholar_id: None,
+            scholar_name: None,
+            book_id: None,
+            book_name: None,
+        };
+    }
+
+    DownloadHistoryEntry {
+        id: row.id,
+        file_id: row.file_id,
+        downloaded_at: row.downloaded_at.naive_utc(),
+        is_available,
+        file_name: row.file_name,
+        file_url: row.location.map(|location| config.get_upload_url(&location)),
+        scholar_id: row.scholar_id,
+        scholar_name: row.scholar_name,
+        book_id: row.book_id,
+        book_name: row.book_name,
+    }
+}
+
+// Download history with file, scholar and book details, newest first
+pub async fn get_user_download_history_details(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    user_id: i32,
+    limit: i32,
+    offset: i32,
+) -> Result<(Vec<DownloadHistoryEntry>, i64), AppError> {
+    let rows = sqlx::query_as!(
+        DownloadHistoryRow,
+        r#"
+        SELECT
+            dl.id, dl.file_id, dl.downloaded_at,
+            f.name as file_name, f.location, f.status as file_status,
+            s.id as scholar_id, s.name as scholar_name,
+            b.id as book_id, b.name as book_name
+        FROM tbl_download_logs dl
+        LEFT JOIN tbl_files f ON dl.file_id = f.id
+        LEFT JOIN tbl_scholars s ON f.scholar = s.id
+        LEFT JOIN tbl_books b ON f.book = b.id
+        WHERE dl.user_id = ?
+        ORDER BY dl.downloaded_at DESC, dl.id DESC
+        LIMIT ? OFFSET ?
+        "#,
+        user_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let entries = rows
+        .into_iter()
+        .map(|row| download_history_entry(config, row))
+        .collect();
+
+    let total_count: i64 = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM tbl_download_logs WHERE user_id = ?",
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok((entries, total_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_reports_carry_their_age_oldest_first() {
+        let now = Utc::now();
+        let report = |id: i32, hours_ago: i64| PendingReportRow {
+            id,
+            user_id: Some(3),
+            file_id: 9,
+            reason: "inaccurate".to_string(),
+            description: None,
+            status: "pending".to_string(),
+            admin_notes: None,
+            resolved_by: None,
+            created_at: now - chrono::Duration::hours(hours_ago),
+            resolved_at: None,
+        };
+        // As the default ORDER BY created_at ASC returns them
+        let rows = vec![report(1, 72), report(2, 5), report(3, 0)];
+
+        let reports: Vec<PendingFileReport> =
+            rows.into_iter().map(|row| pending_report(now, row)).collect();
+
+        assert_eq!(
+            reports.iter().map(|r| (r.report.id, r.age_hours)).collect::<Vec<_>>(),
+            vec![(1, 72), (2, 5), (3, 0)]
+        );
+
+        let meta = crate::models::pagination::PaginationMeta::new(1, 2, 3);
+        assert_eq!(meta.total_items, 3);
+        assert_eq!(meta.total_pages, 2);
+    }
+
+    #[test]
+    fn rejected_comments_are_reported_as_rejected() {
+        assert_eq!(comment_status(true, false), CommentStatus::Approved);
+        assert_eq!(comment_status(false, false), CommentStatus::PendingModeration);
+        assert_eq!(comment_status(false, true), CommentStatus::Rejected);
+    }
+
+    fn download_row(file_status: Option<&str>) -> DownloadHistoryRow {
+        DownloadHistoryRow {
+            id: 1,
+            file_id: 42,
+            downloaded_at: Utc::now(),
+            file_name: Some("Lesson 3".to_string()),
+            location: Some("lesson-3.mp3".to_string()),
+            file_status: file_status.map(str::to_string),
+            scholar_id: Some(7),
+            scholar_name: Some("Sheikh Albani".to_string()),
+            book_id: Some(9),
+            book_name: Some("Sifatus Salah".to_string()),
+        }
+    }
+
+    #[test]
+    fn logged_downloads_carry_their_file_details() {
+        let config = AppConfig::new().expect("local configuration");
+
+        let entry = download_history_entry(&config, download_row(Some("active")));
+        assert!(entry.is_available);
+        assert_eq!(entry.file_name.as_deref(), Some("Lesson 3"));
+        assert_eq!(entry.file_url, Some(config.get_upload_url("lesson-3.mp3")));
+        assert_eq!(entry.scholar_name.as_deref(), Some("Sheikh Albani"));
+        assert_eq!(entry.book_name.as_deref(), Some("Sifatus Salah"));
+
+        // Purged files stay listed as tombstones
+        let purged = download_history_entry(&config, download_row(None));
+        assert!(!purged.is_available);
+        assert_eq!(purged.file_id, 42);
+        assert!(purged.file_name.is_none() && purged.file_url.is_none());
+    }
+}
@@ -1,10 +1,16 @@
-use crate::core::{calculate_total_duration_from_strings, AppConfig, AppError};
+use crate::core::{
+    calculate_total_duration_from_strings, is_safe_storage_location, push_in_list, search_like_pattern,
+    AppConfig, AppError,
+};
 use crate::models::files::{
-    FileSearchResult, FileStatistics, Files, FilesWithStats, RecentFiles, RecentFilesWithStats,
+    BookFilesFilter, BookFilesQuery, FileSearchResult, FileSearchScope, FileStatistics, Files, FilesWithStats, RecentFiles, RecentFilesWithStats,
     RelatedFiles, ViewFileDetails,
 };
-use crate::models::pagination::PaginationQuery;
-use sqlx::MySqlPool;
+use crate::models::pagination::{DateIdCursor, PaginationQuery};
+use crate::models::play_history::FileListeningState;
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use std::collections::HashMap;
 
 pub async fn fetch_files_by_book(
     pool: &MySqlPool,
@@ -12,6 +18,8 @@ pub async fn fetch_files_by_book(
     book_id: i32,
     pagination: &PaginationQuery,
 ) -> Result<(Vec<Files>, i64), AppError> {
+    crate::db::books::assert_book_active(pool, book_id).await?;
+
     let raw_files = sqlx::query!(
         "SELECT
             f.id as file_id,
@@ -26,8 +34,10 @@ pub async fn fetch_files_by_book(
             s.name as scholar_name,
             s.image as scholar_image
         FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON f.scholar = s.id
         WHERE f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
         AND f.book = ?
         LIMIT ? OFFSET ?",
         book_id,
@@ -57,7 +67,12 @@ pub async fn fetch_files_by_book(
         .collect();
 
     let total_count: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM tbl_files WHERE book = ? AND status = 'active'",
+        r#"
+        SELECT COUNT(*) FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
         book_id
     )
     .fetch_one(pool)
@@ -87,8 +102,10 @@ pub async fn fetch_recent_files(
             s.name as scholar_name,
             s.image as scholar_image
         FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON f.scholar = s.id
         WHERE f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
         ORDER BY f.date DESC
         LIMIT ? OFFSET ?
         "#,
@@ -117,27 +134,108 @@ pub async fn fetch_recent_files(
         })
         .collect();
 
-    let total_count: i64 =
-        sqlx::query_scalar!("SELECT COUNT(*) FROM tbl_files WHERE status = 'active'")
-            .fetch_one(pool)
-            .await
-            .map_err(AppError::db_error)?;
+    let total_count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.status = 'active' AND is_published(f.publish_at, b.publish_at)
+        "#
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
 
     Ok((files, total_count))
 }
 
+// Latest active files of a scholar, skipping files in inactive books
+pub async fn fetch_recent_files_by_scholar(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    scholar_id: i32,
+    limit: i32,
+    include_restricted: bool,
+) -> Result<Vec<RecentFiles>, AppError> {
+    let raw_files = sqlx::query!(
+        r#"
+        SELECT
+            f.id as file_id,
+            f.name as file_name,
+            f.book as book_id,
+            f.size as file_size,
+            f.duration as file_duration,
+            f.date,
+            f.downloads,
+            f.location,
+            s.id as scholar_id,
+            s.name as scholar_name,
+            s.image as scholar_image
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        JOIN tbl_scholars s ON f.scholar = s.id
+        WHERE f.scholar = ? AND f.status = 'active' AND b.status = 'active'
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        ORDER BY f.date DESC, f.id DESC
+        LIMIT ?
+        "#,
+        scholar_id,
+        include_restricted,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(raw_files
+        .into_iter()
+        .map(|row| RecentFiles {
+            file_id: row.file_id,
+            file_name: row.file_name,
+            file_url: config.get_upload_url(&row.location),
+            file_size: row.file_size,
+            file_duration: row.file_duration,
+            downloads: row.downloads,
+            book_id: row.book_id,
+            scholar_id: row.scholar_id,
+            scholar_name: row.scholar_name,
+            scholar_image: config.get_image_url(&row.scholar_image),
+            date: row.date.into(),
+        })
+        .collect())
+}
+
+#[derive(sqlx::FromRow)]
+struct FileSearchRow {
+    file_id: i32,
+    file_name: String,
+    book_id: i32,
+    file_size: String,
+    file_duration: String,
+    downloads: i32,
+    location: String,
+    scholar_id: i32,
+    scholar_name: String,
+    scholar_image: String,
+}
+
 pub async fn search_files(
     pool: &MySqlPool,
     config: &AppConfig,
     search_term: &str,
+    scope: FileSearchScope,
     page: i32,
     items_per_page: i32,
+    include_restricted: bool,
 ) -> Result<(Vec<FileSearchResult>, i64), AppError> {
+    let Some(pattern) = search_like_pattern(search_term) else {
+        return Ok((Vec::new(), 0));
+    };
+    let term = search_term.trim();
     let offset = (page - 1) * items_per_page;
 
-    let raw_files = sqlx::query!(
-        r#"
-        SELECT 
+    let mut query = QueryBuilder::<MySql>::new(
+        r#"SELECT 
             f.id as file_id,
             f.name as file_name,
             f.book as book_id,
@@ -149,19 +247,22 @@ pub async fn search_files(
             s.name as scholar_name,
             s.image as scholar_image
         FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON f.scholar = s.id
-        WHERE (f.name LIKE ? OR f.location LIKE ?) AND f.status = 'active'
-        ORDER BY f.date DESC
-        LIMIT ? OFFSET ?
         "#,
-        format!("%{}%", search_term),
-        format!("%{}%", search_term),
-        items_per_page,
-        offset
-    )
-    .fetch_all(pool)
-    .await
-    .map_err(|e| AppError::db_error(e))?;
+    );
+    push_file_search_filters(&mut query, &pattern, scope, include_restricted);
+    push_file_search_ranking(&mut query, term, &pattern);
+    query.push(" LIMIT ");
+    query.push_bind(items_per_page);
+    query.push(" OFFSET ");
+    query.push_bind(offset);
+
+    let raw_files = query
+        .build_query_as::<FileSearchRow>()
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?;
 
     // Convert raw data to FileSearchResult struct with formatted URLs
     let files: Vec<FileSearchResult> = raw_files
@@ -177,29 +278,72 @@ pub async fn search_files(
             scholar_id: row.scholar_id,
             scholar_name: row.scholar_name,
             scholar_image: config.get_image_url(&row.scholar_image),
+            highlight: None,
         })
         .collect();
 
-    let total_count: i64 = sqlx::query_scalar!(
-        r#"
-        SELECT COUNT(*) 
-        FROM tbl_files f
-        WHERE (f.name LIKE ? OR f.location LIKE ?) AND f.status = 'active'
-        "#,
-        format!("%{}%", search_term),
-        format!("%{}%", search_term)
-    )
-    .fetch_one(pool)
-    .await
-    .map_err(|e| AppError::db_error(e))?;
+    let mut query = QueryBuilder::<MySql>::new(
+        "SELECT COUNT(*) FROM tbl_files f JOIN tbl_books b ON f.book = b.id ",
+    );
+    push_file_search_filters(&mut query, &pattern, scope, include_restricted);
+    let total_count: i64 = query
+        .build_query_scalar()
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::db_error)?;
 
     Ok((files, total_count))
 }
 
+// The WHERE clause shared by file search and its count; a scope adds its
+// scholar or book filter, so files outside it are never matched
+fn push_file_search_filters<'args>(
+    query: &mut QueryBuilder<'args, MySql>,
+    pattern: &'args str,
+    scope: FileSearchScope,
+    include_restricted: bool,
+) {
+    query.push("WHERE (f.name LIKE ");
+    query.push_bind(pattern);
+    query.push(" OR f.location LIKE ");
+    query.push_bind(pattern);
+    query.push(") AND f.status = 'active' AND (f.restricted = FALSE OR ");
+    query.push_bind(include_restricted);
+    query.push(") AND is_published(f.publish_at, b.publish_at)");
+    match scope {
+        FileSearchScope::All => {}
+        FileSearchScope::Scholar(scholar_id) => {
+            query.push(" AND f.scholar = ");
+            query.push_bind(scholar_id);
+        }
+        FileSearchScope::Book(book_id) => {
+            query.push(" AND f.book = ");
+            query.push_bind(book_id);
+        }
+    }
+}
+
+// Relevance: an exact name, then a name starting with the term, then a name
+// containing it, then a match on the stored location only; newest first within each
+fn push_file_search_ranking<'args>(query: &mut QueryBuilder<'args, MySql>, term: &'args str, pattern: &'args str) {
+    // `pattern` is `%term%` with the term escaped; dropping the leading `%` anchors it
+    let prefix_pattern = &pattern[1..];
+    query.push(" ORDER BY CASE WHEN f.name = ");
+    query.push_bind(term);
+    query.push(" THEN 0 WHEN f.name LIKE ");
+    query.push_bind(prefix_pattern);
+    query.push(" THEN 1 WHEN f.name LIKE ");
+    query.push_bind(pattern);
+    query.push(" THEN 2 ELSE 3 END, f.date DESC, f.id DESC");
+}
+
+/// Fails with "File not found" for a file that isn't published yet unless
+/// `include_unpublished` is set, as it is for the people editing it
 pub async fn fetch_file_details(
     pool: &MySqlPool,
     config: &AppConfig,
     file_id: i32,
+    include_unpublished: bool,
 ) -> Result<ViewFileDetails, AppError> {
     let raw_file = sqlx::query!(
         r#"
@@ -211,6 +355,7 @@ pub async fn fetch_file_details(
             f.size,
             f.date as created_at,
             f.downloads,
+            f.version,
             b.id as book_id,
             b.name as book_name,
             b.image as book_image,
@@ -221,15 +366,18 @@ pub async fn fetch_file_details(
         JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON f.scholar = s.id
         WHERE f.id = ? AND f.status = 'active'
+        AND (? OR is_published(f.publish_at, b.publish_at))
         "#,
-        file_id
+        file_id,
+        include_unpublished
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
     .map_err(|e| {
         tracing::error!("Failed to fetch file details: {:?}", e);
         AppError::db_error(e.to_string())
-    })?;
+    })?
+    .ok_or_else(|| AppError::not_found("File not found"))?;
 
     Ok(ViewFileDetails {
         file_id: raw_file.file_id,
@@ -245,9 +393,70 @@ pub async fn fetch_file_details(
         scholar_name: raw_file.scholar_name,
         scholar_image: config.get_image_url(&raw_file.scholar_image),
         downloads: raw_file.downloads,
+        version: raw_file.version,
     })
 }
 
+/// Restricted files are only listed for signed-in users
+pub fn can_view_restricted(user_id: Option<i32>) -> bool {
+    user_id.is_some()
+}
+
+/// Set or clear the restricted flag; false when no active file has this id
+pub async fn set_file_restricted(
+    pool: &MySqlPool,
+    file_id: i32,
+    restricted: bool,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE tbl_files SET restricted = ? WHERE id = ? AND status = 'active'",
+        restricted,
+        file_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    if result.rows_affected() > 0 {
+        return Ok(true);
+    }
+
+    // MySQL reports 0 affected rows when the flag already had this value
+    active_file_exists(pool, file_id).await
+}
+
+/// Whether an active, published file with this id exists
+pub async fn file_exists(pool: &MySqlPool, file_id: i32) -> Result<bool, AppError> {
+    let id = sqlx::query_scalar!(
+        r#"
+        SELECT f.id FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.id = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
+        file_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(id.is_some())
+}
+
+/// Whether an active file with this id exists, even one not published yet;
+/// for admin and uploader paths
+pub async fn active_file_exists(pool: &MySqlPool, file_id: i32) -> Result<bool, AppError> {
+    let id = sqlx::query_scalar!(
+        "SELECT id FROM tbl_files WHERE id = ? AND status = 'active'",
+        file_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(id.is_some())
+}
+
 pub async fn fetch_book_id_for_file(pool: &MySqlPool, file_id: i32) -> Result<i32, AppError> {
     let result = sqlx::query!(
         r#"
@@ -257,21 +466,35 @@ pub async fn fetch_book_id_for_file(pool: &MySqlPool, file_id: i32) -> Result<i3
         "#,
         file_id
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
     .map_err(|e| {
         tracing::error!("Failed to fetch main file's book_id: {:?}", e);
         AppError::db_error(e.to_string())
-    })?;
+    })?
+    .ok_or_else(|| AppError::not_found("File not found"))?;
 
     Ok(result.book)
 }
 
+/// Stored duration string ("MM:SS" or "HH:MM:SS") of an active file
+pub async fn fetch_file_duration(pool: &MySqlPool, file_id: i32) -> Result<String, AppError> {
+    sqlx::query_scalar!(
+        "SELECT duration FROM tbl_files WHERE id = ? AND status = 'active'",
+        file_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("File not found"))
+}
+
 pub async fn fetch_related_files(
     pool: &MySqlPool,
     config: &AppConfig,
     book_id: i32,
     exclude_file_id: i32,
+    include_restricted: bool,
     pagination: &PaginationQuery,
 ) -> Result<(Vec<RelatedFiles>, i64), AppError> {
     let raw_files = sqlx::query!(
@@ -288,13 +511,17 @@ pub async fn fetch_related_files(
             s.name as scholar_name,
             s.image as scholar_image
         FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON f.scholar = s.id
         WHERE f.book = ? AND f.id != ? AND f.status = 'active'
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
         ORDER BY f.created_at DESC
         LIMIT ? OFFSET ?
         "#,
         book_id,
         exclude_file_id,
+        include_restricted,
         pagination.per_page,
         pagination.offset()
     )
@@ -325,11 +552,15 @@ pub async fn fetch_related_files(
     let total_count: i64 = sqlx::query_scalar!(
         r#"
         SELECT COUNT(*) 
-        FROM tbl_files 
-        WHERE book = ? AND id != ? AND status = 'active'
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.id != ? AND f.status = 'active'
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
         "#,
         book_id,
-        exclude_file_id
+        exclude_file_id,
+        include_restricted
     )
     .fetch_one(pool)
     .await
@@ -350,10 +581,14 @@ pub async fn create_file_record(
     book_id: i32,
     scholar_id: i32,
 ) -> Result<i32, AppError> {
+    if !is_safe_storage_location(location) {
+        return Err(AppError::bad_request(format!("Invalid file location: {}", location)));
+    }
+
     let result = sqlx::query!(
         r#"
         INSERT INTO tbl_files (name, location, size, duration, book, scholar, status, created_at, date)
-        VALUES (?, ?, ?, ?, ?, ?, 'active', NOW(), NOW())
+        VALUES (?, ?, ?, ?, ?, ?, 'active', UTC_TIMESTAMP(), UTC_TIMESTAMP())
         "#,
         name,
         location,
@@ -371,15 +606,36 @@ pub async fn create_file_record(
 
     Ok(result.last_insert_id() as i32)
 }
+/// Whether the listing is limited to files the user hasn't played. That
+/// filter only makes sense for a signed-in user
+fn book_files_not_played(options: &BookFilesQuery, user_id: Option<i32>) -> Result<bool, AppError> {
+    let not_played = options.filter == Some(BookFilesFilter::NotPlayed);
+    if not_played && user_id.is_none() {
+        return Err(AppError::unauthorized("Sign in to filter by files you haven't played"));
+    }
+    Ok(not_played)
+}
+
+/// Files of a book with their stats. `sort` falls back to upload order, which
+/// is also the tiebreak for every sort; `not_played` needs a `user_id`
 pub async fn fetch_files_by_book_with_stats(
     pool: &MySqlPool,
     config: &AppConfig,
     book_id: i32,
     pagination: &PaginationQuery,
+    options: &BookFilesQuery,
     user_id: Option<i32>,
 ) -> Result<(Vec<FilesWithStats>, i64), AppError> {
+    crate::db::books::assert_book_active(pool, book_id).await?;
+
+    let not_played = book_files_not_played(options, user_id)?;
+
+    let include_restricted = can_view_restricted(user_id);
+    let sort = options.sort.map(|sort| sort.as_str()).unwrap_or("");
+    // Durations are stored as "M:SS" or "H:MM:SS"
     let raw_files = sqlx::query!(
-        "SELECT
+        r#"
+        SELECT
             f.id as file_id,
             f.name as file_name,
             f.book as book_id,
@@ -393,11 +649,39 @@ pub async fn fetch_files_by_book_with_stats(
             s.name as scholar_name,
             s.image as scholar_image
         FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON f.scholar = s.id
         WHERE f.status = 'active'
         AND f.book = ?
-        LIMIT ? OFFSET ?",
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        AND (? = FALSE OR NOT EXISTS (
+            SELECT 1 FROM tbl_play_history ph WHERE ph.file_id = f.id AND ph.user_id = ?
+        ))
+        ORDER BY
+            CASE WHEN ? = 'newest' THEN f.date END DESC,
+            CASE WHEN ? = 'oldest' THEN f.date END ASC,
+            CASE WHEN ? = 'most_played' THEN
+                (SELECT COUNT(*) FROM tbl_play_history ph WHERE ph.file_id = f.id)
+            END DESC,
+            CASE WHEN ? = 'duration' THEN
+                TIME_TO_SEC(IF(
+                    LENGTH(f.duration) - LENGTH(REPLACE(f.duration, ':', '')) = 2,
+                    f.duration,
+                    CONCAT('0:', f.duration)
+                ))
+            END ASC,
+            f.id ASC
+        LIMIT ? OFFSET ?
+        "#,
         book_id,
+        include_restricted,
+        not_played,
+        user_id,
+        sort,
+        sort,
+        sort,
+        sort,
         pagination.per_page,
         pagination.offset()
     )
@@ -406,17 +690,37 @@ pub async fn fetch_files_by_book_with_stats(
     .map_err(AppError::db_error)?;
 
     let total_count: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM tbl_files WHERE book = ? AND status = 'active'",
-        book_id
+        r#"
+        SELECT COUNT(*) FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        AND (? = FALSE OR NOT EXISTS (
+            SELECT 1 FROM tbl_play_history ph WHERE ph.file_id = f.id AND ph.user_id = ?
+        ))
+        "#,
+        book_id,
+        include_restricted,
+        not_played,
+        user_id
     )
     .fetch_one(pool)
     .await
     .map_err(AppError::db_error)?;
 
+    let listening_states = match user_id {
+        Some(uid) => {
+            let file_ids: Vec<i32> = raw_files.iter().map(|row| row.file_id).collect();
+            Some(crate::db::play_history::get_listening_states(pool, uid, &file_ids).await?)
+        }
+        None => None,
+    };
+
     // Convert raw data to FilesWithStats by adding statistics and formatting URLs
     let mut files_with_stats = Vec::new();
     for row in raw_files {
         let statistics = get_file_statistics(pool, row.file_id, user_id).await?;
+        let listening = listening_state_for(listening_states.as_ref(), row.file_id);
         files_with_stats.push(FilesWithStats {
             file_id: row.file_id,
             file_name: row.file_name,
@@ -430,6 +734,223 @@ pub async fn fetch_files_by_book_with_stats(
             date: row.date.into(),
             uploaded_by: row.created_by,
             statistics,
+            has_played: listening.map(|state| state.has_played),
+            last_position_seconds: listening.and_then(|state| state.last_position_seconds),
+            completed: listening.map(|state| state.completed),
+        });
+    }
+
+    Ok((files_with_stats, total_count))
+}
+
+pub async fn fetch_file_with_stats(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    file_id: i32,
+    user_id: Option<i32>,
+) -> Result<Option<FilesWithStats>, AppError> {
+    Ok(fetch_files_with_stats_by_ids(pool, config, &[file_id], user_id)
+        .await?
+        .pop())
+}
+
+/// Load several files with their statistics in one round trip, keeping the
+/// order of `file_ids`. Ids that are missing, inactive, unpublished or
+/// hidden from the user are skipped.
+pub async fn fetch_files_with_stats_by_ids(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    file_ids: &[i32],
+    user_id: Option<i32>,
+) -> Result<Vec<FilesWithStats>, AppError> {
+    if file_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = files_with_stats_query(file_ids, user_id);
+
+    let rows = query
+        .build_query_as::<FileWithStatsRow>()
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    let listening_states = match user_id {
+        Some(uid) => Some(crate::db::play_history::get_listening_states(pool, uid, file_ids).await?),
+        None => None,
+    };
+
+    let mut by_id: HashMap<i32, FilesWithStats> = rows
+        .into_iter()
+        .map(|row| {
+            let listening = listening_state_for(listening_states.as_ref(), row.file_id);
+            let file = FilesWithStats {
+                file_id: row.file_id,
+                file_name: row.file_name,
+                file_url: config.get_upload_url(&row.location),
+                file_size: row.file_size,
+                book_id: row.book_id,
+                file_duration: row.file_duration,
+                scholar_id: row.scholar_id,
+                scholar_name: row.scholar_name,
+                scholar_image: config.get_image_url(&row.scholar_image),
+                date: row.date.into(),
+                uploaded_by: row.created_by,
+                statistics: FileStatistics {
+                    total_downloads: row.total_downloads,
+                    total_plays: row.total_plays,
+                    total_likes: row.total_likes,
+                    total_comments: row.total_comments,
+                    is_liked_by_user: user_id.map(|_| row.liked_by_user > 0),
+                },
+                has_played: listening.map(|state| state.has_played),
+                last_position_seconds: listening.and_then(|state| state.last_position_seconds),
+                completed: listening.map(|state| state.completed),
+            };
+            (row.file_id, file)
+        })
+        .collect();
+
+    Ok(file_ids.iter().filter_map(|id| by_id.remove(id)).collect())
+}
+
+// Download totals come from the `tbl_files.downloads` counter, the same value
+// listings show as `downloads`, never from counting `tbl_download_logs`
+fn files_with_stats_query(file_ids: &[i32], user_id: Option<i32>) -> QueryBuilder<'static, MySql> {
+    let mut query = QueryBuilder::<MySql>::new(
+        r#"
+        SELECT
+            f.id as file_id,
+            f.name as file_name,
+            f.book as book_id,
+            f.size as file_size,
+            f.duration as file_duration,
+            f.date,
+            f.location,
+            f.created_by,
+            s.id as scholar_id,
+            s.name as scholar_name,
+            s.image as scholar_image,
+            CAST(f.downloads AS SIGNED) as total_downloads,
+            (SELECT COUNT(*) FROM tbl_play_history ph WHERE ph.file_id = f.id) as total_plays,
+            (SELECT COUNT(*) FROM tbl_file_likes fl WHERE fl.file_id = f.id) as total_likes,
+            (SELECT COUNT(*) FROM tbl_file_comments fc WHERE fc.file_id = f.id AND fc.is_approved = 1) as total_comments,
+            (SELECT COUNT(*) FROM tbl_file_likes ul WHERE ul.file_id = f.id AND ul.user_id = "#,
+    );
+    query.push_bind(user_id);
+    query.push(
+        r#") as liked_by_user
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        JOIN tbl_scholars s ON f.scholar = s.id
+        WHERE "#,
+    );
+    push_in_list(&mut query, "f.id", file_ids.iter().copied());
+    query.push(" AND f.status = 'active' AND (f.restricted = FALSE OR ");
+    query.push_bind(can_view_restricted(user_id));
+    query.push(") AND is_published(f.publish_at, b.publish_at)");
+
+    query
+}
+
+#[derive(sqlx::FromRow)]
+struct FileWithStatsRow {
+    file_id: i32,
+    file_name: String,
+    book_id: i32,
+    file_size: String,
+    file_duration: String,
+    date: DateTime<Utc>,
+    location: String,
+    created_by: i32,
+    scholar_id: i32,
+    scholar_name: String,
+    scholar_image: String,
+    total_downloads: i64,
+    total_plays: i64,
+    total_likes: i64,
+    total_comments: i64,
+    liked_by_user: i64,
+}
+
+// Without a user every field stays None; a user who never played the file gets the default state
+fn listening_state_for(
+    states: Option<&HashMap<i32, FileListeningState>>,
+    file_id: i32,
+) -> Option<FileListeningState> {
+    states.map(|states| states.get(&file_id).copied().unwrap_or_default())
+}
+
+pub async fn fetch_liked_files_with_stats(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    user_id: i32,
+    pagination: &PaginationQuery,
+) -> Result<(Vec<FilesWithStats>, i64), AppError> {
+    let raw_files = sqlx::query!(
+        "SELECT
+            f.id as file_id,
+            f.name as file_name,
+            f.book as book_id,
+            f.size as file_size,
+            f.duration as file_duration,
+            f.date,
+            f.downloads,
+            f.location,
+            f.created_by,
+            s.id as scholar_id,
+            s.name as scholar_name,
+            s.image as scholar_image
+        FROM tbl_file_likes fl
+        JOIN tbl_files f ON fl.file_id = f.id
+        JOIN tbl_scholars s ON f.scholar = s.id
+        WHERE fl.user_id = ?
+        AND f.status = 'active'
+        ORDER BY fl.created_at DESC, fl.id DESC
+        LIMIT ? OFFSET ?",
+        user_id,
+        pagination.per_page,
+        pagination.offset()
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let total_count: i64 = sqlx::query_scalar!(
+        "SELECT COUNT(*)
+        FROM tbl_file_likes fl
+        JOIN tbl_files f ON fl.file_id = f.id
+        WHERE fl.user_id = ? AND f.status = 'active'",
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let file_ids: Vec<i32> = raw_files.iter().map(|row| row.file_id).collect();
+    let listening_states =
+        crate::db::play_history::get_listening_states(pool, user_id, &file_ids).await?;
+
+    let mut files_with_stats = Vec::new();
+    for row in raw_files {
+        let statistics = get_file_statistics(pool, row.file_id, Some(user_id)).await?;
+        let listening = listening_state_for(Some(&listening_states), row.file_id);
+        files_with_stats.push(FilesWithStats {
+            file_id: row.file_id,
+            file_name: row.file_name,
+            file_url: config.get_upload_url(&row.location),
+            file_size: row.file_size,
+            book_id: row.book_id,
+            file_duration: row.file_duration,
+            scholar_id: row.scholar_id,
+            scholar_name: row.scholar_name,
+            scholar_image: config.get_image_url(&row.scholar_image),
+            date: row.date.into(),
+            uploaded_by: row.created_by,
+            statistics,
+            has_played: listening.map(|state| state.has_played),
+            last_position_seconds: listening.and_then(|state| state.last_position_seconds),
+            completed: listening.map(|state| state.completed),
         });
     }
 
@@ -442,6 +963,7 @@ pub async fn fetch_recent_files_with_stats(
     pagination: &PaginationQuery,
     user_id: Option<i32>,
 ) -> Result<(Vec<RecentFilesWithStats>, i64), AppError> {
+    let include_restricted = can_view_restricted(user_id);
     let raw_files = sqlx::query!(
         r#"
         SELECT
@@ -457,11 +979,14 @@ pub async fn fetch_recent_files_with_stats(
             s.name as scholar_name,
             s.image as scholar_image
         FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON f.scholar = s.id
-        WHERE f.status = 'active'
-        ORDER BY f.date DESC
+        WHERE f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        ORDER BY f.date DESC, f.id DESC
         LIMIT ? OFFSET ?
         "#,
+        include_restricted,
         pagination.per_page,
         pagination.offset()
     )
@@ -469,11 +994,18 @@ pub async fn fetch_recent_files_with_stats(
     .await
     .map_err(AppError::db_error)?;
 
-    let total_count: i64 =
-        sqlx::query_scalar!("SELECT COUNT(*) FROM tbl_files WHERE status = 'active'")
-            .fetch_one(pool)
-            .await
-            .map_err(AppError::db_error)?;
+    let total_count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
+        include_restricted
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
 
     // Convert raw data to RecentFilesWithStats by adding statistics and formatting URLs
     let mut files_with_stats = Vec::new();
@@ -497,6 +1029,88 @@ pub async fn fetch_recent_files_with_stats(
     Ok((files_with_stats, total_count))
 }
 
+/// Keyset variant of `fetch_recent_files_with_stats` for clients paging while
+/// files are being uploaded. Rows sort by `(date DESC, id DESC)` and each page
+/// starts strictly after `after`, so inserts never shift or repeat items.
+/// Returns the page and the cursor for the next one, if any.
+pub async fn fetch_recent_files_after(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    after: Option<DateIdCursor>,
+    limit: i32,
+    user_id: Option<i32>,
+) -> Result<(Vec<RecentFilesWithStats>, Option<DateIdCursor>), AppError> {
+    let include_restricted = can_view_restricted(user_id);
+    let after_date = after.map(|cursor| cursor.date);
+    let after_id = after.map(|cursor| cursor.id);
+
+    // One extra row tells whether another page follows
+    let mut raw_files = sqlx::query!(
+        r#"
+        SELECT
+            f.id as file_id,
+            f.name as file_name,
+            f.book as book_id,
+            f.size as file_size,
+            f.duration as file_duration,
+            f.date,
+            f.location,
+            s.id as scholar_id,
+            s.name as scholar_name,
+            s.image as scholar_image
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        JOIN tbl_scholars s ON f.scholar = s.id
+        WHERE f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        AND (? IS NULL OR f.date < ? OR (f.date = ? AND f.id < ?))
+        ORDER BY f.date DESC, f.id DESC
+        LIMIT ?
+        "#,
+        include_restricted,
+        after_date,
+        after_date,
+        after_date,
+        after_id,
+        limit + 1
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let has_next = raw_files.len() > limit as usize;
+    raw_files.truncate(limit as usize);
+
+    let mut files_with_stats = Vec::new();
+    for row in raw_files {
+        let statistics = get_file_statistics(pool, row.file_id, user_id).await?;
+        files_with_stats.push(RecentFilesWithStats {
+            file_id: row.file_id,
+            file_name: row.file_name,
+            file_url: config.get_upload_url(&row.location),
+            file_size: row.file_size,
+            file_duration: row.file_duration,
+            book_id: row.book_id,
+            scholar_id: row.scholar_id,
+            scholar_name: row.scholar_name,
+            scholar_image: config.get_image_url(&row.scholar_image),
+            date: row.date.into(),
+            statistics,
+        });
+    }
+
+    let next_cursor = if has_next {
+        files_with_stats.last().map(|file| DateIdCursor {
+            date: file.date,
+            id: file.file_id,
+        })
+    } else {
+        None
+    };
+
+    Ok((files_with_stats, next_cursor))
+}
+
 pub async fn get_file_statistics(
     pool: &MySqlPool,
     file_id: i32,
@@ -504,12 +1118,13 @@ pub async fn get_file_statistics(
 ) -> Result<FileStatistics, AppError> {
     // Get total downloads
     let total_downloads: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM tbl_download_logs WHERE file_id = ?",
+        r#"SELECT CAST(downloads AS SIGNED) AS "downloads!: i64" FROM tbl_files WHERE id = ?"#,
         file_id
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(AppError::db_error)?
+    .unwrap_or(0);
 
     // Get total plays
     let total_plays: i64 = sqlx::query_scalar!(
@@ -567,6 +1182,7 @@ pub async fn get_all_files_for_book_play_all(
     pool: &MySqlPool,
     config: &AppConfig,
     book_id: i32,
+    include_restricted: bool,
 ) -> Result<crate::models::files::PlayAllResponse, AppError> {
     // First, get book and scholar information
     let book_info = sqlx::query!(
@@ -584,9 +1200,10 @@ pub async fn get_all_files_for_book_play_all(
         "#,
         book_id
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Book not found"))?;
 
     // Get all files for the book, ordered by creation date
     let raw_files = sqlx::query!(
@@ -599,10 +1216,13 @@ pub async fn get_all_files_for_book_play_all(
             f.date,
             f.location
         FROM tbl_files f
-        WHERE f.book = ? AND f.status = 'active'
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
         ORDER BY f.date ASC, f.id ASC
         "#,
-        book_id
+        book_id,
+        include_restricted
     )
     .fetch_all(pool)
     .await
@@ -642,6 +1262,101 @@ pub async fn get_all_files_for_book_play_all(
     })
 }
 
+/// Book and scholar details plus every active file of the book with its URLs
+/// and content hash, in play order
+struct OfflineFileRow {
+    file_id: i32,
+    file_name: String,
+    file_size: String,
+    file_duration: String,
+    location: String,
+    content_hash: Option<String>,
+}
+
+fn offline_manifest_files(
+    config: &AppConfig,
+    rows: Vec<OfflineFileRow>,
+) -> Vec<crate::models::files::OfflineManifestFile> {
+    rows.into_iter()
+        .map(|row| crate::models::files::OfflineManifestFile {
+            file_id: row.file_id,
+            file_name: row.file_name,
+            stream_url: config.get_upload_url(&row.location),
+            download_url: config.get_download_url(row.file_id),
+            file_size: row.file_size,
+            file_duration: row.file_duration,
+            content_hash: row.content_hash,
+        })
+        .collect()
+}
+
+pub async fn get_book_offline_manifest(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    book_id: i32,
+    include_restricted: bool,
+) -> Result<crate::models::files::OfflineManifest, AppError> {
+    let book_info = sqlx::query!(
+        r#"
+        SELECT 
+            b.id as book_id,
+            b.name as book_name,
+            b.image as book_image,
+            s.id as scholar_id,
+            s.name as scholar_name,
+            s.image as scholar_image
+        FROM tbl_books b
+        JOIN tbl_scholars s ON b.scholar_id = s.id
+        WHERE b.id = ? AND b.status = 'active' AND s.status = 'active'
+        "#,
+        book_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Book not found"))?;
+
+    let raw_files = sqlx::query_as!(
+        OfflineFileRow,
+        r#"
+        SELECT
+            f.id as file_id,
+            f.name as file_name,
+            f.size as file_size,
+            f.duration as file_duration,
+            f.location,
+            f.content_hash
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.book = ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        ORDER BY f.date ASC, f.id ASC
+        "#,
+        book_id,
+        include_restricted
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let files = offline_manifest_files(config, raw_files);
+
+    let duration_strings: Vec<String> = files.iter().map(|f| f.file_duration.clone()).collect();
+
+    Ok(crate::models::files::OfflineManifest {
+        book_id: book_info.book_id,
+        book_name: book_info.book_name,
+        book_image: Some(config.get_image_url(&book_info.book_image)),
+        scholar_id: book_info.scholar_id,
+        scholar_name: book_info.scholar_name,
+        scholar_image: Some(config.get_image_url(&book_info.scholar_image)),
+        total_files: files.len() as i32,
+        total_duration: calculate_total_duration_from_strings(&duration_strings),
+        generated_at: chrono::Utc::now(),
+        files,
+    })
+}
+
 pub async fn update_file(
     pool: &MySqlPool,
     file_id: i32,
@@ -649,6 +1364,28 @@ pub async fn update_file(
 ) -> Result<(), AppError> {
     let now = chrono::Utc::now().naive_utc();
 
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    // Bump the version first; with an expected version this doubles as the precondition
+    let bumped = match request.version {
+        Some(expected) => sqlx::query!(
+            "UPDATE tbl_files SET version = version + 1 WHERE id = ? AND status = 'active' AND version = ?",
+            file_id,
+            expected
+        )
+        .execute(&mut *tx)
+        .await,
+        None => sqlx::query!(
+            "UPDATE tbl_files SET version = version + 1 WHERE id = ? AND status = 'active'",
+            file_id
+        )
+        .execute(&mut *tx)
+        .await,
+    }
+    .map_err(AppError::db_error)?;
+
+    crate::core::check_version_bump(bumped.rows_affected(), request.version, "File")?;
+
     // Update each field individually if provided
     if let Some(ref title) = request.name {
         sqlx::query!(
@@ -657,7 +1394,7 @@ pub async fn update_file(
             now,
             file_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -669,11 +1406,13 @@ pub async fn update_file(
             now,
             file_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
 
+    tx.commit().await.map_err(AppError::db_error)?;
+
     Ok(())
 }
 
@@ -725,3 +1464,116 @@ pub async fn delete_file(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn book_file_search_excludes_files_from_other_books() {
+        let mut query = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM tbl_files f ");
+        push_file_search_filters(&mut query, "%tawheed%", FileSearchScope::Book(7), false);
+        let sql = query.sql();
+        assert!(sql.ends_with("AND is_published(f.publish_at, b.publish_at) AND f.book = ?"));
+        assert!(!sql.contains("f.scholar ="));
+
+        let mut query = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM tbl_files f ");
+        push_file_search_filters(&mut query, "%tawheed%", FileSearchScope::All, false);
+        assert!(!query.sql().contains("f.book ="));
+    }
+
+    #[test]
+    fn file_search_ranks_exact_then_prefix_then_contains() {
+        let mut query = QueryBuilder::<MySql>::new("SELECT f.id FROM tbl_files f");
+        push_file_search_ranking(&mut query, "tawheed", "%tawheed%");
+        assert_eq!(
+            query.sql(),
+            "SELECT f.id FROM tbl_files f ORDER BY CASE WHEN f.name = ? THEN 0 \
+             WHEN f.name LIKE ? THEN 1 WHEN f.name LIKE ? THEN 2 ELSE 3 END, f.date DESC, f.id DESC"
+        );
+    }
+
+    #[test]
+    fn offline_manifest_lists_every_file_with_its_hash_and_urls() {
+        let config = AppConfig::new().expect("local configuration");
+        let rows = vec![
+            OfflineFileRow {
+                file_id: 1,
+                file_name: "Lesson 1".to_string(),
+                file_size: "4.2 MB".to_string(),
+                file_duration: "00:30:00".to_string(),
+                location: "lesson-1.mp3".to_string(),
+                content_hash: Some("ab12".to_string()),
+            },
+            OfflineFileRow {
+                file_id: 2,
+                file_name: "Lesson 2".to_string(),
+                file_size: "3.1 MB".to_string(),
+                file_duration: "00:25:00".to_string(),
+                location: "lesson-2.mp3".to_string(),
+                content_hash: None,
+            },
+        ];
+
+        let files = offline_manifest_files(&config, rows);
+
+        assert_eq!(files.iter().map(|f| f.file_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(files[0].content_hash.as_deref(), Some("ab12"));
+        assert_eq!(files[1].content_hash, None);
+        assert_eq!(files[0].stream_url, config.get_upload_url("lesson-1.mp3"));
+        assert_eq!(files[0].download_url, config.get_download_url(1));
+        assert!(files[1].download_url.ends_with("/api/v1/files/2/download"));
+    }
+
+    #[test]
+    fn not_played_filter_requires_a_signed_in_user() {
+        let options = BookFilesQuery {
+            sort: None,
+            filter: Some(BookFilesFilter::NotPlayed),
+        };
+        assert!(book_files_not_played(&options, Some(42)).unwrap());
+        let err = book_files_not_played(&options, None).unwrap_err();
+        assert_eq!(err.error_type, crate::core::AppErrorType::AuthError);
+
+        // Without the filter anonymous listings are unaffected
+        assert!(!book_files_not_played(&BookFilesQuery::default(), None).unwrap());
+    }
+
+    #[test]
+    fn restricted_files_are_only_listed_for_signed_in_users() {
+        // Bound to `(f.restricted = FALSE OR ?)` in every listing
+        assert!(!can_view_restricted(None));
+        assert!(can_view_restricted(Some(42)));
+    }
+
+    #[test]
+    fn listening_state_is_only_reported_to_signed_in_users() {
+        assert!(listening_state_for(None, 7).is_none());
+
+        let mut states = HashMap::new();
+        states.insert(
+            7,
+            FileListeningState {
+                has_played: true,
+                last_position_seconds: Some(90),
+                completed: false,
+            },
+        );
+
+        let played = listening_state_for(Some(&states), 7).unwrap();
+        assert!(played.has_played);
+        assert_eq!(played.last_position_seconds, Some(90));
+
+        let unplayed = listening_state_for(Some(&states), 8).unwrap();
+        assert!(!unplayed.has_played && !unplayed.completed);
+        assert_eq!(unplayed.last_position_seconds, None);
+    }
+
+    #[test]
+    fn file_statistics_read_the_listed_download_counter() {
+        let query = files_with_stats_query(&[4, 9], Some(2));
+        let sql = query.sql();
+        assert!(sql.contains("CAST(f.downloads AS SIGNED) as total_downloads"));
+        assert!(!sql.contains("tbl_download_logs"));
+    }
+}
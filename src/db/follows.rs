@@ -1,9 +1,14 @@
 use crate::core::AppError;
-use crate::models::follows::{UserScholarFollow, FollowScholarRequest, UpdateFollowRequest, FollowResponse};
+use crate::db::files::can_view_restricted;
+use crate::models::follows::{
+    FollowResponse, FollowScholarRequest, FollowStateResponse, NewFollowedContentCount,
+    UpdateFollowRequest, UserScholarFollow,
+};
 use sqlx::MySqlPool;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
-// Follow a scholar
+// Follow a scholar; following again keeps the original follow and only updates
+// notifications when they were explicitly supplied
 pub async fn follow_scholar(
     pool: &MySqlPool,
     user_id: i32,
@@ -17,13 +22,13 @@ pub async fn follow_scholar(
         INSERT INTO tbl_user_scholar_follows (user_id, scholar_id, notifications_enabled, followed_at)
         VALUES (?, ?, ?, ?)
         ON DUPLICATE KEY UPDATE 
-            notifications_enabled = VALUES(notifications_enabled),
-            followed_at = VALUES(followed_at)
+            notifications_enabled = COALESCE(?, notifications_enabled)
         "#,
         user_id,
         request.scholar_id,
         notifications_enabled,
-        now
+        now,
+        request.notifications_enabled
     )
     .execute(pool)
     .await
@@ -32,7 +37,7 @@ pub async fn follow_scholar(
     get_user_follow(pool, user_id, request.scholar_id).await
 }
 
-// Unfollow a scholar
+// Unfollow a scholar; a no-op when the user is not following
 pub async fn unfollow_scholar(
     pool: &MySqlPool,
     user_id: i32,
@@ -162,4 +167,158 @@ pub async fn get_scholar_followers_count(
     .map_err(AppError::db_error)?;
 
     Ok(row.count)
-}
\ No newline at end of file
+}
+
+// Current follow state of a user for a scholar, with the live follower count
+pub async fn get_follow_state(
+    pool: &MySqlPool,
+    user_id: i32,
+    scholar_id: i32,
+) -> Result<FollowStateResponse, AppError> {
+    let follow = if is_following_scholar(pool, user_id, scholar_id).await? {
+        Some(get_user_follow(pool, user_id, scholar_id).await?)
+    } else {
+        None
+    };
+    let follower_count = get_scholar_followers_count(pool, scholar_id).await?;
+
+    Ok(FollowStateResponse {
+        scholar_id,
+        is_following: follow.is_some(),
+        follower_count,
+        follow,
+    })
+}
+
+// When a file became listable: its upload, or a later scheduled publish of the
+// file or its book. The count query computes the same with GREATEST/COALESCE
+fn file_visible_since(
+    uploaded_at: DateTime<Utc>,
+    file_publish_at: Option<DateTime<Utc>>,
+    book_publish_at: Option<DateTime<Utc>>,
+) -> DateTime<Utc> {
+    [file_publish_at, book_publish_at]
+        .into_iter()
+        .flatten()
+        .fold(uploaded_at, DateTime::max)
+}
+
+// A file is new once it became visible after both the follow and the last mark-seen
+fn is_new_followed_file(
+    visible_since: DateTime<Utc>,
+    followed_at: DateTime<Utc>,
+    last_seen_at: Option<DateTime<Utc>>,
+) -> bool {
+    visible_since > last_seen_at.map_or(followed_at, |seen| seen.max(followed_at))
+}
+
+// Files from followed scholars that became visible since the user last marked
+// the feed seen, under the same restricted and publish rules as the listings.
+// A file older than the follow itself is never new, so a fresh follow does not
+// flag the scholar's whole back catalogue
+pub async fn count_new_followed_files(
+    pool: &MySqlPool,
+    user_id: i32,
+) -> Result<NewFollowedContentCount, AppError> {
+    let last_seen_at = sqlx::query_scalar!(
+        r#"SELECT follows_last_seen AS "follows_last_seen?: DateTime<Utc>" FROM tbl_users WHERE id = ?"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .flatten();
+
+    let new_files: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)
+        FROM tbl_user_scholar_follows fo
+        JOIN tbl_books b ON b.scholar_id = fo.scholar_id AND b.status = 'active'
+        JOIN tbl_files f ON f.book = b.id AND f.status = 'active'
+        WHERE fo.user_id = ?
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        AND GREATEST(f.date, COALESCE(f.publish_at, f.date), COALESCE(b.publish_at, f.date))
+            > GREATEST(fo.followed_at, COALESCE(?, fo.followed_at))
+        "#,
+        user_id,
+        can_view_restricted(Some(user_id)),
+        last_seen_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(NewFollowedContentCount {
+        new_files,
+        last_seen_at,
+    })
+}
+
+// Reset the new-content badge; returns the count as `count_new_followed_files` now sees it
+pub async fn mark_follows_seen(
+    pool: &MySqlPool,
+    user_id: i32,
+) -> Result<NewFollowedContentCount, AppError> {
+    let now = Utc::now();
+    sqlx::query!(
+        "UPDATE tbl_users SET follows_last_seen = ? WHERE id = ?",
+        now,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(NewFollowedContentCount {
+        new_files: 0,
+        last_seen_at: Some(now),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn a_file_uploaded_after_mark_seen_is_counted() {
+        let followed_at = Utc::now() - Duration::days(10);
+        let before_seen = Utc::now() - Duration::days(2);
+        let seen_at = Utc::now() - Duration::days(1);
+        let uploaded = [before_seen, Utc::now()];
+
+        let new_files = uploaded
+            .iter()
+            .filter(|at| is_new_followed_file(file_visible_since(**at, None, None), followed_at, Some(seen_at)))
+            .count();
+        assert_eq!(new_files, 1);
+    }
+
+    #[test]
+    fn a_scheduled_file_is_new_when_it_publishes() {
+        let followed_at = Utc::now() - Duration::days(10);
+        let uploaded_at = Utc::now() - Duration::days(3);
+        let seen_at = Utc::now() - Duration::days(1);
+        let published_at = Utc::now() - Duration::hours(1);
+
+        assert!(!is_new_followed_file(file_visible_since(uploaded_at, None, None), followed_at, Some(seen_at)));
+        assert!(is_new_followed_file(
+            file_visible_since(uploaded_at, Some(published_at), None),
+            followed_at,
+            Some(seen_at)
+        ));
+        assert!(is_new_followed_file(
+            file_visible_since(uploaded_at, None, Some(published_at)),
+            followed_at,
+            Some(seen_at)
+        ));
+    }
+
+    #[test]
+    fn files_from_before_the_follow_are_not_new() {
+        let uploaded_at = Utc::now() - Duration::days(5);
+        let followed_at = Utc::now() - Duration::days(1);
+        assert!(!is_new_followed_file(file_visible_since(uploaded_at, None, None), followed_at, None));
+    }
+}
@@ -0,0 +1,48 @@
+use crate::core::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+
+/// Record that `admin_id` started acting as `user_id`
+pub async fn log_impersonation_start(
+    pool: &MySqlPool,
+    session_id: &str,
+    admin_id: i32,
+    user_id: i32,
+    reason: Option<&str>,
+    client_ip: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_impersonation_sessions
+        (session_id, admin_id, user_id, reason, client_ip, started_at, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+        session_id,
+        admin_id,
+        user_id,
+        reason,
+        client_ip,
+        Utc::now().naive_utc(),
+        expires_at.naive_utc()
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+/// Stamp `ended_at` on a session; false when it was already ended
+pub async fn log_impersonation_end(pool: &MySqlPool, session_id: &str) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE tbl_impersonation_sessions SET ended_at = ? WHERE session_id = ? AND ended_at IS NULL",
+        Utc::now().naive_utc(),
+        session_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,186 @@
+use crate::core::AppError;
+use crate::models::notifications::{
+    Notification, NotificationKind, NotificationPreferences, UpdateNotificationPreferencesRequest,
+};
+use sqlx::MySqlPool;
+
+/// Preferences for a user, falling back to the all-on defaults when none were saved
+pub async fn get_notification_preferences(
+    pool: &MySqlPool,
+    user_id: i32,
+) -> Result<NotificationPreferences, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT new_uploads_from_followed, comment_replies, likes_on_my_uploads, subscription_reminders,
+               listening_goals
+        FROM tbl_notification_prefs
+        WHERE user_id = ?
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(match row {
+        Some(row) => NotificationPreferences {
+            new_uploads_from_followed: row.new_uploads_from_followed,
+            comment_replies: row.comment_replies,
+            likes_on_my_uploads: row.likes_on_my_uploads,
+            subscription_reminders: row.subscription_reminders,
+            listening_goals: row.listening_goals,
+        },
+        None => NotificationPreferences::default(),
+    })
+}
+
+pub async fn update_notification_preferences(
+    pool: &MySqlPool,
+    user_id: i32,
+    request: &UpdateNotificationPreferencesRequest,
+) -> Result<NotificationPreferences, AppError> {
+    let updated = get_notification_preferences(pool, user_id)
+        .await?
+        .updated_with(request);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_notification_prefs
+            (user_id, new_uploads_from_followed, comment_replies, likes_on_my_uploads,
+             subscription_reminders, listening_goals)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            new_uploads_from_followed = VALUES(new_uploads_from_followed),
+            comment_replies = VALUES(comment_replies),
+            likes_on_my_uploads = VALUES(likes_on_my_uploads),
+            subscription_reminders = VALUES(subscription_reminders),
+            listening_goals = VALUES(listening_goals)
+        "#,
+        user_id,
+        updated.new_uploads_from_followed,
+        updated.comment_replies,
+        updated.likes_on_my_uploads,
+        updated.subscription_reminders,
+        updated.listening_goals
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(updated)
+}
+
+/// Whether `user_id` wants notifications of this kind; every emitter checks this first
+pub async fn is_notification_enabled(
+    pool: &MySqlPool,
+    user_id: i32,
+    kind: NotificationKind,
+) -> Result<bool, AppError> {
+    Ok(get_notification_preferences(pool, user_id)
+        .await?
+        .allows(kind))
+}
+
+/// Record an in-app notification unless the recipient is the actor or has this kind
+/// switched off. Returns whether a notification was created
+pub async fn create_notification(
+    pool: &MySqlPool,
+    user_id: i32,
+    actor_id: i32,
+    kind: NotificationKind,
+    file_id: Option<i32>,
+    comment_id: Option<i32>,
+) -> Result<bool, AppError> {
+    if user_id == actor_id || !is_notification_enabled(pool, user_id, kind).await? {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_notifications (user_id, actor_id, kind, file_id, comment_id)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        user_id,
+        actor_id,
+        kind.as_str(),
+        file_id,
+        comment_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(true)
+}
+
+/// Record a notification the system raises on its own (no actor), unless the
+/// recipient has this kind switched off. Returns whether one was created
+pub async fn create_system_notification(
+    pool: &MySqlPool,
+    user_id: i32,
+    kind: NotificationKind,
+) -> Result<bool, AppError> {
+    if !is_notification_enabled(pool, user_id, kind).await? {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "INSERT INTO tbl_notifications (user_id, kind) VALUES (?, ?)",
+        user_id,
+        kind.as_str()
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(true)
+}
+
+pub async fn get_user_notifications(
+    pool: &MySqlPool,
+    user_id: i32,
+    limit: i32,
+    offset: i32,
+) -> Result<(Vec<Notification>, i64), AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT n.id, n.kind, n.actor_id, u.name as actor_name, n.file_id, n.comment_id,
+               n.is_read, n.created_at
+        FROM tbl_notifications n
+        LEFT JOIN tbl_users u ON n.actor_id = u.id
+        WHERE n.user_id = ?
+        ORDER BY n.created_at DESC, n.id DESC
+        LIMIT ? OFFSET ?
+        "#,
+        user_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let total: i64 = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM tbl_notifications WHERE user_id = ?",
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let notifications = rows
+        .into_iter()
+        .map(|row| Notification {
+            id: row.id,
+            kind: row.kind,
+            actor_id: row.actor_id,
+            actor_name: row.actor_name,
+            file_id: row.file_id,
+            comment_id: row.comment_id,
+            is_read: row.is_read,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    Ok((notifications, total))
+}
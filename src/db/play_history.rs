@@ -1,12 +1,21 @@
-use crate::core::AppError;
-use crate::models::play_history::{PlayHistory, PlayHistoryResponse, RecordPlayRequest};
-use chrono::Utc;
-use sqlx::MySqlPool;
+use crate::core::{push_in_list, AppConfig, AppError};
+use crate::models::files::FilesWithStats;
+use crate::models::play_history::{
+    FileListeningState, FilePlayStats, ListeningGoalProgress, MostPlayedFile, PlayHistory,
+    PlayHistoryResponse, RecordPlayRequest, SyncEntryStatus, SyncPlayEntry,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use std::collections::HashMap;
 
-// Record play history
+// Record play history. Guest plays pass no user_id and are keyed by
+// their anonymous client id and IP instead
 pub async fn record_play(
     pool: &MySqlPool,
-    user_id: i32,
+    user_id: Option<i32>,
+    anonymous_id: Option<&str>,
+    client_ip: Option<&str>,
     request: &RecordPlayRequest,
 ) -> Result<PlayHistory, AppError> {
     let now = Utc::now().naive_utc();
@@ -15,23 +24,27 @@ pub async fn record_play(
         r#"
         INSERT INTO tbl_play_history (
             user_id, 
+            anonymous_id, 
             file_id, 
             played_duration, 
             total_duration, 
             play_position, 
             play_action, 
             device_type, 
+            client_ip, 
             played_at
         )
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         user_id,
+        anonymous_id,
         request.file_id,
         request.played_duration,
         request.total_duration,
         request.play_position,
         request.play_action.as_str(),
         request.device_type,
+        client_ip,
         now
     )
     .execute(pool)
@@ -42,6 +55,175 @@ pub async fn record_play(
     get_play_history_by_id(pool, play_id).await
 }
 
+// Apply one offline play entry, merging it into an existing play of the same file
+// when one was recorded within `debounce_seconds` of it
+pub async fn sync_play_entry(
+    pool: &MySqlPool,
+    user_id: i32,
+    entry: &SyncPlayEntry,
+    debounce_seconds: i64,
+) -> Result<SyncEntryStatus, AppError> {
+    let played_at = entry.played_at.naive_utc();
+    let window_start = played_at - chrono::Duration::seconds(debounce_seconds);
+    let window_end = played_at + chrono::Duration::seconds(debounce_seconds);
+
+    let existing_id = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM tbl_play_history
+        WHERE user_id = ? AND file_id = ? AND played_at BETWEEN ? AND ?
+        ORDER BY played_at DESC
+        LIMIT 1
+        "#,
+        user_id,
+        entry.file_id,
+        window_start,
+        window_end
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    if let Some(play_id) = existing_id {
+        sqlx::query!(
+            r#"
+            UPDATE tbl_play_history
+            SET play_position = GREATEST(COALESCE(play_position, 0), ?)
+            WHERE id = ?
+            "#,
+            entry.position_seconds,
+            play_id
+        )
+        .execute(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+        return Ok(SyncEntryStatus::Merged);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_play_history (
+            user_id,
+            file_id,
+            played_duration,
+            play_position,
+            play_action,
+            device_type,
+            played_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+        user_id,
+        entry.file_id,
+        entry.position_seconds,
+        entry.position_seconds,
+        "Progress",
+        entry.device_type,
+        played_at
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(SyncEntryStatus::Accepted)
+}
+
+// Returns the subset of file ids that refer to active files
+pub async fn get_active_file_ids(pool: &MySqlPool, file_ids: &[i32]) -> Result<Vec<i32>, AppError> {
+    if file_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query =
+        QueryBuilder::<MySql>::new("SELECT id FROM tbl_files WHERE status = 'active' AND ");
+    push_in_list(&mut query, "id", file_ids.iter().copied());
+    let ids = query
+        .build_query_scalar::<i32>()
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok(ids)
+}
+
+// Per-file listening state of a user for a page of files. Completion comes
+// from tbl_file_completions; files the user never played are absent from the map
+pub async fn get_listening_states(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_ids: &[i32],
+) -> Result<HashMap<i32, FileListeningState>, AppError> {
+    if file_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut query = QueryBuilder::<MySql>::new(
+        r#"
+        SELECT
+            ph.file_id,
+            (
+                SELECT latest.play_position
+                FROM tbl_play_history latest
+                WHERE latest.user_id = ph.user_id AND latest.file_id = ph.file_id
+                ORDER BY latest.played_at DESC, latest.id DESC
+                LIMIT 1
+            ) as last_position
+        FROM tbl_play_history ph
+        WHERE ph.user_id = "#,
+    );
+    query.push_bind(user_id);
+    query.push(" AND ");
+    push_in_list(&mut query, "ph.file_id", file_ids.iter().copied());
+    query.push(" GROUP BY ph.user_id, ph.file_id");
+    let positions = query
+        .build_query_as::<(i32, Option<i32>)>()
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    // Completions are the same source book progress reads from
+    let mut query = QueryBuilder::<MySql>::new(
+        "SELECT DISTINCT file_id FROM tbl_file_completions WHERE user_id = ",
+    );
+    query.push_bind(user_id);
+    query.push(" AND ");
+    push_in_list(&mut query, "file_id", file_ids.iter().copied());
+    let completed_ids = query
+        .build_query_scalar::<i32>()
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok(merge_listening_states(positions, completed_ids))
+}
+
+// A completed file counts as played even when its plays were pruned
+fn merge_listening_states(
+    positions: Vec<(i32, Option<i32>)>,
+    completed_ids: Vec<i32>,
+) -> HashMap<i32, FileListeningState> {
+    let mut states: HashMap<i32, FileListeningState> = positions
+        .into_iter()
+        .map(|(file_id, last_position)| {
+            (
+                file_id,
+                FileListeningState {
+                    has_played: true,
+                    last_position_seconds: last_position,
+                    completed: false,
+                },
+            )
+        })
+        .collect();
+    for file_id in completed_ids {
+        let state = states.entry(file_id).or_default();
+        state.has_played = true;
+        state.completed = true;
+    }
+
+    states
+}
+
 // Get play history by ID
 pub async fn get_play_history_by_id(
     pool: &MySqlPool,
@@ -52,6 +234,7 @@ pub async fn get_play_history_by_id(
         SELECT 
             id, 
             user_id, 
+            anonymous_id, 
             file_id, 
             played_duration, 
             total_duration, 
@@ -71,6 +254,7 @@ pub async fn get_play_history_by_id(
     Ok(PlayHistory {
         id: row.id,
         user_id: row.user_id,
+        anonymous_id: row.anonymous_id,
         file_id: row.file_id,
         played_duration: row.played_duration.unwrap_or(0),
         total_duration: row.total_duration,
@@ -87,7 +271,7 @@ pub async fn get_user_play_history(
     user_id: i32,
     limit: Option<i32>,
     offset: Option<i32>,
-) -> Result<Vec<PlayHistoryResponse>, AppError> {
+) -> Result<(Vec<PlayHistoryResponse>, i64), AppError> {
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
 
@@ -133,69 +317,123 @@ pub async fn get_user_play_history(
         })
         .collect();
 
-    Ok(history)
+    let total_count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)
+        FROM tbl_play_history ph
+        JOIN tbl_files f ON ph.file_id = f.id
+        WHERE ph.user_id = ?
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok((history, total_count))
 }
 
-// Get most played files for user
+/// The user's most played active files, optionally limited to a scholar, a
+/// book or plays since `since`. Ranked by play count, then time listened
+#[allow(clippy::too_many_arguments)]
 pub async fn get_user_most_played_files(
     pool: &MySqlPool,
+    config: &AppConfig,
     user_id: i32,
-    limit: Option<i32>,
-) -> Result<Vec<PlayHistoryResponse>, AppError> {
-    let limit = limit.unwrap_or(10);
-
+    scholar_id: Option<i32>,
+    book_id: Option<i32>,
+    since: Option<NaiveDateTime>,
+    limit: i32,
+    offset: i32,
+) -> Result<(Vec<MostPlayedFile>, i64), AppError> {
     let rows = sqlx::query!(
         r#"
-        SELECT 
+        SELECT
             ph.file_id,
-            f.name as file_title,
-            s.name as scholar_name,
-            SUM(ph.played_duration) as total_played_duration,
-            COUNT(*) as play_count,
-            MAX(ph.played_at) as last_played_at
+            COUNT(*) as "play_count!: i64",
+            CAST(COALESCE(SUM(ph.played_duration), 0) AS SIGNED) as "total_played_seconds!: i64",
+            MAX(ph.played_at) as "last_played_at!: DateTime<Utc>"
         FROM tbl_play_history ph
         JOIN tbl_files f ON ph.file_id = f.id
-        LEFT JOIN tbl_scholars s ON f.scholar = s.id
         WHERE ph.user_id = ?
-        GROUP BY ph.file_id, f.name, s.name
-        ORDER BY total_played_duration DESC, play_count DESC
-        LIMIT ?
+        AND f.status = 'active'
+        AND (? IS NULL OR f.scholar = ?)
+        AND (? IS NULL OR f.book = ?)
+        AND (? IS NULL OR ph.played_at >= ?)
+        GROUP BY ph.file_id
+        ORDER BY play_count DESC, total_played_seconds DESC, last_played_at DESC, ph.file_id
+        LIMIT ? OFFSET ?
         "#,
         user_id,
-        limit
+        scholar_id,
+        scholar_id,
+        book_id,
+        book_id,
+        since,
+        since,
+        limit,
+        offset
     )
     .fetch_all(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    let history = rows
+    let total_count: i64 = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(DISTINCT ph.file_id)
+        FROM tbl_play_history ph
+        JOIN tbl_files f ON ph.file_id = f.id
+        WHERE ph.user_id = ?
+        AND f.status = 'active'
+        AND (? IS NULL OR f.scholar = ?)
+        AND (? IS NULL OR f.book = ?)
+        AND (? IS NULL OR ph.played_at >= ?)
+        "#,
+        user_id,
+        scholar_id,
+        scholar_id,
+        book_id,
+        book_id,
+        since,
+        since
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let file_ids: Vec<i32> = rows.iter().map(|row| row.file_id).collect();
+    let mut files_by_id: HashMap<i32, FilesWithStats> =
+        crate::db::files::fetch_files_with_stats_by_ids(pool, config, &file_ids, Some(user_id))
+            .await?
+            .into_iter()
+            .map(|file| (file.file_id, file))
+            .collect();
+
+    // Skips a file deactivated between the two queries
+    let most_played = rows
         .into_iter()
-        .map(|row| PlayHistoryResponse {
-            file_id: row.file_id,
-            file_title: row.file_title,
-            scholar_name: row.scholar_name,
-            played_duration: row
-                .total_played_duration
-                .and_then(|bd| bd.to_string().parse::<i32>().ok())
-                .unwrap_or(0),
-            total_duration: None, // Aggregated data doesn't have individual total_duration
-            play_position: None,  // Aggregated data doesn't have individual position
-            play_action: "Aggregated".to_string(), // Indicate this is aggregated data
-            device_type: None,
-            played_at: row.last_played_at.unwrap().naive_utc(),
+        .filter_map(|row| {
+            files_by_id.remove(&row.file_id).map(|file| MostPlayedFile {
+                file,
+                play_count: row.play_count,
+                total_played_seconds: row.total_played_seconds,
+                last_played_at: row.last_played_at.naive_utc(),
+            })
         })
         .collect();
 
-    Ok(history)
+    Ok((most_played, total_count))
 }
 
 // Get file play stats
-pub async fn get_file_play_stats(pool: &MySqlPool, file_id: i32) -> Result<(i64, i64), AppError> {
+pub async fn get_file_play_stats(pool: &MySqlPool, file_id: i32) -> Result<FilePlayStats, AppError> {
     let row = sqlx::query!(
         r#"
         SELECT 
-            COUNT(*) as total_plays,
-            COUNT(DISTINCT user_id) as unique_listeners
+            COUNT(user_id) as total_plays,
+            COUNT(DISTINCT user_id) as unique_listeners,
+            COUNT(CASE WHEN user_id IS NULL THEN 1 END) as guest_plays,
+            COUNT(DISTINCT CASE WHEN user_id IS NULL THEN anonymous_id END) as unique_guests
         FROM tbl_play_history
         WHERE file_id = ?
         "#,
@@ -205,15 +443,274 @@ pub async fn get_file_play_stats(pool: &MySqlPool, file_id: i32) -> Result<(i64,
     .await
     .map_err(AppError::db_error)?;
 
-    Ok((row.total_plays, row.unique_listeners))
+    Ok(FilePlayStats {
+        total_plays: row.total_plays,
+        unique_listeners: row.unique_listeners,
+        guest_plays: row.guest_plays,
+        unique_guests: row.unique_guests,
+    })
 }
 
-// Clear user play history
+/// The user's most recent saved position in a file with the duration that play
+/// reported, or None when no play of it saved a position
+pub async fn get_last_saved_position(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_id: i32,
+) -> Result<Option<(i32, Option<i32>)>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT play_position as "play_position!: i32", total_duration
+        FROM tbl_play_history
+        WHERE user_id = ? AND file_id = ? AND play_position IS NOT NULL
+        ORDER BY played_at DESC, id DESC
+        LIMIT 1
+        "#,
+        user_id,
+        file_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(row.map(|row| (row.play_position, row.total_duration)))
+}
+
+/// Record a completion; false when the session already has one for this file
+pub async fn record_file_completion(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_id: i32,
+    session_key: &str,
+    position_seconds: i32,
+    duration_seconds: i32,
+    completed_at: NaiveDateTime,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT IGNORE INTO tbl_file_completions
+        (user_id, file_id, session_key, position_seconds, duration_seconds, completed_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        user_id,
+        file_id,
+        session_key,
+        position_seconds,
+        duration_seconds,
+        completed_at
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Clear user play history, completions included
 pub async fn clear_user_play_history(pool: &MySqlPool, user_id: i32) -> Result<(), AppError> {
     sqlx::query!("DELETE FROM tbl_play_history WHERE user_id = ?", user_id)
         .execute(pool)
         .await
         .map_err(AppError::db_error)?;
+    sqlx::query!("DELETE FROM tbl_file_completions WHERE user_id = ?", user_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+// Daily listening goal in minutes, or None when the user has not set one
+pub async fn get_listening_goal(pool: &MySqlPool, user_id: i32) -> Result<Option<i32>, AppError> {
+    sqlx::query_scalar!(
+        "SELECT daily_minutes FROM tbl_listening_goals WHERE user_id = ?",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
+// Set the daily goal, or remove it when `daily_minutes` is None
+pub async fn set_listening_goal(
+    pool: &MySqlPool,
+    user_id: i32,
+    daily_minutes: Option<i32>,
+) -> Result<(), AppError> {
+    match daily_minutes {
+        Some(minutes) => sqlx::query!(
+            r#"
+            INSERT INTO tbl_listening_goals (user_id, daily_minutes)
+            VALUES (?, ?)
+            ON DUPLICATE KEY UPDATE daily_minutes = VALUES(daily_minutes)
+            "#,
+            user_id,
+            minutes
+        )
+        .execute(pool)
+        .await
+        .map_err(AppError::db_error)?,
+        None => sqlx::query!("DELETE FROM tbl_listening_goals WHERE user_id = ?", user_id)
+            .execute(pool)
+            .await
+            .map_err(AppError::db_error)?,
+    };
 
     Ok(())
 }
+
+// Seconds listened today in the user's timezone against their goal. Counts
+// `played_duration` the same way most-played does
+pub async fn get_listening_goal_progress(
+    pool: &MySqlPool,
+    user_id: i32,
+) -> Result<ListeningGoalProgress, AppError> {
+    let timezone = crate::db::users::get_user_timezone(pool, user_id).await?;
+    let goal_minutes = get_listening_goal(pool, user_id).await?;
+    listening_goal_progress(pool, user_id, goal_minutes, timezone).await
+}
+
+async fn listening_goal_progress(
+    pool: &MySqlPool,
+    user_id: i32,
+    goal_minutes: Option<i32>,
+    timezone: Tz,
+) -> Result<ListeningGoalProgress, AppError> {
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let (day_start, day_end) = local_day_bounds(timezone, today);
+
+    let listened_seconds = sqlx::query_scalar!(
+        r#"
+        SELECT CAST(COALESCE(SUM(played_duration), 0) AS SIGNED) AS "listened_seconds!: i64"
+        FROM tbl_play_history
+        WHERE user_id = ? AND played_at >= ? AND played_at < ?
+        "#,
+        user_id,
+        day_start,
+        day_end
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let listened_minutes = listened_seconds / 60;
+    Ok(ListeningGoalProgress {
+        goal_minutes,
+        listened_minutes,
+        listened_seconds,
+        goal_met: goal_minutes.is_some_and(|goal| listened_minutes >= i64::from(goal)),
+        date: today,
+        timezone: timezone.name().to_string(),
+    })
+}
+
+// Today's progress for the goal-met check after a play, or None when there is
+// nothing to check: no goal, or already met today. The goal, the day it was
+// last met and the timezone come from one read, so a play by a user without a
+// goal or past it today costs a single lookup
+pub async fn get_unmet_listening_goal_progress(
+    pool: &MySqlPool,
+    user_id: i32,
+) -> Result<Option<ListeningGoalProgress>, AppError> {
+    let goal = sqlx::query!(
+        r#"
+        SELECT g.daily_minutes, g.last_met_on, u.timezone
+        FROM tbl_listening_goals g
+        JOIN tbl_users u ON u.id = g.user_id
+        WHERE g.user_id = ?
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let Some(goal) = goal else {
+        return Ok(None);
+    };
+    let timezone = goal
+        .timezone
+        .and_then(|timezone| timezone.parse().ok())
+        .unwrap_or(chrono_tz::UTC);
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    if goal_met_on(goal.last_met_on, today) {
+        return Ok(None);
+    }
+
+    listening_goal_progress(pool, user_id, Some(goal.daily_minutes), timezone)
+        .await
+        .map(Some)
+}
+
+fn goal_met_on(last_met_on: Option<NaiveDate>, date: NaiveDate) -> bool {
+    last_met_on == Some(date)
+}
+
+// Stamp the goal as met for `date`. Only the first call of the day returns true,
+// so the congratulation is sent once
+pub async fn mark_listening_goal_met(
+    pool: &MySqlPool,
+    user_id: i32,
+    date: NaiveDate,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE tbl_listening_goals
+        SET last_met_on = ?
+        WHERE user_id = ? AND (last_met_on IS NULL OR last_met_on <> ?)
+        "#,
+        date,
+        user_id,
+        date
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// UTC bounds of the local day `date` in `timezone`. A midnight skipped by a DST
+// change falls back to reading it as UTC
+fn local_day_bounds(timezone: Tz, date: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+    let start_of = |day: NaiveDate| {
+        let midnight = day.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+        timezone
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|start| start.naive_utc())
+            .unwrap_or(midnight)
+    };
+    let next_day = date.succ_opt().unwrap_or(date);
+    (start_of(date), start_of(next_day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_goal_met_today_is_not_checked_again() {
+        let today = NaiveDate::from_ymd_opt(2025, 10, 27).unwrap();
+        assert!(goal_met_on(Some(today), today));
+        assert!(!goal_met_on(today.pred_opt(), today));
+        assert!(!goal_met_on(None, today));
+    }
+
+    #[test]
+    fn listening_states_mark_completed_files_as_played() {
+        let states = merge_listening_states(vec![(1, Some(120)), (2, None)], vec![2, 3]);
+
+        let played = states[&1];
+        assert!(played.has_played && !played.completed);
+        assert_eq!(played.last_position_seconds, Some(120));
+
+        assert!(states[&2].completed);
+        // Completed with its plays pruned: still played, with no position
+        let pruned = states[&3];
+        assert!(pruned.has_played && pruned.completed);
+        assert_eq!(pruned.last_position_seconds, None);
+
+        assert!(!states.contains_key(&4));
+    }
+}
@@ -1,7 +1,7 @@
-use crate::core::AppError;
+use crate::core::{sum_durations, AppError};
 use crate::models::playlists::{
     AddToPlaylistRequest, CreatePlaylistRequest, Playlist, PlaylistFile, PlaylistFileResponse,
-    PlaylistResponse, UpdatePlaylistRequest,
+    PlaylistMembership, PlaylistResponse, UpdatePlaylistRequest,
 };
 use chrono::Utc;
 use sqlx::MySqlPool;
@@ -65,6 +65,42 @@ pub async fn get_playlist_by_id(pool: &MySqlPool, playlist_id: i32) -> Result<Pl
     })
 }
 
+// Get a playlist only if the viewer may see it: public playlists are visible to
+// everyone, private ones only to their owner. Returns None otherwise so callers
+// can answer 404 without revealing that a private playlist exists.
+pub async fn get_visible_playlist(
+    pool: &MySqlPool,
+    playlist_id: i32,
+    viewer_id: Option<i32>,
+) -> Result<Option<Playlist>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, user_id, name, description, is_public, cover_image,
+               total_files, total_duration, created_at, updated_at
+        FROM tbl_playlists
+        WHERE id = ? AND (is_public = 1 OR user_id = ?)
+        "#,
+        playlist_id,
+        viewer_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(row.map(|row| Playlist {
+        id: row.id,
+        user_id: row.user_id,
+        name: row.name,
+        description: row.description,
+        is_public: row.is_public.unwrap_or(0) != 0,
+        cover_image: row.cover_image,
+        total_files: row.total_files.unwrap_or(0),
+        total_duration: row.total_duration.unwrap_or(0),
+        created_at: row.created_at.naive_utc(),
+        updated_at: row.updated_at.naive_utc(),
+    }))
+}
+
 // Get user playlists
 pub async fn get_user_playlists(
     pool: &MySqlPool,
@@ -105,12 +141,58 @@ pub async fn get_user_playlists(
     Ok(playlists)
 }
 
+struct PlaylistMembershipRow {
+    id: i32,
+    name: String,
+    is_public: Option<i8>,
+    total_files: Option<i32>,
+    contains_file: bool,
+}
+
+fn playlist_membership(row: PlaylistMembershipRow) -> PlaylistMembership {
+    PlaylistMembership {
+        playlist_id: row.id,
+        name: row.name,
+        is_public: row.is_public.unwrap_or(0) != 0,
+        total_files: row.total_files.unwrap_or(0),
+        contains_file: row.contains_file,
+    }
+}
+
+// Get user playlists, flagging the ones that already hold `file_id`
+pub async fn get_user_playlists_containing(
+    pool: &MySqlPool,
+    user_id: i32,
+    file_id: i32,
+) -> Result<Vec<PlaylistMembership>, AppError> {
+    let rows = sqlx::query_as!(
+        PlaylistMembershipRow,
+        r#"
+        SELECT p.id, p.name, p.is_public AS "is_public: i8", p.total_files AS "total_files: i32",
+               EXISTS(
+                   SELECT 1 FROM tbl_playlist_files pf
+                   WHERE pf.playlist_id = p.id AND pf.file_id = ?
+               ) AS "contains_file!: bool"
+        FROM tbl_playlists p
+        WHERE p.user_id = ?
+        ORDER BY p.updated_at DESC
+        "#,
+        file_id,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(rows.into_iter().map(playlist_membership).collect())
+}
+
 // Get public playlists
 pub async fn get_public_playlists(
     pool: &MySqlPool,
     limit: Option<i32>,
     offset: Option<i32>,
-) -> Result<Vec<PlaylistResponse>, AppError> {
+) -> Result<(Vec<PlaylistResponse>, i64), AppError> {
     let limit = limit.unwrap_or(20);
     let offset = offset.unwrap_or(0);
 
@@ -148,7 +230,14 @@ pub async fn get_public_playlists(
         })
         .collect();
 
-    Ok(playlists)
+    let total_count: i64 = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM tbl_playlists WHERE is_public = 1 AND total_files > 0"
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok((playlists, total_count))
 }
 
 // Update playlist
@@ -222,22 +311,54 @@ pub async fn update_playlist(
     get_playlist_by_id(pool, playlist_id).await
 }
 
+/// Decide whether `user_id` may delete a playlist owned by `owner_id`.
+/// None means the playlist is already gone, which is Ok(false) rather than an error
+fn check_playlist_owner(owner_id: Option<i32>, user_id: i32) -> Result<bool, AppError> {
+    match owner_id {
+        None => Ok(false),
+        Some(owner_id) if owner_id != user_id => {
+            Err(AppError::forbidden_error("You don't own this playlist"))
+        }
+        Some(_) => Ok(true),
+    }
+}
+
 // Delete playlist
+/// Delete an owned playlist together with its file rows, in one transaction.
+/// A playlist that no longer exists is not an error; false means there was
+/// nothing to delete
 pub async fn delete_playlist(
     pool: &MySqlPool,
     playlist_id: i32,
     user_id: i32,
-) -> Result<(), AppError> {
-    sqlx::query!(
-        "DELETE FROM tbl_playlists WHERE id = ? AND user_id = ?",
-        playlist_id,
-        user_id
+) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    let owner_id = sqlx::query_scalar!(
+        "SELECT user_id FROM tbl_playlists WHERE id = ? FOR UPDATE",
+        playlist_id
     )
-    .execute(pool)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::db_error)?;
 
-    Ok(())
+    if !check_playlist_owner(owner_id, user_id)? {
+        return Ok(false);
+    }
+
+    sqlx::query!("DELETE FROM tbl_playlist_files WHERE playlist_id = ?", playlist_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::db_error)?;
+
+    sqlx::query!("DELETE FROM tbl_playlists WHERE id = ?", playlist_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::db_error)?;
+
+    tx.commit().await.map_err(AppError::db_error)?;
+
+    Ok(true)
 }
 
 // Add file to playlist
@@ -314,6 +435,7 @@ pub async fn get_playlist_files(
     pool: &MySqlPool,
     config: &crate::core::AppConfig,
     playlist_id: i32,
+    include_restricted: bool,
 ) -> Result<Vec<PlaylistFileResponse>, AppError> {
     let rows = sqlx::query!(
         r#"
@@ -325,10 +447,12 @@ pub async fn get_playlist_files(
         JOIN tbl_files f ON pf.file_id = f.id
         LEFT JOIN tbl_scholars s ON f.scholar = s.id
         LEFT JOIN tbl_books b ON f.book = b.id
-        WHERE pf.playlist_id = ?
+        WHERE pf.playlist_id = ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
         ORDER BY pf.sort_order ASC, pf.created_at ASC
         "#,
-        playlist_id
+        playlist_id,
+        include_restricted
     )
     .fetch_all(pool)
     .await
@@ -379,7 +503,12 @@ async fn update_playlist_stats(pool: &MySqlPool, playlist_id: i32) -> Result<(),
 
     // Get the count of files
     let total_files: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM tbl_playlist_files WHERE playlist_id = ?",
+        r#"
+        SELECT COUNT(*)
+        FROM tbl_playlist_files pf
+        JOIN tbl_files f ON pf.file_id = f.id
+        WHERE pf.playlist_id = ? AND f.status = 'active'
+        "#,
         playlist_id
     )
     .fetch_one(pool)
@@ -392,7 +521,7 @@ async fn update_playlist_stats(pool: &MySqlPool, playlist_id: i32) -> Result<(),
         SELECT f.duration 
         FROM tbl_playlist_files pf 
         JOIN tbl_files f ON pf.file_id = f.id 
-        WHERE pf.playlist_id = ?
+        WHERE pf.playlist_id = ? AND f.status = 'active'
         "#,
         playlist_id
     )
@@ -400,32 +529,8 @@ async fn update_playlist_stats(pool: &MySqlPool, playlist_id: i32) -> Result<(),
     .await
     .map_err(AppError::db_error)?;
 
-    // Calculate total duration in seconds from duration strings
-    let total_duration_seconds = {
-        let mut total_seconds: u32 = 0;
-        for duration_str in &durations {
-            // Parse duration string (e.g., "2:53" or "1:23:45")
-            let parts: Vec<&str> = duration_str.split(':').collect();
-            let seconds = match parts.len() {
-                2 => {
-                    // MM:SS format
-                    let minutes: u32 = parts[0].parse().unwrap_or(0);
-                    let secs: u32 = parts[1].parse().unwrap_or(0);
-                    minutes * 60 + secs
-                }
-                3 => {
-                    // HH:MM:SS format
-                    let hours: u32 = parts[0].parse().unwrap_or(0);
-                    let minutes: u32 = parts[1].parse().unwrap_or(0);
-                    let secs: u32 = parts[2].parse().unwrap_or(0);
-                    hours * 3600 + minutes * 60 + secs
-                }
-                _ => 0,
-            };
-            total_seconds += seconds;
-        }
-        total_seconds as i32
-    };
+    let (total_duration_seconds, _) = sum_durations(durations.iter().map(String::as_str));
+    let total_duration_seconds = i32::try_from(total_duration_seconds).unwrap_or(i32::MAX);
 
     // Update playlist stats
     sqlx::query!(
@@ -448,3 +553,47 @@ async fn update_playlist_stats(pool: &MySqlPool, playlist_id: i32) -> Result<(),
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::AppErrorType;
+
+    #[test]
+    fn only_the_playlist_holding_the_file_is_flagged() {
+        let row = |id: i32, contains_file: bool| PlaylistMembershipRow {
+            id,
+            name: format!("Playlist {id}"),
+            is_public: Some(1),
+            total_files: Some(3),
+            contains_file,
+        };
+
+        let playlists: Vec<PlaylistMembership> =
+            vec![row(1, true), row(2, false)].into_iter().map(playlist_membership).collect();
+
+        assert_eq!(
+            playlists.iter().map(|p| (p.playlist_id, p.contains_file)).collect::<Vec<_>>(),
+            vec![(1, true), (2, false)]
+        );
+        assert!(playlists[0].is_public);
+        assert_eq!(playlists[1].total_files, 3);
+    }
+
+    #[test]
+    fn owner_may_delete_their_playlist() {
+        assert!(check_playlist_owner(Some(7), 7).unwrap());
+    }
+
+    #[test]
+    fn deleting_a_missing_playlist_is_not_an_error() {
+        // The second of two deletes finds no row and reports nothing to delete
+        assert!(!check_playlist_owner(None, 7).unwrap());
+    }
+
+    #[test]
+    fn other_users_cannot_delete_a_playlist() {
+        let err = check_playlist_owner(Some(7), 8).unwrap_err();
+        assert_eq!(err.error_type, AppErrorType::ForbiddenError);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::core::{AppConfig, AppError};
+use crate::models::scheduled::{CreateScheduleRequest, ScheduledContent};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use sqlx::MySqlPool;
+
+/// Rust twin of the `is_published` SQL function: content is visible once
+/// neither its own nor its book's `publish_at` (UTC) is in the future
+pub fn is_published(
+    file_publish_at: Option<NaiveDateTime>,
+    book_publish_at: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> bool {
+    [file_publish_at, book_publish_at]
+        .into_iter()
+        .flatten()
+        .all(|publish_at| publish_at <= now)
+}
+
+/// Store a schedule. With `publish_at` the content is also hidden from
+/// listings until then, in the same transaction
+pub async fn create_schedule(
+    pool: &MySqlPool,
+    request: &CreateScheduleRequest,
+    note: Option<&str>,
+    publish_at: Option<NaiveDateTime>,
+    user_id: i32,
+) -> Result<i32, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO tbl_scheduled_content
+        (content_type, content_id, surface_on, note, hide_until_surfaced, publish_at, created_by, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        request.content_type,
+        request.content_id,
+        request.surface_on,
+        note,
+        publish_at.is_some(),
+        publish_at,
+        user_id,
+        Utc::now().naive_utc()
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::db_error_or_conflict(e, "This content is already scheduled for that day"))?;
+
+    if let Some(publish_at) = publish_at {
+        let query = match request.content_type.as_str() {
+            "book" => sqlx::query!(
+                "UPDATE tbl_books SET publish_at = ? WHERE id = ?",
+                publish_at,
+                request.content_id
+            ),
+            _ => sqlx::query!(
+                "UPDATE tbl_files SET publish_at = ? WHERE id = ?",
+                publish_at,
+                request.content_id
+            ),
+        };
+        query.execute(&mut *tx).await.map_err(AppError::db_error)?;
+    }
+
+    tx.commit().await.map_err(AppError::db_error)?;
+
+    Ok(result.last_insert_id() as i32)
+}
+
+/// Remove a schedule; false when there was none. Content it hid is published
+/// again unless another schedule has since moved its `publish_at`
+pub async fn delete_schedule(pool: &MySqlPool, id: i32) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    let Some(schedule) = sqlx::query!(
+        r#"
+        SELECT content_type, content_id, publish_at
+        FROM tbl_scheduled_content
+        WHERE id = ?
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::db_error)?
+    else {
+        return Ok(false);
+    };
+
+    sqlx::query!("DELETE FROM tbl_scheduled_content WHERE id = ?", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::db_error)?;
+
+    // Cleared only while it still holds the value this schedule set
+    if let Some(publish_at) = schedule.publish_at {
+        let query = match schedule.content_type.as_str() {
+            "book" => sqlx::query!(
+                "UPDATE tbl_books SET publish_at = NULL WHERE id = ? AND publish_at = ?",
+                schedule.content_id,
+                publish_at
+            ),
+            _ => sqlx::query!(
+                "UPDATE tbl_files SET publish_at = NULL WHERE id = ? AND publish_at = ?",
+                schedule.content_id,
+                publish_at
+            ),
+        };
+        query.execute(&mut *tx).await.map_err(AppError::db_error)?;
+    }
+
+    tx.commit().await.map_err(AppError::db_error)?;
+
+    Ok(true)
+}
+
+/// Schedules surfacing between `from` and `to` (inclusive) whose content is
+/// still active, in date order
+pub async fn fetch_schedules(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    from: NaiveDate,
+    to: NaiveDate,
+    include_restricted: bool,
+) -> Result<Vec<ScheduledContent>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            sc.id,
+            sc.content_type,
+            sc.content_id,
+            sc.surface_on,
+            sc.note,
+            f.name AS "file_name?",
+            f.location AS "file_location?",
+            f.publish_at AS "file_publish_at?",
+            b.id AS book_id,
+            b.name AS "book_name?",
+            b.image AS "book_image?",
+            b.publish_at AS "book_publish_at?"
+        FROM tbl_scheduled_content sc
+        LEFT JOIN tbl_files f
+            ON sc.content_type = 'file' AND f.id = sc.content_id
+            AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+        JOIN tbl_books b
+            ON b.id = IF(sc.content_type = 'book', sc.content_id, f.book) AND b.status = 'active'
+        WHERE sc.surface_on BETWEEN ? AND ?
+        AND (sc.content_type = 'book' OR f.id IS NOT NULL)
+        ORDER BY sc.surface_on ASC, sc.id ASC
+        "#,
+        include_restricted,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let is_file = row.content_type == "file";
+            let (name, publish_at) = if is_file {
+                (row.file_name, row.file_publish_at)
+            } else {
+                (row.book_name, row.book_publish_at)
+            };
+
+            ScheduledContent {
+                id: row.id,
+                content_type: row.content_type,
+                content_id: row.content_id,
+                name: name.unwrap_or_default(),
+                file_url: row.file_location.map(|location| config.get_upload_url(&location)),
+                book_id: row.book_id,
+                image: row.book_image.map(|image| config.get_image_url(&image)),
+                surface_on: row.surface_on,
+                note: row.note,
+                publish_at,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::SchedulingConfig;
+
+    fn lagos() -> SchedulingConfig {
+        SchedulingConfig {
+            timezone: "Africa/Lagos".to_string(),
+        }
+    }
+
+    fn utc(value: &str) -> NaiveDateTime {
+        chrono::DateTime::parse_from_rfc3339(value).unwrap().naive_utc()
+    }
+
+    #[test]
+    fn file_scheduled_for_tomorrow_is_hidden_until_then() {
+        let scheduling = lagos();
+        // 10:00 in Lagos on 2025-03-01
+        let now = utc("2025-03-01T09:00:00Z");
+        let tomorrow = scheduling.date_at(now.and_utc()) + chrono::Duration::days(1);
+        let publish_at = scheduling.start_of_day_utc(tomorrow);
+
+        assert!(!is_published(Some(publish_at), None, now));
+        // Still hidden a second before local midnight (23:00 UTC)
+        assert!(!is_published(Some(publish_at), None, utc("2025-03-01T22:59:59Z")));
+        assert!(is_published(Some(publish_at), None, utc("2025-03-01T23:00:00Z")));
+        assert!(is_published(Some(publish_at), None, utc("2025-03-02T09:00:00Z")));
+    }
+
+    #[test]
+    fn book_schedule_hides_its_files_too() {
+        let now = utc("2025-03-01T09:00:00Z");
+        let later = utc("2025-03-05T23:00:00Z");
+
+        assert!(is_published(None, None, now));
+        assert!(!is_published(None, Some(later), now));
+        assert!(!is_published(Some(utc("2025-02-01T00:00:00Z")), Some(later), now));
+        assert!(is_published(Some(now), Some(now), now));
+    }
+}
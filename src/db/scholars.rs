@@ -1,50 +1,269 @@
-use crate::core::{AppConfig, AppError};
+use crate::core::{
+    format_duration, like_contains_pattern, push_in_list, search_like_pattern,
+    unique_slug, AppConfig, AppError,
+};
 use crate::models::pagination::PaginationQuery;
-use crate::models::scholars::{CreateScholarRequest, Scholar, ScholarDetails, ScholarSearchResult, ScholarStatistics};
-use chrono::Utc;
-use sqlx::MySqlPool;
+use crate::models::scholars::{
+    CatalogBook, CatalogFile, CreateScholarRequest, Scholar, ScholarCatalog, ScholarDetails,
+    ScholarLink, ScholarReportRow, ScholarSearchResult, ScholarStatistics, TopFileRank, TrendingScholar,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
 
 pub async fn fetch_scholars(
     pool: &MySqlPool,
     config: &AppConfig,
+    sort: &str,
     pagination: &PaginationQuery,
 ) -> Result<(Vec<Scholar>, i64), AppError> {
-    let raw_scholars = sqlx::query!(
-        "SELECT 
-            tbl_scholars.id,
-            tbl_scholars.name,
-            tbl_scholars.image,
-            tbl_states.name AS state
-        FROM tbl_scholars
-        JOIN tbl_states ON tbl_scholars.state = tbl_states.id
-        WHERE tbl_scholars.status = 'active'
-        ORDER BY tbl_scholars.priority DESC
-        LIMIT ? OFFSET ?",
-        pagination.per_page,
-        pagination.offset()
+    let mut query = QueryBuilder::<MySql>::new(SCHOLAR_LIST_SELECT);
+    query.push("WHERE tbl_scholars.status = 'active' ORDER BY ");
+    query.push(scholar_order_by(sort));
+    query.push(" LIMIT ");
+    query.push_bind(pagination.per_page);
+    query.push(" OFFSET ");
+    query.push_bind(pagination.offset());
+
+    let scholars = fetch_scholar_list(pool, config, query).await?;
+
+    let total_count: i64 =
+        sqlx::query_scalar!("SELECT COUNT(*) FROM tbl_scholars WHERE status = 'active'")
+            .fetch_one(pool)
+            .await
+            .map_err(AppError::db_error)?;
+
+    Ok((scholars, total_count))
+}
+
+/// Active featured scholars in their curated order
+pub async fn fetch_featured_scholars(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    limit: i64,
+) -> Result<Vec<Scholar>, AppError> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            s.id,
+            s.name,
+            s.image,
+            st.name AS state,
+            latest.last_upload_at AS "last_upload_at?: DateTime<Utc>"
+        FROM tbl_featured_scholars fs
+        JOIN tbl_scholars s ON s.id = fs.scholar_id
+        JOIN tbl_states st ON s.state = st.id
+        LEFT JOIN (
+            SELECT b.scholar_id, MAX(f.date) AS last_upload_at
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            WHERE f.status = 'active' AND b.status = 'active'
+            AND is_published(f.publish_at, b.publish_at)
+            GROUP BY b.scholar_id
+        ) latest ON latest.scholar_id = s.id
+        WHERE s.status = 'active'
+        ORDER BY fs.sort_order, s.id
+        LIMIT ?"#,
+        limit
     )
     .fetch_all(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    // Convert raw data to Scholar struct with formatted URLs
-    let scholars: Vec<Scholar> = raw_scholars
+    Ok(rows
         .into_iter()
         .map(|row| Scholar {
             id: row.id,
             name: row.name,
             image: Some(config.get_image_url(&row.image)),
             state: row.state,
+            last_upload_at: row.last_upload_at,
         })
-        .collect();
+        .collect())
+}
 
-    let total_count: i64 =
-        sqlx::query_scalar!("SELECT COUNT(*) FROM tbl_scholars WHERE status = 'active'")
-            .fetch_one(pool)
-            .await
-            .map_err(AppError::db_error)?;
+/// Replace the featured list with `scholar_ids`, in that order
+pub async fn set_featured_scholars(
+    pool: &MySqlPool,
+    scholar_ids: &[i32],
+    user_id: i32,
+) -> Result<(), AppError> {
+    let now = Utc::now().naive_utc();
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
 
-    Ok((scholars, total_count))
+    sqlx::query!("DELETE FROM tbl_featured_scholars")
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::db_error)?;
+
+    for (sort_order, scholar_id) in scholar_ids.iter().enumerate() {
+        sqlx::query!(
+            r#"
+            INSERT INTO tbl_featured_scholars (scholar_id, sort_order, created_by, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+            scholar_id,
+            sort_order as i32,
+            user_id,
+            now
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::db_error)?;
+    }
+
+    tx.commit().await.map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+/// Rank a scholar's active files by plays, downloads or likes, dropping files
+/// with no activity for the metric. Restricted files are kept and flagged so
+/// one ranking serves both guests and signed-in users. Each branch of the
+/// activity union is limited to the scholar's files, so the derived table
+/// never materializes activity from the rest of the catalog.
+pub async fn fetch_scholar_top_files(
+    pool: &MySqlPool,
+    scholar_id: i32,
+    metric: &str,
+    limit: i64,
+) -> Result<Vec<TopFileRank>, AppError> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            f.id AS file_id,
+            f.restricted AS "restricted: bool",
+            CAST(COUNT(m.file_id) AS SIGNED) AS "score!: i64"
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        JOIN (
+            SELECT ph.file_id FROM tbl_play_history ph
+            JOIN tbl_files pf ON ph.file_id = pf.id
+            JOIN tbl_books pb ON pf.book = pb.id
+            WHERE ? = 'plays' AND pb.scholar_id = ?
+            UNION ALL
+            SELECT dl.file_id FROM tbl_download_logs dl
+            JOIN tbl_files df ON dl.file_id = df.id
+            JOIN tbl_books dlb ON df.book = dlb.id
+            WHERE ? = 'downloads' AND dlb.scholar_id = ?
+            UNION ALL
+            SELECT fl.file_id FROM tbl_file_likes fl
+            JOIN tbl_files lf ON fl.file_id = lf.id
+            JOIN tbl_books lb ON lf.book = lb.id
+            WHERE ? = 'likes' AND lb.scholar_id = ?
+        ) m ON m.file_id = f.id
+        WHERE b.scholar_id = ? AND b.status = 'active' AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
+        GROUP BY f.id, f.restricted
+        ORDER BY COUNT(m.file_id) DESC, f.id DESC
+        LIMIT ?"#,
+        metric,
+        scholar_id,
+        metric,
+        scholar_id,
+        metric,
+        scholar_id,
+        scholar_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TopFileRank {
+            file_id: row.file_id,
+            score: row.score,
+            restricted: row.restricted,
+        })
+        .collect())
+}
+
+/// Active scholars ranked by plays, downloads and new followers since `since`,
+/// weighted per `config.trending`. Scholars with no activity in the window are left out.
+pub async fn fetch_trending_scholars(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<TrendingScholar>, AppError> {
+    let weights = &config.trending;
+    let rows = sqlx::query!(
+        r#"SELECT
+            s.id,
+            s.name,
+            s.image,
+            st.name AS state,
+            latest.last_upload_at AS "last_upload_at?: DateTime<Utc>",
+            CAST(COALESCE(p.plays, 0) AS SIGNED) AS "plays!: i64",
+            CAST(COALESCE(d.downloads, 0) AS SIGNED) AS "downloads!: i64",
+            CAST(COALESCE(fl.new_followers, 0) AS SIGNED) AS "new_followers!: i64"
+        FROM tbl_scholars s
+        JOIN tbl_states st ON s.state = st.id
+        LEFT JOIN (
+            SELECT b.scholar_id, MAX(f.date) AS last_upload_at
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            WHERE f.status = 'active' AND b.status = 'active'
+            AND is_published(f.publish_at, b.publish_at)
+            GROUP BY b.scholar_id
+        ) latest ON latest.scholar_id = s.id
+        LEFT JOIN (
+            SELECT b.scholar_id, COUNT(*) AS plays
+            FROM tbl_play_history ph
+            JOIN tbl_files f ON ph.file_id = f.id
+            JOIN tbl_books b ON f.book = b.id
+            WHERE ph.played_at >= ? AND f.status = 'active' AND b.status = 'active'
+            GROUP BY b.scholar_id
+        ) p ON p.scholar_id = s.id
+        LEFT JOIN (
+            SELECT b.scholar_id, COUNT(*) AS downloads
+            FROM tbl_download_logs dl
+            JOIN tbl_files f ON dl.file_id = f.id
+            JOIN tbl_books b ON f.book = b.id
+            WHERE dl.downloaded_at >= ? AND f.status = 'active' AND b.status = 'active'
+            GROUP BY b.scholar_id
+        ) d ON d.scholar_id = s.id
+        LEFT JOIN (
+            SELECT scholar_id, COUNT(*) AS new_followers
+            FROM tbl_user_scholar_follows
+            WHERE followed_at >= ?
+            GROUP BY scholar_id
+        ) fl ON fl.scholar_id = s.id
+        WHERE s.status = 'active'
+          AND (p.plays IS NOT NULL OR d.downloads IS NOT NULL OR fl.new_followers IS NOT NULL)
+        ORDER BY
+            COALESCE(p.plays, 0) * ? + COALESCE(d.downloads, 0) * ? + COALESCE(fl.new_followers, 0) * ? DESC,
+            s.priority DESC
+        LIMIT ?"#,
+        since,
+        since,
+        since,
+        weights.play_weight,
+        weights.download_weight,
+        weights.follow_weight,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let trending = rows
+        .into_iter()
+        .map(|row| TrendingScholar {
+            score: weights.score(row.plays, row.downloads, row.new_followers),
+            plays: row.plays,
+            downloads: row.downloads,
+            new_followers: row.new_followers,
+            scholar: Scholar {
+                id: row.id,
+                name: row.name,
+                image: Some(config.get_image_url(&row.image)),
+                state: row.state,
+                last_upload_at: row.last_upload_at,
+            },
+        })
+        .collect();
+
+    Ok(trending)
 }
 
 pub async fn fetch_scholars_by_state(
@@ -54,16 +273,25 @@ pub async fn fetch_scholars_by_state(
     pagination: &PaginationQuery,
 ) -> Result<(Vec<Scholar>, i64), AppError> {
     let raw_scholars = sqlx::query!(
-        "SELECT 
+        r#"SELECT 
             tbl_scholars.id,
             tbl_scholars.name,
             tbl_scholars.image,
-            tbl_states.name AS state
+            tbl_states.name AS state,
+            latest.last_upload_at AS "last_upload_at?: DateTime<Utc>"
         FROM tbl_scholars
         JOIN tbl_states ON tbl_scholars.state = tbl_states.id
+        LEFT JOIN (
+            SELECT b.scholar_id, MAX(f.date) AS last_upload_at
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            WHERE f.status = 'active' AND b.status = 'active'
+            AND is_published(f.publish_at, b.publish_at)
+            GROUP BY b.scholar_id
+        ) latest ON latest.scholar_id = tbl_scholars.id
         WHERE tbl_states.id = ? AND tbl_scholars.status = 'active'
         ORDER BY tbl_scholars.priority DESC
-        LIMIT ? OFFSET ?",
+        LIMIT ? OFFSET ?"#,
         state_id,
         pagination.per_page,
         pagination.offset()
@@ -80,6 +308,7 @@ pub async fn fetch_scholars_by_state(
             name: row.name,
             image: Some(config.get_image_url(&row.image)),
             state: row.state,
+            last_upload_at: row.last_upload_at,
         })
         .collect();
 
@@ -94,6 +323,116 @@ pub async fn fetch_scholars_by_state(
     Ok((scholars, total_count))
 }
 
+/// Filters active scholars by any of `state_ids` (all states when empty) and a name fragment.
+/// `sort` must already be validated as one of priority, name or newest.
+pub async fn fetch_scholars_filtered(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    state_ids: &[i32],
+    name_query: Option<&str>,
+    sort: &str,
+    pagination: &PaginationQuery,
+) -> Result<(Vec<Scholar>, i64), AppError> {
+    let name_pattern = name_query
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(like_contains_pattern);
+
+    let mut query = QueryBuilder::<MySql>::new(SCHOLAR_LIST_SELECT);
+    push_scholar_filters(&mut query, state_ids, name_pattern.as_deref());
+    query.push(" ORDER BY ");
+    query.push(scholar_order_by(sort));
+    query.push(" LIMIT ");
+    query.push_bind(pagination.per_page);
+    query.push(" OFFSET ");
+    query.push_bind(pagination.offset());
+
+    let scholars = fetch_scholar_list(pool, config, query).await?;
+
+    let mut query = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM tbl_scholars ");
+    push_scholar_filters(&mut query, state_ids, name_pattern.as_deref());
+    let total_count: i64 = query
+        .build_query_scalar()
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok((scholars, total_count))
+}
+
+/// Scholar list columns with each scholar's newest active upload, up to the WHERE clause
+const SCHOLAR_LIST_SELECT: &str = r#"SELECT 
+            tbl_scholars.id,
+            tbl_scholars.name,
+            tbl_scholars.image,
+            tbl_states.name AS state,
+            latest.last_upload_at
+        FROM tbl_scholars
+        JOIN tbl_states ON tbl_scholars.state = tbl_states.id
+        LEFT JOIN (
+            SELECT b.scholar_id, MAX(f.date) AS last_upload_at
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            WHERE f.status = 'active' AND b.status = 'active'
+            AND is_published(f.publish_at, b.publish_at)
+            GROUP BY b.scholar_id
+        ) latest ON latest.scholar_id = tbl_scholars.id
+        "#;
+
+// ORDER BY for an already validated sort; anything else falls back to priority.
+// `recent` lists scholars without uploads last
+fn scholar_order_by(sort: &str) -> &'static str {
+    match sort {
+        "name" => "tbl_scholars.name ASC, tbl_scholars.id ASC",
+        "newest" => "tbl_scholars.created_at DESC, tbl_scholars.id ASC",
+        "recent" => {
+            "latest.last_upload_at IS NULL, latest.last_upload_at DESC, \
+             tbl_scholars.priority DESC, tbl_scholars.id ASC"
+        }
+        _ => "tbl_scholars.priority DESC, tbl_scholars.id ASC",
+    }
+}
+
+async fn fetch_scholar_list(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    mut query: QueryBuilder<'_, MySql>,
+) -> Result<Vec<Scholar>, AppError> {
+    let rows = query
+        .build_query_as::<(i32, String, String, String, Option<DateTime<Utc>>)>()
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, image, state, last_upload_at)| Scholar {
+            id,
+            name,
+            image: Some(config.get_image_url(&image)),
+            state,
+            last_upload_at,
+        })
+        .collect())
+}
+
+// The WHERE clause shared by the filtered list and its count, so both always agree
+fn push_scholar_filters<'args>(
+    query: &mut QueryBuilder<'args, MySql>,
+    state_ids: &'args [i32],
+    name_pattern: Option<&'args str>,
+) {
+    query.push("WHERE tbl_scholars.status = 'active'");
+    if !state_ids.is_empty() {
+        query.push(" AND ");
+        push_in_list(query, "tbl_scholars.state", state_ids.iter().copied());
+    }
+    if let Some(pattern) = name_pattern {
+        query.push(" AND tbl_scholars.name LIKE ");
+        query.push_bind(pattern);
+    }
+}
+
 pub async fn search_scholars(
     pool: &MySqlPool,
     config: &AppConfig,
@@ -101,6 +440,9 @@ pub async fn search_scholars(
     page: i32,
     items_per_page: i32,
 ) -> Result<(Vec<ScholarSearchResult>, i64), AppError> {
+    let Some(pattern) = search_like_pattern(search_term) else {
+        return Ok((Vec::new(), 0));
+    };
     let offset = (page - 1) * items_per_page;
 
     let raw_scholars = sqlx::query!(
@@ -112,11 +454,12 @@ pub async fn search_scholars(
             tbl_states.name AS state
         FROM tbl_scholars
         JOIN tbl_states ON tbl_scholars.state = tbl_states.id
-        WHERE (tbl_scholars.name LIKE ? ) 
+        WHERE (tbl_scholars.name LIKE ? OR tbl_scholars.about LIKE ?)
         AND tbl_scholars.status = 'active'
         LIMIT ? OFFSET ?
         "#,
-        format!("%{}%", search_term),
+        &pattern,
+        &pattern,
         items_per_page,
         offset
     )
@@ -132,17 +475,19 @@ pub async fn search_scholars(
             name: row.name,
             image: Some(config.get_image_url(&row.image)),
             state: Some(row.state),
+            highlight: None,
         })
         .collect();
 
     let total_count: i64 = sqlx::query_scalar!(
         r#"
         SELECT COUNT(*) 
-        FROM tbl_scholars 
-        WHERE (name LIKE ? OR about LIKE ?) AND status = 'active'
+        FROM tbl_scholars
+        WHERE (tbl_scholars.name LIKE ? OR tbl_scholars.about LIKE ?)
+        AND tbl_scholars.status = 'active'
         "#,
-        format!("%{}%", search_term),
-        format!("%{}%", search_term)
+        &pattern,
+        &pattern
     )
     .fetch_one(pool)
     .await
@@ -151,6 +496,16 @@ pub async fn search_scholars(
     Ok((scholars, total_count))
 }
 
+/// Fails with "Scholar not found" unless an active scholar has this id
+pub async fn assert_scholar_active(pool: &MySqlPool, scholar_id: i32) -> Result<(), AppError> {
+    active_scholar_found(fetch_scholar_name(pool, scholar_id).await?)
+}
+
+// `name` is only returned for an active scholar
+fn active_scholar_found(name: Option<String>) -> Result<(), AppError> {
+    name.map(|_| ()).ok_or_else(|| AppError::not_found("Scholar not found"))
+}
+
 pub async fn get_scholar_details(
     pool: &MySqlPool,
     config: &AppConfig,
@@ -162,20 +517,23 @@ pub async fn get_scholar_details(
         r#"
         SELECT 
             s.id, s.name, s.about, s.state as state_id, s.image, s.created_at, s.updated_at, s.created_by,
-            st.name as state_name
+            s.version, st.name as state_name
         FROM tbl_scholars s
         JOIN tbl_states st ON s.state = st.id
         WHERE s.id = ? AND s.status = 'active'
         "#,
         scholar_id
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Scholar not found"))?;
 
     // Get statistics
     let statistics = get_scholar_statistics(pool, scholar_id).await?;
 
+    let links = get_scholar_links(pool, scholar_id).await?;
+
     // Check if user follows this scholar (if user_id is provided)
     let is_followed_by_user = if let Some(uid) = user_id {
         check_user_follows_scholar(pool, uid, scholar_id).await?
@@ -200,7 +558,9 @@ pub async fn get_scholar_details(
         created_at: Utc::now().naive_utc(), // Using current time as placeholder
         updated_at: Utc::now().naive_utc(), // Using current time as placeholder
         created_by: scholar_row.created_by,
+        version: scholar_row.version,
         statistics,
+        links,
         is_followed_by_user,
         has_access,
     })
@@ -212,7 +572,7 @@ pub async fn get_scholar_statistics(
 ) -> Result<ScholarStatistics, AppError> {
     // Get total books
     let total_books: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM tbl_books WHERE scholar_id = ? AND status = 'active'",
+        "SELECT COUNT(*) FROM tbl_books WHERE scholar_id = ? AND status = 'active' AND is_published(NULL, publish_at)",
         scholar_id
     )
     .fetch_one(pool)
@@ -226,6 +586,7 @@ pub async fn get_scholar_statistics(
         FROM tbl_files f
         JOIN tbl_books b ON f.book = b.id
         WHERE b.scholar_id = ? AND f.status = 'active' AND b.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
         "#,
         scholar_id
     )
@@ -236,9 +597,8 @@ pub async fn get_scholar_statistics(
     // Get total downloads
     let total_downloads: i64 = sqlx::query_scalar!(
         r#"
-        SELECT COUNT(*) 
-        FROM tbl_download_logs dl
-        JOIN tbl_files f ON dl.file_id = f.id
+        SELECT CAST(COALESCE(SUM(f.downloads), 0) AS SIGNED) AS "total!: i64"
+        FROM tbl_files f
         JOIN tbl_books b ON f.book = b.id
         WHERE b.scholar_id = ? AND f.status = 'active' AND b.status = 'active'
         "#,
@@ -345,39 +705,69 @@ pub async fn check_user_has_scholar_access(
     Ok(Some(access_count > 0))
 }
 
+/// Dropdown entries by priority then name. With `name_query`, only scholars whose
+/// name contains it (ignoring case) are kept; `limit` of
+/// None returns every match
 pub async fn get_scholars_dropdown(
     pool: &MySqlPool,
+    name_query: Option<&str>,
+    limit: Option<i64>,
 ) -> Result<Vec<crate::models::scholars::ScholarDropdown>, AppError> {
-    let scholars = sqlx::query!(
-        "SELECT id, name FROM tbl_scholars WHERE status = 'active' ORDER BY name"
+    // The same pattern as scholar search, so the dropdown matches what search finds
+    let pattern = match name_query {
+        Some(term) => match search_like_pattern(term) {
+            Some(pattern) => Some(pattern),
+            None => return Ok(Vec::new()),
+        },
+        None => None,
+    };
+
+    let dropdown_scholars = sqlx::query_as!(
+        crate::models::scholars::ScholarDropdown,
+        r#"
+        SELECT id, name
+        FROM tbl_scholars
+        WHERE status = 'active' AND (? IS NULL OR name LIKE ?)
+        ORDER BY priority DESC, name
+        LIMIT ?
+        "#,
+        pattern,
+        pattern,
+        limit.unwrap_or(i64::MAX)
     )
     .fetch_all(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    let dropdown_scholars = scholars
-        .into_iter()
-        .map(|row| crate::models::scholars::ScholarDropdown {
-            id: row.id,
-            name: row.name,
-        })
-        .collect();
-
     Ok(dropdown_scholars)
 }
 
+/// Insert a scholar under `base_slug`, or the first free `base_slug-N` when it
+/// is taken. Returns the new id and the slug it got
 pub async fn create_scholar(
     pool: &MySqlPool,
     request: &CreateScholarRequest,
     user_id: i32,
-    slug_value: &str,
-) -> Result<i32, AppError> {
-
+    base_slug: &str,
+    default_image: &str,
+) -> Result<(i32, String), AppError> {
     let about_value: String = request.about.clone().unwrap_or_default();
-    let image_value: &str = request.image.as_deref().unwrap_or("scholar.jpg");
+    let image_value: &str = request.image.as_deref().unwrap_or(default_image);
     let priority_value: i32 = request.priority.unwrap_or(0);
     let now = Utc::now().naive_utc();
 
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    // Locks the matching slug range so a concurrent create waits for this one
+    let taken = sqlx::query_scalar!(
+        r#"SELECT slug AS "slug!" FROM tbl_scholars WHERE slug = ? OR slug LIKE CONCAT(?, '-%') FOR UPDATE"#,
+        base_slug,
+        base_slug
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(AppError::db_error_or_retry)?;
+    let slug_value = unique_slug(base_slug, &taken);
 
     let result = sqlx::query!(
         r#"
@@ -394,11 +784,18 @@ pub async fn create_scholar(
         now,
         now
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(|e| {
+        AppError::db_error_or_conflict(
+            e,
+            format!("A scholar with the slug '{}' already exists", slug_value),
+        )
+    })?;
+
+    tx.commit().await.map_err(AppError::db_error_or_retry)?;
 
-    Ok(result.last_insert_id() as i32)
+    Ok((result.last_insert_id() as i32, slug_value))
 }
 
 pub async fn update_scholar(
@@ -408,6 +805,28 @@ pub async fn update_scholar(
 ) -> Result<(), AppError> {
     let now = Utc::now().naive_utc();
 
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    // Bump the version first; with an expected version this doubles as the precondition
+    let bumped = match request.version {
+        Some(expected) => sqlx::query!(
+            "UPDATE tbl_scholars SET version = version + 1 WHERE id = ? AND status = 'active' AND version = ?",
+            scholar_id,
+            expected
+        )
+        .execute(&mut *tx)
+        .await,
+        None => sqlx::query!(
+            "UPDATE tbl_scholars SET version = version + 1 WHERE id = ? AND status = 'active'",
+            scholar_id
+        )
+        .execute(&mut *tx)
+        .await,
+    }
+    .map_err(AppError::db_error)?;
+
+    crate::core::check_version_bump(bumped.rows_affected(), request.version, "Scholar")?;
+
     // Update each field individually if provided
     if let Some(ref name) = request.name {
         sqlx::query!(
@@ -416,7 +835,7 @@ pub async fn update_scholar(
             now,
             scholar_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -428,7 +847,7 @@ pub async fn update_scholar(
             now,
             scholar_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -440,7 +859,7 @@ pub async fn update_scholar(
             now,
             scholar_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -452,7 +871,7 @@ pub async fn update_scholar(
             now,
             scholar_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
@@ -464,50 +883,374 @@ pub async fn update_scholar(
             now,
             scholar_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::db_error)?;
     }
 
+    tx.commit().await.map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+pub async
+ fn delete_scholar(
+    pool: &MySqlPool,
+    scholar_id: i32,
+) -> Result<(), AppError> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        "UPDATE tbl_scholars SET status = 'inactive', updated_at = ? WHERE id = ?",
+        now,
+        scholar_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
     Ok(())
 }
 
-pub async fn check_duplicate_scholar(
+/// Upper bound on files returned across all books of a catalog, regardless of files_per_book
+const MAX_CATALOG_FILES: usize = 2000;
+
+pub async fn get_scholar_catalog(
     pool: &MySqlPool,
-    name: &str,
-    slug_value: &str,
-) -> Result<Option<String>, AppError> {
-    
-    let existing = sqlx::query!(
+    config: &AppConfig,
+    scholar_id: i32,
+    files_per_book: usize,
+    include_restricted: bool,
+) -> Result<Option<ScholarCatalog>, AppError> {
+    let scholar = sqlx::query!(
         r#"
-        SELECT name FROM tbl_scholars 
-        WHERE name = ? OR slug = ?
-        LIMIT 1
+        SELECT s.id, s.name, s.about, s.image, st.name as state_name
+        FROM tbl_scholars s
+        JOIN tbl_states st ON s.state = st.id
+        WHERE s.id = ? AND s.status = 'active'
         "#,
-        name,
-        slug_value
+        scholar_id
     )
     .fetch_optional(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    Ok(existing.map(|s| s.name))
+    let scholar = match scholar {
+        Some(scholar) => scholar,
+        None => return Ok(None),
+    };
+
+    let book_rows = sqlx::query!(
+        r#"
+        SELECT b.id, b.name, b.image
+        FROM tbl_books b
+        WHERE b.scholar_id = ? AND b.status = 'active'
+        AND is_published(NULL, b.publish_at)
+        ORDER BY b.name ASC, b.id ASC
+        "#,
+        scholar_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    // Per-book totals are aggregated in SQL so they cover every file, not just those returned.
+    // Durations are MM:SS or HH:MM:SS; unparseable ones are skipped as in sum_durations
+    let book_totals = sqlx::query!(
+        r#"
+        SELECT
+            f.book as book_id,
+            COUNT(*) as total_files,
+            CAST(SUM(TIME_TO_SEC(IF(
+                LENGTH(f.duration) - LENGTH(REPLACE(f.duration, ':', '')) = 2,
+                f.duration,
+                CONCAT('0:', f.duration)
+            ))) AS SIGNED) as total_seconds
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE b.scholar_id = ? AND b.status = 'active' AND f.status = 'active'
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        GROUP BY f.book
+        "#,
+        scholar_id,
+        include_restricted
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+    let mut totals_by_book: std::collections::HashMap<i32, (i64, Option<i64>)> = book_totals
+        .into_iter()
+        .map(|row| (row.book_id, (row.total_files, row.total_seconds)))
+        .collect();
+
+    // Only the first files_per_book files of each book are read, and never more
+    // than MAX_CATALOG_FILES in all, in the order the books are listed
+    let file_rows = sqlx::query!(
+        r#"
+        SELECT
+            file_id as "file_id!",
+            file_name as "file_name!",
+            book_id as "book_id!",
+            file_size as "file_size!",
+            file_duration as "file_duration!",
+            location as "location!"
+        FROM (
+            SELECT
+                f.id as file_id,
+                f.name as file_name,
+                f.book as book_id,
+                f.size as file_size,
+                f.duration as file_duration,
+                f.location,
+                b.name as book_name,
+                ROW_NUMBER() OVER (PARTITION BY f.book ORDER BY f.date ASC, f.id ASC) as book_position
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            WHERE b.scholar_id = ? AND b.status = 'active' AND f.status = 'active'
+            AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+        ) ranked
+        WHERE ranked.book_position <= ?
+        ORDER BY ranked.book_name ASC, ranked.book_id ASC, ranked.book_position ASC
+        LIMIT ?
+        "#,
+        scholar_id,
+        include_restricted,
+        files_per_book as i64,
+        MAX_CATALOG_FILES as i64
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let mut files_by_book: std::collections::HashMap<i32, Vec<CatalogFile>> =
+        std::collections::HashMap::new();
+    for f in file_rows {
+        files_by_book.entry(f.book_id).or_default().push(CatalogFile {
+            file_id: f.file_id,
+            file_name: f.file_name,
+            file_url: config.get_upload_url(&f.location),
+            file_size: f.file_size,
+            file_duration: f.file_duration,
+        });
+    }
+
+    let mut total_files: i64 = 0;
+    let books: Vec<CatalogBook> = book_rows
+        .into_iter()
+        .map(|book| {
+            let files = files_by_book.remove(&book.id).unwrap_or_default();
+            let (book_total, total_seconds) = totals_by_book.remove(&book.id).unwrap_or((0, None));
+            total_files += book_total;
+
+            CatalogBook {
+                book_id: book.id,
+                book_name: book.name,
+                book_image: Some(config.get_image_url(&book.image)),
+                total_files: book_total,
+                total_duration: total_seconds.map(|seconds| format_duration(seconds.max(0) as u64)),
+                has_more_files: (files.len() as i64) < book_total,
+                files,
+            }
+        })
+        .collect();
+
+    Ok(Some(ScholarCatalog {
+        scholar_id: scholar.id,
+        scholar_name: scholar.name,
+        about: Some(scholar.about),
+        state: scholar.state_name,
+        image: Some(config.get_image_url(&scholar.image)),
+        total_books: books.len() as i64,
+        total_files,
+        books,
+    }))
 }
-pub async
- fn delete_scholar(
+
+pub async fn fetch_scholar_name(pool: &MySqlPool, scholar_id: i32) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar!(
+        "SELECT name FROM tbl_scholars WHERE id = ? AND status = 'active'",
+        scholar_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
+// One page of a scholar's active files with play and download counts in
+// [start, end), keyed by file id so large catalogs can be read page by page
+pub async fn fetch_scholar_report_page(
     pool: &MySqlPool,
     scholar_id: i32,
-) -> Result<(), AppError> {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    after_file_id: i32,
+    limit: i32,
+) -> Result<Vec<ScholarReportRow>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            f.id as file_id,
+            f.name as file_name,
+            b.name as book_name,
+            (
+                SELECT COUNT(*) FROM tbl_play_history ph
+                WHERE ph.file_id = f.id AND ph.played_at >= ? AND ph.played_at < ?
+            ) as plays,
+            (
+                SELECT COUNT(*) FROM tbl_download_logs dl
+                WHERE dl.file_id = f.id AND dl.downloaded_at >= ? AND dl.downloaded_at < ?
+            ) as downloads
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE b.scholar_id = ? AND f.status = 'active' AND b.status = 'active'
+        AND f.id > ?
+        ORDER BY f.id ASC
+        LIMIT ?
+        "#,
+        start,
+        end,
+        start,
+        end,
+        scholar_id,
+        after_file_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ScholarReportRow {
+            file_id: row.file_id,
+            file_name: row.file_name,
+            book_name: row.book_name,
+            plays: row.plays.unwrap_or(0),
+            downloads: row.downloads.unwrap_or(0),
+        })
+        .collect())
+}
+
+pub async fn get_scholar_links(
+    pool: &MySqlPool,
+    scholar_id: i32,
+) -> Result<Vec<ScholarLink>, AppError> {
+    let links = sqlx::query_as!(
+        ScholarLink,
+        r#"
+        SELECT id, link_type, url, created_at
+        FROM tbl_scholar_links
+        WHERE scholar_id = ?
+        ORDER BY id
+        "#,
+        scholar_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(links)
+}
+
+pub async fn add_scholar_link(
+    pool: &MySqlPool,
+    scholar_id: i32,
+    link_type: &str,
+    url: &str,
+    user_id: i32,
+) -> Result<ScholarLink, AppError> {
     let now = Utc::now().naive_utc();
 
-    sqlx::query!(
-        "UPDATE tbl_scholars SET status = 'inactive', updated_at = ? WHERE id = ?",
-        now,
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO tbl_scholar_links (scholar_id, link_type, url, created_by, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        scholar_id,
+        link_type,
+        url,
+        user_id,
+        now
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::db_error_or_conflict(e, "This link is already on the scholar's profile"))?;
+
+    Ok(ScholarLink {
+        id: result.last_insert_id() as i32,
+        link_type: link_type.to_string(),
+        url: url.to_string(),
+        created_at: now,
+    })
+}
+
+/// Returns false when the link does not exist or belongs to another scholar
+pub async fn remove_scholar_link(
+    pool: &MySqlPool,
+    scholar_id: i32,
+    link_id: i32,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM tbl_scholar_links WHERE id = ? AND scholar_id = ?",
+        link_id,
         scholar_id
     )
     .execute(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    Ok(())
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assert_scholar_active_is_not_found_for_an_inactive_scholar() {
+        // fetch_scholar_name finds no row for an inactive scholar
+        let err = active_scholar_found(None).unwrap_err();
+        assert_eq!(err.error_type, crate::core::AppErrorType::NotFoundError);
+        assert_eq!(err.message.as_deref(), Some("Scholar not found"));
+
+        assert!(active_scholar_found(Some("Sheikh Jafar".to_string())).is_ok());
+    }
+
+    #[test]
+    fn scholar_filters_bind_states_and_the_escaped_name() {
+        let mut query = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM tbl_scholars ");
+        push_scholar_filters(&mut query, &[1, 2], Some("%ibn\\_%"));
+        assert_eq!(
+            query.sql(),
+            "SELECT COUNT(*) FROM tbl_scholars WHERE tbl_scholars.status = 'active' \
+             AND tbl_scholars.state IN (?, ?) AND tbl_scholars.name LIKE ?"
+        );
+
+        let mut query = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM tbl_scholars ");
+        push_scholar_filters(&mut query, &[], None);
+        assert_eq!(
+            query.sql(),
+            "SELECT COUNT(*) FROM tbl_scholars WHERE tbl_scholars.status = 'active'"
+        );
+    }
+
+    #[test]
+    fn recent_sort_puts_the_latest_upload_first() {
+        // Newer uploads sort first, scholars with none go last, priority breaks ties
+        assert_eq!(
+            scholar_order_by("recent"),
+            "latest.last_upload_at IS NULL, latest.last_upload_at DESC, \
+             tbl_scholars.priority DESC, tbl_scholars.id ASC"
+        );
+        assert_eq!(scholar_order_by("priority"), "tbl_scholars.priority DESC, tbl_scholars.id ASC");
+
+        let mut query = QueryBuilder::<MySql>::new(SCHOLAR_LIST_SELECT);
+        push_scholar_filters(&mut query, &[], None);
+        query.push(" ORDER BY ");
+        query.push(scholar_order_by("recent"));
+        assert!(query.sql().contains("MAX(f.date) AS last_upload_at"));
+        assert!(query.sql().ends_with("ORDER BY latest.last_upload_at IS NULL, latest.last_upload_at DESC, tbl_scholars.priority DESC, tbl_scholars.id ASC"));
+    }
 }
@@ -0,0 +1,188 @@
+use crate::core::{format_duration, parse_duration, sum_durations, AppConfig, AppError};
+use crate::models::share::{ShareCard, ShareKind};
+use sqlx::MySqlPool;
+
+// Share cards are public, so they only describe active content under an active
+// scholar and count files the way a listing for the same caller would
+
+struct FileShareRow {
+    id: i32,
+    name: String,
+    duration: String,
+    location: String,
+    book_name: String,
+    book_image: String,
+    scholar_name: String,
+    scholar_image: String,
+}
+
+// Books without their own image fall back to the scholar's
+fn file_share_card(config: &AppConfig, row: FileShareRow) -> ShareCard {
+    let image = if row.book_image.trim().is_empty() {
+        &row.scholar_image
+    } else {
+        &row.book_image
+    };
+
+    ShareCard {
+        kind: ShareKind::File,
+        id: row.id,
+        title: row.name,
+        description: format!("{} by {}", row.book_name, row.scholar_name),
+        image_url: config.get_image_url(image),
+        audio_url: Some(config.get_upload_url(&row.location)),
+        duration_seconds: parse_duration(&row.duration).ok().map(u64::from),
+        duration: Some(row.duration),
+    }
+}
+
+pub async fn fetch_file_share_card(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    file_id: i32,
+    include_restricted: bool,
+) -> Result<ShareCard, AppError> {
+    let row = sqlx::query_as!(
+        FileShareRow,
+        r#"
+        SELECT
+            f.id, f.name, f.duration, f.location,
+            b.name as book_name, b.image as book_image,
+            s.name as scholar_name, s.image as scholar_image
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        JOIN tbl_scholars s ON f.scholar = s.id
+        WHERE f.id = ? AND f.status = 'active' AND b.status = 'active' AND s.status = 'active'
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
+        file_id,
+        include_restricted
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("File not found"))?;
+
+    Ok(file_share_card(config, row))
+}
+
+pub async fn fetch_book_share_card(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    book_id: i32,
+    include_restricted: bool,
+) -> Result<ShareCard, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT b.id, b.name, b.about, b.image, s.name as scholar_name
+        FROM tbl_books b
+        JOIN tbl_scholars s ON b.scholar_id = s.id
+        WHERE b.id = ? AND b.status = 'active' AND s.status = 'active'
+        AND is_published(NULL, b.publish_at)
+        "#,
+        book_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Book not found"))?;
+
+    let durations: Vec<String> = sqlx::query_scalar!(
+        r#"
+        SELECT duration FROM tbl_files
+        WHERE book = ? AND status = 'active' AND (restricted = FALSE OR ?)
+        AND is_published(publish_at, NULL)
+        "#,
+        book_id,
+        include_restricted
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let (total_seconds, _) = sum_durations(durations.iter().map(String::as_str));
+    let description = if row.about.trim().is_empty() {
+        format!("{} lectures by {}", durations.len(), row.scholar_name)
+    } else {
+        row.about
+    };
+
+    Ok(ShareCard {
+        kind: ShareKind::Book,
+        id: row.id,
+        title: row.name,
+        description,
+        image_url: config.get_image_url(&row.image),
+        audio_url: None,
+        duration: Some(format_duration(total_seconds)),
+        duration_seconds: Some(total_seconds),
+    })
+}
+
+pub async fn fetch_scholar_share_card(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    scholar_id: i32,
+) -> Result<ShareCard, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            s.id, s.name, s.about, s.image,
+            (SELECT COUNT(*) FROM tbl_books b WHERE b.scholar_id = s.id AND b.status = 'active'
+                AND is_published(NULL, b.publish_at)) as "total_books!: i64"
+        FROM tbl_scholars s
+        WHERE s.id = ? AND s.status = 'active'
+        "#,
+        scholar_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError::not_found("Scholar not found"))?;
+
+    let description = if row.about.trim().is_empty() {
+        format!("{} books of lectures by {}", row.total_books, row.name)
+    } else {
+        row.about
+    };
+
+    Ok(ShareCard {
+        kind: ShareKind::Scholar,
+        id: row.id,
+        title: row.name,
+        description,
+        image_url: config.get_image_url(&row.image),
+        audio_url: None,
+        duration: None,
+        duration_seconds: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_share_card_has_title_image_and_audio() {
+        let config = AppConfig::new().expect("local configuration");
+        let row = FileShareRow {
+            id: 12,
+            name: "Lesson 12".to_string(),
+            duration: "45:00".to_string(),
+            location: "lesson-12.mp3".to_string(),
+            book_name: "Kitabut Tauhid".to_string(),
+            book_image: "tauhid.jpg".to_string(),
+            scholar_name: "Sheikh Jafar".to_string(),
+            scholar_image: "jafar.jpg".to_string(),
+        };
+
+        let card = file_share_card(&config, row);
+
+        assert_eq!(card.title, "Lesson 12");
+        assert_eq!(card.description, "Kitabut Tauhid by Sheikh Jafar");
+        assert_eq!(card.image_url, config.get_image_url("tauhid.jpg"));
+        assert_eq!(card.audio_url, Some(config.get_upload_url("lesson-12.mp3")));
+        assert_eq!(card.duration_seconds, Some(2700));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::core::{sum_durations, AppError};
+use crate::models::stats::PublicStats;
+use sqlx::MySqlPool;
+
+pub async fn fetch_public_stats(pool: &MySqlPool) -> Result<PublicStats, AppError> {
+    let total_scholars =
+        sqlx::query_scalar!("SELECT COUNT(*) FROM tbl_scholars WHERE status = 'active'")
+            .fetch_one(pool)
+            .await
+            .map_err(AppError::db_error)?;
+
+    let total_books = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM tbl_books WHERE status = 'active' AND is_published(NULL, publish_at)"
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    // Durations are stored as "MM:SS" / "HH:MM:SS" text, so they are summed in Rust.
+    // Files in an inactive or unpublished book are not listed anywhere, so they are not counted
+    let durations: Vec<String> = sqlx::query_scalar!(
+        r#"
+        SELECT f.duration
+        FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.status = 'active' AND b.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let (total_duration_seconds, unparsed_durations) =
+        sum_durations(durations.iter().map(String::as_str));
+
+    if unparsed_durations > 0 {
+        tracing::warn!(
+            "{} active files have a malformed duration and were left out of the total",
+            unparsed_durations
+        );
+    }
+
+    Ok(PublicStats {
+        total_scholars,
+        total_books,
+        total_files: durations.len() as i64,
+        total_duration_seconds,
+        total_hours: total_duration_seconds / 3600,
+        unparsed_durations,
+    })
+}
@@ -1,4 +1,4 @@
-use crate::core::AppError;
+use crate::core::{AppError, AppErrorType};
 use crate::models::subscriptions::{
     CreateSubscriptionRequest, SubscriptionPlan, SubscriptionPlanSummary, SubscriptionStatus,
     UserSubscription, UserSubscriptionMinimal, UserSubscriptionWithPlanSummary,
@@ -152,6 +152,45 @@ pub async fn get_user_subscription_by_id(
     })
 }
 
+// Get a user's own subscription while it is still awaiting payment verification
+pub async fn get_pending_subscription_for_user(
+    pool: &MySqlPool,
+    subscription_id: i32,
+    user_id: i32,
+) -> Result<Option<UserSubscription>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, user_id, subscription_plan_id, status, start_date, end_date,
+               payment_method, transaction_reference, payment_amount, payment_currency,
+               payment_date, notes, created_at, updated_at
+        FROM tbl_user_subscriptions
+        WHERE id = ? AND user_id = ? AND status = 'pending'
+        "#,
+        subscription_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(row.map(|row| UserSubscription {
+        id: row.id,
+        user_id: row.user_id,
+        subscription_plan_id: row.subscription_plan_id,
+        status: row.status,
+        start_date: row.start_date,
+        end_date: row.end_date,
+        payment_method: row.payment_method,
+        transaction_reference: row.transaction_reference,
+        payment_amount: row.payment_amount,
+        payment_currency: row.payment_currency,
+        payment_date: row.payment_date,
+        notes: row.notes,
+        created_at: row.created_at.naive_utc(),
+        updated_at: row.updated_at.naive_utc(),
+    }))
+}
+
 // Get user subscriptions
 pub async fn get_user_subscriptions(
     pool: &MySqlPool,
@@ -210,7 +249,7 @@ pub async fn get_user_active_subscription_with_plan(
         FROM tbl_user_subscriptions us
         JOIN tbl_subscription_plans sp ON us.subscription_plan_id = sp.id
         WHERE us.user_id = ? AND us.status = 'active' 
-        AND (us.end_date IS NULL OR us.end_date >= CURDATE())
+        AND (us.end_date IS NULL OR us.end_date >= UTC_DATE())
         ORDER BY us.created_at DESC
         LIMIT 1
         "#,
@@ -254,7 +293,7 @@ pub async fn get_user_active_subscription(
                payment_date, notes, created_at, updated_at
         FROM tbl_user_subscriptions
         WHERE user_id = ? AND status = 'active' 
-        AND (end_date IS NULL OR end_date >= CURDATE())
+        AND (end_date IS NULL OR end_date >= UTC_DATE())
         ORDER BY created_at DESC
         LIMIT 1
         "#,
@@ -287,28 +326,40 @@ pub async fn get_user_active_subscription(
 }
 
 // Verify user subscription (admin function) - Auto-calculates dates based on plan
+// Only pending subscriptions can be verified. The row is locked and the UPDATE
+// itself only matches a pending row, so of two concurrent verifications of the
+// same subscription exactly one applies and the other gets a conflict.
 pub async fn verify_user_subscription(
     pool: &MySqlPool,
     subscription_id: i32,
     request: &VerifySubscriptionRequest,
 ) -> Result<UserSubscription, AppError> {
     let now = Utc::now().naive_utc();
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
 
-    if request.status == "active" {
-        // Get subscription plan details to calculate dates
-        let subscription_with_plan = sqlx::query!(
-            r#"
-            SELECT us.id, sp.duration_months
-            FROM tbl_user_subscriptions us
-            JOIN tbl_subscription_plans sp ON us.subscription_plan_id = sp.id
-            WHERE us.id = ?
-            "#,
-            subscription_id
-        )
-        .fetch_one(pool)
-        .await
-        .map_err(AppError::db_error)?;
+    // Get subscription plan details to calculate dates
+    let subscription_with_plan = sqlx::query!(
+        r#"
+        SELECT us.id, us.status, sp.duration_months
+        FROM tbl_user_subscriptions us
+        JOIN tbl_subscription_plans sp ON us.subscription_plan_id = sp.id
+        WHERE us.id = ?
+        FOR UPDATE
+        "#,
+        subscription_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| AppError {
+        message: Some("Subscription not found".to_string()),
+        cause: None,
+        error_type: AppErrorType::NotFoundError,
+    })?;
 
+    ensure_pending(&subscription_with_plan.status)?;
+
+    let result = if request.status == "active" {
         // Calculate start and end dates based on plan duration
         let start_date = chrono::Utc::now().date_naive();
         let end_date =
@@ -318,7 +369,7 @@ pub async fn verify_user_subscription(
             r#"
             UPDATE tbl_user_subscriptions 
             SET status = ?, start_date = ?, end_date = ?, notes = ?, updated_at = ?
-            WHERE id = ?
+            WHERE id = ? AND status = 'pending'
             "#,
             request.status,
             start_date,
@@ -327,30 +378,54 @@ pub async fn verify_user_subscription(
             now,
             subscription_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(AppError::db_error)?;
+        .map_err(AppError::db_error)?
     } else {
         // For cancelled status, don't update dates
         sqlx::query!(
             r#"
             UPDATE tbl_user_subscriptions 
             SET status = ?, notes = ?, updated_at = ?
-            WHERE id = ?
+            WHERE id = ? AND status = 'pending'
             "#,
             request.status,
             request.notes,
             now,
             subscription_id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(AppError::db_error)?;
+        .map_err(AppError::db_error)?
+    };
+
+    if result.rows_affected() == 0 {
+        // Another verification got there first; leave its result untouched
+        let current = get_user_subscription_by_id(pool, subscription_id).await?;
+        return Err(already_processed(&current.status));
     }
 
+    tx.commit().await.map_err(AppError::db_error)?;
+
     get_user_subscription_by_id(pool, subscription_id).await
 }
 
+/// Only a pending subscription may be activated or cancelled
+fn ensure_pending(status: &str) -> Result<(), AppError> {
+    if status == "pending" {
+        Ok(())
+    } else {
+        Err(already_processed(status))
+    }
+}
+
+fn already_processed(status: &str) -> AppError {
+    AppError::conflict_error(format!(
+        "Subscription has already been processed and is {}",
+        status
+    ))
+}
+
 // Get pending subscriptions (admin function)
 pub async fn get_pending_subscriptions(
     pool: &MySqlPool,
@@ -421,3 +496,33 @@ pub async fn get_user_subscription_status(
         days_remaining,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_first_of_two_concurrent_activations_applies() {
+        // Both verifications target the same pending row. The row lock makes
+        // the second read the status the first one committed
+        let mut status = "pending".to_string();
+
+        let first = ensure_pending(&status);
+        assert!(first.is_ok());
+        status = "active".to_string();
+
+        let second = ensure_pending(&status).unwrap_err();
+        assert_eq!(second.error_type, AppErrorType::ConflictError);
+        assert_eq!(
+            second.message.as_deref(),
+            Some("Subscription has already been processed and is active")
+        );
+        assert_eq!(status, "active");
+    }
+
+    #[test]
+    fn a_cancelled_subscription_cannot_be_activated() {
+        let err = ensure_pending("cancelled").unwrap_err();
+        assert_eq!(err.error_type, AppErrorType::ConflictError);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::core::AppError;
-use crate::models::uploads::{FileDownloadInfo, FileUploadResponse};
+use crate::core::{is_safe_storage_location, resolve_storage_path, AppError};
+use crate::models::uploads::{
+    FileDownloadInfo, FilePreviewSource, FileUploadResponse, NewImportedFile,
+};
 use sqlx::MySqlPool;
 
 
@@ -11,19 +13,33 @@ pub async fn save_uploaded_file(
     file_size: i64,
     content_type: &str,
     duration: &str,        // Formatted duration (MM:SS or HH:MM:SS)
+    content_hash: &str,    // Hex SHA-256 of the stored file
     random_id: &str,
     user_id: i32,
 ) -> Result<FileUploadResponse, AppError> {
     let now = chrono::Utc::now();
 
-    // Get scholar_id from book_id first
-    let scholar_id = get_scholar_id_from_book(pool, book_id).await?;
+    if !is_safe_storage_location(file_path) {
+        return Err(AppError::bad_request(format!("Invalid file location: {}", file_path)));
+    }
+
+    let mut tx = pool.begin().await.map_err(AppError::db_error)?;
+
+    // Holds the book so it can't be deleted before the file row is in
+    let scholar_id: i32 = sqlx::query_scalar!(
+        "SELECT scholar_id FROM tbl_books WHERE id = ? AND status = 'active' FOR SHARE",
+        book_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::db_error_or_retry)?
+    .ok_or_else(|| AppError::not_found("Book not found"))?;
 
     let result = sqlx::query!(
         r#"
         INSERT INTO tbl_files 
-        (book, scholar, name, location, size, type, duration, uid, created_by, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        (book, scholar, name, location, size, type, duration, content_hash, uid, created_by, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         book_id,
         scholar_id,
@@ -32,14 +48,17 @@ pub async fn save_uploaded_file(
         file_size,
         content_type,
         duration,
+        content_hash,
         random_id,
         user_id,
         now,
         now
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(AppError::db_error_or_retry)?;
+
+    tx.commit().await.map_err(AppError::db_error_or_retry)?;
 
     let file_id = result.last_insert_id() as i32;
 
@@ -52,13 +71,60 @@ pub async fn save_uploaded_file(
     })
 }
 
+/// Register a file that already sits in the uploads dir (bulk import); unlike
+/// `save_uploaded_file` it records the content hash used for deduplication
+pub async fn save_imported_file(pool: &MySqlPool, file: &NewImportedFile<'_>) -> Result<i32, AppError> {
+    if !is_safe_storage_location(file.location) {
+        return Err(AppError::bad_request(format!("Invalid file location: {}", file.location)));
+    }
+
+    let now = chrono::Utc::now();
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO tbl_files
+        (book, scholar, name, location, size, type, duration, content_hash, uid, created_by, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, 'audio/mpeg', ?, ?, ?, ?, ?, ?)
+        "#,
+        file.book_id,
+        file.scholar_id,
+        file.name,
+        file.location,
+        file.file_size,
+        file.duration,
+        file.content_hash,
+        file.uid,
+        file.created_by,
+        now,
+        now
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(result.last_insert_id() as i32)
+}
+
+/// Id of an active file with this content hash, if any
+pub async fn find_file_by_content_hash(
+    pool: &MySqlPool,
+    content_hash: &str,
+) -> Result<Option<i32>, AppError> {
+    sqlx::query_scalar!(
+        "SELECT id FROM tbl_files WHERE content_hash = ? AND status = 'active' LIMIT 1",
+        content_hash
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
 // const UPLOAD_DIR: &str = "/home/mubarak/Documents/my-documents/muryar_sunnah/web/uploads";
 
 pub async fn get_file_download_info(
     pool: &MySqlPool,
     uploads_dir: &str,
     file_id: i32,
-) -> Result<FileDownloadInfo, AppError> {
+) -> Result<Option<FileDownloadInfo>, AppError> {
     let file_data = sqlx::query!(
         r#"
         SELECT
@@ -70,25 +136,145 @@ pub async fn get_file_download_info(
             f.scholar,
             f.type
         FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
         WHERE f.id = ? AND f.status = 'active'
+        AND is_published(f.publish_at, b.publish_at)
         "#,
         file_id
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    let file_info = FileDownloadInfo {
+    Ok(file_data.map(|file_data| FileDownloadInfo {
         file_id: file_data.id,
         filename: file_data.name,
-        file_path: format!("{}/{}", uploads_dir, file_data.location),
+        file_path: resolve_storage_path(uploads_dir, &file_data.location),
         content_type: file_data.r#type, // Default since not stored
         file_size: file_data.size.parse().unwrap_or(0),
         book_id: file_data.book,
         scholar_id: file_data.scholar,
-    };
+    }))
+}
+
+/// Source of a preview clip; restricted and not yet published files have none
+pub async fn get_file_preview_source(
+    pool: &MySqlPool,
+    uploads_dir: &str,
+    file_id: i32,
+    include_restricted: bool,
+) -> Result<Option<FilePreviewSource>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT f.id, f.location, f.duration FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.id = ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
+        file_id,
+        include_restricted
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?;
 
-    Ok(file_info)
+    Ok(row.map(|row| FilePreviewSource {
+        file_id: row.id,
+        file_path: resolve_storage_path(uploads_dir, &row.location),
+        duration: row.duration,
+    }))
+}
+
+/// Log a served preview clip; kept out of the play history on purpose
+pub async fn log_preview_play(
+    pool: &MySqlPool,
+    file_id: i32,
+    user_id: Option<i32>,
+    anonymous_id: Option<&str>,
+    client_ip: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO tbl_preview_plays (file_id, user_id, anonymous_id, client_ip, played_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        file_id,
+        user_id,
+        anonymous_id,
+        client_ip,
+        chrono::Utc::now().naive_utc()
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
+/// Whether a stored location belongs to an active file, published or not
+pub async fn is_active_file_location(pool: &MySqlPool, location: &str) -> Result<bool, AppError> {
+    let count = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM tbl_files WHERE location = ? AND status = 'active'",
+        location
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(count > 0)
+}
+
+/// Whether a stored location belongs to an active, published file the caller may
+/// stream; restricted files only with `include_restricted`
+pub async fn is_published_file_location(
+    pool: &MySqlPool,
+    location: &str,
+    include_restricted: bool,
+) -> Result<bool, AppError> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) FROM tbl_files f
+        JOIN tbl_books b ON f.book = b.id
+        WHERE f.location = ? AND f.status = 'active'
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        "#,
+        location,
+        include_restricted
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(count > 0)
+}
+
+/// Raises a pending `missing_file` report so admins see files whose audio is gone from disk.
+/// The unique pending key skips the insert while an earlier missing_file report on the file is still pending.
+/// The unique pending key skips the insert while an earlier server report on the file is still pending.
+pub async fn flag_missing_file(
+    pool: &MySqlPool,
+    file_id: i32,
+    file_path: &str,
+) -> Result<(), AppError> {
+    let description = format!("Audio not found on disk at {}", file_path);
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO tbl_file_reports (user_id, file_id, reason, description, status, created_at)
+        VALUES (NULL, ?, 'missing_file', ?, 'pending', ?)
+        "#,
+        file_id,
+        description,
+        chrono::Utc::now().naive_utc()
+    )
+    .execute(pool)
+    .await;
+
+    match inserted {
+        Ok(_) => Ok(()),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Ok(()),
+        Err(e) => Err(AppError::db_error(e)),
+    }
 }
 
 pub async fn check_file_access_permission(
@@ -114,14 +300,3 @@ pub async fn check_file_access_permission(
     Ok(count > 0)
 }
 
-pub async fn get_scholar_id_from_book(pool: &MySqlPool, book_id: i32) -> Result<i32, AppError> {
-    let scholar_id: i32 = sqlx::query_scalar!(
-        "SELECT scholar_id FROM tbl_books WHERE id = ? AND status = 'active'",
-        book_id
-    )
-    .fetch_one(pool)
-    .await
-    .map_err(AppError::db_error)?;
-
-    Ok(scholar_id)
-}
@@ -1,20 +1,20 @@
-use crate::core::AppError;
+use crate::core::config::PasswordHashingConfig;
+use crate::core::{handle_from_name, AppError};
 use crate::models::users::{RegisterRequest, UpdateProfileRequest, User};
 use argon2::password_hash::{rand_core::OsRng, SaltString};
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use chrono::Utc;
 use sqlx::MySqlPool;
 
-pub async fn create_user(pool: &MySqlPool, request: &RegisterRequest) -> Result<User, AppError> {
+pub async fn create_user(
+    pool: &MySqlPool,
+    request: &RegisterRequest,
+    hashing: &PasswordHashingConfig,
+) -> Result<User, AppError> {
     let now = Utc::now().naive_utc();
     
     // Hash the password
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(request.password.as_bytes(), &salt)
-        .map_err(|_| AppError::internal_error("Failed to hash password"))?
-        .to_string();
+    let password_hash = hash_password(&request.password, hashing)?;
 
     let role = request.role.as_deref().unwrap_or("user");
 
@@ -34,18 +34,44 @@ pub async fn create_user(pool: &MySqlPool, request: &RegisterRequest) -> Result<
     )
     .execute(pool)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(|e| AppError::db_error_or_conflict(e, "A user with this email address already exists"))?;
 
     let user_id = result.last_insert_id() as i32;
+    assign_handle(pool, user_id, &request.name).await?;
 
     get_user_by_id(pool, user_id).await
 }
 
+/// Give a new user the handle derived from their name, or `<handle>_<id>`
+/// when another user already holds it
+async fn assign_handle(pool: &MySqlPool, user_id: i32, name: &str) -> Result<(), AppError> {
+    let handle = handle_from_name(name);
+    match sqlx::query!("UPDATE tbl_users SET handle = ? WHERE id = ?", handle, user_id)
+        .execute(pool)
+        .await
+    {
+        Ok(_) => return Ok(()),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {}
+        Err(e) => return Err(AppError::db_error(e)),
+    }
+
+    sqlx::query!(
+        "UPDATE tbl_users SET handle = ? WHERE id = ?",
+        format!("{}_{}", handle, user_id),
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    Ok(())
+}
+
 pub async fn get_user_by_email(pool: &MySqlPool, email: &str) -> Result<User, AppError> {
     let row = sqlx::query!(
         r#"
-        SELECT id, name, email, address, phone, role, password, status, 
-               created_at, updated_at
+        SELECT id, name, handle, email, address, phone, role, password, status, 
+               timezone, created_at, updated_at
         FROM tbl_users
         WHERE email = ? AND status = 1
         "#,
@@ -58,22 +84,50 @@ pub async fn get_user_by_email(pool: &MySqlPool, email: &str) -> Result<User, Ap
     Ok(User {
         id: row.id,
         name: row.name,
+        handle: row.handle,
         email: row.email,
         address: row.address,
         phone: row.phone,
         role: row.role,
         password: row.password,
         status: row.status,
+        timezone: row.timezone,
         created_at: row.created_at.naive_utc(),
         updated_at: row.updated_at.naive_utc(),
     })
 }
 
+/// Resolve `@mention` handles to active user ids via the unique `handle`
+/// column; unknown handles are skipped
+pub async fn resolve_mentioned_users(
+    pool: &MySqlPool,
+    handles: &[String],
+) -> Result<Vec<i32>, AppError> {
+    let mut user_ids = Vec::new();
+    for handle in handles {
+        let user_id: Option<i32> = sqlx::query_scalar!(
+            "SELECT id FROM tbl_users WHERE handle = ? AND status = 1",
+            handle
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+        if let Some(user_id) = user_id {
+            if !user_ids.contains(&user_id) {
+                user_ids.push(user_id);
+            }
+        }
+    }
+
+    Ok(user_ids)
+}
+
 pub async fn get_user_by_id(pool: &MySqlPool, user_id: i32) -> Result<User, AppError> {
     let row = sqlx::query!(
         r#"
-        SELECT id, name, email, address, phone, role, password, status, 
-               created_at, updated_at
+        SELECT id, name, handle, email, address, phone, role, password, status, 
+               timezone, created_at, updated_at
         FROM tbl_users
         WHERE id = ? AND status = 1
         "#,
@@ -86,17 +140,93 @@ pub async fn get_user_by_id(pool: &MySqlPool, user_id: i32) -> Result<User, AppE
     Ok(User {
         id: row.id,
         name: row.name,
+        handle: row.handle,
         email: row.email,
         address: row.address,
         phone: row.phone,
         role: row.role,
         password: row.password,
         status: row.status,
+        timezone: row.timezone,
         created_at: row.created_at.naive_utc(),
         updated_at: row.updated_at.naive_utc(),
     })
 }
 
+/// The current role of an active user. Role checks read it here rather than
+/// from the token, whose role may predate a promotion or demotion
+pub async fn get_current_role(pool: &MySqlPool, user_id: i32) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar!(
+        "SELECT role FROM tbl_users WHERE id = ? AND status = 1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)
+}
+
+/// Fails with forbidden unless the user is currently an admin
+pub async fn require_admin(pool: &MySqlPool, user_id: i32) -> Result<(), AppError> {
+    match get_current_role(pool, user_id).await?.as_deref() {
+        Some("admin") => Ok(()),
+        _ => Err(AppError::forbidden_error("Access denied. Admin role required.")),
+    }
+}
+
+/// Fails with forbidden unless the user is currently an admin or a manager
+pub async fn require_staff(pool: &MySqlPool, user_id: i32) -> Result<(), AppError> {
+    match get_current_role(pool, user_id).await?.as_deref() {
+        Some("admin" | "manager") => Ok(()),
+        _ => Err(AppError::forbidden_error("Access denied. Manager or admin role required.")),
+    }
+}
+
+/// The user's timezone, falling back to UTC when unset or no longer recognised
+pub async fn get_user_timezone(pool: &MySqlPool, user_id: i32) -> Result<chrono_tz::Tz, AppError> {
+    let timezone = sqlx::query_scalar!("SELECT timezone FROM tbl_users WHERE id = ?", user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::db_error)?
+        .flatten();
+
+    Ok(timezone
+        .and_then(|timezone| timezone.parse().ok())
+        .unwrap_or(chrono_tz::UTC))
+}
+
+fn password_hasher(hashing: &PasswordHashingConfig) -> Result<Argon2<'static>, AppError> {
+    let params = Params::new(hashing.memory_kib, hashing.iterations, hashing.parallelism, None)
+        .map_err(|e| AppError::internal_error(format!("Invalid password hashing params: {}", e)))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+fn hash_password(password: &str, hashing: &PasswordHashingConfig) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(password_hasher(hashing)?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|_| AppError::internal_error("Failed to hash password"))?
+        .to_string())
+}
+
+/// Whether a stored hash was made with a different algorithm or weaker params than configured
+pub fn password_needs_rehash(hash: &str, hashing: &PasswordHashingConfig) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    if parsed_hash.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    match Params::try_from(&parsed_hash) {
+        Ok(params) => {
+            params.m_cost() < hashing.memory_kib
+                || params.t_cost() < hashing.iterations
+                || params.p_cost() < hashing.parallelism
+        }
+        Err(_) => true,
+    }
+}
+
 pub async fn verify_password(password: &str, hash: &str) -> Result<bool, AppError> {
     let parsed_hash =
         PasswordHash::new(hash).map_err(|_| AppError::internal_error("Invalid password"))?;
@@ -123,16 +253,21 @@ pub async fn update_user_profile(
         .as_deref()
         .or(current_user.address.as_deref());
     let phone = request.phone.as_deref().or(current_user.phone.as_deref());
+    let timezone = request
+        .timezone
+        .as_deref()
+        .or(current_user.timezone.as_deref());
 
     sqlx::query!(
         r#"
         UPDATE tbl_users 
-        SET name = ?, address = ?, phone = ?, updated_at = ?
+        SET name = ?, address = ?, phone = ?, timezone = ?, updated_at = ?
         WHERE id = ?
         "#,
         name,
         address,
         phone,
+        timezone,
         now,
         user_id
     )
@@ -147,16 +282,12 @@ pub async fn change_user_password(
     pool: &MySqlPool,
     user_id: i32,
     new_password: &str,
+    hashing: &PasswordHashingConfig,
 ) -> Result<(), AppError> {
     let now = Utc::now().naive_utc();
 
     // Hash the new password
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(new_password.as_bytes(), &salt)
-        .map_err(|_| AppError::internal_error("Failed to hash password"))?
-        .to_string();
+    let password_hash = hash_password(new_password, hashing)?;
 
     sqlx::query!(
         r#"
@@ -175,6 +306,30 @@ pub async fn change_user_password(
     Ok(())
 }
 
+pub async fn update_user_email(
+    pool: &MySqlPool,
+    user_id: i32,
+    new_email: &str,
+) -> Result<User, AppError> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        r#"
+        UPDATE tbl_users 
+        SET email = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        new_email,
+        now,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::db_error_or_conflict(e, "Email is already in use"))?;
+
+    get_user_by_id(pool, user_id).await
+}
+
 pub async fn email_exists(
     pool: &MySqlPool,
     email: &str,
@@ -0,0 +1,108 @@
+use crate::core::{
+    is_safe_storage_location, resolve_storage_path, sha256_file, spawn_blocking_with_tracing,
+    AppError,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::path::Path;
+use tracing::{info, warn};
+
+const DEFAULT_BATCH_SIZE: i64 = 100;
+const MAX_BATCH_SIZE: i64 = 1000;
+
+/// One step of a resumable backfill. Start with `{}` and send back the
+/// returned `next_after_id` as `after_id` until it is null.
+#[derive(Debug, Default, Deserialize)]
+pub struct BackfillContentHashesRequest {
+    /// Last `tbl_files.id` handled by the previous step
+    #[serde(default)]
+    pub after_id: i32,
+    pub batch_size: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackfillContentHashesReport {
+    pub hashed: usize,
+    /// Files in this step whose audio could not be read; their hash stays null
+    pub unreadable_ids: Vec<i32>,
+    /// Null once every file without a hash has been visited
+    pub next_after_id: Option<i32>,
+}
+
+/// Hash one batch of active files that have no `content_hash` yet, such as
+/// uploads from before hashing existed. Files are walked by id, so a file
+/// that can't be read is reported and skipped instead of blocking later steps.
+pub async fn backfill_content_hashes(
+    pool: &MySqlPool,
+    uploads_dir: &str,
+    request: &BackfillContentHashesRequest,
+) -> Result<BackfillContentHashesReport, AppError> {
+    let batch_size = request
+        .batch_size
+        .unwrap_or(DEFAULT_BATCH_SIZE)
+        .clamp(1, MAX_BATCH_SIZE);
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, location
+        FROM tbl_files
+        WHERE status = 'active' AND content_hash IS NULL AND id > ?
+        ORDER BY id
+        LIMIT ?
+        "#,
+        request.after_id,
+        batch_size
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let next_after_id = if rows.len() as i64 == batch_size {
+        rows.last().map(|row| row.id)
+    } else {
+        None
+    };
+
+    let mut hashed = 0;
+    let mut unreadable_ids = Vec::new();
+    for row in rows {
+        if !is_safe_storage_location(&row.location) {
+            unreadable_ids.push(row.id);
+            continue;
+        }
+        let path = resolve_storage_path(uploads_dir, &row.location);
+        let hash = spawn_blocking_with_tracing(move || sha256_file(Path::new(&path)))
+            .await
+            .map_err(|e| AppError::internal_error(format!("Hash task failed: {}", e)))?;
+        let hash = match hash {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("Failed to hash file {} at {}: {}", row.id, row.location, e);
+                unreadable_ids.push(row.id);
+                continue;
+            }
+        };
+
+        sqlx::query!(
+            "UPDATE tbl_files SET content_hash = ? WHERE id = ? AND content_hash IS NULL",
+            hash,
+            row.id
+        )
+        .execute(pool)
+        .await
+        .map_err(AppError::db_error)?;
+        hashed += 1;
+    }
+
+    info!(
+        "Content hash backfill: {} hashed, {} unreadable",
+        hashed,
+        unreadable_ids.len()
+    );
+
+    Ok(BackfillContentHashesReport {
+        hashed,
+        unreadable_ids,
+        next_after_id,
+    })
+}
@@ -0,0 +1,414 @@
+use crate::core::config::AppConfig;
+use crate::core::{
+    ensure_within_dir, extract_mp3_metadata, is_safe_storage_location, prepare_storage_location,
+    sha256_file, spawn_blocking_with_tracing, AppError,
+};
+use crate::db::{books, uploads};
+use crate::jobs::integrity_scan::collect_locations;
+use crate::models::uploads::NewImportedFile;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const DEFAULT_IMPORT_LIMIT: usize = 200;
+const MAX_IMPORT_LIMIT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ImportScanRequest {
+    /// Server-side directory; must be inside one of `imports.allowed_dirs`
+    pub directory: String,
+    pub book_id: i32,
+    /// Move each file into the uploads dir. Without it the directory must
+    /// already be inside `uploads_dir` and files are registered where they are
+    #[serde(default)]
+    pub move_files: bool,
+    /// Most MP3s hashed in one call; run again to pick up the rest
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Imported,
+    Duplicate,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportFileOutcome {
+    /// Relative to the scanned directory
+    pub path: String,
+    pub status: ImportStatus,
+    /// The new row, or the existing row a duplicate matched
+    pub file_id: Option<i32>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportScanReport {
+    pub found: usize,
+    pub imported: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    /// MP3s left for a later call because of `limit`
+    pub remaining: usize,
+    pub files: Vec<ImportFileOutcome>,
+}
+
+/// Register the MP3s under an allowlisted directory as files of `book_id`.
+/// Files are deduplicated by SHA-256 against `tbl_files.content_hash` and
+/// against each other, so a scan can be re-run safely. Directory walks,
+/// hashing and moves run on the blocking pool.
+pub async fn import_directory(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    request: &ImportScanRequest,
+    user_id: i32,
+) -> Result<ImportScanReport, AppError> {
+    let directory = resolve_import_dir(&config.imports.allowed_dirs, &request.directory)?;
+    let uploads_dir = fs::canonicalize(&config.app_paths.uploads_dir)
+        .map_err(|e| AppError::internal_error(format!("Failed to read uploads dir: {}", e)))?;
+
+    if !request.move_files && !directory.starts_with(&uploads_dir) {
+        return Err(AppError::bad_request(
+            "Directories outside the uploads dir can only be imported with move_files",
+        ));
+    }
+
+    let scholar_id = books::assert_book_active(pool, request.book_id).await?;
+
+    let scan_dir = directory.clone();
+    let paths = run_blocking("read import dir", move || list_import_mp3s(&scan_dir)).await?;
+
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_IMPORT_LIMIT)
+        .clamp(1, MAX_IMPORT_LIMIT);
+
+    let mut report = ImportScanReport {
+        found: paths.len(),
+        imported: 0,
+        duplicates: 0,
+        failed: 0,
+        remaining: 0,
+        files: Vec::new(),
+    };
+    let mut seen_hashes = HashSet::new();
+    let mut hashed = 0;
+
+    for relative in paths {
+        let source = directory.join(&relative);
+
+        // Files registered in place by an earlier run are skipped without
+        // hashing, so they don't count against the limit
+        let location_in_place = if request.move_files {
+            None
+        } else {
+            match storage_location(&uploads_dir, &source) {
+                Ok(location) => Some(location),
+                Err(e) => {
+                    report.failed += 1;
+                    report
+                        .files
+                        .push(outcome(relative, ImportStatus::Failed, None, e.message));
+                    continue;
+                }
+            }
+        };
+        if let Some(location) = &location_in_place {
+            if uploads::is_active_file_location(pool, location).await? {
+                report.duplicates += 1;
+                report.files.push(outcome(
+                    relative,
+                    ImportStatus::Duplicate,
+                    None,
+                    Some("Already registered at this location".to_string()),
+                ));
+                continue;
+            }
+        }
+
+        if hashed == limit {
+            report.remaining += 1;
+            continue;
+        }
+        hashed += 1;
+
+        let result = import_file(
+            pool,
+            config,
+            &source,
+            location_in_place.as_deref(),
+            request.book_id,
+            scholar_id,
+            user_id,
+            &mut seen_hashes,
+        )
+        .await;
+
+        let file_outcome = match result {
+            Ok(ImportResult::Imported(file_id)) => {
+                report.imported += 1;
+                outcome(relative, ImportStatus::Imported, Some(file_id), None)
+            }
+            Ok(ImportResult::Duplicate(existing_id)) => {
+                report.duplicates += 1;
+                outcome(
+                    relative,
+                    ImportStatus::Duplicate,
+                    existing_id,
+                    Some("Same audio is already in the library".to_string()),
+                )
+            }
+            Err(e) => {
+                warn!("Failed to import {}: {}", relative, e);
+                report.failed += 1;
+                let reason = e.message.clone().unwrap_or_else(|| e.to_string());
+                outcome(relative, ImportStatus::Failed, None, Some(reason))
+            }
+        };
+        report.files.push(file_outcome);
+    }
+
+    info!(
+        "Bulk import of {} into book {}: {} imported, {} duplicates, {} failed, {} remaining",
+        directory.display(),
+        request.book_id,
+        report.imported,
+        report.duplicates,
+        report.failed,
+        report.remaining
+    );
+
+    Ok(report)
+}
+
+enum ImportResult {
+    Imported(i32),
+    /// Id of the matching row, or None when the match was earlier in this batch
+    Duplicate(Option<i32>),
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn import_file(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    source: &Path,
+    location_in_place: Option<&str>,
+    book_id: i32,
+    scholar_id: i32,
+    user_id: i32,
+    seen_hashes: &mut HashSet<String>,
+) -> Result<ImportResult, AppError> {
+    let source_str = source
+        .to_str()
+        .ok_or_else(|| AppError::bad_request("File path is not valid UTF-8"))?;
+
+    let min_file_bytes = config.uploads.min_file_bytes as u64;
+    let hash_source = source.to_path_buf();
+    let (file_size, content_hash) =
+        run_blocking("hash file", move || hash_for_import(&hash_source, min_file_bytes)).await?;
+    let content_hash = content_hash
+        .ok_or_else(|| AppError::bad_request("The audio file is too small to be valid"))?;
+    if !seen_hashes.insert(content_hash.clone()) {
+        return Ok(ImportResult::Duplicate(None));
+    }
+    if let Some(existing_id) = uploads::find_file_by_content_hash(pool, &content_hash).await? {
+        return Ok(ImportResult::Duplicate(Some(existing_id)));
+    }
+
+    let (_, duration) = extract_mp3_metadata(
+        source_str,
+        config.uploads.min_duration_seconds,
+        config.uploads.max_file_bytes,
+    )
+    .await?;
+
+    let file_stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Audio")
+        .to_string();
+    let uid = Uuid::new_v4().to_string()[..5].to_string();
+
+    let (location, moved_to) = match location_in_place {
+        Some(location) => (location.to_string(), None),
+        None => {
+            let unique_filename = format!("{}_{}.mp3", file_stem, uid);
+            let (location, file_path) = prepare_storage_location(
+                &config.app_paths.uploads_dir,
+                &unique_filename,
+                config.app_paths.shard_by_date,
+            )
+            .map_err(|e| AppError::internal_error(format!("Failed to prepare storage: {}", e)))?;
+            let (from, to) = (source.to_path_buf(), PathBuf::from(&file_path));
+            run_blocking("move file", move || move_file(&from, &to)).await?;
+            (location, Some(file_path))
+        }
+    };
+
+    let saved = uploads::save_imported_file(
+        pool,
+        &NewImportedFile {
+            book_id,
+            scholar_id,
+            name: &file_stem,
+            location: &location,
+            file_size: file_size as i64,
+            duration: &duration,
+            content_hash: &content_hash,
+            uid: &uid,
+            created_by: user_id,
+        },
+    )
+    .await;
+
+    match saved {
+        Ok(file_id) => Ok(ImportResult::Imported(file_id)),
+        Err(e) => {
+            // Put the file back so a later run can retry it
+            if let Some(file_path) = moved_to {
+                let (from, to) = (PathBuf::from(&file_path), source.to_path_buf());
+                if let Err(restore_err) = run_blocking("restore file", move || move_file(&from, &to)).await {
+                    warn!("Failed to restore {} after import error: {}", file_path, restore_err);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// MP3s under `directory`, relative to it and sorted so runs with a `limit`
+/// pick files up in a stable order
+fn list_import_mp3s(directory: &Path) -> std::io::Result<Vec<String>> {
+    let mut paths = Vec::new();
+    collect_locations(directory, "", &mut paths)?;
+    paths.retain(|path| path.to_lowercase().ends_with(".mp3"));
+    paths.sort();
+    Ok(paths)
+}
+
+/// Size and SHA-256 of a file to import; no hash when it is too small to be audio
+fn hash_for_import(path: &Path, min_file_bytes: u64) -> std::io::Result<(u64, Option<String>)> {
+    let file_size = fs::metadata(path)?.len();
+    if file_size < min_file_bytes {
+        return Ok((file_size, None));
+    }
+    Ok((file_size, Some(sha256_file(path)?)))
+}
+
+/// Canonical form of `directory`, refused unless it is inside an allowlisted dir
+fn resolve_import_dir(allowed_dirs: &[String], directory: &str) -> Result<PathBuf, AppError> {
+    if allowed_dirs.is_empty() {
+        return Err(AppError::forbidden_error("Bulk import is disabled on this server"));
+    }
+
+    allowed_dirs
+        .iter()
+        .find_map(|allowed| ensure_within_dir(allowed, directory).ok())
+        .filter(|resolved| resolved.is_dir())
+        .ok_or_else(|| AppError::bad_request("Directory is not an allowed import directory"))
+}
+
+/// `tbl_files.location` for a file already inside the uploads dir
+fn storage_location(uploads_dir: &Path, path: &Path) -> Result<String, AppError> {
+    let location = path
+        .strip_prefix(uploads_dir)
+        .ok()
+        .and_then(|relative| relative.to_str())
+        .map(|relative| relative.replace(std::path::MAIN_SEPARATOR, "/"))
+        .filter(|location| is_safe_storage_location(location))
+        .ok_or_else(|| AppError::bad_request("File is not inside the uploads dir"))?;
+    Ok(location)
+}
+
+async fn run_blocking<T, F>(what: &'static str, work: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> std::io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_blocking_with_tracing(work)
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to {}: {}", what, e)))?
+        .map_err(|e| AppError::internal_error(format!("Failed to {}: {}", what, e)))
+}
+
+/// Rename, falling back to copy and remove when the import dir is on another filesystem
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+fn outcome(
+    path: String,
+    status: ImportStatus,
+    file_id: Option<i32>,
+    reason: Option<String>,
+) -> ImportFileOutcome {
+    ImportFileOutcome {
+        path,
+        status,
+        file_id,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixture_directory_lists_mp3s_and_hashes_duplicates_alike() {
+        let root = std::env::temp_dir().join(format!("bulk_import_{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("part 2")).unwrap();
+        let audio = vec![7u8; 4096];
+        fs::write(root.join("b.mp3"), &audio).unwrap();
+        fs::write(root.join("a.MP3"), &audio).unwrap();
+        fs::write(root.join("part 2/c.mp3"), vec![9u8; 4096]).unwrap();
+        fs::write(root.join("tiny.mp3"), b"id3").unwrap();
+        fs::write(root.join("notes.txt"), b"not audio").unwrap();
+
+        let paths = list_import_mp3s(&root).unwrap();
+        assert_eq!(paths, ["a.MP3", "b.mp3", "part 2/c.mp3", "tiny.mp3"]);
+
+        // The import skips every hash it has already seen in the batch
+        let mut seen_hashes = HashSet::new();
+        let mut imported = Vec::new();
+        let mut duplicates = Vec::new();
+        let mut too_small = Vec::new();
+        for path in &paths {
+            match hash_for_import(&root.join(path), 1024).unwrap() {
+                (_, None) => too_small.push(path.as_str()),
+                (_, Some(hash)) if seen_hashes.insert(hash) => imported.push(path.as_str()),
+                _ => duplicates.push(path.as_str()),
+            }
+        }
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(imported, ["a.MP3", "part 2/c.mp3"]);
+        assert_eq!(duplicates, ["b.mp3"]);
+        assert_eq!(too_small, ["tiny.mp3"]);
+    }
+
+    #[test]
+    fn import_dirs_must_be_allowlisted() {
+        let root = std::env::temp_dir().join(format!("bulk_import_allow_{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("legacy")).unwrap();
+        let allowed = vec![root.to_string_lossy().into_owned()];
+
+        let legacy = root.join("legacy").to_string_lossy().into_owned();
+        let outside = root.join("..").to_string_lossy().into_owned();
+
+        assert!(resolve_import_dir(&allowed, &legacy).is_ok());
+        assert!(resolve_import_dir(&allowed, &outside).is_err());
+        assert!(resolve_import_dir(&[], &legacy).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
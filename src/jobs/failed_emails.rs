@@ -0,0 +1,49 @@
+use crate::core::config::FailedEmailConfig;
+use crate::core::email_service::FailedEmail;
+use crate::db::failed_emails::{prune_failed_emails, record_failed_email};
+use sqlx::MySqlPool;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+/// How often stored failures are checked against the retention
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Background job storing emails the email worker failed to send in
+/// `tbl_failed_emails`, where admins can re-send them
+pub fn start_failed_email_recorder(pool: MySqlPool, mut failures: mpsc::UnboundedReceiver<FailedEmail>) {
+    tokio::spawn(async move {
+        while let Some(failed) = failures.recv().await {
+            if let Err(e) = record_failed_email(&pool, &failed.email_type, &failed.error).await {
+                error!(
+                    "Failed to record failed {} email to {}: {}",
+                    failed.email_type.kind(),
+                    failed.email_type.recipient(),
+                    e
+                );
+            }
+        }
+    });
+}
+
+/// Background job deleting stored failures past the retention, at startup and daily
+pub fn start_failed_email_pruner(pool: MySqlPool, config: FailedEmailConfig) {
+    if config.retention_days <= 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+        loop {
+            // The first tick completes immediately, so this also runs at startup
+            interval.tick().await;
+
+            match prune_failed_emails(&pool, config.retention_days).await {
+                Ok(0) => {}
+                Ok(removed) => info!("Pruned {} stored failed emails", removed),
+                Err(e) => error!("Failed to prune stored failed emails: {}", e),
+            }
+        }
+    });
+}
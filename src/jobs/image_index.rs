@@ -0,0 +1,37 @@
+use crate::core::image_index::{scan_images, ImageIndex};
+use crate::core::spawn_blocking_with_tracing;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// How often the images directory is rescanned to pick up files changed outside the API
+const RESCAN_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Fill `index` from `images_dir` before the server starts, then rescan it periodically
+pub async fn start_image_index_refresher(index: ImageIndex, images_dir: String) {
+    let dir = PathBuf::from(images_dir);
+    refresh_image_index(&index, &dir).await;
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RESCAN_INTERVAL);
+        // The first tick completes immediately and the index was just filled
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            refresh_image_index(&index, &dir).await;
+        }
+    });
+}
+
+async fn refresh_image_index(index: &ImageIndex, dir: &PathBuf) {
+    let scan_dir = dir.clone();
+    match spawn_blocking_with_tracing(move || scan_images(&scan_dir)).await {
+        Ok(Ok(names)) => {
+            info!("Indexed {} image(s) in {}", names.len(), dir.display());
+            index.replace(names);
+        }
+        Ok(Err(e)) => error!("Failed to scan images in {}: {}", dir.display(), e),
+        Err(e) => error!("Image scan task failed: {}", e),
+    }
+}
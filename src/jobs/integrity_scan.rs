@@ -0,0 +1,408 @@
+use crate::core::{
+    is_safe_storage_location, push_in_list, resolve_storage_path, spawn_blocking_with_tracing,
+    AppError,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::info;
+
+const DEFAULT_BATCH_SIZE: i64 = 500;
+const MAX_BATCH_SIZE: i64 = 2000;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegrityScanStage {
+    /// Active `tbl_files` rows checked against disk
+    #[default]
+    Files,
+    /// Files on disk checked against `tbl_files`
+    Disk,
+}
+
+/// Where the next step of a scan starts; echoed back to the client as `next`
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct IntegrityScanCursor {
+    #[serde(default)]
+    pub stage: IntegrityScanStage,
+    /// Last `tbl_files.id` checked during the files stage
+    pub after_id: Option<i32>,
+    /// Last on-disk location checked during the disk stage
+    pub after_path: Option<String>,
+}
+
+/// One step of a resumable scan. Start with `{}` and send back the
+/// returned `next` cursor until it is null.
+#[derive(Debug, Default, Deserialize)]
+pub struct IntegrityScanRequest {
+    #[serde(flatten)]
+    pub cursor: IntegrityScanCursor,
+    pub batch_size: Option<i64>,
+    /// Stamp `missing_since` on missing rows and clear it on rows found again
+    #[serde(default)]
+    pub mark_missing: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MissingFile {
+    pub id: i32,
+    pub name: String,
+    pub location: String,
+    pub book_id: i32,
+    pub scholar_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntegrityScanReport {
+    pub stage: IntegrityScanStage,
+    pub checked: usize,
+    /// Active rows in this step whose audio is not on disk
+    pub missing_files: Vec<MissingFile>,
+    /// Locations on disk in this step that no `tbl_files` row points at
+    pub orphaned_files: Vec<String>,
+    /// Null once both stages are done
+    pub next: Option<IntegrityScanCursor>,
+}
+
+/// Run one batch of the integrity scan: active files are checked against
+/// `uploads_dir` first, then everything on disk is checked against `tbl_files`.
+/// Both are walked in a stable order so each step resumes from the cursor.
+pub async fn scan_integrity(
+    pool: &MySqlPool,
+    uploads_dir: &str,
+    request: &IntegrityScanRequest,
+) -> Result<IntegrityScanReport, AppError> {
+    let batch_size = request
+        .batch_size
+        .unwrap_or(DEFAULT_BATCH_SIZE)
+        .clamp(1, MAX_BATCH_SIZE);
+
+    let report = match request.cursor.stage {
+        IntegrityScanStage::Files => {
+            let (checked, missing_files, next_after_id) = scan_database_batch(
+                pool,
+                uploads_dir,
+                request.cursor.after_id.unwrap_or(0),
+                batch_size,
+                request.mark_missing,
+            )
+            .await?;
+
+            IntegrityScanReport {
+                stage: IntegrityScanStage::Files,
+                checked,
+                missing_files,
+                orphaned_files: Vec::new(),
+                next: Some(match next_after_id {
+                    Some(after_id) => IntegrityScanCursor {
+                        stage: IntegrityScanStage::Files,
+                        after_id: Some(after_id),
+                        after_path: None,
+                    },
+                    None => IntegrityScanCursor {
+                        stage: IntegrityScanStage::Disk,
+                        after_id: None,
+                        after_path: None,
+                    },
+                }),
+            }
+        }
+        IntegrityScanStage::Disk => {
+            let (checked, orphaned_files, next_after_path) = scan_disk_batch(
+                pool,
+                uploads_dir,
+                request.cursor.after_path.as_deref(),
+                batch_size as usize,
+            )
+            .await?;
+
+            IntegrityScanReport {
+                stage: IntegrityScanStage::Disk,
+                checked,
+                missing_files: Vec::new(),
+                orphaned_files,
+                next: next_after_path.map(|after_path| IntegrityScanCursor {
+                    stage: IntegrityScanStage::Disk,
+                    after_id: None,
+                    after_path: Some(after_path),
+                }),
+            }
+        }
+    };
+
+    info!(
+        "Integrity scan step ({:?}): {} checked, {} missing, {} orphaned",
+        report.stage,
+        report.checked,
+        report.missing_files.len(),
+        report.orphaned_files.len()
+    );
+
+    Ok(report)
+}
+
+async fn scan_database_batch(
+    pool: &MySqlPool,
+    uploads_dir: &str,
+    after_id: i32,
+    batch_size: i64,
+    mark_missing: bool,
+) -> Result<(usize, Vec<MissingFile>, Option<i32>), AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, name, location, book as book_id, scholar as scholar_id
+        FROM tbl_files
+        WHERE status = 'active' AND id > ?
+        ORDER BY id
+        LIMIT ?
+        "#,
+        after_id,
+        batch_size
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::db_error)?;
+
+    let next_after_id = if rows.len() as i64 == batch_size {
+        rows.last().map(|row| row.id)
+    } else {
+        None
+    };
+
+    let mut missing_files = Vec::new();
+    let mut present_ids = Vec::new();
+    for row in &rows {
+        let on_disk = is_safe_storage_location(&row.location)
+            && Path::new(&resolve_storage_path(uploads_dir, &row.location)).is_file();
+        if on_disk {
+            present_ids.push(row.id);
+        } else {
+            missing_files.push(MissingFile {
+                id: row.id,
+                name: row.name.clone(),
+                location: row.location.clone(),
+                book_id: row.book_id,
+                scholar_id: row.scholar_id,
+            });
+        }
+    }
+
+    if mark_missing && !missing_files.is_empty() {
+        let mut query = QueryBuilder::<MySql>::new(
+            "UPDATE tbl_files SET missing_since = COALESCE(missing_since, UTC_TIMESTAMP()) WHERE ",
+        );
+        push_in_list(&mut query, "id", missing_files.iter().map(|file| file.id));
+        query.build().execute(pool).await.map_err(AppError::db_error)?;
+    }
+
+    if mark_missing && !present_ids.is_empty() {
+        let mut query = QueryBuilder::<MySql>::new(
+            "UPDATE tbl_files SET missing_since = NULL WHERE missing_since IS NOT NULL AND ",
+        );
+        push_in_list(&mut query, "id", present_ids);
+        query.build().execute(pool).await.map_err(AppError::db_error)?;
+    }
+
+    Ok((rows.len(), missing_files, next_after_id))
+}
+
+async fn scan_disk_batch(
+    pool: &MySqlPool,
+    uploads_dir: &str,
+    after_path: Option<&str>,
+    batch_size: usize,
+) -> Result<(usize, Vec<String>, Option<String>), AppError> {
+    let dir = uploads_dir.to_string();
+    let after = after_path.map(str::to_string);
+    let batch = spawn_blocking_with_tracing(move || {
+        let mut batch = Vec::new();
+        collect_locations_after(Path::new(&dir), "", after.as_deref(), batch_size, &mut batch)
+            .map(|_| batch)
+    })
+    .await
+    .map_err(|e| AppError::internal_error(format!("Disk scan task failed: {}", e)))?
+    .map_err(|e| AppError::internal_error(format!("Failed to read uploads dir: {}", e)))?;
+
+    if batch.is_empty() {
+        return Ok((0, Vec::new(), None));
+    }
+
+    // Flat files may still be referenced by a sharded location (see `resolve_storage_path`),
+    // so a row owns a disk entry by full location or by bare filename. Names are bound one
+    // by one since a filename may itself contain a comma
+    let mut query = QueryBuilder::<MySql>::new("SELECT location FROM tbl_files WHERE ");
+    push_in_list(&mut query, "location", batch.iter().map(String::as_str));
+    query.push(" OR ");
+    push_in_list(
+        &mut query,
+        "SUBSTRING_INDEX(location, '/', -1)",
+        batch.iter().map(String::as_str),
+    );
+    let known_locations: Vec<String> = query
+        .build_query_scalar()
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    let next_after_path = if batch.len() == batch_size {
+        batch.last().cloned()
+    } else {
+        None
+    };
+    let orphaned_files = orphaned_locations(&batch, &known_locations);
+
+    Ok((batch.len(), orphaned_files, next_after_path))
+}
+
+/// Disk entries no row owns, by full location or, for flat files, by bare filename
+fn orphaned_locations(batch: &[String], known_locations: &[String]) -> Vec<String> {
+    let known_names: HashSet<&str> = known_locations
+        .iter()
+        .filter_map(|location| location.rsplit('/').next())
+        .collect();
+    let known_locations: HashSet<&str> = known_locations.iter().map(String::as_str).collect();
+
+    batch
+        .iter()
+        .filter(|location| {
+            let owned = known_locations.contains(location.as_str())
+                || (!location.contains('/') && known_names.contains(location.as_str()));
+            !owned
+        })
+        .cloned()
+        .collect()
+}
+
+/// Relative paths of every regular file under `dir`, skipping hidden entries
+/// such as the `.tmp` upload staging dir
+pub(crate) fn collect_locations(dir: &Path, prefix: &str, out: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let location = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_locations(&entry.path(), &location, out)?;
+        } else if file_type.is_file() {
+            out.push(location);
+        }
+    }
+    Ok(())
+}
+
+/// Up to `limit` relative paths under `dir` that come after `after` in walk
+/// order (entries sorted by name, depth first). Subtrees that lie wholly
+/// before the cursor are skipped without being read, so each step only
+/// walks as far as the batch it returns.
+fn collect_locations_after(
+    dir: &Path,
+    prefix: &str,
+    after: Option<&str>,
+    limit: usize,
+    out: &mut Vec<String>,
+) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if out.len() >= limit {
+            break;
+        }
+
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let location = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let holds_cursor = after.is_some_and(|after| after.starts_with(&format!("{}/", location)));
+            if holds_cursor || comes_after(&location, after) {
+                collect_locations_after(&entry.path(), &location, after, limit, out)?;
+            }
+        } else if file_type.is_file() && comes_after(&location, after) {
+            out.push(location);
+        }
+    }
+    Ok(())
+}
+
+/// Compare by path segment so the order matches the depth-first walk
+/// (`a/b` before `a-c`, unlike a plain string compare)
+fn comes_after(location: &str, after: Option<&str>) -> bool {
+    after.map_or(true, |after| location.split('/').gt(after.split('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_locations_after_pages_in_walk_order() {
+        let root = std::env::temp_dir().join(format!("integrity_scan_{}", uuid::Uuid::new_v4()));
+        for location in ["a/b.mp3", "a-c.mp3", "b/2024/x.mp3", "z.mp3", ".tmp/partial.mp3"] {
+            let path = root.join(location);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"audio").unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let mut batch = Vec::new();
+            collect_locations_after(&root, "", after.as_deref(), 2, &mut batch).unwrap();
+            if batch.is_empty() {
+                break;
+            }
+            after = batch.last().cloned();
+            seen.extend(batch);
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(seen, ["a/b.mp3", "a-c.mp3", "b/2024/x.mp3", "z.mp3"]);
+    }
+
+    #[test]
+    fn comes_after_compares_by_segment() {
+        assert!(comes_after("a-c.mp3", Some("a/b.mp3")));
+        assert!(!comes_after("a/b.mp3", Some("a-c.mp3")));
+        assert!(comes_after("anything", None));
+    }
+
+    #[test]
+    fn filenames_with_commas_are_matched_whole() {
+        let batch = vec![
+            "talk, part 1.mp3".to_string(),
+            "part 1.mp3".to_string(),
+            "2024/01/flat.mp3".to_string(),
+            "flat.mp3".to_string(),
+        ];
+        let known = vec!["talk, part 1.mp3".to_string(), "2024/01/flat.mp3".to_string()];
+
+        assert_eq!(orphaned_locations(&batch, &known), ["part 1.mp3"]);
+    }
+
+    #[test]
+    fn in_list_binds_each_filename() {
+        let batch = ["talk, part 1.mp3", "z.mp3"];
+        let mut query = QueryBuilder::<MySql>::new("SELECT location FROM tbl_files WHERE ");
+        push_in_list(&mut query, "location", batch);
+        assert_eq!(query.sql(), "SELECT location FROM tbl_files WHERE location IN (?, ?)");
+    }
+}
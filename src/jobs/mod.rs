@@ -1,3 +1,14 @@
+pub mod backfill_content_hashes;
+pub mod bulk_import;
+pub mod failed_emails;
+pub mod image_index;
+pub mod integrity_scan;
+pub mod prune_logs;
+pub mod prune_play_history;
+pub mod recompute_counters;
 pub mod subscription_expiry;
 
+pub use failed_emails::{start_failed_email_pruner, start_failed_email_recorder};
+pub use image_index::start_image_index_refresher;
+pub use prune_logs::start_log_pruner;
 pub use subscription_expiry::start_subscription_expiry_checker;
@@ -0,0 +1,203 @@
+use crate::core::config::LoggingConfig;
+use crate::core::spawn_blocking_with_tracing;
+use chrono::{NaiveDate, Utc};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// How often rolled log files are checked against the retention
+const PRUNE_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+#[derive(Debug, Default)]
+pub struct PruneLogsReport {
+    pub expired_removed: usize,
+    pub overflow_removed: usize,
+    pub bytes_freed: u64,
+}
+
+struct RolledLog {
+    path: PathBuf,
+    date: NaiveDate,
+    size: u64,
+}
+
+/// Background job applying the log retention at startup and every few hours
+pub fn start_log_pruner(config: LoggingConfig) {
+    if config.max_age_days == 0 && config.max_total_mb == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+        loop {
+            // The first tick completes immediately, so this also runs at startup
+            interval.tick().await;
+
+            let job_config = config.clone();
+            let today = Utc::now().date_naive();
+            match spawn_blocking_with_tracing(move || prune_log_files(&job_config, today)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => error!("Failed to prune log files in {}: {}", config.directory, e),
+                Err(e) => error!("Log prune task failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Delete rolled `{file_prefix}.YYYY-MM-DD` files older than `max_age_days`,
+/// then the oldest remaining ones until the logs fit in `max_total_mb`.
+/// The file for `today` (UTC, as the appender rolls) is the one being written
+/// and is never removed; files not named like a rolled log are left alone.
+/// A file that can't be removed is logged and skipped, so one bad entry
+/// doesn't stop the rest from being pruned.
+pub fn prune_log_files(config: &LoggingConfig, today: NaiveDate) -> std::io::Result<PruneLogsReport> {
+    let mut report = PruneLogsReport::default();
+    let mut logs = rolled_logs(Path::new(&config.directory), &config.file_prefix)?;
+    logs.sort_by_key(|log| log.date);
+
+    let mut total_bytes: u64 = logs.iter().map(|log| log.size).sum();
+    let mut rolled: Vec<RolledLog> = logs.into_iter().filter(|log| log.date < today).collect();
+
+    if config.max_age_days > 0 {
+        let cutoff = today - chrono::Duration::days(config.max_age_days as i64);
+        let mut kept = Vec::new();
+        for log in rolled {
+            if log.date < cutoff {
+                if remove_log(&log) {
+                    total_bytes -= log.size;
+                    report.expired_removed += 1;
+                    report.bytes_freed += log.size;
+                }
+            } else {
+                kept.push(log);
+            }
+        }
+        rolled = kept;
+    }
+
+    if config.max_total_mb > 0 {
+        let max_bytes = config.max_total_mb * 1024 * 1024;
+        for log in rolled {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            if remove_log(&log) {
+                total_bytes -= log.size;
+                report.overflow_removed += 1;
+                report.bytes_freed += log.size;
+            }
+        }
+    }
+
+    if report.expired_removed + report.overflow_removed > 0 {
+        info!(
+            "Pruned log files: {} expired and {} over the size cap removed ({} bytes)",
+            report.expired_removed, report.overflow_removed, report.bytes_freed
+        );
+    }
+
+    Ok(report)
+}
+
+fn remove_log(log: &RolledLog) -> bool {
+    match std::fs::remove_file(&log.path) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to remove log file {}: {}", log.path.display(), e);
+            false
+        }
+    }
+}
+
+fn rolled_logs(dir: &Path, prefix: &str) -> std::io::Result<Vec<RolledLog>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut logs = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let name = entry.file_name();
+        let date = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(prefix))
+            .and_then(|rest| rest.strip_prefix('.'))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+        if let Some(date) = date {
+            logs.push(RolledLog {
+                path: entry.path(),
+                date,
+                size: entry.metadata()?.len(),
+            });
+        }
+    }
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_log(dir: &Path, name: &str, bytes: usize) {
+        std::fs::write(dir.join(name), vec![b'x'; bytes]).unwrap();
+    }
+
+    fn remaining(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn prune_log_files_applies_age_then_size() {
+        let dir = std::env::temp_dir().join(format!("prune_logs_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mb = 1024 * 1024;
+        write_log(&dir, "app.log.2025-01-01", 10);
+        write_log(&dir, "app.log.2025-01-08", mb);
+        write_log(&dir, "app.log.2025-01-09", mb);
+        write_log(&dir, "app.log.2025-01-10", mb);
+        write_log(&dir, "other.txt", 10);
+
+        let config = LoggingConfig {
+            directory: dir.to_string_lossy().into_owned(),
+            file_prefix: "app.log".to_string(),
+            max_age_days: 5,
+            max_total_mb: 2,
+            ..LoggingConfig::default()
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        let report = prune_log_files(&config, today).unwrap();
+        let left = remaining(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.expired_removed, 1);
+        assert_eq!(report.overflow_removed, 1);
+        assert_eq!(report.bytes_freed, 10 + mb as u64);
+        // Today's file is never removed, and unrelated files are left alone
+        assert_eq!(left, ["app.log.2025-01-09", "app.log.2025-01-10", "other.txt"]);
+    }
+
+    #[test]
+    fn prune_log_files_tolerates_a_missing_directory() {
+        let config = LoggingConfig {
+            directory: std::env::temp_dir()
+                .join(format!("prune_logs_missing_{}", uuid::Uuid::new_v4()))
+                .to_string_lossy()
+                .into_owned(),
+            ..LoggingConfig::default()
+        };
+        let report = prune_log_files(&config, Utc::now().date_naive()).unwrap();
+        assert_eq!(report.expired_removed + report.overflow_removed, 0);
+    }
+}
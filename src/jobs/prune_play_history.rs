@@ -0,0 +1,111 @@
+use crate::core::config::PlayHistoryRetentionConfig;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use tracing::info;
+
+/// Rows deleted per statement so the table is never locked in one go
+const BATCH_SIZE: i64 = 1000;
+
+#[derive(Debug, Serialize)]
+pub struct PrunePlayHistoryReport {
+    pub expired_removed: u64,
+    pub overflow_removed: u64,
+}
+
+/// Trim `tbl_play_history` to the configured retention: entries older than
+/// `max_age_days`, then anything beyond each user's `max_entries_per_user` most recent plays.
+pub async fn prune_play_history(
+    pool: &MySqlPool,
+    retention: &PlayHistoryRetentionConfig,
+) -> Result<PrunePlayHistoryReport, sqlx::Error> {
+    let expired_removed = if retention.max_age_days > 0 {
+        prune_expired(pool, retention.max_age_days).await?
+    } else {
+        0
+    };
+
+    let overflow_removed = if retention.max_entries_per_user > 0 {
+        prune_overflow(pool, retention.max_entries_per_user).await?
+    } else {
+        0
+    };
+
+    info!(
+        "Pruned play history: {} expired and {} over-cap entries removed",
+        expired_removed, overflow_removed
+    );
+
+    Ok(PrunePlayHistoryReport {
+        expired_removed,
+        overflow_removed,
+    })
+}
+
+async fn prune_expired(pool: &MySqlPool, max_age_days: i64) -> Result<u64, sqlx::Error> {
+    let mut removed = 0;
+    loop {
+        let result = sqlx::query!(
+            "DELETE FROM tbl_play_history WHERE played_at < UTC_TIMESTAMP() - INTERVAL ? DAY LIMIT ?",
+            max_age_days,
+            BATCH_SIZE
+        )
+        .execute(pool)
+        .await?;
+
+        removed += result.rows_affected();
+        if (result.rows_affected() as i64) < BATCH_SIZE {
+            return Ok(removed);
+        }
+    }
+}
+
+/// Keep only the newest `max_entries` rows for every user above the cap
+async fn prune_overflow(pool: &MySqlPool, max_entries: i64) -> Result<u64, sqlx::Error> {
+    // Guest plays have no user and are only bounded by age
+    let user_ids: Vec<Option<i32>> = sqlx::query_scalar!(
+        "SELECT user_id FROM tbl_play_history WHERE user_id IS NOT NULL GROUP BY user_id HAVING COUNT(*) > ?",
+        max_entries
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut removed = 0;
+    for user_id in user_ids.into_iter().flatten() {
+        // The newest row that falls outside the cap; it and everything older goes
+        let cutoff = sqlx::query!(
+            r#"
+            SELECT id, played_at
+            FROM tbl_play_history
+            WHERE user_id = ?
+            ORDER BY played_at DESC, id DESC
+            LIMIT 1 OFFSET ?
+            "#,
+            user_id,
+            max_entries
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        let Some(cutoff) = cutoff else {
+            continue;
+        };
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM tbl_play_history
+            WHERE user_id = ?
+            AND (played_at < ? OR (played_at = ? AND id <= ?))
+            "#,
+            user_id,
+            cutoff.played_at,
+            cutoff.played_at,
+            cutoff.id
+        )
+        .execute(pool)
+        .await?;
+
+        removed += result.rows_affected();
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,149 @@
+use crate::core::parse_duration;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Rows touched per statement so large tables are never locked in one go
+const BATCH_SIZE: i32 = 500;
+
+#[derive(Debug, Serialize)]
+pub struct RecomputeCountersReport {
+    pub files_corrected: u64,
+    pub playlists_corrected: u64,
+}
+
+/// Repair denormalized counters: download counters are raised to the log,
+/// playlist totals are rebuilt from their files. Only rows whose stored value
+/// differs are written, so running it repeatedly is harmless.
+pub async fn recompute_counters(pool: &MySqlPool) -> Result<RecomputeCountersReport, sqlx::Error> {
+    let files_corrected = recompute_file_downloads(pool).await?;
+    let playlists_corrected = recompute_playlist_totals(pool).await?;
+
+    info!(
+        "Recomputed counters: {} file(s) and {} playlist(s) corrected",
+        files_corrected, playlists_corrected
+    );
+
+    Ok(RecomputeCountersReport {
+        files_corrected,
+        playlists_corrected,
+    })
+}
+
+/// Raise `tbl_files.downloads` to the number of rows in `tbl_download_logs`
+/// where the log has more, repairing a download whose log row was written
+/// without the increment. The counter is never lowered: it predates the log
+/// and must survive the log being pruned
+async fn recompute_file_downloads(pool: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let max_id: i32 = sqlx::query_scalar!("SELECT MAX(id) FROM tbl_files")
+        .fetch_one(pool)
+        .await?
+        .unwrap_or(0);
+
+    let mut corrected = 0;
+    let mut start = 1;
+    while start <= max_id {
+        let end = start + BATCH_SIZE - 1;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE tbl_files f
+            JOIN (
+                SELECT file_id, COUNT(*) as total
+                FROM tbl_download_logs
+                WHERE file_id BETWEEN ? AND ?
+                GROUP BY file_id
+            ) d ON d.file_id = f.id
+            SET f.downloads = d.total
+            WHERE f.id BETWEEN ? AND ?
+            AND f.downloads < d.total
+            "#,
+            start,
+            end,
+            start,
+            end
+        )
+        .execute(pool)
+        .await?;
+
+        corrected += result.rows_affected();
+        start = end + 1;
+    }
+
+    Ok(corrected)
+}
+
+/// Reset playlist `total_files`/`total_duration` from the active files in `tbl_playlist_files`
+async fn recompute_playlist_totals(pool: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let max_id: i32 = sqlx::query_scalar!("SELECT MAX(id) FROM tbl_playlists")
+        .fetch_one(pool)
+        .await?
+        .unwrap_or(0);
+
+    let mut corrected = 0;
+    let mut start = 1;
+    while start <= max_id {
+        let end = start + BATCH_SIZE - 1;
+
+        let playlists = sqlx::query!(
+            "SELECT id, total_files, total_duration FROM tbl_playlists WHERE id BETWEEN ? AND ?",
+            start,
+            end
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let entries = sqlx::query!(
+            r#"
+            SELECT pf.playlist_id, f.duration
+            FROM tbl_playlist_files pf
+            JOIN tbl_files f ON pf.file_id = f.id
+            WHERE pf.playlist_id BETWEEN ? AND ? AND f.status = 'active'
+            "#,
+            start,
+            end
+        )
+        .fetch_all(pool)
+        .await?;
+
+        // playlist_id -> (file count, total seconds)
+        let mut totals: HashMap<i32, (i32, i32)> = HashMap::new();
+        for entry in entries {
+            let total = totals.entry(entry.playlist_id).or_insert((0, 0));
+            total.0 += 1;
+            total.1 += parse_duration(&entry.duration).unwrap_or(0) as i32;
+        }
+
+        let now = Utc::now().naive_utc();
+        for playlist in playlists {
+            let (total_files, total_duration) = totals.get(&playlist.id).copied().unwrap_or((0, 0));
+            if playlist.total_files.unwrap_or(0) == total_files
+                && playlist.total_duration.unwrap_or(0) == total_duration
+            {
+                continue;
+            }
+
+            sqlx::query!(
+                r#"
+                UPDATE tbl_playlists
+                SET total_files = ?, total_duration = ?, updated_at = ?
+                WHERE id = ?
+                "#,
+                total_files,
+                total_duration,
+                now,
+                playlist.id
+            )
+            .execute(pool)
+            .await?;
+
+            corrected += 1;
+        }
+
+        start = end + 1;
+    }
+
+    Ok(corrected)
+}
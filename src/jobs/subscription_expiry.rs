@@ -1,10 +1,27 @@
+use crate::core::config::SubscriptionConfig;
+use crate::core::EmailService;
+use crate::db::notifications::is_notification_enabled;
+use crate::models::notifications::NotificationKind;
 use chrono::Utc;
 use sqlx::MySqlPool;
 use std::time::Duration;
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// A subscription moved from active to expired by this run
+#[derive(Debug)]
+pub struct ExpiredSubscription {
+    pub subscription_id: i32,
+    pub user_id: i32,
+    pub email: String,
+    pub plan_name: String,
+}
 
 /// Background job that checks for expired subscriptions and updates their status
-pub async fn start_subscription_expiry_checker(pool: MySqlPool) {
+pub async fn start_subscription_expiry_checker(
+    pool: MySqlPool,
+    settings: SubscriptionConfig,
+    email_service: EmailService,
+) {
     info!("Starting subscription expiry checker background job");
     
     tokio::spawn(async move {
@@ -13,60 +30,106 @@ pub async fn start_subscription_expiry_checker(pool: MySqlPool) {
         loop {
             interval.tick().await;
             
-            if let Err(e) = check_and_expire_subscriptions(&pool).await {
-                error!("Failed to check expired subscriptions: {}", e);
+            match expire_subscriptions_now(&pool, settings.grace_days).await {
+                Ok(expired) => {
+                    if settings.send_expiry_emails {
+                        notify_expired_subscriptions(&pool, &email_service, &expired).await;
+                    }
+                }
+                Err(e) => error!("Failed to check expired subscriptions: {}", e),
             }
         }
     });
 }
 
-/// Check and update expired subscriptions
-async fn check_and_expire_subscriptions(pool: &MySqlPool) -> Result<(), sqlx::Error> {
+/// Expire active subscriptions whose end_date plus `grace_days` has passed.
+/// Each row is only transitioned while still active, so repeated or concurrent
+/// runs never report the same subscription twice.
+pub async fn expire_subscriptions_now(
+    pool: &MySqlPool,
+    grace_days: i64,
+) -> Result<Vec<ExpiredSubscription>, sqlx::Error> {
     let now = Utc::now().naive_utc();
-    let today = Utc::now().date_naive();
-    
-    // Update subscriptions that have passed their end_date
-    let result = sqlx::query!(
+    let cutoff = Utc::now().date_naive() - chrono::Duration::days(grace_days.max(0));
+
+    let candidates = sqlx::query!(
         r#"
-        UPDATE tbl_user_subscriptions
-        SET status = 'expired', updated_at = ?
-        WHERE status = 'active' 
-        AND end_date IS NOT NULL 
-        AND end_date < ?
+        SELECT us.id, us.user_id, u.email, sp.name as plan_name
+        FROM tbl_user_subscriptions us
+        JOIN tbl_users u ON us.user_id = u.id
+        JOIN tbl_subscription_plans sp ON us.subscription_plan_id = sp.id
+        WHERE us.status = 'active'
+        AND us.end_date IS NOT NULL
+        AND us.end_date < ?
         "#,
-        now,
-        today
+        cutoff
     )
-    .execute(pool)
+    .fetch_all(pool)
     .await?;
-    
-    let rows_affected = result.rows_affected();
-    
-    if rows_affected > 0 {
-        info!("Expired {} subscription(s)", rows_affected);
+
+    let mut expired = Vec::new();
+    for candidate in candidates {
+        let result = sqlx::query!(
+            r#"
+            UPDATE tbl_user_subscriptions
+            SET status = 'expired', updated_at = ?
+            WHERE id = ? AND status = 'active'
+            "#,
+            now,
+            candidate.id
+        )
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            expired.push(ExpiredSubscription {
+                subscription_id: candidate.id,
+                user_id: candidate.user_id,
+                email: candidate.email,
+                plan_name: candidate.plan_name,
+            });
+        }
     }
-    
-    Ok(())
+
+    if !expired.is_empty() {
+        info!("Expired {} subscription(s)", expired.len());
+    }
+
+    Ok(expired)
 }
 
-/// Manual function to expire subscriptions (can be called from an admin endpoint)
-pub async fn expire_subscriptions_now(pool: &MySqlPool) -> Result<u64, sqlx::Error> {
-    let now = Utc::now().naive_utc();
-    let today = Utc::now().date_naive();
-    
-    let result = sqlx::query!(
-        r#"
-        UPDATE tbl_user_subscriptions
-        SET status = 'expired', updated_at = ?
-        WHERE status = 'active' 
-        AND end_date IS NOT NULL 
-        AND end_date < ?
-        "#,
-        now,
-        today
-    )
-    .execute(pool)
-    .await?;
-    
-    Ok(result.rows_affected())
+/// Queue a "subscription expired" email for each newly expired subscription,
+/// skipping users who turned off subscription reminders
+pub async fn notify_expired_subscriptions(
+    pool: &MySqlPool,
+    email_service: &EmailService,
+    expired: &[ExpiredSubscription],
+) {
+    for subscription in expired {
+        match is_notification_enabled(
+            pool,
+            subscription.user_id,
+            NotificationKind::SubscriptionReminder,
+        )
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => continue,
+            // Err on the side of sending; the email is the only notice the user gets
+            Err(e) => warn!(
+                "Failed to load notification preferences for user {}: {:?}",
+                subscription.user_id, e
+            ),
+        }
+
+        if let Err(e) = email_service
+            .send_subscription_expired(&subscription.email, &subscription.plan_name)
+            .await
+        {
+            warn!(
+                "Failed to queue expiry email for subscription {}: {:?}",
+                subscription.subscription_id, e
+            );
+        }
+    }
 }
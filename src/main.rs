@@ -8,12 +8,19 @@ use colored::*;
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
-    let file_appender = tracing_appender::rolling::daily("/var/tmp/log/sunnah_audio", "app");
+    let config = AppConfig::new().expect("cant build our appConfig object");
 
-    let subscriber = get_subscriber("sunnah_audio".into(), "info".into(), file_appender);
-    init_subscriber(subscriber);
+    let file_appender = tracing_appender::rolling::daily(
+        &config.logging.directory,
+        &config.logging.file_prefix,
+    );
 
-    let config = AppConfig::new().expect("cant build our appConfig object");
+    let (subscriber, log_filter) = get_subscriber(
+        "sunnah_audio".into(),
+        config.logging.level.clone(),
+        file_appender,
+    );
+    init_subscriber(subscriber);
 
     // let postgres = PgPoolOptions::new()
     //     .acquire_timeout(std::time::Duration::from_secs(5))
@@ -21,7 +28,7 @@ async fn main() -> std::io::Result<()> {
 
     // let redis_client = config.redis.connect();
 
-    let sunnah_audio_web_server = SunnahWebServer::build(config.clone())
+    let sunnah_audio_web_server = SunnahWebServer::build(config.clone(), log_filter)
         .await
         .expect("application could run for some obvious reasons");
 
@@ -15,9 +15,56 @@ pub struct UserAccess {
 pub struct UserPermissions {
     pub user_id: i32,
     pub accessible_scholars: Vec<ScholarAccess>,
+    /// Single books and files shared with the user outside a scholar grant
+    pub accessible_books: Vec<ContentAccess>,
+    pub accessible_files: Vec<ContentAccess>,
     pub role: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContentAccess {
+    pub id: i32,
+    pub name: String,
+}
+
+/// What a grant covers: a whole scholar, or a single book or file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessTarget {
+    Scholar(i32),
+    Book(i32),
+    File(i32),
+}
+
+impl AccessTarget {
+    /// The target named by a grant/revoke request; exactly one id must be set
+    pub fn from_ids(scholar_id: Option<i32>, book_id: Option<i32>, file_id: Option<i32>) -> Option<Self> {
+        match (scholar_id, book_id, file_id) {
+            (Some(id), None, None) => Some(AccessTarget::Scholar(id)),
+            (None, Some(id), None) => Some(AccessTarget::Book(id)),
+            (None, None, Some(id)) => Some(AccessTarget::File(id)),
+            _ => None,
+        }
+    }
+
+    /// Whether this grant lets its holder manage `book_id` of `scholar_id`
+    pub fn covers_book(&self, scholar_id: i32, book_id: i32) -> bool {
+        match *self {
+            AccessTarget::Scholar(id) => id == scholar_id,
+            AccessTarget::Book(id) => id == book_id,
+            AccessTarget::File(_) => false,
+        }
+    }
+
+    /// Whether this grant lets its holder manage `file_id`, filed under `book_id` of `scholar_id`
+    pub fn covers_file(&self, scholar_id: i32, book_id: i32, file_id: i32) -> bool {
+        match *self {
+            AccessTarget::Scholar(id) => id == scholar_id,
+            AccessTarget::Book(id) => id == book_id,
+            AccessTarget::File(id) => id == file_id,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScholarAccess {
     pub scholar_id: i32,
@@ -27,14 +74,84 @@ pub struct ScholarAccess {
     pub can_manage: bool,
 }
 
+/// Set one of `scholar_id`, `book_id` or `file_id`
 #[derive(Debug, Deserialize)]
 pub struct GrantAccessRequest {
     pub user_id: i32,
-    pub scholar_id: i32,
+    pub scholar_id: Option<i32>,
+    pub book_id: Option<i32>,
+    pub file_id: Option<i32>,
 }
 
+/// Set one of `scholar_id`, `book_id` or `file_id`
 #[derive(Debug, Deserialize)]
 pub struct RevokeAccessRequest {
     pub user_id: i32,
-    pub scholar_id: i32,
-}
\ No newline at end of file
+    pub scholar_id: Option<i32>,
+    pub book_id: Option<i32>,
+    pub file_id: Option<i32>,
+}
+
+/// Why a user may manage a scholar or book, checked most general first
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ManageReason {
+    Admin,
+    ScholarAccess,
+    BookAccess,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CanManageResponse {
+    pub can_manage: bool,
+    /// None when `can_manage` is false
+    pub reason: Option<ManageReason>,
+}
+
+impl From<Option<ManageReason>> for CanManageResponse {
+    fn from(reason: Option<ManageReason>) -> Self {
+        Self {
+            can_manage: reason.is_some(),
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_target_from_ids_takes_exactly_one_id() {
+        assert_eq!(AccessTarget::from_ids(Some(1), None, None), Some(AccessTarget::Scholar(1)));
+        assert_eq!(AccessTarget::from_ids(None, Some(2), None), Some(AccessTarget::Book(2)));
+        assert_eq!(AccessTarget::from_ids(None, None, Some(3)), Some(AccessTarget::File(3)));
+        assert_eq!(AccessTarget::from_ids(None, None, None), None);
+        assert_eq!(AccessTarget::from_ids(Some(1), Some(2), None), None);
+        assert_eq!(AccessTarget::from_ids(Some(1), Some(2), Some(3)), None);
+    }
+
+    #[test]
+    fn a_book_grant_covers_that_book_and_its_files_only() {
+        let grant = AccessTarget::Book(20);
+
+        assert!(grant.covers_book(1, 20));
+        assert!(grant.covers_file(1, 20, 300));
+        // A sibling book of the same scholar, and its files
+        assert!(!grant.covers_book(1, 21));
+        assert!(!grant.covers_file(1, 21, 301));
+    }
+
+    #[test]
+    fn scholar_grants_are_broad_and_file_grants_narrow() {
+        let scholar = AccessTarget::Scholar(1);
+        assert!(scholar.covers_book(1, 21));
+        assert!(scholar.covers_file(1, 21, 301));
+        assert!(!scholar.covers_file(2, 40, 500));
+
+        let file = AccessTarget::File(300);
+        assert!(file.covers_file(1, 20, 300));
+        assert!(!file.covers_file(1, 20, 302));
+        assert!(!file.covers_book(1, 20));
+    }
+}
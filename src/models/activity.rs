@@ -0,0 +1,17 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// One entry in a user's own activity timeline. `kind` is one of `played`,
+/// `downloaded`, `liked`, `commented` or `playlist_created`; the fields that
+/// don't apply to a kind are null
+#[derive(Debug, Serialize)]
+pub struct ActivityItem {
+    pub kind: String,
+    pub occurred_at: NaiveDateTime,
+    pub file_id: Option<i32>,
+    /// Null when the file has since been removed
+    pub file_name: Option<String>,
+    pub comment_id: Option<i32>,
+    pub playlist_id: Option<i32>,
+    pub playlist_name: Option<String>,
+}
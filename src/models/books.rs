@@ -19,6 +19,8 @@ pub struct BookSearchResult {
     pub name: Option<String>,
     pub image: Option<String>,
     pub scholar_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<crate::models::common::SearchHighlight>,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,6 +34,7 @@ pub struct BookDetails {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub created_by: i32,
+    pub version: i32,
     pub statistics: BookStatistics,
     pub has_access: Option<bool>, // Will be None if no user context, true if manager has access
 }
@@ -44,6 +47,24 @@ pub struct BookDropdown {
     pub scholar_name: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct SiblingBook {
+    pub id: i32,
+    pub name: String,
+    pub image: String,
+}
+
+/// Neighbours of a book in its scholar's catalog, oldest first
+#[derive(Debug, Serialize)]
+pub struct BookSiblings {
+    pub book_id: i32,
+    pub scholar_id: i32,
+    pub previous: Option<SiblingBook>, // None for the scholar's first book
+    pub next: Option<SiblingBook>,     // None for the scholar's last book
+    pub position: i64,                 // 1-based
+    pub total_books: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateBookRequest {
     pub name: String,
@@ -58,6 +79,7 @@ pub struct UpdateBookRequest {
     pub about: Option<String>,
     pub scholar_id: Option<i32>,
     pub image: Option<String>,
+    pub version: Option<i32>, // Version the client last read; a mismatch is a conflict
 }
 
 #[derive(Debug, Serialize)]
@@ -68,3 +90,22 @@ pub struct BookStatistics {
     pub total_likes: i64,
     pub average_rating: Option<f64>,
 }
+
+#[derive(Debug, Serialize)]
+pub struct BookProgress {
+    pub book_id: i32,
+    pub total_files: i64,
+    pub completed_files: i64,
+    pub percentage: f64,
+    pub is_completed: bool,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompletedBook {
+    pub book_id: i32,
+    pub book_name: String,
+    pub book_image: String,
+    pub scholar_name: String,
+    pub completed_at: NaiveDateTime,
+}
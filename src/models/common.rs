@@ -1,8 +1,17 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 
 #[derive(Deserialize)]
 pub struct PaginationParams {
     pub page: Option<i64>,
     pub items_per_page: Option<i64>,
-}
\ No newline at end of file
+}
+
+/// Where a search term matched a result's name, as character offsets into the
+/// original text plus the HTML-escaped text with the match wrapped in `<mark>` tags
+#[derive(Debug, Serialize)]
+pub struct SearchHighlight {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
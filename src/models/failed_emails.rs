@@ -0,0 +1,23 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// A stored failed email as admins see it; the payload stays server-side
+#[derive(Debug, Serialize)]
+pub struct FailedEmailSummary {
+    pub id: i32,
+    pub email_kind: String,
+    pub recipient: String,
+    pub last_error: String,
+    pub attempts: i32,
+    pub created_at: NaiveDateTime,
+    pub last_attempt_at: NaiveDateTime,
+    pub retried_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug)]
+pub struct FailedEmailRecord {
+    pub id: i32,
+    pub payload: String,
+    pub created_at: NaiveDateTime,
+    pub retried_at: Option<NaiveDateTime>,
+}
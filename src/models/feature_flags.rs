@@ -0,0 +1,72 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Features that can be switched at runtime. Every flag defaults to off so an
+/// upgrade never turns a feature on by itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureFlag {
+    /// `popular` and `because_you_listened` buckets in file suggestions
+    Recommendations,
+    /// Play tracking for signed-out listeners; also needs `guest_tracking.enabled`
+    GuestTracking,
+    /// Hold every new or edited comment for review
+    ModerationMode,
+}
+
+impl FeatureFlag {
+    pub const ALL: [FeatureFlag; 3] = [
+        FeatureFlag::Recommendations,
+        FeatureFlag::GuestTracking,
+        FeatureFlag::ModerationMode,
+    ];
+
+    /// Value stored in `tbl_feature_flags.flag_key` and used in the API
+    pub fn key(&self) -> &'static str {
+        match self {
+            FeatureFlag::Recommendations => "recommendations",
+            FeatureFlag::GuestTracking => "guest_tracking",
+            FeatureFlag::ModerationMode => "moderation_mode",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|flag| flag.key() == key)
+    }
+
+    pub fn default_enabled(&self) -> bool {
+        false
+    }
+
+    /// Public flags are listed in the site settings so clients can adapt
+    pub fn is_public(&self) -> bool {
+        match self {
+            FeatureFlag::Recommendations | FeatureFlag::GuestTracking => true,
+            FeatureFlag::ModerationMode => false,
+        }
+    }
+}
+
+/// A stored override of a flag's default
+#[derive(Debug)]
+pub struct FeatureFlagOverride {
+    pub flag_key: String,
+    pub enabled: bool,
+    pub updated_by: Option<i32>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureFlagState {
+    pub key: String,
+    pub enabled: bool,
+    pub default_enabled: bool,
+    pub is_public: bool,
+    /// Null while the flag is at its default
+    pub updated_by: Option<i32>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateFeatureFlagRequest {
+    pub enabled: bool,
+}
@@ -0,0 +1,46 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct FileAnalyticsQuery {
+    /// Days covered by the series and windowed counts; defaults to 30
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+/// Downloads grouped by a coarse network prefix of the client IP
+/// (`a.b.*.*` for IPv4, first two groups for IPv6). There is no GeoIP
+/// lookup, so this is as close to a region as the logs get
+#[derive(Debug, Serialize)]
+pub struct ListenerRegion {
+    pub network: String,
+    pub downloads: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileAnalytics {
+    pub file_id: i32,
+    pub window_days: i64,
+    /// One entry per UTC day in the window, oldest first, zero-filled
+    pub plays_per_day: Vec<DailyCount>,
+    pub downloads_per_day: Vec<DailyCount>,
+    pub plays_in_window: i64,
+    pub downloads_in_window: i64,
+    /// Completion events, at most one per user and session
+    pub completions_in_window: i64,
+    pub unique_completers: i64,
+    /// All-time counters
+    pub total_downloads: i64,
+    pub likes: i64,
+    pub comments: i64,
+    pub top_regions: Vec<ListenerRegion>,
+    /// Mean of each listener's furthest saved position over the file's
+    /// duration, 0.0-1.0; None when no play in the window saved a position
+    pub average_completion: Option<f64>,
+    pub listeners_with_position: i64,
+}
@@ -5,7 +5,8 @@ use chrono::NaiveDateTime;
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileReport {
     pub id: i32,
-    pub user_id: i32,
+    /// None for reports the server raised itself, such as missing_file
+    pub user_id: Option<i32>,
     pub file_id: i32,
     pub reason: String,
     pub description: Option<String>,
@@ -16,6 +17,21 @@ pub struct FileReport {
     pub resolved_at: Option<NaiveDateTime>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PendingReportsQuery {
+    pub reason: Option<String>,
+    pub file_id: Option<i32>,
+    pub sort: Option<String>, // oldest (default) | newest
+}
+
+/// A pending report with how long it has waited for review
+#[derive(Debug, Serialize)]
+pub struct PendingFileReport {
+    #[serde(flatten)]
+    pub report: FileReport,
+    pub age_hours: i64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateReportRequest {
     pub file_id: i32,
@@ -38,6 +54,14 @@ pub struct FileLike {
     pub created_at: NaiveDateTime,
 }
 
+#[derive(Debug, Serialize)]
+pub struct LikeFileResponse {
+    #[serde(flatten)]
+    pub like: FileLike,
+    /// False when the user had already liked the file; the original like is kept
+    pub is_new: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LikeFileRequest {
     pub file_id: i32,
@@ -52,6 +76,7 @@ pub struct FileComment {
     pub parent_id: Option<i32>,
     pub comment: String,
     pub is_approved: bool,
+    pub mentioned_user_ids: Vec<i32>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -68,6 +93,20 @@ pub struct UpdateCommentRequest {
     pub comment: String,
 }
 
+/// A comment held for moderation, with enough context for a moderator to judge it
+#[derive(Debug, Serialize)]
+pub struct PendingComment {
+    pub id: i32,
+    pub file_id: i32,
+    pub file_name: String,
+    pub user_id: i32,
+    pub user_name: String,
+    pub parent_id: Option<i32>,
+    pub comment: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct CommentResponse {
     pub id: i32,
@@ -75,6 +114,7 @@ pub struct CommentResponse {
     pub parent_id: Option<i32>,
     pub comment: String,
     pub is_approved: bool,
+    pub mentioned_user_ids: Vec<i32>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub replies: Vec<CommentResponse>,
@@ -84,7 +124,8 @@ pub struct CommentResponse {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DownloadLog {
     pub id: i32,
-    pub user_id: i32,
+    pub user_id: Option<i32>,
+    pub anonymous_id: Option<String>,
     pub subscription_id: Option<i32>,
     pub file_id: i32,
     pub download_ip: Option<String>,
@@ -92,6 +133,49 @@ pub struct DownloadLog {
     pub downloaded_at: NaiveDateTime,
 }
 
+/// Whether a comment is visible on its file; unapproved comments are held
+/// for moderation and only shown to their author, as are rejected ones
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentStatus {
+    Approved,
+    PendingModeration,
+    Rejected,
+}
+
+/// A comment by the current user joined with its file. Comments on files purged
+/// or deleted since are kept: `is_file_available` is false and the file fields are null
+#[derive(Debug, Serialize)]
+pub struct MyCommentEntry {
+    pub id: i32,
+    pub file_id: i32,
+    pub parent_id: Option<i32>,
+    pub comment: String,
+    pub status: CommentStatus,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub is_file_available: bool,
+    pub file_name: Option<String>,
+    pub scholar_id: Option<i32>,
+    pub scholar_name: Option<String>,
+}
+
+/// A download joined with its file. Files purged or deleted since are kept as
+/// tombstones: `is_available` is false and the file fields are null
+#[derive(Debug, Serialize)]
+pub struct DownloadHistoryEntry {
+    pub id: i32,
+    pub file_id: i32,
+    pub downloaded_at: NaiveDateTime,
+    pub is_available: bool,
+    pub file_name: Option<String>,
+    pub file_url: Option<String>,
+    pub scholar_id: Option<i32>,
+    pub scholar_name: Option<String>,
+    pub book_id: Option<i32>,
+    pub book_name: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DownloadStats {
     pub total_downloads: i64,
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -13,7 +13,8 @@ pub struct Files {
     pub scholar_id: i32,
     pub scholar_name: String,
     pub scholar_image: String,
-    pub date: DateTime<Local>,
+    pub date: DateTime<Utc>,
+    /// `tbl_files.downloads`, the same counter every download total is read from
     pub downloads: i32,
 }
 
@@ -28,9 +29,68 @@ pub struct FilesWithStats {
     pub scholar_id: i32,
     pub scholar_name: String,
     pub scholar_image: String,
-    pub date: DateTime<Local>,
+    pub date: DateTime<Utc>,
     pub uploaded_by: i32,
     pub statistics: FileStatistics,
+    // Listening state of the requesting user; null for anonymous requests
+    pub has_played: Option<bool>,
+    pub last_position_seconds: Option<i32>,
+    pub completed: Option<bool>,
+}
+
+/// Pins a file for `date`, or adds it to the curated pool when `date` is omitted
+#[derive(Debug, Deserialize)]
+pub struct FeatureFileRequest {
+    pub file_id: i32,
+    pub date: Option<chrono::NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecentFilesQuery {
+    /// Switches to keyset pagination: send it empty for the first page, then
+    /// the returned `next_cursor`. Without it `page`/`per_page` offsets apply
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookFilesSort {
+    Newest,
+    Oldest,
+    MostPlayed,
+    /// Shortest first
+    Duration,
+}
+
+impl BookFilesSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BookFilesSort::Newest => "newest",
+            BookFilesSort::Oldest => "oldest",
+            BookFilesSort::MostPlayed => "most_played",
+            BookFilesSort::Duration => "duration",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookFilesFilter {
+    /// Files the signed-in user has no play history for
+    NotPlayed,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BookFilesQuery {
+    /// Without it files keep their upload order
+    pub sort: Option<BookFilesSort>,
+    pub filter: Option<BookFilesFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetFileRestrictionRequest {
+    /// Restricted files are hidden from anonymous listings and search
+    pub restricted: bool,
 }
 
 #[derive(FromRow, Serialize)]
@@ -45,7 +105,7 @@ pub struct RecentFiles {
     pub scholar_id: i32,
     pub scholar_name: String,
     pub scholar_image: String,
-    pub date: DateTime<Local>,
+    pub date: DateTime<Utc>,
 }
 
 #[derive(Serialize)]
@@ -59,7 +119,7 @@ pub struct RecentFilesWithStats {
     pub scholar_id: i32,
     pub scholar_name: String,
     pub scholar_image: String,
-    pub date: DateTime<Local>,
+    pub date: DateTime<Utc>,
     pub statistics: FileStatistics,
 }
 
@@ -72,6 +132,14 @@ pub struct FileStatistics {
     pub is_liked_by_user: Option<bool>, // Will be None if no user context
 }
 
+/// Which files a search runs over
+#[derive(Debug, Clone, Copy)]
+pub enum FileSearchScope {
+    All,
+    Scholar(i32),
+    Book(i32),
+}
+
 #[derive(Debug, Serialize)]
 pub struct FileSearchResult {
     pub file_id: i32,
@@ -84,6 +152,8 @@ pub struct FileSearchResult {
     pub scholar_id: i32,
     pub scholar_name: String,
     pub scholar_image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<crate::models::common::SearchHighlight>,
 }
 
 #[derive(Debug, Serialize)]
@@ -93,7 +163,7 @@ pub struct ViewFileDetails {
     pub file_url: String,
     pub duration: String,
     pub size: String,
-    pub created_at: DateTime<Local>,
+    pub created_at: DateTime<Utc>,
     pub book_id: i32,
     pub book_name: String,
     pub book_image: Option<String>, // URL to the book's image
@@ -101,6 +171,7 @@ pub struct ViewFileDetails {
     pub scholar_name: String,
     pub scholar_image: String,
     pub downloads: i32,
+    pub version: i32,
 }
 
 #[derive(Debug, Serialize)]
@@ -111,7 +182,7 @@ pub struct RelatedFiles {
     pub duration: String,
     pub size: String,
     pub downloads: i32,
-    pub date: DateTime<Local>,
+    pub date: DateTime<Utc>,
     pub book_id: i32,
     pub scholar_name: String,
     pub scholar_image: String,
@@ -131,7 +202,7 @@ pub struct PlayAllFile {
     pub file_size: String,
     pub file_duration: String,
     pub sort_order: Option<i32>, // For proper ordering in playlist
-    pub date: DateTime<Local>,
+    pub date: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize)]
@@ -147,9 +218,78 @@ pub struct PlayAllResponse {
     pub files: Vec<PlayAllFile>,
 }
 
+/// Everything an offline client needs to fetch a whole book and verify it
+#[derive(Debug, Serialize)]
+pub struct OfflineManifest {
+    pub book_id: i32,
+    pub book_name: String,
+    pub book_image: Option<String>,
+    pub scholar_id: i32,
+    pub scholar_name: String,
+    pub scholar_image: Option<String>,
+    pub total_files: i32,
+    pub total_duration: Option<String>,
+    pub generated_at: DateTime<Utc>,
+    pub files: Vec<OfflineManifestFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OfflineManifestFile {
+    pub file_id: i32,
+    pub file_name: String,
+    pub stream_url: String,
+    pub download_url: String,
+    pub file_size: String,
+    pub file_duration: String,
+    /// SHA-256 of the stored audio, hex; None until the content hash backfill
+    /// has reached a file stored before uploads were hashed
+    pub content_hash: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateFileRequest {
     pub name: Option<String>,
     pub book_id: Option<i32>,
     pub scholar_id: Option<i32>,
+    pub version: Option<i32>, // Version the client last read; a mismatch is a conflict
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web::Query;
+
+    #[test]
+    fn every_sort_option_parses_to_the_value_the_order_by_matches() {
+        // fetch_files_by_book_with_stats compares these strings in its CASE arms
+        for (param, sort, sql_value) in [
+            ("newest", BookFilesSort::Newest, "newest"),
+            ("oldest", BookFilesSort::Oldest, "oldest"),
+            ("most_played", BookFilesSort::MostPlayed, "most_played"),
+            ("duration", BookFilesSort::Duration, "duration"),
+        ] {
+            let query = Query::<BookFilesQuery>::from_query(&format!("sort={param}")).unwrap();
+            assert_eq!(query.sort, Some(sort));
+            assert_eq!(sort.as_str(), sql_value);
+        }
+    }
+
+    #[test]
+    fn not_played_filter_parses() {
+        let query = Query::<BookFilesQuery>::from_query("filter=not_played").unwrap();
+        assert_eq!(query.filter, Some(BookFilesFilter::NotPlayed));
+    }
+
+    #[test]
+    fn values_outside_the_allowlist_are_rejected() {
+        assert!(Query::<BookFilesQuery>::from_query("sort=name; DROP TABLE tbl_files").is_err());
+        assert!(Query::<BookFilesQuery>::from_query("filter=played").is_err());
+    }
+
+    #[test]
+    fn no_params_keeps_the_default_order() {
+        let query = Query::<BookFilesQuery>::from_query("").unwrap();
+        assert_eq!(query.sort, None);
+        assert_eq!(query.filter, None);
+    }
 }
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserScholarFollow {
@@ -10,6 +10,14 @@ pub struct UserScholarFollow {
     pub followed_at: NaiveDateTime,
 }
 
+#[derive(Debug, Serialize)]
+pub struct FollowStateResponse {
+    pub scholar_id: i32,
+    pub is_following: bool,
+    pub follower_count: i64,
+    pub follow: Option<UserScholarFollow>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FollowScholarRequest {
     pub scholar_id: i32,
@@ -27,4 +35,12 @@ pub struct FollowResponse {
     pub scholar_name: String,
     pub notifications_enabled: bool,
     pub followed_at: NaiveDateTime,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewFollowedContentCount {
+    /// Files from followed scholars that became visible to this user since `last_seen_at`
+    pub new_files: i64,
+    /// Null until the user first marks the feed seen
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
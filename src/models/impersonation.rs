@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::users::UserProfile;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StartImpersonationRequest {
+    /// Why support needs to act as the user; stored in the audit log
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    /// Read-only bearer token acting as `user`; it cannot be refreshed
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub session_id: String,
+    pub user: UserProfile,
+}
@@ -10,7 +10,17 @@ pub mod access;
 pub mod uploads;
 pub mod subscriptions;
 pub mod follows;
+pub mod notifications;
 pub mod play_history;
 pub mod playlists;
 pub mod file_interactions;
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+pub mod stats;
+pub mod v2;
+pub mod share;
+pub mod impersonation;
+pub mod file_analytics;
+pub mod activity;
+pub mod feature_flags;
+pub mod failed_emails;
+pub mod scheduled;
@@ -0,0 +1,127 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Kinds of notification; each is governed by one preference toggle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationKind {
+    NewUploadFromFollowed,
+    CommentReply,
+    /// Shares the `comment_replies` toggle
+    Mention,
+    LikeOnMyUpload,
+    SubscriptionReminder,
+    /// Sent by the system, so it has no actor
+    ListeningGoalMet,
+}
+
+impl NotificationKind {
+    /// Value stored in `tbl_notifications.kind`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::NewUploadFromFollowed => "new_upload",
+            NotificationKind::CommentReply => "comment_reply",
+            NotificationKind::Mention => "mention",
+            NotificationKind::LikeOnMyUpload => "like",
+            NotificationKind::SubscriptionReminder => "subscription_reminder",
+            NotificationKind::ListeningGoalMet => "listening_goal_met",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    pub id: i32,
+    pub kind: String,
+    pub actor_id: Option<i32>,
+    pub actor_name: Option<String>,
+    pub file_id: Option<i32>,
+    pub comment_id: Option<i32>,
+    pub is_read: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct NotificationPreferences {
+    pub new_uploads_from_followed: bool,
+    pub comment_replies: bool,
+    pub likes_on_my_uploads: bool,
+    pub subscription_reminders: bool,
+    pub listening_goals: bool,
+}
+
+impl Default for NotificationPreferences {
+    // Everything on, so users who never saved preferences keep getting notified
+    fn default() -> Self {
+        Self {
+            new_uploads_from_followed: true,
+            comment_replies: true,
+            likes_on_my_uploads: true,
+            subscription_reminders: true,
+            listening_goals: true,
+        }
+    }
+}
+
+impl NotificationPreferences {
+    pub fn allows(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::NewUploadFromFollowed => self.new_uploads_from_followed,
+            NotificationKind::CommentReply | NotificationKind::Mention => self.comment_replies,
+            NotificationKind::LikeOnMyUpload => self.likes_on_my_uploads,
+            NotificationKind::SubscriptionReminder => self.subscription_reminders,
+            NotificationKind::ListeningGoalMet => self.listening_goals,
+        }
+    }
+
+    /// These preferences with the toggles `request` sets replaced
+    pub fn updated_with(&self, request: &UpdateNotificationPreferencesRequest) -> Self {
+        Self {
+            new_uploads_from_followed: request
+                .new_uploads_from_followed
+                .unwrap_or(self.new_uploads_from_followed),
+            comment_replies: request.comment_replies.unwrap_or(self.comment_replies),
+            likes_on_my_uploads: request
+                .likes_on_my_uploads
+                .unwrap_or(self.likes_on_my_uploads),
+            subscription_reminders: request
+                .subscription_reminders
+                .unwrap_or(self.subscription_reminders),
+            listening_goals: request.listening_goals.unwrap_or(self.listening_goals),
+        }
+    }
+}
+
+/// Partial update; omitted toggles keep their current value
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub new_uploads_from_followed: Option<bool>,
+    pub comment_replies: Option<bool>,
+    pub likes_on_my_uploads: Option<bool>,
+    pub subscription_reminders: Option<bool>,
+    pub listening_goals: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_disabled_preference_suppresses_only_its_notifications() {
+        let defaults = NotificationPreferences::default();
+        assert!(defaults.allows(NotificationKind::CommentReply));
+
+        let prefs = defaults.updated_with(&UpdateNotificationPreferencesRequest {
+            new_uploads_from_followed: None,
+            comment_replies: Some(false),
+            likes_on_my_uploads: None,
+            subscription_reminders: None,
+            listening_goals: None,
+        });
+
+        // `create_notification` skips the insert for these
+        assert!(!prefs.allows(NotificationKind::CommentReply));
+        assert!(!prefs.allows(NotificationKind::Mention));
+        assert!(prefs.allows(NotificationKind::LikeOnMyUpload));
+        assert!(prefs.allows(NotificationKind::SubscriptionReminder));
+    }
+}
@@ -1,3 +1,5 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
@@ -35,6 +37,7 @@ pub struct PaginationMeta {
     pub per_page: i32,
     pub total_items: i64,
     pub total_pages: i32,
+    pub has_next: bool,
 }
 
 impl PaginationMeta {
@@ -50,10 +53,44 @@ impl PaginationMeta {
             per_page,
             total_items,
             total_pages,
+            has_next: current_page < total_pages,
         }
     }
 }
 
+/// Keyset position for lists sorted by `(date DESC, id DESC)`. The id breaks
+/// ties between rows sharing a timestamp, so a page resumes exactly after the
+/// last row seen even while newer rows are being inserted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateIdCursor {
+    pub date: DateTime<Utc>,
+    pub id: i32,
+}
+
+impl DateIdCursor {
+    /// Opaque, URL-safe form handed to clients as `next_cursor`
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.date.timestamp_micros(), self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let decoded = String::from_utf8(bytes).ok()?;
+        let (micros, id) = decoded.split_once(':')?;
+        Some(Self {
+            date: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+/// One page of a keyset-paginated list; `next_cursor` is null on the last page
+#[derive(Debug, Serialize)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
 fn default_page() -> i32 {
     1
 }
@@ -61,3 +98,28 @@ fn default_page() -> i32 {
 fn default_per_page() -> i32 {
     10
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_id_cursor_round_trips() {
+        let cursor = DateIdCursor {
+            date: DateTime::from_timestamp_micros(1_730_000_000_123_456).unwrap(),
+            id: 42,
+        };
+        let encoded = cursor.encode();
+        assert!(encoded.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(DateIdCursor::decode(&encoded), Some(cursor));
+    }
+
+    #[test]
+    fn date_id_cursor_rejects_garbage() {
+        assert_eq!(DateIdCursor::decode(""), None);
+        assert_eq!(DateIdCursor::decode("not base64!"), None);
+        assert_eq!(DateIdCursor::decode(&URL_SAFE_NO_PAD.encode("123")), None);
+        assert_eq!(DateIdCursor::decode(&URL_SAFE_NO_PAD.encode("abc:1")), None);
+        assert_eq!(DateIdCursor::decode(&URL_SAFE_NO_PAD.encode("123:x")), None);
+    }
+}
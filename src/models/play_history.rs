@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlayHistory {
     pub id: i32,
-    pub user_id: i32,
+    pub user_id: Option<i32>,        // None for guest plays
+    pub anonymous_id: Option<String>,
     pub file_id: i32,
     pub played_duration: i32,
     pub total_duration: Option<i32>,
@@ -60,4 +61,103 @@ pub struct PlayHistoryResponse {
     pub play_action: String,
     pub device_type: Option<String>,
     pub played_at: NaiveDateTime,
-}
\ No newline at end of file
+}
+#[derive(Debug, Deserialize)]
+pub struct SyncPlayEntry {
+    pub file_id: i32,
+    pub played_at: DateTime<Utc>,        // When the play happened on the device
+    pub position_seconds: i32,           // Last known position in seconds
+    pub device_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncEntryStatus {
+    Accepted, // Stored as a new play entry
+    Merged,   // Folded into an existing play within the debounce window
+    Rejected, // Failed validation, see reason
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncEntryResult {
+    pub index: usize,
+    pub file_id: i32,
+    pub status: SyncEntryStatus,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncPlayHistoryResponse {
+    pub accepted: usize,
+    pub merged: usize,
+    pub rejected: usize,
+    pub results: Vec<SyncEntryResult>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileListeningState {
+    pub has_played: bool,
+    pub last_position_seconds: Option<i32>, // Position from the most recent play entry
+    pub completed: bool,                    // Has a row in tbl_file_completions
+}
+
+#[derive(Debug, Serialize)]
+pub struct FilePlayStats {
+    pub total_plays: i64,      // Plays by signed-in users; guests are counted apart
+    pub unique_listeners: i64, // Distinct signed-in users
+    pub guest_plays: i64,      // Plays by anonymous clients, not part of total_plays
+    pub unique_guests: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MostPlayedQuery {
+    pub scholar_id: Option<i32>,
+    pub book_id: Option<i32>,
+    /// e.g. "30d"; without it all retained history counts
+    pub window: Option<String>,
+}
+
+/// A file the user played, ranked by how often they played it
+#[derive(Debug, Serialize)]
+pub struct MostPlayedFile {
+    #[serde(flatten)]
+    pub file: crate::models::files::FilesWithStats,
+    pub play_count: i64,
+    pub total_played_seconds: i64,
+    pub last_played_at: NaiveDateTime,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CompleteFileRequest {
+    /// Client playback session; completions repeat at most once per session.
+    /// Without one, the UTC day is the session
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileCompletionResponse {
+    pub file_id: i32,
+    /// False when this session already counted a completion of the file
+    pub recorded: bool,
+    pub position_seconds: i32,
+    pub duration_seconds: i32,
+    pub completed_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetListeningGoalRequest {
+    /// Minutes per day; null removes the goal
+    pub daily_minutes: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListeningGoalProgress {
+    /// None when no goal is set
+    pub goal_minutes: Option<i32>,
+    pub listened_minutes: i64,
+    pub listened_seconds: i64,
+    pub goal_met: bool,
+    /// The user's local date the progress is counted for
+    pub date: NaiveDate,
+    pub timezone: String,
+}
@@ -71,6 +71,16 @@ pub struct PlaylistResponse {
     pub owner_name: String,
 }
 
+/// One of the caller's playlists and whether it already holds a given file
+#[derive(Debug, Serialize)]
+pub struct PlaylistMembership {
+    pub playlist_id: i32,
+    pub name: String,
+    pub is_public: bool,
+    pub total_files: i32,
+    pub contains_file: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PlaylistFileResponse {
     pub file_id: i32,
@@ -82,4 +92,12 @@ pub struct PlaylistFileResponse {
     pub duration: String,
     pub sort_order: i32,
     pub added_at: NaiveDateTime,
-}
\ No newline at end of file
+}
+
+/// Playlist metadata, owner and ordered files in a single payload
+#[derive(Debug, Serialize)]
+pub struct PlaylistWithFiles {
+    pub playlist: Playlist,
+    pub owner_name: String,
+    pub files: Vec<PlaylistFileResponse>,
+}
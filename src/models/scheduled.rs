@@ -0,0 +1,39 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+pub const SCHEDULED_CONTENT_TYPES: [&str; 2] = ["file", "book"];
+
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleRequest {
+    /// `file` or `book`
+    pub content_type: String,
+    pub content_id: i32,
+    /// Day the content surfaces, in the platform timezone
+    pub surface_on: NaiveDate,
+    pub note: Option<String>,
+    /// Keep the content out of listings until `surface_on` starts
+    #[serde(default)]
+    pub hide_until_surfaced: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleListQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduledContent {
+    pub id: i32,
+    pub content_type: String,
+    pub content_id: i32,
+    pub name: String,
+    /// Set for files only
+    pub file_url: Option<String>,
+    pub book_id: i32,
+    pub image: Option<String>,
+    pub surface_on: NaiveDate,
+    pub note: Option<String>,
+    /// UTC; None unless the content is hidden from listings until then
+    pub publish_at: Option<NaiveDateTime>,
+}
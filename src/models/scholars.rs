@@ -1,13 +1,15 @@
 use serde::{Serialize, Deserialize};
 use sqlx::FromRow;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
-#[derive(FromRow, Serialize)]
+#[derive(FromRow, Serialize, Deserialize)]
 pub struct Scholar {
     pub id: i32,
     pub name: String,
     pub state: String,
     pub image: Option<String>,
+    /// Date of the newest active file across the scholar's books
+    pub last_upload_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -16,6 +18,8 @@ pub struct ScholarSearchResult {
     pub name: String,
     pub image: Option<String>,
     pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<crate::models::common::SearchHighlight>,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,17 +33,62 @@ pub struct ScholarDetails {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub created_by: i32,
+    pub version: i32,
     pub statistics: ScholarStatistics,
+    pub links: Vec<ScholarLink>,
     pub is_followed_by_user: Option<bool>, // Will be None if no user context
     pub has_access: Option<bool>, // Will be None if no user context, true if manager has access
 }
 
+/// Everything the scholar screen shows on first load
+#[derive(Serialize)]
+pub struct ScholarHome {
+    pub scholar: ScholarDetails,
+    pub books: Vec<crate::models::books::Book>,
+    pub books_pagination: crate::models::pagination::PaginationMeta,
+    pub recent_files: Vec<crate::models::files::RecentFiles>,
+}
+
+/// Link types a scholar profile may carry
+pub const SCHOLAR_LINK_TYPES: &[&str] = &[
+    "website",
+    "youtube",
+    "telegram",
+    "facebook",
+    "twitter",
+    "instagram",
+    "whatsapp",
+    "soundcloud",
+];
+
+#[derive(Debug, Serialize)]
+pub struct ScholarLink {
+    pub id: i32,
+    pub link_type: String,
+    pub url: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddScholarLinkRequest {
+    pub link_type: String,
+    pub url: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ScholarDropdown {
     pub id: i32,
     pub name: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ScholarDropdownQuery {
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    /// `1` returns every match, for deployments small enough to list in full
+    pub all: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateScholarRequest {
     pub name: String,
@@ -56,9 +105,10 @@ pub struct UpdateScholarRequest {
     pub state_id: Option<i32>,
     pub image: Option<String>,
     pub priority: Option<i32>,
+    pub version: Option<i32>, // Version the client last read; a mismatch is a conflict
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ScholarStatistics {
     pub total_books: i64,
     pub total_files: i64,
@@ -67,3 +117,121 @@ pub struct ScholarStatistics {
     pub total_likes: i64,
     pub total_followers: i64,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ScholarListQuery {
+    pub sort: Option<String>, // priority | recent
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingScholarsQuery {
+    pub window: Option<String>, // e.g. "7d"; defaults to `trending.default_window_days`
+    pub limit: Option<i64>,
+}
+
+/// A scholar ranked by activity within the trending window
+#[derive(Serialize, Deserialize)]
+pub struct TrendingScholar {
+    #[serde(flatten)]
+    pub scholar: Scholar,
+    pub plays: i64,
+    pub downloads: i64,
+    pub new_followers: i64,
+    /// Weighted sum of the three counts, see `TrendingConfig`
+    pub score: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeaturedScholarsQuery {
+    pub limit: Option<i64>,
+}
+
+/// Replaces the featured list; scholars are shown in the order given
+#[derive(Debug, Deserialize)]
+pub struct SetFeaturedScholarsRequest {
+    pub scholar_ids: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopFilesQuery {
+    pub metric: Option<String>, // plays | downloads | likes; defaults to plays
+    pub limit: Option<i64>,
+}
+
+pub const TOP_FILES_METRICS: &[&str] = &["plays", "downloads", "likes"];
+
+/// One entry of a scholar's cached top-files ranking
+#[derive(Serialize, Deserialize)]
+pub struct TopFileRank {
+    pub file_id: i32,
+    pub score: i64,
+    pub restricted: bool,
+}
+
+/// A file from a scholar's top-files strip with its score for the chosen metric
+#[derive(Serialize)]
+pub struct TopFile {
+    #[serde(flatten)]
+    pub file: crate::models::files::FilesWithStats,
+    pub score: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScholarFilterQuery {
+    pub states: Option<String>, // Comma-separated state ids, e.g. "1,2,3"
+    pub q: Option<String>,
+    pub sort: Option<String>,   // priority | name | newest | recent
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScholarCatalogQuery {
+    pub files_per_book: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CatalogFile {
+    pub file_id: i32,
+    pub file_name: String,
+    pub file_url: String,
+    pub file_size: String,
+    pub file_duration: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CatalogBook {
+    pub book_id: i32,
+    pub book_name: String,
+    pub book_image: Option<String>,
+    pub total_files: i64,
+    pub total_duration: Option<String>, // Sum of all active file durations in the book
+    pub has_more_files: bool, // True when files were truncated by files_per_book
+    pub files: Vec<CatalogFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScholarCatalog {
+    pub scholar_id: i32,
+    pub scholar_name: String,
+    pub about: Option<String>,
+    pub state: String,
+    pub image: Option<String>,
+    pub total_books: i64,
+    pub total_files: i64,
+    pub books: Vec<CatalogBook>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScholarReportQuery {
+    pub from: chrono::NaiveDate, // Inclusive, YYYY-MM-DD
+    pub to: chrono::NaiveDate,   // Inclusive, YYYY-MM-DD
+}
+
+/// One file's activity within a report date range
+#[derive(Debug)]
+pub struct ScholarReportRow {
+    pub file_id: i32,
+    pub file_name: String,
+    pub book_name: String,
+    pub plays: i64,
+    pub downloads: i64,
+}
@@ -23,3 +23,11 @@ pub struct SiteSettings {
 }
 
 
+
+#[derive(Debug, Serialize)]
+pub struct SiteSettingsResponse {
+    #[serde(flatten)]
+    pub settings: SiteSettings,
+    /// Keys of the public feature flags that are on
+    pub features: Vec<String>,
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareKind {
+    Scholar,
+    Book,
+    File,
+}
+
+impl ShareKind {
+    /// OpenGraph `og:type` for the card
+    pub fn og_type(&self) -> &'static str {
+        match self {
+            ShareKind::Scholar => "profile",
+            ShareKind::Book => "music.album",
+            ShareKind::File => "music.song",
+        }
+    }
+}
+
+/// OpenGraph-style preview of a shared link; the frontend renders it into meta tags
+#[derive(Debug, Serialize)]
+pub struct ShareCard {
+    pub kind: ShareKind,
+    pub id: i32,
+    pub title: String,
+    pub description: String,
+    pub image_url: String,
+    /// Only set for files
+    pub audio_url: Option<String>,
+    /// "MM:SS" or "HH:MM:SS"; a book's is the total of its files
+    pub duration: Option<String>,
+    pub duration_seconds: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShareCardQuery {
+    /// `html` returns a minimal page with the meta tags, for crawlers
+    pub format: Option<String>,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Platform-wide totals for the public landing page; holds no per-user data
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PublicStats {
+    pub total_scholars: i64,
+    pub total_books: i64,
+    pub total_files: i64,
+    pub total_duration_seconds: u64,
+    pub total_hours: u64,
+    /// Active files whose duration could not be parsed and were left out of the total
+    pub unparsed_durations: usize,
+}
@@ -1,6 +1,38 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use chrono::{NaiveDate, NaiveDateTime};
 use bigdecimal::BigDecimal;
+use std::str::FromStr;
+
+// Money is BigDecimal end to end and serializes as a string ("1500.00"), so
+// clients never see an amount that went through a float.
+
+/// Reads an amount sent as a decimal string or a whole JSON number. A JSON
+/// number with a fractional part has already been parsed into a float, so it is
+/// refused rather than stored with whatever rounding that introduced
+fn deserialize_money<'de, D>(deserializer: D) -> Result<BigDecimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawAmount {
+        Text(String),
+        Whole(i64),
+        Float(f64),
+    }
+
+    match RawAmount::deserialize(deserializer)? {
+        RawAmount::Text(text) => BigDecimal::from_str(text.trim())
+            .map_err(|_| D::Error::custom(format!("invalid amount '{}'", text))),
+        RawAmount::Whole(value) => Ok(BigDecimal::from(value)),
+        RawAmount::Float(value) if value.fract() == 0.0 && value.abs() < i64::MAX as f64 => {
+            Ok(BigDecimal::from(value as i64))
+        }
+        RawAmount::Float(_) => Err(D::Error::custom(
+            "amounts with a fractional part must be sent as strings, e.g. \"1500.50\"",
+        )),
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SubscriptionPlan {
@@ -18,6 +50,23 @@ pub struct SubscriptionPlan {
     pub updated_at: NaiveDateTime,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionPlansQuery {
+    /// Show prices converted to this currency, per `currencies.rates`
+    pub currency: Option<String>,
+}
+
+/// A plan with its price formatted for display. `price`/`currency` stay the
+/// plan's own; `display_*` are converted when a currency was requested
+#[derive(Debug, Serialize)]
+pub struct SubscriptionPlanWithDisplay {
+    #[serde(flatten)]
+    pub plan: SubscriptionPlan,
+    pub display_price: BigDecimal,
+    pub display_currency: String,
+    pub price_display: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserSubscription {
     pub id: i32,
@@ -60,8 +109,9 @@ pub struct CreateSubscriptionRequest {
     pub subscription_plan_id: i32,
     pub payment_method: String,
     pub transaction_reference: String,
+    #[serde(deserialize_with = "deserialize_money")]
     pub payment_amount: BigDecimal,
-    pub payment_currency: Option<String>,
+    pub payment_currency: Option<String>, // Defaults to the plan's currency
 }
 
 #[derive(Debug, Deserialize)]
@@ -70,6 +120,21 @@ pub struct VerifySubscriptionRequest {
     pub notes: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BatchVerifySubscriptionItem {
+    pub subscription_id: i32,
+    pub status: String, // active, cancelled
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchVerifySubscriptionResult {
+    pub subscription_id: i32,
+    pub success: bool,
+    pub status: Option<String>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SubscriptionPlanSummary {
     pub id: i32,
@@ -112,3 +177,70 @@ pub struct UserSubscriptionMinimal {
     pub duration_type: String,
     pub duration_months: i32,
 }
+
+/// How to pay for a pending subscription
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PaymentInstructions {
+    pub subscription_id: i32,
+    pub plan_name: String,
+    pub amount: BigDecimal,
+    pub currency: String,
+    pub transaction_reference: Option<String>,
+    pub channels: Vec<crate::core::config::PaymentChannel>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_amount(amount: serde_json::Value) -> Result<BigDecimal, serde_json::Error> {
+        let request: CreateSubscriptionRequest = serde_json::from_value(serde_json::json!({
+            "subscription_plan_id": 1,
+            "payment_method": "bank_transfer",
+            "transaction_reference": "REF-1",
+            "payment_amount": amount,
+        }))?;
+        Ok(request.payment_amount)
+    }
+
+    #[test]
+    fn deserialize_money_keeps_exact_amounts() {
+        assert_eq!(parse_amount("1500.50".into()).unwrap(), BigDecimal::from_str("1500.50").unwrap());
+        assert_eq!(parse_amount(" 0.10 ".into()).unwrap(), BigDecimal::from_str("0.10").unwrap());
+        assert_eq!(parse_amount(2500.into()).unwrap(), BigDecimal::from(2500));
+        assert_eq!(parse_amount(2500.0.into()).unwrap(), BigDecimal::from(2500));
+    }
+
+    #[test]
+    fn deserialize_money_rejects_fractional_floats_and_garbage() {
+        assert!(parse_amount(1500.5.into()).is_err());
+        assert!(parse_amount("fifteen".into()).is_err());
+        assert!(parse_amount(serde_json::Value::Null).is_err());
+    }
+
+    #[test]
+    fn plan_price_round_trips_as_an_exact_string() {
+        let now = chrono::Utc::now().naive_utc();
+        let plan = SubscriptionPlan {
+            id: 1,
+            name: "Monthly".to_string(),
+            description: None,
+            duration_type: "monthly".to_string(),
+            duration_months: 1,
+            price: BigDecimal::from_str("1500.00").unwrap(),
+            currency: "NGN".to_string(),
+            features: None,
+            is_active: true,
+            sort_order: 0,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["price"], "1500.00");
+
+        let parsed: SubscriptionPlan = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.price, plan.price);
+        assert_eq!(parsed.price.to_string(), "1500.00");
+    }
+}
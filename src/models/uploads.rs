@@ -10,6 +10,20 @@ pub struct FileUploadResponse {
     pub content_type: String,
 }
 
+/// A `tbl_files` row for an MP3 registered by the bulk import
+#[derive(Debug)]
+pub struct NewImportedFile<'a> {
+    pub book_id: i32,
+    pub scholar_id: i32,
+    pub name: &'a str,
+    pub location: &'a str,
+    pub file_size: i64,
+    pub duration: &'a str,
+    pub content_hash: &'a str,
+    pub uid: &'a str,
+    pub created_by: i32,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FileUploadRequest {
     pub book_id: i32,
@@ -28,6 +42,14 @@ pub struct FileDownloadInfo {
     pub scholar_id: i32,
 }
 
+/// Where a preview clip is cut from
+#[derive(Debug)]
+pub struct FilePreviewSource {
+    pub file_id: i32,
+    pub file_path: String,
+    pub duration: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FileMetadata {
     pub title: String,
@@ -35,4 +57,28 @@ pub struct FileMetadata {
     pub duration: Option<i32>, // in seconds for audio files
     pub file_size: i64,
     pub content_type: String,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadZipRequest {
+    pub file_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZipManifestFile {
+    pub file_id: i32,
+    pub entry_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZipOmittedFile {
+    pub file_id: i32,
+    pub reason: String, // not_found, unavailable, access_denied, size_limit
+}
+
+/// Written as `manifest.json` inside the archive
+#[derive(Debug, Serialize)]
+pub struct ZipManifest {
+    pub included: Vec<ZipManifestFile>,
+    pub omitted: Vec<ZipOmittedFile>,
+}
@@ -5,6 +5,8 @@ use chrono::{DateTime, NaiveDateTime, Utc};
 pub struct User {
     pub id: i32,
     pub name: String,
+    /// Unique `@mention` handle
+    pub handle: Option<String>,
     pub email: String,
     pub address: Option<String>,
     pub phone: Option<String>,
@@ -12,6 +14,8 @@ pub struct User {
     #[serde(skip_serializing)]
     pub password: String,
     pub status: i32,
+    /// IANA zone name; None means UTC
+    pub timezone: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -46,12 +50,17 @@ pub struct LoginResponse {
 pub struct UserProfile {
     pub id: i32,
     pub name: String,
+    pub handle: Option<String>,
     pub email: String,
     pub address: Option<String>,
     pub phone: Option<String>,
     pub role: String,
+    pub timezone: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// Only filled on `GET /auth/profile`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_books: Option<Vec<crate::models::books::CompletedBook>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -59,6 +68,8 @@ pub struct UpdateProfileRequest {
     pub name: Option<String>,
     pub address: Option<String>,
     pub phone: Option<String>,
+    /// IANA zone name such as `Africa/Lagos`
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,6 +97,25 @@ pub struct OtpData {
     pub created_at: i64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ChangeEmailRequest {
+    pub new_email: String,
+    pub current_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailChangeRequest {
+    pub otp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingEmailChange {
+    pub user_id: i32,
+    pub new_email: String,
+    pub otp: String,
+    pub created_at: i64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct MessageResponse {
     pub message: String,
@@ -96,12 +126,15 @@ impl From<User> for UserProfile {
         UserProfile {
             id: user.id,
             name: user.name,
+            handle: user.handle,
             email: user.email,
             address: user.address,
             phone: user.phone,
             role: user.role,
+            timezone: user.timezone,
             created_at: user.created_at,
             updated_at: user.updated_at,
+            completed_books: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::core::parse_duration;
+use crate::models::files::ViewFileDetails;
+
+#[derive(Debug, Serialize)]
+pub struct BookRef {
+    pub id: i32,
+    pub name: String,
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScholarRef {
+    pub id: i32,
+    pub name: String,
+    pub image_url: String,
+}
+
+/// v2 shape of a single file: the duration in seconds rather than "MM:SS",
+/// and the book and scholar as nested objects
+#[derive(Debug, Serialize)]
+pub struct FileDetails {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    /// None when the stored duration is malformed
+    pub duration_seconds: Option<u32>,
+    pub size: String,
+    pub created_at: DateTime<Utc>,
+    pub downloads: i32,
+    pub version: i32,
+    pub book: BookRef,
+    pub scholar: ScholarRef,
+}
+
+impl From<ViewFileDetails> for FileDetails {
+    fn from(file: ViewFileDetails) -> Self {
+        Self {
+            id: file.file_id,
+            name: file.file_name,
+            url: file.file_url,
+            duration_seconds: parse_duration(&file.duration).ok(),
+            size: file.size,
+            created_at: file.created_at,
+            downloads: file.downloads,
+            version: file.version,
+            book: BookRef {
+                id: file.book_id,
+                name: file.book_name,
+                image_url: file.book_image,
+            },
+            scholar: ScholarRef {
+                id: file.scholar_id,
+                name: file.scholar_name,
+                image_url: file.scholar_image,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view_file_details() -> ViewFileDetails {
+        ViewFileDetails {
+            file_id: 5,
+            file_name: "Lesson 5".to_string(),
+            file_url: "https://cdn.example.com/lesson-5.mp3".to_string(),
+            duration: "12:30".to_string(),
+            size: "6.1 MB".to_string(),
+            created_at: Utc::now(),
+            book_id: 2,
+            book_name: "Bulughul Maram".to_string(),
+            book_image: None,
+            scholar_id: 3,
+            scholar_name: "Sheikh Aminu".to_string(),
+            scholar_image: "https://cdn.example.com/aminu.jpg".to_string(),
+            downloads: 11,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn v1_and_v2_file_details_have_their_own_shapes() {
+        let v1 = serde_json::to_value(view_file_details()).unwrap();
+        assert_eq!(v1["file_id"], 5);
+        assert_eq!(v1["duration"], "12:30");
+        assert_eq!(v1["book_id"], 2);
+        assert!(v1.get("book").is_none());
+
+        let v2 = serde_json::to_value(FileDetails::from(view_file_details())).unwrap();
+        assert_eq!(v2["id"], 5);
+        assert_eq!(v2["duration_seconds"], 750);
+        assert_eq!(v2["book"]["id"], 2);
+        assert_eq!(v2["scholar"]["name"], "Sheikh Aminu");
+        assert!(v2.get("file_id").is_none() && v2.get("duration").is_none());
+    }
+}
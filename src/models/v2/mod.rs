@@ -0,0 +1,6 @@
+//! Response shapes for `/api/v2`.
+//!
+//! v2 handlers call the same `db` functions as v1 and convert the v1 models into
+//! these types with `From` impls, so the queries stay shared and only the JSON
+//! differs. v1 models are frozen; a breaking shape change gets a type here instead.
+pub mod files;
@@ -0,0 +1,37 @@
+use crate::core::jwt_auth::JwtClaims;
+use crate::core::{AppError, AppSuccessResponse};
+use crate::db::activity;
+use crate::models::pagination::{PaginationMeta, PaginationQuery};
+use actix_web::{get, web, HttpResponse, Result};
+use sqlx::MySqlPool;
+
+#[tracing::instrument(name = "Get My Activity", skip(pool, claims, pagination))]
+#[get("/my-activity")]
+pub async fn get_my_activity(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+
+    let (items, total_count) = activity::get_user_activity(
+        &pool,
+        user_id,
+        pagination.per_page,
+        pagination.offset(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: items,
+        message: "Activity retrieved successfully".to_string(),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
+    }))
+}
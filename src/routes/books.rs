@@ -1,9 +1,12 @@
 use crate::{
     core::{
-        jwt_auth::JwtMiddleware, slugify, AppConfig, AppError, AppErrorType, AppSuccessResponse,
+        error_codes, jwt_auth::JwtMiddleware, prepare_storage_location, slugify, validate_about,
+        validate_name, AppConfig, AppError, AppErrorType, AppSuccessResponse, StagedFile,
+        VersionConflictResponse,
     },
-    db::books,
+    db::{access, books},
     models::{
+        access::{CanManageResponse, ManageReason},
         books::{CreateBookRequest, UpdateBookRequest},
         pagination::{PaginationMeta, PaginationQuery},
     },
@@ -42,10 +45,13 @@ pub async fn get_books_by_scholar(
     .await
     .map_err(|e| {
         tracing::error!("Failed to fetch books: {:?}", e);
-        AppError {
-            message: Some("Failed to fetch books".to_string()),
-            cause: Some(e.to_string()),
-            error_type: AppErrorType::InternalServerError,
+        match e.error_type {
+            AppErrorType::NotFoundError => e,
+            _ => AppError {
+                message: Some("Failed to fetch books".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            },
         }
     })?;
 
@@ -69,7 +75,7 @@ pub async fn get_book_details(
     let book_id = book_id.into_inner();
     let user_id = crate::core::extract_user_id_from_request(&req, &config);
 
-    let book_details = books::get_book_details(pool.get_ref(), &config, book_id, user_id)
+    let book_details = books::get_book_details(pool.get_ref(), &config, book_id, user_id, false)
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch book details: {:?}", e);
@@ -102,6 +108,7 @@ pub async fn get_book_statistics(
     book_id: web::Path<i32>,
 ) -> Result<impl Responder, AppError> {
     let book_id = book_id.into_inner();
+    books::assert_book_published(pool.get_ref(), book_id).await?;
 
     let statistics = books::get_book_statistics(pool.get_ref(), book_id)
         .await
@@ -121,6 +128,87 @@ pub async fn get_book_statistics(
         pagination: None,
     }))
 }
+
+#[instrument(name = "Get Book Siblings", skip(pool, config))]
+#[get("/{book_id}/siblings")]
+pub async fn get_book_siblings(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    book_id: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let siblings = books::get_book_siblings(pool.get_ref(), &config, book_id.into_inner())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch book siblings: {:?}", e);
+            match e.error_type {
+                AppErrorType::NotFoundError => e,
+                _ => AppError {
+                    message: Some("Failed to fetch book siblings".to_string()),
+                    cause: Some(e.to_string()),
+                    error_type: AppErrorType::InternalServerError,
+                },
+            }
+        })?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Book siblings retrieved successfully".to_string(),
+        data: Some(siblings),
+        pagination: None,
+    }))
+}
+
+/// Whether the caller may edit this book or upload to it. A user without
+/// access gets `false` rather than 403 so clients can hide controls
+#[instrument(name = "Can Manage Book", skip(pool, auth))]
+#[get("/{book_id}/can-manage")]
+pub async fn can_manage_book(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    book_id: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let book_id = book_id.into_inner();
+    let scholar_id = books::assert_book_active(pool.get_ref(), book_id).await?;
+
+    // Same checks as update_book/upload_file, split so the reason can be reported
+    // Role from the database like the write paths use, since the token's may be stale
+    let user = crate::db::users::get_user_by_id(pool.get_ref(), auth.user_id).await?;
+    let reason = if user.role == "admin" {
+        Some(ManageReason::Admin)
+    } else if access::check_user_access_to_scholar(pool.get_ref(), auth.user_id, scholar_id).await? {
+        Some(ManageReason::ScholarAccess)
+    } else if access::check_user_access_to_book(pool.get_ref(), auth.user_id, book_id).await? {
+        Some(ManageReason::BookAccess)
+    } else {
+        None
+    };
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Book permissions retrieved successfully".to_string(),
+        data: Some(CanManageResponse::from(reason)),
+        pagination: None,
+    }))
+}
+
+#[instrument(name = "Get Book Progress", skip(pool, auth))]
+#[get("/{book_id}/progress")]
+pub async fn get_book_progress(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    book_id: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let progress = books::get_book_progress(pool.get_ref(), auth.user_id, book_id.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Book progress retrieved successfully".to_string(),
+        data: Some(progress),
+        pagination: None,
+    }))
+}
+
 #[instrument(name = "Get Books Dropdown", skip(pool))]
 #[get("/dropdown")]
 pub async fn get_books_dropdown(
@@ -173,6 +261,7 @@ pub async fn create_book(
     let mut about: Option<String> = None;
     let mut scholar_id_field: Option<i32> = None;
     let mut image_filename: Option<String> = None;
+    let mut staged_image: Option<StagedFile> = None;
 
     let images_dir = &config.app_paths.images_dir;
     fs::create_dir_all(images_dir).ok();
@@ -194,12 +283,19 @@ pub async fn create_book(
                     .and_then(|f| std::path::Path::new(f).extension().and_then(|e| e.to_str()))
                     .unwrap_or("jpg");
                 let generated = format!("book_{}.{}", Uuid::new_v4(), file_ext);
-                let filepath = format!("{}/{}", images_dir, generated);
+                let (generated, filepath) = prepare_storage_location(
+                    images_dir,
+                    &generated,
+                    config.app_paths.shard_by_date,
+                )
+                .map_err(|e| AppError::internal_error(format!("Failed to prepare image path: {}", e)))?;
                 tracing::info!("Creating book image file at: {}", filepath);
                 let mut f = fs::File::create(&filepath).map_err(|e| {
                     tracing::error!("Failed to create book image file: {:?}", e);
                     AppError::internal_error(format!("Failed to create image: {}", e))
                 })?;
+                // Removed again unless the book row referencing it commits
+                staged_image = Some(StagedFile::new(&filepath));
                 while let Some(chunk) = field
                     .try_next()
                     .await
@@ -217,6 +313,7 @@ pub async fn create_book(
                 })?;
                 drop(f); // Explicitly close the file
                 tracing::info!("Successfully wrote book image: {}", generated);
+                config.image_index.insert(&generated);
                 image_filename = Some(generated);
             } else if field_name == "name" {
                 let bytes = field
@@ -245,7 +342,8 @@ pub async fn create_book(
         }
     }
 
-    let book_name = name.ok_or_else(|| AppError::bad_request("name is required"))?;
+    let book_name = validate_name(&name.ok_or_else(|| AppError::bad_request("name is required"))?)?;
+    let about = validate_about(about)?;
     let book_scholar_id =
         scholar_id_field.ok_or_else(|| AppError::bad_request("scholar_id is required"))?;
     let slug_value = slugify(&book_name);
@@ -267,20 +365,6 @@ pub async fn create_book(
         }
     }
 
-    if let Some(existing_name) =
-        books::check_duplicate_book(pool.get_ref(), &book_name, book_scholar_id, &slug_value)
-            .await?
-    {
-        return Err(AppError {
-            message: Some(format!(
-                "A book with the name '{}' already exists for this scholar",
-                existing_name
-            )),
-            cause: None,
-            error_type: AppErrorType::ConflictError,
-        });
-    }
-
     let request = CreateBookRequest {
         name: book_name,
         about,
@@ -288,21 +372,35 @@ pub async fn create_book(
         image: image_filename,
     };
 
-    let book_id = books::create_book(pool.get_ref(), &request, &slug_value, auth.user_id)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to create book: {:?}", e);
-            AppError {
-                message: Some("Failed to create book".to_string()),
-                cause: Some(e.to_string()),
-                error_type: AppErrorType::InternalServerError,
-            }
-        })?;
+    let (book_id, slug) = books::create_book(
+        pool.get_ref(),
+        &request,
+        &slug_value,
+        auth.user_id,
+        &config.app_paths.default_book_image,
+    )
+    .await
+    .map_err(|e| {
+        if matches!(e.error_type, AppErrorType::ConflictError | AppErrorType::NotFoundError) {
+            return e;
+        }
+        tracing::error!("Failed to create book: {:?}", e);
+        AppError {
+            message: Some("Failed to create book".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    })?;
+
+    // The row is committed, so the image it points at stays
+    if let Some(image) = staged_image {
+        image.keep();
+    }
 
     Ok(HttpResponse::Created().json(AppSuccessResponse {
         success: true,
         message: "Book created successfully".to_string(),
-        data: Some(serde_json::json!({"id": book_id})),
+        data: Some(serde_json::json!({"id": book_id, "slug": slug})),
         pagination: None,
     }))
 }
@@ -319,20 +417,7 @@ pub async fn update_book(
     let book_id = book_id.into_inner();
 
     // Get current book to check scholar_id
-    let current_book = sqlx::query!(
-        "SELECT scholar_id FROM tbl_books WHERE id = ? AND status = 'active'",
-        book_id
-    )
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get book: {:?}", e);
-        AppError {
-            message: Some("Book not found".to_string()),
-            cause: Some(e.to_string()),
-            error_type: AppErrorType::NotFoundError,
-        }
-    })?;
+    let current_scholar_id = books::assert_book_active(pool.get_ref(), book_id).await?;
 
     // Check if user has access to update this book
     let user = crate::db::users::get_user_by_id(pool.get_ref(), auth.user_id)
@@ -352,7 +437,9 @@ pub async fn update_book(
     let mut name: Option<String> = None;
     let mut about: Option<String> = None;
     let mut scholar_id: Option<i32> = None;
+    let mut version: Option<i32> = None;
     let mut image_filename: Option<String> = None;
+    let mut staged_image: Option<StagedFile> = None;
 
     let images_dir = &config.app_paths.images_dir;
     fs::create_dir_all(images_dir).ok();
@@ -372,10 +459,16 @@ pub async fn update_book(
                     .and_then(|f| std::path::Path::new(f).extension().and_then(|e| e.to_str()))
                     .unwrap_or("jpg");
                 let generated = format!("book_{}.{}", Uuid::new_v4(), file_ext);
-                let filepath = format!("{}/{}", images_dir, generated);
+                let (generated, filepath) = prepare_storage_location(
+                    images_dir,
+                    &generated,
+                    config.app_paths.shard_by_date,
+                )
+                .map_err(|e| AppError::internal_error(format!("Failed to prepare image path: {}", e)))?;
                 let mut f = fs::File::create(&filepath).map_err(|e| {
                     AppError::internal_error(format!("Failed to create image: {}", e))
                 })?;
+                staged_image = Some(StagedFile::new(&filepath));
                 while let Some(chunk) = field
                     .try_next()
                     .await
@@ -385,6 +478,7 @@ pub async fn update_book(
                         AppError::internal_error(format!("Failed to write image: {}", e))
                     })?;
                 }
+                config.image_index.insert(&generated);
                 image_filename = Some(generated);
             } else if field_name == "name" {
                 let bytes = field
@@ -409,17 +503,35 @@ pub async fn update_book(
                 scholar_id = String::from_utf8(bytes.to_vec())
                     .ok()
                     .and_then(|s| s.parse::<i32>().ok());
+            } else if field_name == "version" {
+                let bytes = field
+                    .try_next()
+                    .await
+                    .map_err(|e| AppError::bad_request(format!("Invalid version: {}", e)))?
+                    .unwrap_or_default();
+                version = Some(
+                    String::from_utf8(bytes.to_vec())
+                        .ok()
+                        .and_then(|s| s.trim().parse::<i32>().ok())
+                        .ok_or_else(|| AppError::bad_request("version must be an integer"))?,
+                );
             }
         }
     }
 
+    let name = name.as_deref().map(validate_name).transpose()?;
+    // Sent but blank clears the about text
+    let about = about
+        .map(|about| validate_about(Some(about)).map(Option::unwrap_or_default))
+        .transpose()?;
+
     // Run permission checks now that potential new scholar_id is known
     if user.role != "admin" {
-        // Must have access to current scholar
-        let has_access = crate::db::access::check_user_access_to_scholar(
+        // Must have access to the book, through its scholar or a book grant
+        let has_access = crate::db::access::check_user_access_to_book(
             pool.get_ref(),
             auth.user_id,
-            current_book.scholar_id,
+            book_id,
         )
         .await
         .map_err(|e| AppError::internal_error(format!("Failed to verify permissions: {}", e)))?;
@@ -431,7 +543,7 @@ pub async fn update_book(
 
         // If moving to a different scholar, must have access there too
         if let Some(new_scholar_id) = scholar_id {
-            if new_scholar_id != current_book.scholar_id {
+            if new_scholar_id != current_scholar_id {
                 let has_new_access = crate::db::access::check_user_access_to_scholar(
                     pool.get_ref(),
                     auth.user_id,
@@ -455,22 +567,41 @@ pub async fn update_book(
         about,
         scholar_id,
         image: image_filename,
+        version,
     };
 
-    books::update_book(pool.get_ref(), book_id, &request)
-        .await
-        .map_err(|e| {
+    match books::update_book(pool.get_ref(), book_id, &request).await {
+        Ok(()) => {}
+        Err(e) if matches!(e.error_type, AppErrorType::ConflictError) => {
+            let current =
+                books::get_book_details(pool.get_ref(), &config, book_id, Some(auth.user_id), true)
+                    .await?;
+            return Ok(HttpResponse::Conflict().json(VersionConflictResponse {
+                success: false,
+                code: error_codes::VERSION_CONFLICT.to_string(),
+                message: "Book was modified by someone else; reload and retry".to_string(),
+                current,
+            }));
+        }
+        Err(e) if matches!(e.error_type, AppErrorType::NotFoundError) => return Err(e),
+        Err(e) => {
             tracing::error!("Failed to update book: {:?}", e);
-            AppError {
+            return Err(AppError {
                 message: Some("Failed to update book".to_string()),
                 cause: Some(e.to_string()),
                 error_type: AppErrorType::InternalServerError,
-            }
-        })?;
+            });
+        }
+    }
+
+    // The row now points at the new image, so it stays
+    if let Some(image) = staged_image {
+        image.keep();
+    }
 
     // Fetch the updated book details
     let updated_book =
-        books::get_book_details(pool.get_ref(), &config, book_id, Some(auth.user_id))
+        books::get_book_details(pool.get_ref(), &config, book_id, Some(auth.user_id), true)
             .await
             .map_err(|e| {
                 tracing::error!("Failed to fetch updated book: {:?}", e);
@@ -498,21 +629,8 @@ pub async fn delete_book(
 ) -> Result<impl Responder, AppError> {
     let book_id = book_id.into_inner();
 
-    // Get current book to check scholar_id
-    let current_book = sqlx::query!(
-        "SELECT scholar_id FROM tbl_books WHERE id = ? AND status = 'active'",
-        book_id
-    )
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get book: {:?}", e);
-        AppError {
-            message: Some("Book not found".to_string()),
-            cause: Some(e.to_string()),
-            error_type: AppErrorType::NotFoundError,
-        }
-    })?;
+    // Make sure the book exists
+    books::assert_book_active(pool.get_ref(), book_id).await?;
 
     // Check if user has access to delete this book
     let user = crate::db::users::get_user_by_id(pool.get_ref(), auth.user_id)
@@ -528,10 +646,10 @@ pub async fn delete_book(
 
     // Permission check
     if user.role != "admin" {
-        let has_access = crate::db::access::check_user_access_to_scholar(
+        let has_access = crate::db::access::check_user_access_to_book(
             pool.get_ref(),
             auth.user_id,
-            current_book.scholar_id,
+            book_id,
         )
         .await
         .map_err(|e| AppError::internal_error(format!("Failed to verify permissions: {}", e)))?;
@@ -0,0 +1,179 @@
+use crate::core::email_service::EmailType;
+use crate::core::jwt_auth::JwtClaims;
+use crate::core::config::FailedEmailConfig;
+use crate::core::{AppConfig, AppError, AppSuccessResponse, EmailService};
+use crate::db::{failed_emails, users};
+use crate::models::failed_emails::FailedEmailRecord;
+use crate::models::pagination::{PaginationMeta, PaginationQuery};
+use crate::models::users::MessageResponse;
+use actix_web::{get, post, web, HttpResponse, Result};
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::MySqlPool;
+
+#[tracing::instrument(name = "Get Failed Emails", skip(pool, claims, pagination))]
+#[get("/emails/failed")]
+pub async fn get_failed_emails(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    let user = users::get_user_by_id(pool.get_ref(), user_id).await?;
+    if user.role != "admin" {
+        return Err(AppError::forbidden_error("Access denied. Admin role required."));
+    }
+
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+
+    let (emails, total_count) =
+        failed_emails::list_failed_emails(&pool, pagination.per_page, pagination.offset()).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: emails,
+        message: "Failed emails retrieved successfully".to_string(),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
+    }))
+}
+
+/// The email a stored failure can still be re-sent as. One-time codes are
+/// redacted when stored, so those emails are refused along with anything past
+/// the configured age
+fn resendable_email(
+    failed: &FailedEmailRecord,
+    config: &FailedEmailConfig,
+    now: NaiveDateTime,
+) -> Result<EmailType, AppError> {
+    if failed.retried_at.is_some() {
+        return Err(AppError::conflict_error("This email was already re-sent"));
+    }
+
+    let email_type: EmailType = serde_json::from_str(&failed.payload).map_err(|e| {
+        AppError::internal_error(format!("Stored email {} is unreadable: {}", failed.id, e))
+    })?;
+
+    if email_type.has_one_time_code() {
+        return Err(AppError::bad_request(format!(
+            "{} emails carry a one-time code and can't be re-sent; the user should request a new code",
+            email_type.kind()
+        )));
+    }
+
+    let max_age_minutes = config.retry_max_age_minutes;
+    if now - failed.created_at > Duration::minutes(max_age_minutes) {
+        return Err(AppError::bad_request(format!(
+            "This {} email is older than {} minutes and can no longer be re-sent",
+            email_type.kind(),
+            max_age_minutes
+        )));
+    }
+
+    Ok(email_type)
+}
+
+/// Re-send one stored failure now. The row is claimed before sending so two
+/// admins retrying at once can't both send it
+#[tracing::instrument(name = "Retry Failed Email", skip(pool, config, email_service, claims))]
+#[post("/emails/failed/{id}/retry")]
+pub async fn retry_failed_email(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    email_service: web::Data<EmailService>,
+    claims: JwtClaims,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    let user = users::get_user_by_id(pool.get_ref(), user_id).await?;
+    if user.role != "admin" {
+        return Err(AppError::forbidden_error("Access denied. Admin role required."));
+    }
+
+    let failed = failed_emails::get_failed_email(&pool, path.into_inner()).await?;
+    let email_type = resendable_email(&failed, &config.failed_emails, Utc::now().naive_utc())?;
+
+    if !failed_emails::claim_failed_email(&pool, failed.id).await? {
+        return Err(AppError::conflict_error("This email was already re-sent"));
+    }
+
+    if let Err(e) = email_service.send_now(email_type).await {
+        let error = e.cause.clone().unwrap_or_else(|| e.to_string());
+        failed_emails::release_failed_email_claim(&pool, failed.id, &error).await?;
+        return Err(AppError::service_unavailable(format!("Re-sending failed: {}", error)));
+    }
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: MessageResponse {
+            message: "Email re-sent".to_string(),
+        },
+        message: "Email re-sent".to_string(),
+        pagination: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(email_type: &EmailType, created_at: NaiveDateTime) -> FailedEmailRecord {
+        FailedEmailRecord {
+            id: 1,
+            payload: serde_json::to_string(&email_type.redacted()).unwrap(),
+            created_at,
+            retried_at: None,
+        }
+    }
+
+    fn welcome() -> EmailType {
+        EmailType::Welcome {
+            to_email: "user@example.com".to_string(),
+            name: "User".to_string(),
+        }
+    }
+
+    #[test]
+    fn recent_emails_can_be_resent_until_they_age_out() {
+        let config = FailedEmailConfig::default();
+        let now = Utc::now().naive_utc();
+
+        let recent = stored(&welcome(), now - Duration::hours(1));
+        assert!(resendable_email(&recent, &config, now).is_ok());
+
+        let old = stored(
+            &welcome(),
+            now - Duration::minutes(config.retry_max_age_minutes + 1),
+        );
+        assert!(resendable_email(&old, &config, now).is_err());
+    }
+
+    #[test]
+    fn emails_with_a_one_time_code_are_never_resent() {
+        let config = FailedEmailConfig::default();
+        let now = Utc::now().naive_utc();
+        let otp = EmailType::Otp {
+            to_email: "user@example.com".to_string(),
+            otp: "123456".to_string(),
+        };
+
+        let failed = stored(&otp, now);
+        assert!(!failed.payload.contains("123456"));
+        assert!(resendable_email(&failed, &config, now).is_err());
+    }
+
+    #[test]
+    fn already_resent_emails_are_a_conflict() {
+        let now = Utc::now().naive_utc();
+        let mut failed = stored(&welcome(), now);
+        failed.retried_at = Some(now);
+
+        let err = resendable_email(&failed, &FailedEmailConfig::default(), now).unwrap_err();
+        assert_eq!(err.error_type, crate::core::AppErrorType::ConflictError);
+    }
+}
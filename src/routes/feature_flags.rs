@@ -0,0 +1,75 @@
+use crate::core::jwt_auth::JwtClaims;
+use crate::core::{AppError, AppSuccessResponse, RedisHelper};
+use crate::db::{feature_flags, users};
+use crate::models::feature_flags::{FeatureFlag, UpdateFeatureFlagRequest};
+use actix_web::{get, put, web, HttpResponse, Result};
+use sqlx::MySqlPool;
+
+#[tracing::instrument(name = "Get Feature Flags", skip(pool, redis_service, claims))]
+#[get("/flags")]
+pub async fn get_feature_flags(
+    pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    let admin_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    users::require_admin(&pool, admin_id).await?;
+
+    let flags = feature_flags::load_feature_flags(&pool, &redis_service).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: flags,
+        message: "Feature flags retrieved successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Set Feature Flag", skip(pool, redis_service, claims))]
+#[put("/flags/{key}")]
+pub async fn set_feature_flag(
+    pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
+    claims: JwtClaims,
+    key: web::Path<String>,
+    request: web::Json<UpdateFeatureFlagRequest>,
+) -> Result<HttpResponse, AppError> {
+    let admin_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    users::require_admin(&pool, admin_id).await?;
+
+    let flag = FeatureFlag::from_key(&key)
+        .ok_or_else(|| AppError::not_found(format!("Unknown feature flag '{}'", key)))?;
+
+    feature_flags::set_flag(&pool, flag.key(), request.enabled, admin_id).await?;
+    feature_flags::invalidate_feature_flags(&redis_service).await;
+
+    tracing::warn!(
+        "Admin {} set feature flag {} to {}",
+        admin_id,
+        flag.key(),
+        request.enabled
+    );
+
+    let flags = feature_flags::load_feature_flags(&pool, &redis_service).await?;
+    let state = flags
+        .into_iter()
+        .find(|state| state.key == flag.key())
+        .ok_or_else(|| AppError::internal_error("Feature flag missing after update"))?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: state,
+        message: format!(
+            "Feature flag {} {}",
+            flag.key(),
+            if request.enabled { "enabled" } else { "disabled" }
+        ),
+        pagination: None,
+    }))
+}
@@ -1,16 +1,37 @@
 use crate::core::jwt_auth::JwtClaims;
-use crate::core::AppError;
+use crate::core::AppConfig;
+use crate::core::{AppError, AppErrorType};
 use crate::core::AppSuccessResponse;
-use crate::db::file_interactions;
+use crate::core::RedisHelper;
+use crate::core::{extract_mentions, filter_comment, normalize_comment};
+use crate::db::{file_interactions, files, notifications, users};
+use crate::db::feature_flags::is_feature_enabled;
 use crate::models::file_interactions::{
-    CreateReportRequest, ResolveReportRequest, LikeFileRequest,
-    CreateCommentRequest, UpdateCommentRequest
+    CreateReportRequest, PendingReportsQuery, ResolveReportRequest, LikeFileRequest,
+    CreateCommentRequest, UpdateCommentRequest, FileComment, LikeFileResponse
 };
-use crate::models::pagination::PaginationQuery;
+use crate::models::feature_flags::FeatureFlag;
+use crate::models::notifications::NotificationKind;
+use crate::models::pagination::{PaginationMeta, PaginationQuery};
 use actix_web::{delete, get, post, put, web, HttpResponse, Result};
 use sqlx::MySqlPool;
+use std::time::Duration as StdDuration;
+
+/// Id of the signed-in user once the database confirms they are still an admin
+/// or manager; the role in the token may predate a demotion
+async fn require_staff(pool: &MySqlPool, claims: &JwtClaims) -> Result<i32, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    users::require_staff(pool, user_id).await?;
+    Ok(user_id)
+}
 
 // File Reports
+const MAX_REPORTS_PER_WINDOW: i64 = 10;
+const REPORT_WINDOW_MINUTES: i64 = 60;
+
 #[tracing::instrument(name = "Report File", skip(pool, claims, request))]
 #[post("/reports")]
 pub async fn report_file(
@@ -23,7 +44,39 @@ pub async fn report_file(
         .parse()
         .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
 
-    let report = file_interactions::create_file_report(&pool, user_id, &request).await?;
+    // A file can be reported again by the same user once their earlier report is resolved
+    if let Some(existing) =
+        file_interactions::get_pending_report_by_user(&pool, user_id, request.file_id).await?
+    {
+        return Ok(HttpResponse::Ok().json(AppSuccessResponse {
+            success: true,
+            data: existing,
+            message: "You have already reported this file. It is awaiting review".to_string(),
+            pagination: None,
+        }));
+    }
+
+    let recent_reports =
+        file_interactions::count_recent_reports_by_user(&pool, user_id, REPORT_WINDOW_MINUTES)
+            .await?;
+    if recent_reports >= MAX_REPORTS_PER_WINDOW {
+        let retry_after =
+            file_interactions::report_window_retry_after(&pool, user_id, REPORT_WINDOW_MINUTES)
+                .await?;
+        return Err(AppError::too_many_requests(StdDuration::from_secs(retry_after))
+            .with_message("You have submitted too many reports. Please try again later"));
+    }
+
+    // A concurrent submission may have created the pending report since the check above
+    let (report, created) = file_interactions::create_file_report(&pool, user_id, &request).await?;
+    if !created {
+        return Ok(HttpResponse::Ok().json(AppSuccessResponse {
+            success: true,
+            data: report,
+            message: "You have already reported this file. It is awaiting review".to_string(),
+            pagination: None,
+        }));
+    }
 
     Ok(HttpResponse::Created().json(AppSuccessResponse {
         success: true,
@@ -38,11 +91,15 @@ pub async fn report_file(
 pub async fn get_pending_reports(
     pool: web::Data<MySqlPool>,
     claims: JwtClaims,
+    query: web::Query<PendingReportsQuery>,
     pagination: web::Query<PaginationQuery>,
 ) -> Result<HttpResponse, AppError> {
-    // Check if user is admin
-    if claims.role != "admin" && claims.role != "manager" {
-        return Err(AppError::forbidden_error("Access denied"));
+    require_staff(&pool, &claims).await?;
+
+    if !matches!(query.sort.as_deref(), None | Some("oldest") | Some("newest")) {
+        return Err(AppError::bad_request(
+            "Invalid sort, expected one of: oldest, newest",
+        ));
     }
 
     let mut pagination = pagination.into_inner();
@@ -50,13 +107,14 @@ pub async fn get_pending_reports(
     let limit = pagination.per_page as i32;
     let offset = pagination.offset() as i32;
 
-    let reports = file_interactions::get_pending_reports(&pool, Some(limit), Some(offset)).await?;
+    let (reports, total_count) =
+        file_interactions::get_pending_reports(&pool, &query, Some(limit), Some(offset)).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         data: reports,
         message: "Pending reports retrieved successfully".to_string(),
-        pagination: None,
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
     }))
 }
 
@@ -68,15 +126,7 @@ pub async fn resolve_report(
     path: web::Path<i32>,
     request: web::Json<ResolveReportRequest>,
 ) -> Result<HttpResponse, AppError> {
-    // Check if user is admin
-    if claims.role != "admin" && claims.role != "manager" {
-        return Err(AppError::forbidden_error("Access denied"));
-    }
-
-    let admin_user_id: i32 = claims
-        .sub
-        .parse()
-        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    let admin_user_id = require_staff(&pool, &claims).await?;
 
     let report_id = path.into_inner();
     let report = file_interactions::resolve_file_report(&pool, report_id, admin_user_id, &request).await?;
@@ -104,12 +154,28 @@ pub async fn like_file(
 
     let like = file_interactions::like_file(&pool, user_id, &request).await?;
 
-    Ok(HttpResponse::Created().json(AppSuccessResponse {
+    Ok(like_response(like))
+}
+
+// A repeat like answers 200 with the original like, a new one 201
+fn like_response(like: LikeFileResponse) -> HttpResponse {
+    let mut response = if like.is_new {
+        HttpResponse::Created()
+    } else {
+        HttpResponse::Ok()
+    };
+    let message = if like.is_new {
+        "File liked successfully"
+    } else {
+        "File already liked"
+    };
+
+    response.json(AppSuccessResponse {
         success: true,
         data: like,
-        message: "File liked successfully".to_string(),
+        message: message.to_string(),
         pagination: None,
-    }))
+    })
 }
 
 #[tracing::instrument(name = "Unlike File", skip(pool, claims))]
@@ -184,10 +250,12 @@ pub async fn check_file_like_status(
 }
 
 // File Comments
-#[tracing::instrument(name = "Create Comment", skip(pool, claims, request))]
+#[tracing::instrument(name = "Create Comment", skip(pool, config, redis_service, claims, request))]
 #[post("/comments")]
 pub async fn create_comment(
     pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
     claims: JwtClaims,
     request: web::Json<CreateCommentRequest>,
 ) -> Result<HttpResponse, AppError> {
@@ -196,16 +264,120 @@ pub async fn create_comment(
         .parse()
         .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
 
-    let comment = file_interactions::create_file_comment(&pool, user_id, &request).await?;
+    let mut request = request.into_inner();
+    let normalized = normalize_comment(&config.comments, &request.comment)?;
+    let filtered = filter_comment(&config.comment_filter, &normalized)?;
+    request.comment = filtered.text;
+
+    if !files::file_exists(&pool, request.file_id).await? {
+        return Err(AppError::not_found("File not found"));
+    }
+    if let Some(parent_id) = request.parent_id {
+        let parent_file_id = file_interactions::get_comment_file_id(&pool, parent_id).await?;
+        check_comment_parent(request.file_id, parent_id, parent_file_id)?;
+    }
+
+    let limit_key = format!("rate:comments:{}", user_id);
+    redis_service
+        .check_rate_limit(
+            &limit_key,
+            config.comments.rate_limit,
+            StdDuration::from_secs(config.comments.rate_window_seconds),
+        )
+        .await?
+        .check("You are commenting too quickly. Please try again later")?;
+
+    let held = filtered.held
+        || is_feature_enabled(&pool, &redis_service, FeatureFlag::ModerationMode).await;
+
+    let mentioned = users::resolve_mentioned_users(&pool, &extract_mentions(&request.comment)).await?;
+    let comment =
+        file_interactions::create_file_comment(&pool, user_id, &request, &mentioned, held).await?;
+    // Held comments notify once a moderator approves them
+    if !held {
+        notify_comment_recipients(&pool, &comment, true, &[]).await;
+    }
 
     Ok(HttpResponse::Created().json(AppSuccessResponse {
         success: true,
         data: comment,
-        message: "Comment created successfully".to_string(),
+        message: comment_saved_message(held, "Comment created successfully"),
         pagination: None,
     }))
 }
 
+/// A reply's parent must exist and sit on the same file; `parent_file_id` is
+/// the parent's file, None when there is no such comment
+fn check_comment_parent(file_id: i32, parent_id: i32, parent_file_id: Option<i32>) -> Result<(), AppError> {
+    if parent_file_id == Some(file_id) {
+        return Ok(());
+    }
+    Err(AppError {
+        message: Some("Parent comment does not belong to this file".to_string()),
+        cause: Some(format!("parent_id {}", parent_id)),
+        error_type: AppErrorType::PayloadValidationError,
+    })
+}
+
+/// Notify the parent comment's author of a reply (when `is_new`) and any user
+/// mentioned for the first time. Failures are logged; the comment is already saved
+async fn notify_comment_recipients(
+    pool: &MySqlPool,
+    comment: &FileComment,
+    is_new: bool,
+    previously_mentioned: &[i32],
+) {
+    let mut notified = Vec::new();
+
+    if let (true, Some(parent_id)) = (is_new, comment.parent_id) {
+        match file_interactions::get_file_comment_by_id(pool, parent_id).await {
+            Ok(parent) => {
+                let sent = notifications::create_notification(
+                    pool,
+                    parent.user_id,
+                    comment.user_id,
+                    NotificationKind::CommentReply,
+                    Some(comment.file_id),
+                    Some(comment.id),
+                )
+                .await;
+                match sent {
+                    Ok(_) => notified.push(parent.user_id),
+                    Err(e) => tracing::warn!("Failed to notify reply on comment {}: {:?}", comment.id, e),
+                }
+            }
+            Err(e) => tracing::warn!("Failed to load parent comment {}: {:?}", parent_id, e),
+        }
+    }
+
+    for &user_id in &comment.mentioned_user_ids {
+        // The parent author already heard about this comment as a reply
+        if previously_mentioned.contains(&user_id) || notified.contains(&user_id) {
+            continue;
+        }
+        if let Err(e) = notifications::create_notification(
+            pool,
+            user_id,
+            comment.user_id,
+            NotificationKind::Mention,
+            Some(comment.file_id),
+            Some(comment.id),
+        )
+        .await
+        {
+            tracing::warn!("Failed to notify mention in comment {}: {:?}", comment.id, e);
+        }
+    }
+}
+
+fn comment_saved_message(held: bool, saved: &str) -> String {
+    if held {
+        "Your comment has been submitted and will appear after review".to_string()
+    } else {
+        saved.to_string()
+    }
+}
+
 #[tracing::instrument(name = "Get File Comments", skip(pool))]
 #[get("/{file_id}/comments")]
 pub async fn get_file_comments(
@@ -223,10 +395,12 @@ pub async fn get_file_comments(
     }))
 }
 
-#[tracing::instrument(name = "Update Comment", skip(pool, claims, request))]
+#[tracing::instrument(name = "Update Comment", skip(pool, config, redis_service, claims, request))]
 #[put("/comments/{comment_id}")]
 pub async fn update_comment(
     pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
     claims: JwtClaims,
     path: web::Path<i32>,
     request: web::Json<UpdateCommentRequest>,
@@ -236,13 +410,37 @@ pub async fn update_comment(
         .parse()
         .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
 
+    let mut request = request.into_inner();
+    let normalized = normalize_comment(&config.comments, &request.comment)?;
+    let filtered = filter_comment(&config.comment_filter, &normalized)?;
+    request.comment = filtered.text;
+    let held = filtered.held
+        || is_feature_enabled(&pool, &redis_service, FeatureFlag::ModerationMode).await;
+
     let comment_id = path.into_inner();
-    let comment = file_interactions::update_file_comment(&pool, comment_id, user_id, &request).await?;
+    let previous = file_interactions::get_file_comment_by_id(&pool, comment_id).await?;
+    // Checked before anything is written, mentions included
+    if previous.user_id != user_id {
+        return Err(AppError::forbidden_error("You can only edit your own comments"));
+    }
+    let mentioned = users::resolve_mentioned_users(&pool, &extract_mentions(&request.comment)).await?;
+    let comment = file_interactions::update_file_comment(
+        &pool,
+        comment_id,
+        user_id,
+        &request,
+        &mentioned,
+        held,
+    )
+    .await?;
+    if comment.is_approved {
+        notify_comment_recipients(&pool, &comment, false, &previous.mentioned_user_ids).await;
+    }
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         data: comment,
-        message: "Comment updated successfully".to_string(),
+        message: comment_saved_message(held, "Comment updated successfully"),
         pagination: None,
     }))
 }
@@ -270,6 +468,71 @@ pub async fn delete_comment(
     }))
 }
 
+#[tracing::instrument(name = "Get Pending Comments", skip(pool, claims, pagination))]
+#[get("/admin/comments/pending")]
+pub async fn get_pending_comments(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<HttpResponse, AppError> {
+    require_staff(&pool, &claims).await?;
+
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+    let limit = pagination.per_page as i32;
+    let offset = pagination.offset() as i32;
+
+    let (comments, total_count) =
+        file_interactions::get_pending_comments(&pool, limit, offset).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: comments,
+        message: "Pending comments retrieved successfully".to_string(),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
+    }))
+}
+
+#[tracing::instrument(name = "Approve Comment", skip(pool, claims))]
+#[put("/admin/comments/{comment_id}/approve")]
+pub async fn approve_comment(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    require_staff(&pool, &claims).await?;
+
+    let comment = file_interactions::approve_comment(&pool, path.into_inner()).await?;
+    // Held comments skipped their notifications; they go out now that the comment is visible
+    notify_comment_recipients(&pool, &comment, true, &[]).await;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: comment,
+        message: "Comment approved".to_string(),
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Reject Comment", skip(pool, claims))]
+#[put("/admin/comments/{comment_id}/reject")]
+pub async fn reject_comment(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    require_staff(&pool, &claims).await?;
+
+    file_interactions::reject_comment(&pool, path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: serde_json::json!({"message": "Comment rejected"}),
+        message: "Comment rejected".to_string(),
+        pagination: None,
+    }))
+}
+
 // Download Stats
 #[tracing::instrument(name = "Get File Download Stats", skip(pool))]
 #[get("/{file_id}/download-stats")]
@@ -305,12 +568,155 @@ pub async fn get_my_download_history(
     let limit = pagination.per_page as i32;
     let offset = pagination.offset() as i32;
 
-    let downloads = file_interactions::get_user_download_history(&pool, user_id, Some(limit), Some(offset)).await?;
+    let (downloads, total_count) =
+        file_interactions::get_user_download_history(&pool, user_id, Some(limit), Some(offset)).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         data: downloads,
         message: "Download history retrieved successfully".to_string(),
-        pagination: None,
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
+    }))
+}
+
+#[tracing::instrument(name = "Get User Download History Details", skip(pool, config, claims, pagination))]
+#[get("/my-downloads/details")]
+pub async fn get_my_download_history_details(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    claims: JwtClaims,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+
+    let (downloads, total_count) = file_interactions::get_user_download_history_details(
+        &pool,
+        &config,
+        user_id,
+        pagination.per_page,
+        pagination.offset(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: downloads,
+        message: "Download history retrieved successfully".to_string(),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
+    }))
+}
+
+#[tracing::instrument(name = "Get User Liked Files", skip(pool, config, claims, pagination))]
+#[get("/my-likes")]
+pub async fn get_my_liked_files(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    claims: JwtClaims,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+
+    let (liked_files, total_count) =
+        files::fetch_liked_files_with_stats(&pool, &config, user_id, &pagination).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: liked_files,
+        message: "Liked files retrieved successfully".to_string(),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
+    }))
+}
+
+#[tracing::instrument(name = "Get My Comments", skip(pool, claims, pagination))]
+#[get("/my-comments")]
+pub async fn get_my_comments(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+
+    let (comments, total_count) = file_interactions::get_user_comments(
+        &pool,
+        user_id,
+        pagination.per_page,
+        pagination.offset(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: comments,
+        message: "Comments retrieved successfully".to_string(),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
     }))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_interactions::FileLike;
+
+    #[test]
+    fn a_parent_from_another_file_is_rejected() {
+        assert!(check_comment_parent(10, 5, Some(10)).is_ok());
+
+        let error = check_comment_parent(10, 5, Some(11)).unwrap_err();
+        assert_eq!(error.error_type, AppErrorType::PayloadValidationError);
+        // A missing parent is rejected the same way
+        assert!(check_comment_parent(10, 5, None).is_err());
+    }
+
+    async fn like_body(response: HttpResponse) -> serde_json::Value {
+        let bytes = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn liking_again_returns_the_original_like() {
+        let liked_at = chrono::NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        let like = |is_new| LikeFileResponse {
+            like: FileLike {
+                id: 8,
+                user_id: 2,
+                file_id: 10,
+                created_at: liked_at,
+            },
+            is_new,
+        };
+
+        let first = like_response(like(true));
+        assert_eq!(first.status(), actix_web::http::StatusCode::CREATED);
+        let first = like_body(first).await;
+
+        // `like_file` reads back the stored row, which a repeat insert leaves alone
+        let second = like_response(like(false));
+        assert_eq!(second.status(), actix_web::http::StatusCode::OK);
+        let second = like_body(second).await;
+
+        assert_eq!(second["data"]["created_at"], first["data"]["created_at"]);
+        assert_eq!(second["data"]["is_new"], false);
+        assert_eq!(second["message"], "File already liked");
+    }
+}
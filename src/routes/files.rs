@@ -1,19 +1,24 @@
 use actix_web::{
-    get, put,
+    get, post, put,
     web::{self},
     HttpRequest, HttpResponse, Responder,
 };
+use chrono::{NaiveDate, Timelike, Utc};
 use sqlx::MySqlPool;
+use std::time::Duration;
 use tracing::instrument;
 
 use crate::{
     core::{
-        extract_user_id_from_request, jwt_auth::JwtMiddleware, AppConfig, AppError, AppErrorType,
-        AppSuccessResponse,
+        error_codes, extract_user_id_from_request, jwt_auth::JwtMiddleware, AppConfig, AppError,
+        AppErrorType, AppSuccessResponse, RedisHelper, VersionConflictResponse,
     },
-    db::files,
-    models::files::UpdateFileRequest,
-    models::pagination::{PaginationMeta, PaginationQuery},
+    db::{access, featured_files, file_analytics, files, subscriptions, users},
+    models::file_analytics::FileAnalyticsQuery,
+    models::files::{
+        BookFilesQuery, FeatureFileRequest, RecentFilesQuery, SetFileRestrictionRequest, UpdateFileRequest,
+    },
+    models::pagination::{CursorPage, DateIdCursor, PaginationMeta, PaginationQuery},
 };
 
 #[instrument(name = "Get Files by Book", skip(pool, config))]
@@ -23,6 +28,7 @@ pub async fn get_files_by_book(
     config: web::Data<AppConfig>,
     book_id: web::Path<i32>,
     pagination: web::Query<PaginationQuery>,
+    options: web::Query<BookFilesQuery>,
     req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
     let mut pagination = pagination.into_inner();
@@ -30,20 +36,33 @@ pub async fn get_files_by_book(
 
     let user_id = extract_user_id_from_request(&req, &config);
 
-    let (data, total_items) = files::fetch_files_by_book_with_stats(
+    let files = files::fetch_files_by_book_with_stats(
         pool.get_ref(),
         &config,
         book_id.into_inner(),
         &pagination,
+        &options,
         user_id,
     )
-    .await
-    .map_err(|e| {
+    .await;
+
+    book_files_response(files, &pagination)
+}
+
+// A book with no files is an empty page; only a missing book is a 404
+fn book_files_response<T: serde::Serialize>(
+    files: Result<(Vec<T>, i64), AppError>,
+    pagination: &PaginationQuery,
+) -> Result<HttpResponse, AppError> {
+    let (data, total_items) = files.map_err(|e| {
         tracing::error!("Failed to fetch files by book: {:?}", e);
-        AppError {
-            message: Some("Failed to fetch files".to_string()),
-            cause: Some(e.to_string()),
-            error_type: AppErrorType::InternalServerError,
+        match e.error_type {
+            AppErrorType::NotFoundError | AppErrorType::AuthError => e,
+            _ => AppError {
+                message: Some("Failed to fetch files".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            },
         }
     })?;
 
@@ -63,6 +82,7 @@ pub async fn get_recent_files(
     pool: web::Data<MySqlPool>,
     config: web::Data<AppConfig>,
     pagination: web::Query<PaginationQuery>,
+    query: web::Query<RecentFilesQuery>,
     req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
     let mut pagination = pagination.into_inner();
@@ -70,6 +90,39 @@ pub async fn get_recent_files(
 
     let user_id = extract_user_id_from_request(&req, &config);
 
+    if let Some(cursor) = query.cursor.as_deref() {
+        let after = if cursor.is_empty() {
+            None
+        } else {
+            Some(
+                DateIdCursor::decode(cursor)
+                    .ok_or_else(|| AppError::bad_request("Invalid cursor"))?,
+            )
+        };
+
+        let (items, next_cursor) =
+            files::fetch_recent_files_after(pool.get_ref(), &config, after, pagination.per_page, user_id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to fetch recent files: {:?}", e);
+                    AppError {
+                        message: Some("Failed to fetch recent files".to_string()),
+                        cause: Some(e.to_string()),
+                        error_type: AppErrorType::InternalServerError,
+                    }
+                })?;
+
+        return Ok(HttpResponse::Ok().json(AppSuccessResponse {
+            success: true,
+            message: "Recent files retrieved successfully".to_string(),
+            data: Some(CursorPage {
+                items,
+                next_cursor: next_cursor.map(|cursor| cursor.encode()),
+            }),
+            pagination: None,
+        }));
+    }
+
     let (data, total_items) =
         files::fetch_recent_files_with_stats(pool.get_ref(), &config, &pagination, user_id)
             .await
@@ -100,12 +153,78 @@ pub async fn view_file(
     file_id: web::Path<i32>,
 ) -> Result<impl Responder, AppError> {
     let file_id = file_id.into_inner();
-    let file_details = files::fetch_file_details(pool.get_ref(), &config, file_id)
+    let file_details = files::fetch_file_details(pool.get_ref(), &config, file_id, false)
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch file details {}: {:?}", file_id, e);
+            match e.error_type {
+                AppErrorType::NotFoundError => e,
+                _ => AppError {
+                    message: Some("Failed to fetch file details".to_string()),
+                    cause: Some(e.to_string()),
+                    error_type: AppErrorType::InternalServerError,
+                },
+            }
+        })?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "File details retrieved successfully".to_string(),
+        data: Some(file_details),
+        pagination: None,
+    }))
+}
+
+/// The uploader and admins, or a manager with access to the file's scholar
+fn can_view_file_analytics(
+    role: Option<&str>,
+    is_uploader_or_admin: bool,
+    has_scholar_access: bool,
+) -> bool {
+    is_uploader_or_admin || (role == Some("manager") && has_scholar_access)
+}
+
+const DEFAULT_ANALYTICS_DAYS: i64 = 30;
+const MAX_ANALYTICS_DAYS: i64 = 365;
+
+#[instrument(name = "Get File Analytics", skip(pool, auth))]
+#[get("/{file_id}/analytics")]
+pub async fn get_file_analytics(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    file_id: web::Path<i32>,
+    query: web::Query<FileAnalyticsQuery>,
+) -> Result<impl Responder, AppError> {
+    let file_id = file_id.into_inner();
+
+    if !files::active_file_exists(pool.get_ref(), file_id).await? {
+        return Err(AppError::not_found("File not found"));
+    }
+
+    // Role from the database, since the token's may predate a role change
+    let role = crate::db::users::get_current_role(pool.get_ref(), auth.user_id).await?;
+    let is_uploader_or_admin =
+        files::check_file_owner_or_admin(pool.get_ref(), auth.user_id, file_id).await?;
+    let has_scholar_access = role.as_deref() == Some("manager")
+        && access::check_user_access_to_file_scholar(pool.get_ref(), auth.user_id, file_id).await?;
+    if !can_view_file_analytics(role.as_deref(), is_uploader_or_admin, has_scholar_access) {
+        return Err(AppError {
+            message: Some("You don't have permission to view this file's analytics".to_string()),
+            cause: None,
+            error_type: AppErrorType::ForbiddenError,
+        });
+    }
+
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_ANALYTICS_DAYS)
+        .clamp(1, MAX_ANALYTICS_DAYS);
+    let analytics = file_analytics::get_file_analytics(pool.get_ref(), file_id, days)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch analytics for file {}: {:?}", file_id, e);
             AppError {
-                message: Some("Failed to fetch file details".to_string()),
+                message: Some("Failed to fetch file analytics".to_string()),
                 cause: Some(e.to_string()),
                 error_type: AppErrorType::InternalServerError,
             }
@@ -113,40 +232,53 @@ pub async fn view_file(
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
-        message: "File details retrieved successfully".to_string(),
-        data: Some(file_details),
+        message: "File analytics retrieved successfully".to_string(),
+        data: Some(analytics),
         pagination: None,
     }))
 }
 
-#[instrument(name = "Get Related Files", skip(pool, pagination))]
+#[instrument(name = "Get Related Files", skip(pool, pagination, req))]
 #[get("/{file_id}/related")]
 pub async fn get_related_files(
     pool: web::Data<MySqlPool>,
     config: web::Data<AppConfig>,
     file_id: web::Path<i32>,
     pagination: web::Query<PaginationQuery>,
+    req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
     let mut pagination = pagination.into_inner();
     pagination.validate();
 
     let file_id = file_id.into_inner();
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
 
     // First, fetch the book_id of the current file
     let book_id = files::fetch_book_id_for_file(pool.get_ref(), file_id)
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch book_id for file {}: {:?}", file_id, e);
-            AppError {
-                message: Some("Failed to fetch book_id for file".to_string()),
-                cause: Some(e.to_string()),
-                error_type: AppErrorType::InternalServerError,
+            match e.error_type {
+                AppErrorType::NotFoundError => e,
+                _ => AppError {
+                    message: Some("Failed to fetch book_id for file".to_string()),
+                    cause: Some(e.to_string()),
+                    error_type: AppErrorType::InternalServerError,
+                },
             }
         })?;
 
     // Fetch related files
     let (related_files, total_count) =
-        files::fetch_related_files(pool.get_ref(), &config, book_id, file_id, &pagination).await?;
+        files::fetch_related_files(
+            pool.get_ref(),
+            &config,
+            book_id,
+            file_id,
+            include_restricted,
+            &pagination,
+        )
+        .await?;
 
     let pagination_meta = PaginationMeta::new(pagination.page, pagination.per_page, total_count);
 
@@ -157,32 +289,39 @@ pub async fn get_related_files(
         pagination: Some(pagination_meta),
     }))
 }
-#[instrument(name = "Get All Files for Play All", skip(pool, config))]
+#[instrument(name = "Get All Files for Play All", skip(pool, config, req))]
 #[get("/{book_id}/play-all")]
 pub async fn get_all_files_for_play_all(
     pool: web::Data<MySqlPool>,
     config: web::Data<AppConfig>,
     book_id: web::Path<i32>,
+    req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
     let book_id = book_id.into_inner();
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
 
-    let play_all_data = files::get_all_files_for_book_play_all(pool.get_ref(), &config, book_id)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch files for play all: {:?}", e);
-            match e.error_type {
-                AppErrorType::NotFoundError => AppError {
-                    message: Some("Book not found".to_string()),
-                    cause: Some(e.to_string()),
-                    error_type: AppErrorType::NotFoundError,
-                },
-                _ => AppError {
-                    message: Some("Failed to fetch files for play all".to_string()),
-                    cause: Some(e.to_string()),
-                    error_type: AppErrorType::InternalServerError,
-                },
-            }
-        })?;
+    let play_all_data = files::get_all_files_for_book_play_all(
+        pool.get_ref(),
+        &config,
+        book_id,
+        include_restricted,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch files for play all: {:?}", e);
+        match e.error_type {
+            AppErrorType::NotFoundError => AppError {
+                message: Some("Book not found".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::NotFoundError,
+            },
+            _ => AppError {
+                message: Some("Failed to fetch files for play all".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            },
+        }
+    })?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
@@ -191,10 +330,56 @@ pub async fn get_all_files_for_play_all(
         pagination: None,
     }))
 }
-#[instrument(name = "Update File", skip(pool, auth))]
+
+/// Staff always; everyone else needs an active subscription
+fn can_download_offline(role: Option<&str>, has_active_subscription: bool) -> bool {
+    matches!(role, Some("admin") | Some("manager")) || has_active_subscription
+}
+
+/// Download plan for a whole book: every active file with its stream and
+/// download URLs and content hash, so the client can verify what it saved.
+/// Whole-book offline downloads are a subscriber feature
+#[instrument(name = "Get Book Offline Manifest", skip(pool, config, auth))]
+#[get("/{book_id}/offline-manifest")]
+pub async fn get_book_offline_manifest(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    book_id: web::Path<i32>,
+    auth: JwtMiddleware,
+) -> Result<impl Responder, AppError> {
+    let role = users::get_current_role(pool.get_ref(), auth.user_id).await?;
+    let has_active_subscription = subscriptions::get_user_active_subscription(pool.get_ref(), auth.user_id)
+        .await?
+        .is_some();
+    if !can_download_offline(role.as_deref(), has_active_subscription) {
+        return Err(AppError::subscription_required(
+            "An active subscription is required to download a whole book",
+        ));
+    }
+
+    let include_restricted = files::can_view_restricted(Some(auth.user_id));
+
+    let manifest = files::get_book_offline_manifest(
+        pool.get_ref(),
+        &config,
+        book_id.into_inner(),
+        include_restricted,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Offline manifest retrieved successfully".to_string(),
+        data: Some(manifest),
+        pagination: None,
+    }))
+}
+
+#[instrument(name = "Update File", skip(pool, config, auth))]
 #[put("/{file_id}")]
 pub async fn update_file(
     pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
     auth: JwtMiddleware,
     file_id: web::Path<i32>,
     request: web::Json<UpdateFileRequest>,
@@ -212,50 +397,40 @@ pub async fn update_file(
             }
         })?;
 
-    // if user.role != "admin" {
-    //     let can_update = files::check_file_owner(pool.get_ref(), auth.user_id, file_id)
-    //         .await
-    //         .map_err(|e| {
-    //             tracing::error!("Failed to check file permissions: {:?}", e);
-    //             AppError {
-    //                 message: Some("Failed to verify permissions".to_string()),
-    //                 cause: Some(e.to_string()),
-    //                 error_type: AppErrorType::InternalServerError,
-    //             }
-    //         })?;
-
-    //     if !can_update {
-    //         return Err(AppError {
-    //             message: Some("You don't have permission to update this file".to_string()),
-    //             cause: None,
-    //             error_type: AppErrorType::ForbiddenError,
-    //         });
-    //     }
-    // }
-
-    // If changing book, check if user has access to the new book's scholar
+    // Owners keep editing their uploads; anyone else needs access to the file,
+    // through its scholar, its book or a grant on the file itself
+    if user.role != "admin" {
+        let is_owner = files::check_file_owner_or_admin(pool.get_ref(), auth.user_id, file_id).await?;
+        let can_update = is_owner
+            || crate::db::access::check_user_access_to_file(pool.get_ref(), auth.user_id, file_id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to check file permissions: {:?}", e);
+                    AppError {
+                        message: Some("Failed to verify permissions".to_string()),
+                        cause: Some(e.to_string()),
+                        error_type: AppErrorType::InternalServerError,
+                    }
+                })?;
+
+        if !can_update {
+            return Err(AppError {
+                message: Some("You don't have permission to update this file".to_string()),
+                cause: None,
+                error_type: AppErrorType::ForbiddenError,
+            });
+        }
+    }
+
+    // If changing book, check if user has access to the new book
     if let Some(new_book_id) = request.book_id {
         if user.role != "admin" {
-            // Get the scholar_id for the new book
-            let scholar_id = sqlx::query_scalar!(
-                "SELECT scholar_id FROM tbl_books WHERE id = ? AND status = 'active'",
-                new_book_id
-            )
-            .fetch_one(pool.get_ref())
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to get book scholar: {:?}", e);
-                AppError {
-                    message: Some("Book not found".to_string()),
-                    cause: Some(e.to_string()),
-                    error_type: AppErrorType::NotFoundError,
-                }
-            })?;
+            crate::db::books::assert_book_active(pool.get_ref(), new_book_id).await?;
 
-            let has_access = crate::db::access::check_user_access_to_scholar(
+            let has_access = crate::db::access::check_user_access_to_book(
                 pool.get_ref(),
                 auth.user_id,
-                scholar_id,
+                new_book_id,
             )
             .await
             .map_err(|e| {
@@ -311,21 +486,35 @@ pub async fn update_file(
         }
     }
 
-    files::update_file(pool.get_ref(), file_id, &request)
-        .await
-        .map_err(|e| {
+    match files::update_file(pool.get_ref(), file_id, &request).await {
+        Ok(()) => {}
+        Err(e) if matches!(e.error_type, AppErrorType::ConflictError) => {
+            let current = files::fetch_file_details(pool.get_ref(), &config, file_id, true).await?;
+            return Ok(HttpResponse::Conflict().json(VersionConflictResponse {
+                success: false,
+                code: error_codes::VERSION_CONFLICT.to_string(),
+                message: "File was modified by someone else; reload and retry".to_string(),
+                current,
+            }));
+        }
+        Err(e) if matches!(e.error_type, AppErrorType::NotFoundError) => return Err(e),
+        Err(e) => {
             tracing::error!("Failed to update file: {:?}", e);
-            AppError {
+            return Err(AppError {
                 message: Some("Failed to update file".to_string()),
                 cause: Some(e.to_string()),
                 error_type: AppErrorType::InternalServerError,
-            }
-        })?;
+            });
+        }
+    }
+
+    // Return the fresh details so the client picks up the new version
+    let updated_file = files::fetch_file_details(pool.get_ref(), &config, file_id, true).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         message: "File updated successfully".to_string(),
-        data: None::<()>,
+        data: Some(updated_file),
         pagination: None,
     }))
 }
@@ -340,16 +529,20 @@ pub async fn delete_file(
     let file_id = file_id.into_inner();
 
     // Check if user has permission to delete this file
+    let permission_error = |e: AppError| {
+        tracing::error!("Failed to check file permissions: {:?}", e);
+        AppError {
+            message: Some("Failed to check permissions".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    };
     let has_permission = files::check_file_owner_or_admin(pool.get_ref(), auth.user_id, file_id)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to check file permissions: {:?}", e);
-            AppError {
-                message: Some("Failed to check permissions".to_string()),
-                cause: Some(e.to_string()),
-                error_type: AppErrorType::InternalServerError,
-            }
-        })?;
+        .map_err(permission_error)?
+        || crate::db::access::check_user_access_to_file(pool.get_ref(), auth.user_id, file_id)
+            .await
+            .map_err(permission_error)?;
 
     if !has_permission {
         return Err(AppError {
@@ -377,3 +570,184 @@ pub async fn delete_file(
         pagination: None,
     }))
 }
+
+fn featured_file_cache_key(date: NaiveDate) -> String {
+    format!("featured_file:{}", date)
+}
+
+#[instrument(name = "Get Featured File Today", skip(pool, config, redis_service, req))]
+#[get("/featured-today")]
+pub async fn get_featured_file_today(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let user_id = extract_user_id_from_request(&req, &config);
+    let now = Utc::now();
+    let today = now.date_naive();
+    let cache_key = featured_file_cache_key(today);
+
+    // Keep the pick for the rest of the (UTC) day, even if the pool or the
+    // download counts behind it change in the meantime
+    let cached_file_id = match redis_service.get::<i32>(&cache_key).await {
+        Ok(file_id) => Some(file_id),
+        Err(e) => {
+            if e.is_unavailable() {
+                tracing::warn!("Cache read for {} failed, picking from source: {}", cache_key, e);
+            }
+            None
+        }
+    };
+
+    let mut featured = match cached_file_id {
+        Some(file_id) => files::fetch_file_with_stats(pool.get_ref(), &config, file_id, user_id).await?,
+        None => None,
+    };
+
+    // Nothing cached yet, or the cached file was removed since it was picked
+    if featured.is_none() {
+        if let Some(file_id) = featured_files::pick_featured_file_id(pool.get_ref(), &config, today).await? {
+            featured = files::fetch_file_with_stats(pool.get_ref(), &config, file_id, user_id).await?;
+
+            let ttl = 86_400 - u64::from(now.num_seconds_from_midnight());
+            if let Err(e) = redis_service.set(&cache_key, &file_id, Some(Duration::from_secs(ttl))).await {
+                tracing::warn!("Cache write for {} failed: {}", cache_key, e);
+            }
+        }
+    }
+
+    let message = if featured.is_some() {
+        "Featured file retrieved successfully"
+    } else {
+        "No file is available to feature today"
+    };
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: message.to_string(),
+        data: featured,
+        pagination: None,
+    }))
+}
+
+#[instrument(name = "Feature File", skip(pool, redis_service, auth))]
+#[post("/featured")]
+pub async fn feature_file(
+    pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
+    auth: JwtMiddleware,
+    request: web::Json<FeatureFileRequest>,
+) -> Result<impl Responder, AppError> {
+    crate::db::users::require_admin(pool.get_ref(), auth.user_id).await?;
+
+    let request = request.into_inner();
+    files::fetch_book_id_for_file(pool.get_ref(), request.file_id)
+        .await
+        .map_err(|_| AppError {
+            message: Some("File not found".to_string()),
+            cause: None,
+            error_type: AppErrorType::NotFoundError,
+        })?;
+
+    let message = match request.date {
+        Some(date) => {
+            featured_files::pin_featured_file(pool.get_ref(), request.file_id, date, auth.user_id).await?;
+
+            // Drop a pick already cached for that day so the pin shows immediately
+            let cache_key = featured_file_cache_key(date);
+            if let Err(e) = redis_service.delete(&cache_key).await {
+                tracing::warn!("Cache delete for {} failed: {}", cache_key, e);
+            }
+            format!("File pinned as featured for {}", date)
+        }
+        None => {
+            featured_files::add_to_featured_pool(pool.get_ref(), request.file_id, auth.user_id).await?;
+            "File added to the featured pool".to_string()
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message,
+        data: None::<()>,
+        pagination: None,
+    }))
+}
+
+#[instrument(name = "Set File Restriction", skip(pool, auth))]
+#[put("/{file_id}/restriction")]
+pub async fn set_file_restriction(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    file_id: web::Path<i32>,
+    request: web::Json<SetFileRestrictionRequest>,
+) -> Result<impl Responder, AppError> {
+    crate::db::users::require_admin(pool.get_ref(), auth.user_id).await?;
+
+    let file_id = file_id.into_inner();
+    let restricted = request.restricted;
+    if !files::set_file_restricted(pool.get_ref(), file_id, restricted).await? {
+        return Err(AppError::not_found("File not found"));
+    }
+
+    let message = if restricted {
+        "File restricted to signed-in users"
+    } else {
+        "File restriction removed"
+    };
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: message.to_string(),
+        data: None::<()>,
+        pagination: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::files::FilesWithStats;
+
+    #[test]
+    fn offline_manifest_needs_a_subscription_unless_staff() {
+        assert!(!can_download_offline(Some("user"), false));
+        assert!(!can_download_offline(None, false));
+        assert!(can_download_offline(Some("user"), true));
+        assert!(can_download_offline(Some("admin"), false));
+        assert!(can_download_offline(Some("manager"), false));
+    }
+
+    #[test]
+    fn only_uploaders_admins_and_scholar_managers_see_file_analytics() {
+        assert!(can_view_file_analytics(Some("user"), true, false));
+        assert!(can_view_file_analytics(Some("admin"), true, false));
+        assert!(can_view_file_analytics(Some("manager"), false, true));
+
+        // Everyone else gets a 403
+        assert!(!can_view_file_analytics(Some("user"), false, false));
+        assert!(!can_view_file_analytics(Some("manager"), false, false));
+        // A demoted manager keeps no access through an old grant
+        assert!(!can_view_file_analytics(Some("user"), false, true));
+        assert!(!can_view_file_analytics(None, false, true));
+    }
+
+    #[actix_web::test]
+    async fn an_empty_book_lists_no_files_and_a_missing_book_is_not_found() {
+        let page = PaginationQuery { page: 1, per_page: 20 };
+
+        let response = book_files_response::<FilesWithStats>(Ok((Vec::new(), 0)), &page).unwrap();
+        assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["data"], serde_json::json!([]));
+        assert_eq!(body["pagination"]["total_items"], 0);
+
+        let missing = book_files_response::<FilesWithStats>(Err(AppError::not_found("Book not found")), &page);
+        assert_eq!(missing.unwrap_err().error_type, AppErrorType::NotFoundError);
+
+        let failed = book_files_response::<FilesWithStats>(Err(AppError::internal_error("timeout")), &page);
+        assert_eq!(failed.unwrap_err().error_type, AppErrorType::InternalServerError);
+    }
+}
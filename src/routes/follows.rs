@@ -1,6 +1,6 @@
 use crate::core::jwt_auth::JwtClaims;
 use crate::core::AppError;
-use crate::core::{AppErrorResponse, AppSuccessResponse};
+use crate::core::{error_codes, AppErrorResponse, AppSuccessResponse};
 use crate::db::follows;
 use crate::models::follows::{FollowScholarRequest, UpdateFollowRequest};
 use actix_web::{delete, get, post, put, web, HttpResponse, Result};
@@ -25,15 +25,17 @@ pub async fn follow_scholar(
     if scholar_id != request.scholar_id {
         return Ok(HttpResponse::BadRequest().json(AppErrorResponse {
             success: false,
+            code: error_codes::PATH_MISMATCH.to_string(),
             message: "Scholar ID in path doesn't match request body".to_string(),
         }));
     }
 
-    let follow = follows::follow_scholar(&pool, user_id, &request).await?;
+    follows::follow_scholar(&pool, user_id, &request).await?;
+    let follow_state = follows::get_follow_state(&pool, user_id, scholar_id).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
-        data: follow,
+        data: follow_state,
         message: "Scholar followed successfully".to_string(),
         pagination: None,
     }))
@@ -53,10 +55,11 @@ pub async fn unfollow_scholar(
 
     let scholar_id = path.into_inner();
     follows::unfollow_scholar(&pool, user_id, scholar_id).await?;
+    let follow_state = follows::get_follow_state(&pool, user_id, scholar_id).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
-        data: (),
+        data: follow_state,
         message: "Scholar unfollowed successfully".to_string(),
         pagination: None,
     }))
@@ -132,4 +135,46 @@ pub async fn check_follow_status(
         message: "Follow status retrieved successfully".to_string(),
         pagination: None,
     }))
-}
\ No newline at end of file
+}
+
+#[tracing::instrument(name = "Get New Followed Content Count", skip(pool, claims))]
+#[get("/new-count")]
+pub async fn get_new_followed_content_count(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let new_content = follows::count_new_followed_files(&pool, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: new_content,
+        message: "New content count retrieved successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Mark Follows Seen", skip(pool, claims))]
+#[post("/mark-seen")]
+pub async fn mark_follows_seen(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let seen = follows::mark_follows_seen(&pool, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: seen,
+        message: "Followed content marked as seen".to_string(),
+        pagination: None,
+    }))
+}
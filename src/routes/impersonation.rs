@@ -0,0 +1,149 @@
+use crate::core::jwt_auth::{generate_jwt_token, impersonation_session_key, JwtClaims};
+use crate::core::redis_helper::RedisHelper;
+use crate::core::{client_ip, AppConfig, AppError, AppSuccessResponse};
+use crate::db::{impersonation, users};
+use crate::models::impersonation::{ImpersonationResponse, StartImpersonationRequest};
+use crate::models::users::{MessageResponse, UserProfile};
+use actix_web::{post, web, HttpRequest, HttpResponse, Result};
+use chrono::{Duration, Utc};
+use sqlx::MySqlPool;
+use std::time::Duration as StdDuration;
+use uuid::Uuid;
+
+const IMPERSONATION_TTL_MINUTES: i64 = 30;
+const MAX_REASON_LENGTH: usize = 255;
+
+#[tracing::instrument(name = "Start Impersonation", skip(pool, config, redis_service, claims, req, request))]
+#[post("/admin/impersonate/{user_id}")]
+pub async fn start_impersonation(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    claims: JwtClaims,
+    req: HttpRequest,
+    user_id: web::Path<i32>,
+    request: Option<web::Json<StartImpersonationRequest>>,
+) -> Result<HttpResponse, AppError> {
+    if claims.impersonated_by.is_some() {
+        return Err(AppError::forbidden_error("Access denied. Admin role required."));
+    }
+
+    let admin_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    // A demoted admin's token still says admin until it expires
+    users::require_admin(&pool, admin_id).await?;
+    let user_id = user_id.into_inner();
+    if user_id == admin_id {
+        return Err(AppError::bad_request("You cannot impersonate yourself"));
+    }
+
+    let reason = request
+        .map(|request| request.into_inner())
+        .unwrap_or_default()
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+    if reason.as_ref().is_some_and(|reason| reason.chars().count() > MAX_REASON_LENGTH) {
+        return Err(AppError::bad_request(format!(
+            "Reason cannot be longer than {} characters",
+            MAX_REASON_LENGTH
+        )));
+    }
+
+    let user = users::get_user_by_id(&pool, user_id)
+        .await
+        .map_err(|_| AppError::not_found("User not found"))?;
+    if user.role == "admin" {
+        return Err(AppError::forbidden_error("Admins cannot be impersonated"));
+    }
+
+    let session_id = Uuid::new_v4().to_string();
+    let expires_at = Utc::now() + Duration::minutes(IMPERSONATION_TTL_MINUTES);
+    let impersonation_claims = JwtClaims {
+        sub: user.id.to_string(),
+        email: user.email.clone(),
+        role: user.role.clone(),
+        exp: expires_at.timestamp() as usize,
+        impersonated_by: Some(admin_id),
+        impersonation_id: Some(session_id.clone()),
+    };
+    let token = generate_jwt_token(&impersonation_claims, &config)?;
+
+    // Audit before the session goes live so no usable token is ever unlogged
+    impersonation::log_impersonation_start(
+        &pool,
+        &session_id,
+        admin_id,
+        user_id,
+        reason.as_deref(),
+        client_ip(&req, &config).as_deref(),
+        expires_at,
+    )
+    .await?;
+
+    redis_service
+        .set_secure(
+            &impersonation_session_key(&session_id),
+            &admin_id,
+            Some(StdDuration::from_secs(IMPERSONATION_TTL_MINUTES as u64 * 60)),
+        )
+        .await?;
+
+    tracing::warn!(
+        "Admin {} started impersonating user {} (session {})",
+        admin_id,
+        user_id,
+        session_id
+    );
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: ImpersonationResponse {
+            token,
+            expires_at,
+            session_id,
+            user: UserProfile::from(user),
+        },
+        message: "Impersonation started".to_string(),
+        pagination: None,
+    }))
+}
+
+/// Ends the session the calling impersonation token belongs to
+#[tracing::instrument(name = "End Impersonation", skip(pool, redis_service, claims))]
+#[post("/admin/impersonate/end")]
+pub async fn end_impersonation(
+    pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    let (Some(admin_id), Some(session_id)) =
+        (claims.impersonated_by, claims.impersonation_id.as_deref())
+    else {
+        return Err(AppError::bad_request("Not an impersonation token"));
+    };
+
+    redis_service
+        .delete(&impersonation_session_key(session_id))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to end impersonation: {}", e)))?;
+    impersonation::log_impersonation_end(&pool, session_id).await?;
+
+    tracing::warn!(
+        "Admin {} stopped impersonating user {} (session {})",
+        admin_id,
+        claims.sub,
+        session_id
+    );
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: MessageResponse {
+            message: "Impersonation ended".to_string(),
+        },
+        message: "Impersonation ended".to_string(),
+        pagination: None,
+    }))
+}
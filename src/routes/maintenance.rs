@@ -0,0 +1,217 @@
+use crate::core::jwt_auth::JwtClaims;
+use crate::core::{AppConfig, AppError, AppSuccessResponse, LogFilterHandle};
+use crate::db::users;
+use crate::jobs::backfill_content_hashes::{backfill_content_hashes, BackfillContentHashesRequest};
+use crate::jobs::bulk_import::{import_directory, ImportScanRequest};
+use crate::jobs::integrity_scan::{scan_integrity, IntegrityScanRequest};
+use crate::jobs::prune_play_history::prune_play_history;
+use crate::jobs::recompute_counters::recompute_counters;
+
+use actix_web::{get, post, put, web, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+
+/// Id of the signed-in user once the database confirms they are still an admin;
+/// the role in the token may predate a demotion
+async fn require_admin(pool: &MySqlPool, claims: &JwtClaims) -> Result<i32, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    users::require_admin(pool, user_id).await?;
+    Ok(user_id)
+}
+
+#[tracing::instrument(name = "Recompute Counters", skip(pool, claims))]
+#[post("/maintenance/recompute-counters")]
+pub async fn recompute_counters_now(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&pool, &claims).await?;
+
+    let report = recompute_counters(&pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: format!(
+            "Corrected {} file(s) and {} playlist(s)",
+            report.files_corrected, report.playlists_corrected
+        ),
+        data: report,
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Prune Play History", skip(pool, config, claims))]
+#[post("/maintenance/prune-play-history")]
+pub async fn prune_play_history_now(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&pool, &claims).await?;
+
+    let report = prune_play_history(&pool, &config.play_history_retention)
+        .await
+        .map_err(AppError::db_error)?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: format!(
+            "Removed {} expired and {} over-cap play history entries",
+            report.expired_removed, report.overflow_removed
+        ),
+        data: report,
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Integrity Scan", skip(pool, config, claims, request))]
+#[post("/maintenance/integrity-scan")]
+pub async fn integrity_scan(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    claims: JwtClaims,
+    request: web::Json<IntegrityScanRequest>,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&pool, &claims).await?;
+
+    let report = scan_integrity(&pool, &config.app_paths.uploads_dir, &request).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: format!(
+            "Checked {} entries: {} missing on disk, {} without a database row",
+            report.checked,
+            report.missing_files.len(),
+            report.orphaned_files.len()
+        ),
+        data: report,
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Backfill Content Hashes", skip(pool, config, claims, request))]
+#[post("/maintenance/backfill-content-hashes")]
+pub async fn backfill_content_hashes_now(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    claims: JwtClaims,
+    request: web::Json<BackfillContentHashesRequest>,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&pool, &claims).await?;
+
+    let report = backfill_content_hashes(&pool, &config.app_paths.uploads_dir, &request).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: format!(
+            "Hashed {} file(s), {} unreadable",
+            report.hashed,
+            report.unreadable_ids.len()
+        ),
+        data: report,
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Import Scan", skip(pool, config, claims, request))]
+#[post("/import/scan")]
+pub async fn import_scan(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    claims: JwtClaims,
+    request: web::Json<ImportScanRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = require_admin(&pool, &claims).await?;
+
+    // Runs as its own task so a client that disconnects mid-import does not
+    // cancel it between moving a file and inserting its row
+    let pool = pool.get_ref().clone();
+    let config = config.get_ref().clone();
+    let request = request.into_inner();
+    let report = tokio::spawn(async move { import_directory(&pool, &config, &request, user_id).await })
+        .await
+        .map_err(|e| AppError::internal_error(format!("Import task failed: {}", e)))??;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: format!(
+            "Imported {} file(s): {} duplicate(s), {} failed, {} remaining",
+            report.imported, report.duplicates, report.failed, report.remaining
+        ),
+        data: report,
+        pagination: None,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogLevelRequest {
+    /// `RUST_LOG`-style directives, e.g. `info` or `warn,sunnah_audio::routes=debug`
+    pub filter: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogLevelResponse {
+    pub filter: Option<String>,
+}
+
+#[tracing::instrument(name = "Get Log Level", skip(pool, log_filter, claims))]
+#[get("/log-level")]
+pub async fn get_log_level(
+    pool: web::Data<MySqlPool>,
+    log_filter: web::Data<LogFilterHandle>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&pool, &claims).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Log level retrieved successfully".to_string(),
+        data: LogLevelResponse {
+            filter: log_filter.current(),
+        },
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Set Log Level", skip(pool, log_filter, claims, request))]
+#[put("/log-level")]
+pub async fn set_log_level(
+    pool: web::Data<MySqlPool>,
+    log_filter: web::Data<LogFilterHandle>,
+    claims: JwtClaims,
+    request: web::Json<LogLevelRequest>,
+) -> Result<HttpResponse, AppError> {
+    let admin_id = require_admin(&pool, &claims).await?;
+
+    let filter = request.filter.trim();
+    if filter.is_empty() {
+        return Err(AppError::bad_request("Log filter cannot be empty"));
+    }
+
+    let previous = log_filter.current();
+    log_filter
+        .set(filter)
+        .map_err(|e| AppError::bad_request(format!("Invalid log filter: {}", e)))?;
+
+    // Warn so the change is recorded under all but the quietest filters
+    tracing::warn!(
+        "Log filter changed from {} to {} by user {}",
+        previous.as_deref().unwrap_or("unknown"),
+        filter,
+        admin_id
+    );
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Log level updated successfully".to_string(),
+        data: LogLevelResponse {
+            filter: log_filter.current(),
+        },
+        pagination: None,
+    }))
+}
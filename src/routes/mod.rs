@@ -2,59 +2,87 @@ use actix_files as fs;
 
 use actix_web::web::{scope, ServiceConfig};
 use actix_web::Scope;
-use books::{get_book_details, get_book_statistics, get_books_by_scholar, get_books_dropdown, create_book, update_book, delete_book};
+use std::time::Duration;
+use crate::core::RequestTimeout;
+use activity::get_my_activity;
+use books::{can_manage_book, get_book_details, get_book_progress, get_book_siblings, get_book_statistics, get_books_by_scholar, get_books_dropdown, create_book, update_book, delete_book};
+use failed_emails::{get_failed_emails, retry_failed_email};
+use feature_flags::{get_feature_flags, set_feature_flag};
 use file_interactions::{
-    check_file_like_status, create_comment, delete_comment, get_file_comments,
-    get_file_download_stats, get_file_likes, get_my_download_history, get_pending_reports,
-    like_file, report_file, resolve_report, unlike_file, update_comment,
+    approve_comment, check_file_like_status, create_comment, delete_comment, get_file_comments,
+    get_my_comments, get_file_download_stats, get_file_likes, get_my_download_history,
+    get_my_download_history_details, get_my_liked_files, get_pending_comments, get_pending_reports,
+    like_file, reject_comment, report_file, resolve_report, unlike_file, update_comment,
 };
 use files::{
-    get_all_files_for_play_all, get_files_by_book, get_recent_files, get_related_files, view_file, update_file, delete_file,
+    get_all_files_for_play_all, get_book_offline_manifest, get_file_analytics, get_files_by_book, get_recent_files, get_related_files, view_file, update_file, delete_file,
+    get_featured_file_today, feature_file, set_file_restriction,
 };
 use follows::{
-    check_follow_status, follow_scholar, get_my_followed_scholars, unfollow_scholar,
-    update_follow_settings,
+    check_follow_status, follow_scholar, get_my_followed_scholars, get_new_followed_content_count,
+    mark_follows_seen, unfollow_scholar, update_follow_settings,
 };
+use impersonation::{end_impersonation, start_impersonation};
+use notifications::{get_my_notifications, get_notification_preferences, update_notification_preferences};
 use permissions::{get_all_accesses, get_user_permissions, grant_access, revoke_access};
 use play_history::{
-    clear_play_history, get_file_play_stats, get_most_played_files, get_my_play_history,
-    record_play,
+    clear_play_history, complete_file, get_file_play_stats, get_listening_goal_progress, get_most_played_files,
+    get_my_play_history, record_play, set_listening_goal, sync_play_history,
 };
 use playlists::{
     add_file_to_playlist, create_playlist, delete_playlist, get_my_playlists, get_playlist,
-    get_playlist_files, get_public_playlists, remove_file_from_playlist, update_playlist,
+    get_playlist_files, get_playlist_with_files, get_playlists_containing_file,
+    get_public_playlists, remove_file_from_playlist, update_playlist,
 };
-use related_files::get_file_suggestions;
-use scholars::{get_scholar_details, get_scholar_statistics, get_scholars, get_scholars_by_state, get_scholars_dropdown, create_scholar, update_scholar, delete_scholar};
-use search::full_text_search;
+use maintenance::{backfill_content_hashes_now, get_log_level, import_scan, integrity_scan, prune_play_history_now, recompute_counters_now, set_log_level};
+use related_files::{get_file_suggestions, get_next_file};
+use scholars::{can_manage_scholar, get_scholar_catalog, get_scholar_details, get_scholar_home, get_scholar_report_csv, get_scholars_filtered, get_scholar_statistics, get_scholar_top_files, get_scholars, get_trending_scholars, get_featured_scholars, set_featured_scholars, get_scholars_by_state, get_scholars_dropdown, create_scholar, update_scholar, delete_scholar, add_scholar_link, remove_scholar_link};
+use search::{full_text_search, search_book_files, search_scholar_files};
+use share::{get_book_share_card, get_file_share_card, get_scholar_share_card};
 use states::get_states;
+use stats::get_public_stats;
 use subscriptions::{
-    create_subscription, get_active_subscription, get_pending_subscriptions,
-    get_subscription_plans, get_subscription_status, get_user_subscriptions, verify_subscription,
-    expire_subscriptions,
+    create_subscription, get_active_subscription, get_payment_instructions,
+    get_pending_subscriptions, get_subscription_plans, get_subscription_status,
+    get_user_subscriptions, resend_payment_instructions, verify_subscription,
+    verify_subscriptions_batch, expire_subscriptions,
+};
+use uploads::{
+    download_file, download_files_zip, preview_file, stream_audio, stream_public_audio, track_download,
+    upload_file,
 };
-use uploads::{download_file, track_download, upload_file};
 use users::{
-    change_password, deactivate_account, forgot_password, get_profile, login, register,
+    change_email, change_password, confirm_email_change, deactivate_account, forgot_password, get_profile, login, register,
     reset_password, update_profile, refresh_token_endpoint, logout,
 };
+use scheduled::{create_schedule, delete_schedule, get_scheduled_today, get_schedules};
 use settings::get_site_settings;
+mod activity;
 mod books;
+mod failed_emails;
+mod feature_flags;
 mod file_interactions;
 mod files;
 mod follows;
 mod health_check;
+mod impersonation;
+mod maintenance;
+mod notifications;
 mod permissions;
 mod play_history;
 mod playlists;
 mod related_files;
+mod scheduled;
 mod scholars;
 mod search;
+mod share;
 mod states;
+mod stats;
 mod subscriptions;
 mod uploads;
 mod users;
 mod settings;
+mod v2;
 
 use crate::routes::health_check::*;
 // const IMAGES_DIR: &str = "/home/mubarak/Documents/my-documents/muryar_sunnah/web/images";
@@ -64,30 +92,68 @@ fn util_routes() -> Scope {
     scope("")
         .service(get_states)
         .service(get_site_settings)
+        .service(get_public_stats)
         .service(full_text_search)
         .service(health_check)
 }
 
-fn books_routes() -> Scope {
+fn follows_routes() -> Scope {
+    scope("follows")
+        .service(get_new_followed_content_count)
+        .service(mark_follows_seen)
+}
+
+fn share_routes() -> Scope {
+    scope("share")
+        .service(get_file_share_card)
+        .service(get_book_share_card)
+        .service(get_scholar_share_card)
+}
+
+fn books_routes(timeout: Duration) -> Scope {
     scope("books")
+        // Receives the upload body, so it is registered outside the timeout
+        .service(upload_file)
+        .service(timed_books_routes().wrap(RequestTimeout::new(timeout)))
+}
+
+fn timed_books_routes() -> Scope {
+    scope("")
         .service(get_files_by_book)
         .service(get_all_files_for_play_all)
+        .service(get_book_offline_manifest)
+        .service(search_book_files)
         .service(get_book_details)
         .service(get_book_statistics)
-        .service(upload_file)
+        .service(get_book_progress)
+        .service(get_book_siblings)
+        .service(can_manage_book)
         .service(get_books_dropdown)
         .service(create_book)
         .service(update_book)
         .service(delete_book)
 }
 
-fn files_routes() -> Scope {
+fn files_routes(timeout: Duration) -> Scope {
     scope("files")
+        // Stream file bodies, so they are registered outside the timeout
+        .service(download_file)
+        .service(download_files_zip)
+        .service(timed_files_routes().wrap(RequestTimeout::new(timeout)))
+}
+
+fn timed_files_routes() -> Scope {
+    scope("")
         .service(get_recent_files)
+        .service(get_featured_file_today)
+        .service(feature_file)
+        .service(set_file_restriction)
         .service(view_file)
+        .service(get_file_analytics)
         .service(get_related_files)
         .service(get_file_suggestions) // New endpoint for next/previous suggestions
-        .service(download_file)
+        .service(get_next_file)
+        .service(preview_file)
         .service(track_download) // Track downloads without downloading
         .service(update_file)
         .service(delete_file)
@@ -103,8 +169,16 @@ fn files_routes() -> Scope {
         .service(get_file_comments)
         .service(update_comment)
         .service(delete_comment)
+        .service(get_pending_comments)
+        .service(approve_comment)
+        .service(reject_comment)
         .service(get_file_download_stats)
         .service(get_my_download_history)
+        .service(get_my_download_history_details)
+        .service(get_my_liked_files)
+        .service(get_my_comments)
+        // playlist membership for the "add to playlist" sheet
+        .service(get_playlists_containing_file)
 }
 
 fn auth_routes() -> Scope {
@@ -117,6 +191,8 @@ fn auth_routes() -> Scope {
         .service(get_profile)
         .service(update_profile)
         .service(change_password)
+        .service(change_email)
+        .service(confirm_email_change)
         .service(forgot_password)
         .service(reset_password)
         .service(deactivate_account)
@@ -124,24 +200,50 @@ fn auth_routes() -> Scope {
         .service(grant_access)
         .service(revoke_access)
         .service(get_all_accesses)
+        // `end` first so it is not taken for a user id
+        .service(end_impersonation)
+        .service(start_impersonation)
+        .service(get_notification_preferences)
+        .service(update_notification_preferences)
+        .service(get_my_notifications)
+        .service(get_my_activity)
 }
 
-fn scholars_routes() -> Scope {
+fn scholars_routes(timeout: Duration) -> Scope {
     scope("scholars")
+        // Streams a CSV of unbounded length, so it is registered outside the timeout
+        .service(get_scholar_report_csv)
+        .service(timed_scholars_routes().wrap(RequestTimeout::new(timeout)))
+}
+
+fn timed_scholars_routes() -> Scope {
+    scope("")
         .service(get_scholars)
         .service(get_scholars_by_state)
+        .service(get_scholars_filtered)
+        .service(get_trending_scholars)
+        .service(get_featured_scholars)
+        .service(set_featured_scholars)
+        // Static paths go before `/{scholar_id}`, which would otherwise claim them
+        .service(get_scholars_dropdown)
+        .service(get_my_followed_scholars)
         .service(get_scholar_details)
+        .service(get_scholar_home)
         .service(get_scholar_statistics)
+        .service(get_scholar_top_files)
+        .service(search_scholar_files)
+        .service(can_manage_scholar)
+        .service(get_scholar_catalog)
         .service(get_books_by_scholar)
-        .service(get_scholars_dropdown)
         .service(create_scholar)
         .service(update_scholar)
         .service(delete_scholar)
+        .service(add_scholar_link)
+        .service(remove_scholar_link)
         // follow routes
         .service(follow_scholar)
         .service(unfollow_scholar)
         .service(update_follow_settings)
-        .service(get_my_followed_scholars)
         .service(check_follow_status)
 }
 
@@ -166,18 +268,25 @@ fn subscriptions_routes() -> Scope {
         .service(get_subscription_status)
         .service(get_active_subscription)
         .service(create_subscription)
+        .service(get_payment_instructions)
+        .service(resend_payment_instructions)
         .service(get_pending_subscriptions)
         .service(verify_subscription)
+        .service(verify_subscriptions_batch)
         .service(expire_subscriptions)
 }
 
 fn play_history_routes() -> Scope {
     scope("play-history")
         .service(record_play)
+        .service(sync_play_history)
+        .service(complete_file)
         .service(get_my_play_history)
         .service(get_most_played_files)
         .service(clear_play_history)
         .service(get_file_play_stats)
+        .service(set_listening_goal)
+        .service(get_listening_goal_progress)
 }
 
 fn playlists_routes() -> Scope {
@@ -191,28 +300,80 @@ fn playlists_routes() -> Scope {
         .service(add_file_to_playlist)
         .service(remove_file_from_playlist)
         .service(get_playlist_files)
+        .service(get_playlist_with_files)
+}
+
+fn admin_routes(timeout: Duration) -> Scope {
+    scope("admin")
+        // Maintenance jobs walk whole tables or the uploads dir; they are
+        // registered outside the timeout
+        .service(recompute_counters_now)
+        .service(prune_play_history_now)
+        .service(integrity_scan)
+        .service(backfill_content_hashes_now)
+        .service(import_scan)
+        .service(timed_admin_routes().wrap(RequestTimeout::new(timeout)))
+}
+
+fn timed_admin_routes() -> Scope {
+    scope("")
+        .service(get_log_level)
+        .service(set_log_level)
+        .service(get_feature_flags)
+        .service(set_feature_flag)
+        .service(get_failed_emails)
+        .service(retry_failed_email)
+        .service(get_schedules)
+        .service(create_schedule)
+        .service(delete_schedule)
+}
+
+fn scheduled_routes() -> Scope {
+    scope("scheduled").service(get_scheduled_today)
 }
 
 fn static_files_routes(config: &crate::core::config::AppConfig) -> Scope {
-    scope("static")
+    let scope = scope("static")
         // Serve album images from `/static/images/`
-        .service(fs::Files::new("/images", &config.app_paths.images_dir))
-        // Serve audio files from `/static/audio/`
-        .service(fs::Files::new("/audio", &config.app_paths.uploads_dir))
+        .service(fs::Files::new("/images", &config.app_paths.images_dir));
+
+    // Serve audio files from `/static/audio/`, behind auth unless the deployment is fully free
+    if config.app_paths.public_audio {
+        scope.service(stream_public_audio)
+    } else {
+        scope.service(stream_audio)
+    }
 }
 
 pub fn sunnah_audio_routes(conf: &mut ServiceConfig, config: &crate::core::config::AppConfig) {
+    let timeouts = &config.request_timeouts;
     conf.service(
         scope("api/v1")
-            .service(auth_routes())
-            .service(scholars_routes())
-            .service(books_routes())
-            .service(files_routes())
-            .service(users_routes())
-            .service(subscriptions_routes())
-            .service(play_history_routes())
-            .service(playlists_routes())
+            .service(auth_routes().wrap(RequestTimeout::new(timeouts.for_group("auth"))))
+            .service(scholars_routes(timeouts.for_group("scholars")))
+            .service(books_routes(timeouts.for_group("books")))
+            .service(files_routes(timeouts.for_group("files")))
+            .service(users_routes().wrap(RequestTimeout::new(timeouts.for_group("users"))))
+            .service(
+                subscriptions_routes()
+                    .wrap(RequestTimeout::new(timeouts.for_group("subscriptions"))),
+            )
+            .service(
+                play_history_routes()
+                    .wrap(RequestTimeout::new(timeouts.for_group("play_history"))),
+            )
+            .service(playlists_routes().wrap(RequestTimeout::new(timeouts.for_group("playlists"))))
+            .service(admin_routes(timeouts.for_group("admin")))
+            .service(share_routes().wrap(RequestTimeout::new(timeouts.for_group("share"))))
+            .service(follows_routes().wrap(RequestTimeout::new(timeouts.for_group("follows"))))
+            .service(scheduled_routes().wrap(RequestTimeout::new(timeouts.for_group("scheduled"))))
+            // Static files stream from disk and are never timed out
             .service(static_files_routes(config))
-            .service(util_routes()),
+            .service(util_routes().wrap(RequestTimeout::new(timeouts.for_group("util")))),
+    );
+    // v1 stays frozen; breaking response changes land here (see `routes::v2`)
+    conf.service(
+        scope("api/v2")
+            .service(v2::files_routes().wrap(RequestTimeout::new(timeouts.for_group("files")))),
     );
 }
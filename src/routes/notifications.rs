@@ -0,0 +1,82 @@
+use crate::core::jwt_auth::JwtClaims;
+use crate::core::{AppError, AppSuccessResponse};
+use crate::db::notifications;
+use crate::models::notifications::UpdateNotificationPreferencesRequest;
+use crate::models::pagination::{PaginationMeta, PaginationQuery};
+use actix_web::{get, put, web, HttpResponse, Result};
+use sqlx::MySqlPool;
+
+#[tracing::instrument(name = "Get Notification Preferences", skip(pool, claims))]
+#[get("/notification-preferences")]
+pub async fn get_notification_preferences(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let preferences = notifications::get_notification_preferences(&pool, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: preferences,
+        message: "Notification preferences retrieved successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Update Notification Preferences", skip(pool, claims, request))]
+#[put("/notification-preferences")]
+pub async fn update_notification_preferences(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    request: web::Json<UpdateNotificationPreferencesRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let preferences =
+        notifications::update_notification_preferences(&pool, user_id, &request).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: preferences,
+        message: "Notification preferences updated successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Get Notifications", skip(pool, claims, pagination))]
+#[get("/notifications")]
+pub async fn get_my_notifications(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+
+    let (notifications, total) = notifications::get_user_notifications(
+        &pool,
+        user_id,
+        pagination.per_page,
+        pagination.offset(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: notifications,
+        message: "Notifications retrieved successfully".to_string(),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total)),
+    }))
+}
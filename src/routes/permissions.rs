@@ -6,9 +6,13 @@ use tracing::instrument;
 use crate::{
     core::{jwt_auth::JwtMiddleware, AppError, AppErrorType, AppSuccessResponse},
     db::access,
-    models::access::{GrantAccessRequest, RevokeAccessRequest},
+    models::access::{AccessTarget, GrantAccessRequest, RevokeAccessRequest},
 };
 
+fn invalid_access_target() -> AppError {
+    AppError::bad_request("Exactly one of scholar_id, book_id or file_id is required")
+}
+
 #[instrument(name = "Get User Permissions", skip(pool))]
 #[get("/permissions")]
 pub async fn get_user_permissions(
@@ -52,13 +56,37 @@ pub async fn grant_access(
         });
     }
 
-    access::grant_user_access(
-        pool.get_ref(),
-        request.user_id,
-        request.scholar_id,
-        auth.user_id,
-    )
-    .await
+    // A manager could otherwise widen their own reach
+    if request.user_id == auth.user_id && user_permissions.role != "Admin" {
+        return Err(AppError::forbidden_error("Only admins can grant access to themselves"));
+    }
+
+    let target = AccessTarget::from_ids(request.scholar_id, request.book_id, request.file_id)
+        .ok_or_else(invalid_access_target)?;
+
+    let not_found = |what: &str| AppError {
+        message: Some(format!("{} not found", what)),
+        cause: None,
+        error_type: AppErrorType::NotFoundError,
+    };
+
+    match target {
+        AccessTarget::Scholar(scholar_id) => {
+            access::grant_user_access(pool.get_ref(), request.user_id, scholar_id, auth.user_id).await
+        }
+        AccessTarget::Book(book_id) => {
+            crate::db::books::assert_book_active(pool.get_ref(), book_id).await?;
+            access::grant_user_content_access(pool.get_ref(), request.user_id, "book", book_id, auth.user_id)
+                .await
+        }
+        AccessTarget::File(file_id) => {
+            crate::db::files::fetch_book_id_for_file(pool.get_ref(), file_id)
+                .await
+                .map_err(|_| not_found("File"))?;
+            access::grant_user_content_access(pool.get_ref(), request.user_id, "file", file_id, auth.user_id)
+                .await
+        }
+    }
     .map_err(|e| {
         tracing::error!("Failed to grant access: {:?}", e);
         AppError {
@@ -94,9 +122,21 @@ pub async fn revoke_access(
         });
     }
 
-    access::revoke_user_access(pool.get_ref(), request.user_id, request.scholar_id)
-        .await
-        .map_err(|e| {
+    let target = AccessTarget::from_ids(request.scholar_id, request.book_id, request.file_id)
+        .ok_or_else(invalid_access_target)?;
+
+    match target {
+        AccessTarget::Scholar(scholar_id) => {
+            access::revoke_user_access(pool.get_ref(), request.user_id, scholar_id).await
+        }
+        AccessTarget::Book(book_id) => {
+            access::revoke_user_content_access(pool.get_ref(), request.user_id, "book", book_id).await
+        }
+        AccessTarget::File(file_id) => {
+            access::revoke_user_content_access(pool.get_ref(), request.user_id, "file", file_id).await
+        }
+    }
+    .map_err(|e| {
             tracing::error!("Failed to revoke access: {:?}", e);
             AppError {
                 message: Some("Failed to revoke access".to_string()),
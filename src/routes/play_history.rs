@@ -1,34 +1,342 @@
 use crate::core::jwt_auth::JwtClaims;
 use crate::core::AppError;
+use crate::core::AppConfig;
 use crate::core::AppSuccessResponse;
-use crate::db::play_history;
-use crate::models::pagination::PaginationQuery;
-use crate::models::play_history::RecordPlayRequest;
-use actix_web::{delete, get, post, web, HttpResponse, Result};
+use crate::core::RedisHelper;
+use crate::core::utils::{client_ip, extract_guest_client_id, parse_days_window, parse_duration};
+use crate::db::{books, files, notifications, play_history};
+use crate::db::feature_flags::is_feature_enabled;
+use crate::models::feature_flags::FeatureFlag;
+use crate::models::notifications::NotificationKind;
+use crate::models::pagination::{PaginationMeta, PaginationQuery};
+use crate::models::play_history::{
+    CompleteFileRequest, FileCompletionResponse, MostPlayedQuery, PlayAction, RecordPlayRequest, SetListeningGoalRequest, SyncEntryResult, SyncEntryStatus,
+    SyncPlayEntry, SyncPlayHistoryResponse,
+};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Result};
+use chrono::{DateTime, Duration, Utc};
 use sqlx::MySqlPool;
 
-#[tracing::instrument(name = "Record Play History", skip(pool, claims, request))]
+const MAX_SYNC_ENTRIES: usize = 100;
+const MAX_FUTURE_SKEW_SECONDS: i64 = 300; // Tolerated device clock drift
+const SYNC_DEBOUNCE_SECONDS: i64 = 30;
+const COMPLETION_THRESHOLD_PERCENT: i64 = 95;
+const MAX_SESSION_ID_LENGTH: usize = 64;
+
+#[tracing::instrument(name = "Record Play History", skip(pool, config, redis_service, claims, req, request))]
 #[post("")]
 pub async fn record_play(
     pool: web::Data<MySqlPool>,
-    claims: JwtClaims,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    claims: Option<JwtClaims>,
+    req: HttpRequest,
     request: web::Json<RecordPlayRequest>,
+) -> Result<HttpResponse, AppError> {
+    let play_record = match claims {
+        Some(claims) => {
+            let user_id: i32 = claims
+                .sub
+                .parse()
+                .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+            let play = play_history::record_play(&pool, Some(user_id), None, None, &request).await?;
+            if matches!(request.play_action, PlayAction::Complete) {
+                record_player_completion(&pool, user_id, &request, play.played_at).await;
+            }
+            notify_if_listening_goal_met(&pool, user_id);
+            play
+        }
+        None => {
+            // The config switch says the deployment supports guest plays; the
+            // flag lets an admin pause them without a restart
+            if !config.guest_tracking.enabled
+                || !is_feature_enabled(&pool, &redis_service, FeatureFlag::GuestTracking).await
+            {
+                return Err(AppError::unauthorized("Authentication required"));
+            }
+            let anonymous_id = extract_guest_client_id(&req).ok_or_else(|| {
+                AppError::bad_request("A valid X-Client-Id header is required for guest plays")
+            })?;
+            let client_ip = client_ip(&req, &config);
+            check_guest_rate_limit(&redis_service, &config, &anonymous_id, client_ip.as_deref())
+                .await?;
+
+            play_history::record_play(
+                &pool,
+                None,
+                Some(&anonymous_id),
+                client_ip.as_deref(),
+                &request,
+            )
+            .await?
+        }
+    };
+
+    Ok(HttpResponse::Created().json(AppSuccessResponse {
+        success: true,
+        data: play_record,
+        message: "Play history recorded successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+/// Counts a guest play or download against both the client id and the IP, so
+/// rotating the client id alone does not lift the limit
+pub(crate) async fn check_guest_rate_limit(
+    redis_service: &RedisHelper,
+    config: &AppConfig,
+    anonymous_id: &str,
+    client_ip: Option<&str>,
+) -> Result<(), AppError> {
+    let window = std::time::Duration::from_secs(config.guest_tracking.rate_window_seconds);
+    let message = "Too many requests from this client. Please try again later";
+
+    redis_service
+        .check_rate_limit(
+            &format!("rate:guest:client:{}", anonymous_id),
+            config.guest_tracking.client_rate_limit,
+            window,
+        )
+        .await?
+        .check(message)?;
+
+    if let Some(ip) = client_ip {
+        redis_service
+            .check_rate_limit(
+                &format!("rate:guest:ip:{}", ip),
+                config.guest_tracking.ip_rate_limit,
+                window,
+            )
+            .await?
+            .check(message)?;
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Sync Play History", skip(pool, claims, entries))]
+#[post("/sync")]
+pub async fn sync_play_history(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    entries: web::Json<Vec<SyncPlayEntry>>,
 ) -> Result<HttpResponse, AppError> {
     let user_id: i32 = claims
         .sub
         .parse()
         .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
 
-    let play_record = play_history::record_play(&pool, user_id, &request).await?;
+    let entries = entries.into_inner();
+    if entries.is_empty() {
+        return Err(AppError::bad_request("No play entries to sync"));
+    }
+    if entries.len() > MAX_SYNC_ENTRIES {
+        return Err(AppError::bad_request(format!(
+            "Cannot sync more than {} play entries at once",
+            MAX_SYNC_ENTRIES
+        )));
+    }
 
-    Ok(HttpResponse::Created().json(AppSuccessResponse {
+    let file_ids: Vec<i32> = entries.iter().map(|e| e.file_id).collect();
+    let active_file_ids = play_history::get_active_file_ids(&pool, &file_ids).await?;
+    let latest_allowed = chrono::Utc::now() + chrono::Duration::seconds(MAX_FUTURE_SKEW_SECONDS);
+
+    let mut results = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let rejection = sync_entry_rejection(entry, &active_file_ids, latest_allowed);
+        let (status, reason) = match rejection {
+            Some(reason) => (SyncEntryStatus::Rejected, Some(reason.to_string())),
+            None => (
+                play_history::sync_play_entry(&pool, user_id, entry, SYNC_DEBOUNCE_SECONDS)
+                    .await?,
+                None,
+            ),
+        };
+
+        results.push(SyncEntryResult {
+            index,
+            file_id: entry.file_id,
+            status,
+            reason,
+        });
+    }
+
+    notify_if_listening_goal_met(&pool, user_id);
+
+    let count = |status: SyncEntryStatus| results.iter().filter(|r| r.status == status).count();
+    let response = SyncPlayHistoryResponse {
+        accepted: count(SyncEntryStatus::Accepted),
+        merged: count(SyncEntryStatus::Merged),
+        rejected: count(SyncEntryStatus::Rejected),
+        results,
+    };
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
-        data: play_record,
-        message: "Play history recorded successfully".to_string(),
+        data: response,
+        message: "Play history synced successfully".to_string(),
         pagination: None,
     }))
 }
 
+// Why an offline play entry can't be stored, if it can't
+fn sync_entry_rejection(
+    entry: &SyncPlayEntry,
+    active_file_ids: &[i32],
+    latest_allowed: DateTime<Utc>,
+) -> Option<&'static str> {
+    if entry.played_at > latest_allowed {
+        Some("played_at is in the future")
+    } else if entry.position_seconds < 0 {
+        Some("position_seconds cannot be negative")
+    } else if !active_file_ids.contains(&entry.file_id) {
+        Some("File not found")
+    } else {
+        None
+    }
+}
+
+/// Mark a file finished. The client's word is not enough: the user's last saved
+/// position must be at least `COMPLETION_THRESHOLD_PERCENT` of the duration
+#[tracing::instrument(name = "Complete File", skip(pool, claims, request))]
+#[post("/{file_id}/complete")]
+pub async fn complete_file(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    path: web::Path<i32>,
+    request: Option<web::Json<CompleteFileRequest>>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    let file_id = path.into_inner();
+    let now = Utc::now().naive_utc();
+
+    let session_key = match request
+        .and_then(|request| request.into_inner().session_id)
+        .map(|session_id| session_id.trim().to_string())
+        .filter(|session_id| !session_id.is_empty())
+    {
+        Some(session_id) if session_id.chars().count() > MAX_SESSION_ID_LENGTH => {
+            return Err(AppError::bad_request(format!(
+                "session_id cannot be longer than {} characters",
+                MAX_SESSION_ID_LENGTH
+            )));
+        }
+        Some(session_id) => session_id,
+        None => format!("day:{}", now.date()),
+    };
+
+    let stored_duration = files::fetch_file_duration(&pool, file_id).await?;
+    let (position, reported_duration) = play_history::get_last_saved_position(&pool, user_id, file_id)
+        .await?
+        .ok_or_else(|| AppError::bad_request("No saved position for this file yet"))?;
+    let duration = completion_duration(&stored_duration, reported_duration)
+        .ok_or_else(|| AppError::bad_request("The file's duration is unknown"))?;
+
+    if !reaches_completion(position, duration) {
+        return Err(AppError::bad_request(format!(
+            "Saved position {}s is short of {}% of the file's {}s",
+            position, COMPLETION_THRESHOLD_PERCENT, duration
+        )));
+    }
+
+    let recorded = play_history::record_file_completion(
+        &pool,
+        user_id,
+        file_id,
+        &session_key,
+        position,
+        duration,
+        now,
+    )
+    .await?;
+    if recorded {
+        if let Err(e) = books::sync_book_completion_for_file(&pool, user_id, file_id).await {
+            tracing::warn!("Failed to update book completion for user {}: {:?}", user_id, e);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: FileCompletionResponse {
+            file_id,
+            recorded,
+            position_seconds: position,
+            duration_seconds: duration,
+            completed_at: now,
+        },
+        message: if recorded {
+            "Completion recorded".to_string()
+        } else {
+            "Completion already recorded for this session".to_string()
+        },
+        pagination: None,
+    }))
+}
+
+/// Duration a completion is measured against. The stored duration wins; the
+/// player's report only covers files whose stored value can't be parsed
+fn completion_duration(stored_duration: &str, reported_duration: Option<i32>) -> Option<i32> {
+    parse_duration(stored_duration)
+        .ok()
+        .and_then(|seconds| i32::try_from(seconds).ok())
+        .filter(|seconds| *seconds > 0)
+        .or(reported_duration.filter(|seconds| *seconds > 0))
+}
+
+fn reaches_completion(position: i32, duration: i32) -> bool {
+    (position as i64) * 100 >= (duration as i64) * COMPLETION_THRESHOLD_PERCENT
+}
+
+// A player's Complete action counts once per day like a session-less
+// completion, and only past the same threshold `complete_file` checks.
+// Failures are logged only, they never fail the play being recorded
+async fn record_player_completion(
+    pool: &MySqlPool,
+    user_id: i32,
+    request: &RecordPlayRequest,
+    played_at: chrono::NaiveDateTime,
+) {
+    let result = async {
+        let stored_duration = files::fetch_file_duration(pool, request.file_id).await?;
+        let position = request.play_position.unwrap_or(0);
+        let Some(duration) = completion_duration(&stored_duration, request.total_duration) else {
+            return Ok(());
+        };
+        if !reaches_completion(position, duration) {
+            tracing::debug!(
+                "Complete for file {} at {}s of {}s is short of the threshold",
+                request.file_id,
+                position,
+                duration
+            );
+            return Ok(());
+        }
+
+        let recorded = play_history::record_file_completion(
+            pool,
+            user_id,
+            request.file_id,
+            &format!("day:{}", played_at.date()),
+            position,
+            duration,
+            played_at,
+        )
+        .await?;
+        if recorded {
+            books::sync_book_completion_for_file(pool, user_id, request.file_id).await?;
+        }
+        Ok::<(), AppError>(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to record completion for user {}: {:?}", user_id, e);
+    }
+}
+
 #[tracing::instrument(name = "Get User Play History", skip(pool, claims, pagination))]
 #[get("")]
 pub async fn get_my_play_history(
@@ -46,40 +354,62 @@ pub async fn get_my_play_history(
     let limit = pagination.per_page as i32;
     let offset = pagination.offset() as i32;
 
-    let history = play_history::get_user_play_history(&pool, user_id, Some(limit), Some(offset)).await?;
+    let (history, total_count) =
+        play_history::get_user_play_history(&pool, user_id, Some(limit), Some(offset)).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         data: history,
         message: "Play history retrieved successfully".to_string(),
-        pagination: None,
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
     }))
 
 }
 
-#[tracing::instrument(name = "Get Most Played Files", skip(pool, claims, query))]
+const MAX_MOST_PLAYED_WINDOW_DAYS: i64 = 365;
+
+#[tracing::instrument(name = "Get Most Played Files", skip(pool, config, claims, pagination))]
 #[get("most-played")]
 pub async fn get_most_played_files(
     pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
     claims: JwtClaims,
-    query: web::Query<serde_json::Value>,
+    query: web::Query<MostPlayedQuery>,
+    pagination: web::Query<PaginationQuery>,
 ) -> Result<HttpResponse, AppError> {
     let user_id: i32 = claims
         .sub
         .parse()
         .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
 
-    let limit = query.get("limit")
-        .and_then(|v| v.as_i64())
-        .map(|v| v as i32);
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
 
-    let most_played = play_history::get_user_most_played_files(&pool, user_id, limit).await?;
+    let since = match query.window.as_deref() {
+        Some(window) => {
+            let days = parse_days_window(window, MAX_MOST_PLAYED_WINDOW_DAYS)?;
+            Some((Utc::now() - Duration::days(days)).naive_utc())
+        }
+        None => None,
+    };
+
+    let (most_played, total_count) = play_history::get_user_most_played_files(
+        &pool,
+        &config,
+        user_id,
+        query.scholar_id,
+        query.book_id,
+        since,
+        pagination.per_page,
+        pagination.offset(),
+    )
+    .await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         data: most_played,
         message: "Most played files retrieved successfully".to_string(),
-        pagination: None,
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
     }))
 }
 
@@ -104,23 +434,177 @@ pub async fn clear_play_history(
     }))
 }
 
-#[tracing::instrument(name = "Get File Play Stats", skip(pool))]
+#[tracing::instrument(name = "Get File Play Stats", skip(pool, config))]
 #[get("/files/{file_id}/play-stats")]
 pub async fn get_file_play_stats(
     pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let file_id = path.into_inner();
-    let (total_plays, unique_listeners) = play_history::get_file_play_stats(&pool, file_id).await?;
+    let stats = play_history::get_file_play_stats(&pool, file_id).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         data: serde_json::json!({
             "file_id": file_id,
-            "total_plays": total_plays,
-            "unique_listeners": unique_listeners
+            "total_plays": stats.total_plays,
+            "unique_listeners": stats.unique_listeners,
+            "guest_plays": stats.guest_plays,
+            "unique_guests": stats.unique_guests,
+            // Plays older than this are pruned, so the counts only cover this window;
+            // null while nothing is pruned by age
+            "history_window_days": config.play_history_retention.history_window_days()
         }),
         message: "File play stats retrieved successfully".to_string(),
         pagination: None,
     }))
-}
\ No newline at end of file
+}
+
+const MAX_DAILY_GOAL_MINUTES: i32 = 24 * 60;
+
+#[tracing::instrument(name = "Set Listening Goal", skip(pool, claims, request))]
+#[put("/goal")]
+pub async fn set_listening_goal(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    request: web::Json<SetListeningGoalRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    if let Some(minutes) = request.daily_minutes {
+        if !(1..=MAX_DAILY_GOAL_MINUTES).contains(&minutes) {
+            return Err(AppError::bad_request(format!(
+                "daily_minutes must be between 1 and {}",
+                MAX_DAILY_GOAL_MINUTES
+            )));
+        }
+    }
+
+    play_history::set_listening_goal(&pool, user_id, request.daily_minutes).await?;
+    let progress = play_history::get_listening_goal_progress(&pool, user_id).await?;
+
+    let message = if request.daily_minutes.is_some() {
+        "Listening goal updated successfully"
+    } else {
+        "Listening goal removed successfully"
+    };
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: progress,
+        message: message.to_string(),
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Get Listening Goal Progress", skip(pool, claims))]
+#[get("/goal/progress")]
+pub async fn get_listening_goal_progress(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let progress = play_history::get_listening_goal_progress(&pool, user_id).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: progress,
+        message: "Listening goal progress retrieved successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+// Congratulate the user the first time today's goal is reached. Runs after the
+// response in the background, so the check never delays or fails the play
+fn notify_if_listening_goal_met(pool: &MySqlPool, user_id: i32) {
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let result = async {
+            let Some(progress) = play_history::get_unmet_listening_goal_progress(&pool, user_id).await? else {
+                return Ok(());
+            };
+            if progress.goal_met
+                && play_history::mark_listening_goal_met(&pool, user_id, progress.date).await?
+            {
+                notifications::create_system_notification(
+                    &pool,
+                    user_id,
+                    NotificationKind::ListeningGoalMet,
+                )
+                .await?;
+            }
+            Ok::<(), AppError>(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to check listening goal for user {}: {:?}", user_id, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_needs_95_percent_of_the_duration() {
+        assert!(reaches_completion(95, 100));
+        assert!(reaches_completion(100, 100));
+        assert!(!reaches_completion(94, 100));
+        // A forged position of 0 never completes
+        assert!(!reaches_completion(0, 3600));
+        assert!(reaches_completion(3420, 3600));
+    }
+
+    #[test]
+    fn completion_duration_prefers_the_stored_value() {
+        assert_eq!(completion_duration("10:00", Some(5)), Some(600));
+        assert_eq!(completion_duration("01:00:00", None), Some(3600));
+        // The player's report only fills in an unusable stored duration
+        assert_eq!(completion_duration("garbage", Some(300)), Some(300));
+        assert_eq!(completion_duration("00:00", Some(300)), Some(300));
+        assert_eq!(completion_duration("garbage", Some(0)), None);
+        assert_eq!(completion_duration("garbage", None), None);
+    }
+
+    #[test]
+    fn sync_rejects_future_negative_and_unknown_entries() {
+        let now = Utc::now();
+        let entry = |file_id, played_at, position_seconds| SyncPlayEntry {
+            file_id,
+            played_at,
+            position_seconds,
+            device_type: None,
+        };
+        let latest_allowed = now + Duration::seconds(MAX_FUTURE_SKEW_SECONDS);
+        let active = [1, 2];
+
+        assert_eq!(sync_entry_rejection(&entry(1, now, 30), &active, latest_allowed), None);
+        // Clock drift within the tolerance is accepted
+        assert_eq!(
+            sync_entry_rejection(&entry(1, now + Duration::seconds(60), 30), &active, latest_allowed),
+            None
+        );
+        assert_eq!(
+            sync_entry_rejection(&entry(1, now + Duration::hours(1), 30), &active, latest_allowed),
+            Some("played_at is in the future")
+        );
+        assert_eq!(
+            sync_entry_rejection(&entry(2, now, -1), &active, latest_allowed),
+            Some("position_seconds cannot be negative")
+        );
+        assert_eq!(
+            sync_entry_rejection(&entry(3, now, 30), &active, latest_allowed),
+            Some("File not found")
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use crate::core::jwt_auth::JwtClaims;
-use crate::core::{AppConfig, AppError, AppSuccessResponse};
-use crate::db::playlists;
-use crate::models::playlists::{CreatePlaylistRequest, UpdatePlaylistRequest, AddToPlaylistRequest};
-use crate::models::pagination::PaginationQuery;
-use actix_web::{delete, get, post, put, web, HttpResponse, Result};
+use crate::core::{extract_user_id_from_request, AppConfig, AppError, AppErrorType, AppSuccessResponse};
+use crate::db::{files, playlists, users};
+use crate::models::playlists::{
+    AddToPlaylistRequest, CreatePlaylistRequest, PlaylistWithFiles, UpdatePlaylistRequest,
+};
+use crate::models::pagination::{PaginationMeta, PaginationQuery};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Result};
 use sqlx::MySqlPool;
 
 #[tracing::instrument(name = "Create Playlist", skip(pool, claims, request))]
@@ -49,6 +51,34 @@ pub async fn get_my_playlists(
     }))
 }
 
+/// The caller's playlists, each flagged with whether it already holds the file
+#[tracing::instrument(name = "Get Playlists Containing File", skip(pool, claims))]
+#[get("/{file_id}/in-playlists")]
+pub async fn get_playlists_containing_file(
+    pool: web::Data<MySqlPool>,
+    claims: JwtClaims,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let file_id = path.into_inner();
+    if !files::file_exists(&pool, file_id).await? {
+        return Err(AppError::not_found("File not found"));
+    }
+
+    let memberships = playlists::get_user_playlists_containing(&pool, user_id, file_id).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: memberships,
+        message: "Playlists retrieved successfully".to_string(),
+        pagination: None,
+    }))
+}
+
 #[tracing::instrument(name = "Get Public Playlists", skip(pool, pagination))]
 #[get("/public")]
 pub async fn get_public_playlists(
@@ -60,24 +90,30 @@ pub async fn get_public_playlists(
     let limit = pagination.per_page as i32;
     let offset = pagination.offset() as i32;
 
-    let playlists_list = playlists::get_public_playlists(&pool, Some(limit), Some(offset)).await?;
+    let (playlists_list, total_count) =
+        playlists::get_public_playlists(&pool, Some(limit), Some(offset)).await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         data: playlists_list,
         message: "Public playlists retrieved successfully".to_string(),
-        pagination: None,
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total_count)),
     }))
 }
 
-#[tracing::instrument(name = "Get Playlist", skip(pool))]
+#[tracing::instrument(name = "Get Playlist", skip(pool, config, req))]
 #[get("/{playlist_id}")]
 pub async fn get_playlist(
     pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let playlist_id = path.into_inner();
-    let playlist = playlists::get_playlist_by_id(&pool, playlist_id).await?;
+    let viewer_id = extract_user_id_from_request(&req, &config);
+    let playlist = playlists::get_visible_playlist(&pool, playlist_id, viewer_id)
+        .await?
+        .ok_or_else(playlist_not_found)?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
@@ -111,6 +147,14 @@ pub async fn update_playlist(
     }))
 }
 
+fn delete_playlist_message(deleted: bool) -> &'static str {
+    if deleted {
+        "Playlist deleted successfully"
+    } else {
+        "Playlist already deleted"
+    }
+}
+
 #[tracing::instrument(name = "Delete Playlist", skip(pool, claims))]
 #[delete("/{playlist_id}")]
 pub async fn delete_playlist(
@@ -124,12 +168,14 @@ pub async fn delete_playlist(
         .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
 
     let playlist_id = path.into_inner();
-    playlists::delete_playlist(&pool, playlist_id, user_id).await?;
+    // Deleting twice answers the same way, so a retried request doesn't fail
+    let deleted = playlists::delete_playlist(&pool, playlist_id, user_id).await?;
+    let message = delete_playlist_message(deleted);
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
-        data: serde_json::json!({"message": "Playlist deleted successfully"}),
-        message: "Playlist deleted successfully".to_string(),
+        data: serde_json::json!({"message": message}),
+        message: message.to_string(),
         pagination: None,
     }))
 }
@@ -181,15 +227,27 @@ pub async fn remove_file_from_playlist(
     }))
 }
 
-#[tracing::instrument(name = "Get Playlist Files", skip(pool))]
+#[tracing::instrument(name = "Get Playlist Files", skip(pool, config, req))]
 #[get("/{playlist_id}/files")]
 pub async fn get_playlist_files(
     pool: web::Data<MySqlPool>,
     config: web::Data<AppConfig>,
+    req: HttpRequest,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let playlist_id = path.into_inner();
-    let files = playlists::get_playlist_files(&pool, &config, playlist_id).await?;
+    let viewer_id = extract_user_id_from_request(&req, &config);
+    playlists::get_visible_playlist(&pool, playlist_id, viewer_id)
+        .await?
+        .ok_or_else(playlist_not_found)?;
+
+    let files = playlists::get_playlist_files(
+        &pool,
+        &config,
+        playlist_id,
+        files::can_view_restricted(viewer_id),
+    )
+    .await?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
@@ -197,4 +255,59 @@ pub async fn get_playlist_files(
         message: "Playlist files retrieved successfully".to_string(),
         pagination: None,
     }))
-}
\ No newline at end of file
+}
+
+#[tracing::instrument(name = "Get Playlist With Files", skip(pool, config, req))]
+#[get("/{playlist_id}/full")]
+pub async fn get_playlist_with_files(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let playlist_id = path.into_inner();
+    let viewer_id = extract_user_id_from_request(&req, &config);
+    let playlist = playlists::get_visible_playlist(&pool, playlist_id, viewer_id)
+        .await?
+        .ok_or_else(playlist_not_found)?;
+
+    let owner = users::get_user_by_id(&pool, playlist.user_id).await?;
+    let files = playlists::get_playlist_files(
+        &pool,
+        &config,
+        playlist_id,
+        files::can_view_restricted(viewer_id),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: PlaylistWithFiles {
+            playlist,
+            owner_name: owner.name,
+            files,
+        },
+        message: "Playlist retrieved successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+// Private playlists of other users are reported as missing rather than forbidden
+fn playlist_not_found() -> AppError {
+    AppError {
+        message: Some("Playlist not found".to_string()),
+        cause: None,
+        error_type: AppErrorType::NotFoundError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_delete_answers_with_already_deleted() {
+        assert_eq!(delete_playlist_message(true), "Playlist deleted successfully");
+        assert_eq!(delete_playlist_message(false), "Playlist already deleted");
+    }
+}
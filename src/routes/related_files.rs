@@ -1,9 +1,14 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
 use sqlx::MySqlPool;
 use tracing::instrument;
 
 use crate::{
-    core::{AppConfig, AppError, AppSuccessResponse},
+    core::{
+        extract_user_id_from_request, AppConfig, AppError, AppErrorType, AppSuccessResponse,
+        RedisHelper,
+    },
+    db::{feature_flags::is_feature_enabled, files, playlists},
+    models::feature_flags::FeatureFlag,
 };
 
 #[derive(serde::Serialize)]
@@ -32,6 +37,7 @@ pub struct FileSuggestions {
     pub same_book: Vec<SimpleFileInfo>,
     pub same_scholar: Vec<SimpleFileInfo>,
     pub popular: Vec<SimpleFileInfo>,
+    pub because_you_listened: Vec<SimpleFileInfo>,
 }
 
 #[derive(serde::Serialize)]
@@ -44,22 +50,30 @@ pub struct SimpleFileInfo {
     pub scholar_name: String,
 }
 
-#[instrument(name = "Get File Suggestions", skip(pool, config))]
+#[instrument(name = "Get File Suggestions", skip(pool, config, redis_service, req))]
 #[get("/{file_id}/suggestions")]
 pub async fn get_file_suggestions(
     pool: web::Data<MySqlPool>,
     config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
     file_id: web::Path<i32>,
     query: web::Query<RelatedFilesQuery>,
+    req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
     let file_id = file_id.into_inner();
-    let limit = query.limit.unwrap_or(10).min(50); // Max 50 suggestions
+    let limit = query.limit.unwrap_or(10).clamp(1, 50); // At least 1, max 50 suggestions
+    let buckets = SuggestionBuckets::from_include(query.include.as_deref()).with_recommendations(
+        is_feature_enabled(&pool, &redis_service, FeatureFlag::Recommendations).await,
+    );
+    let user_id = extract_user_id_from_request(&req, &config);
+    let can_view = files::can_view_restricted(user_id);
 
     // Get current file info with book and scholar details
-    let current_file = get_current_file_info(&pool, &config, file_id).await?;
+    let current_file = get_current_file_info(&pool, &config, file_id, can_view).await?;
     
     // Get suggestions
-    let suggestions = build_file_suggestions(&pool, &config, file_id, limit).await?;
+    let suggestions =
+        build_file_suggestions(&pool, &config, file_id, limit, &buckets, user_id, can_view).await?;
 
     let response = RelatedFilesResponse {
         current_file,
@@ -74,103 +88,106 @@ pub async fn get_file_suggestions(
     }))
 }
 
-async fn get_current_file_info(
-    pool: &MySqlPool,
-    config: &AppConfig,
-    file_id: i32,
-) -> Result<Option<CurrentFileInfo>, AppError> {
-    let file_info = sqlx::query!(
-        r#"
-        SELECT 
-            f.id as file_id,
-            f.name as file_name,
-            f.location,
-            f.book as book_id,
-            b.name as book_name,
-            s.id as scholar_id,
-            s.name as scholar_name
-        FROM tbl_files f
-        JOIN tbl_books b ON f.book = b.id
-        JOIN tbl_scholars s ON b.scholar_id = s.id
-        WHERE f.id = ? AND f.status = 'active'
-        "#,
-        file_id
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(AppError::db_error)?;
+/// Next file for auto-advance. Books follow the same order as play-all;
+/// playlists follow `sort_order`. `data` is null after the last file
+#[instrument(name = "Get Next File", skip(pool, config, req))]
+#[get("/{file_id}/next")]
+pub async fn get_next_file(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    file_id: web::Path<i32>,
+    query: web::Query<NextFileQuery>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let file_id = file_id.into_inner();
+    let user_id = extract_user_id_from_request(&req, &config);
+    let can_view = files::can_view_restricted(user_id);
 
-    if let Some(row) = file_info {
-        // Get additional info with separate queries
-        let total_files: i64 = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM tbl_files WHERE book = ? AND status = 'active'",
-            row.book_id
-        )
-        .fetch_one(pool)
-        .await
-        .map_err(AppError::db_error)?;
+    let next = match query.context.as_deref().unwrap_or("book") {
+        "book" => next_in_book(&pool, &config, file_id, query.context_id, can_view).await?,
+        "playlist" => {
+            let playlist_id = query.context_id.ok_or_else(|| {
+                AppError::bad_request("context_id is required for playlist context")
+            })?;
+            playlists::get_visible_playlist(&pool, playlist_id, user_id)
+                .await?
+                .ok_or_else(|| not_found("Playlist not found"))?;
 
-        let position: i64 = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM tbl_files WHERE book = ? AND id <= ? AND status = 'active'",
-            row.book_id,
-            row.file_id
-        )
-        .fetch_one(pool)
-        .await
-        .map_err(AppError::db_error)?;
+            next_in_playlist(&pool, &config, file_id, playlist_id, can_view).await?
+        }
+        _ => {
+            return Err(AppError::bad_request(
+                "Invalid context, expected one of: book, playlist",
+            ))
+        }
+    };
 
-        Ok(Some(CurrentFileInfo {
-            file_id: row.file_id,
-            file_name: row.file_name,
-            file_url: config.get_upload_url(&row.location),
-            book_id: row.book_id,
-            book_name: row.book_name,
-            scholar_id: row.scholar_id,
-            scholar_name: row.scholar_name,
-            position_in_book: Some(position as i32),
-            total_files_in_book: total_files as i32,
-        }))
-    } else {
-        Ok(None)
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: match next {
+            Some(_) => "Next file retrieved successfully".to_string(),
+            None => "No more files in this context".to_string(),
+        },
+        data: next,
+        pagination: None,
+    }))
+}
+
+fn not_found(message: &str) -> AppError {
+    AppError {
+        message: Some(message.to_string()),
+        cause: None,
+        error_type: AppErrorType::NotFoundError,
     }
 }
 
-async fn build_file_suggestions(
+async fn next_in_book(
     pool: &MySqlPool,
     config: &AppConfig,
     file_id: i32,
-    limit: i32,
-) -> Result<FileSuggestions, AppError> {
-    // Get next file in same book
-    let next_in_book = sqlx::query!(
+    book_id: Option<i32>,
+    can_view: bool,
+) -> Result<Option<SimpleFileInfo>, AppError> {
+    let current_book = sqlx::query_scalar!(
+        "SELECT book FROM tbl_files WHERE id = ? AND status = 'active' AND (restricted = FALSE OR ?)",
+        file_id,
+        can_view
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| not_found("File not found"))?;
+
+    if book_id.is_some_and(|id| id != current_book) {
+        return Err(not_found("File is not part of this book"));
+    }
+
+    // Ids only, in play-all order
+    let book_order: Vec<i32> = sqlx::query_scalar!(
         r#"
-        SELECT 
-            f.id as file_id,
-            f.name as file_name,
-            f.location,
-            f.duration as file_duration,
-            b.name as book_name,
-            s.name as scholar_name
+        SELECT f.id
         FROM tbl_files f
         JOIN tbl_books b ON f.book = b.id
-        JOIN tbl_scholars s ON b.scholar_id = s.id
-        WHERE f.book = (SELECT book FROM tbl_files WHERE id = ?)
-        AND f.id > ?
+        WHERE f.book = ?
         AND f.status = 'active'
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
         ORDER BY f.date ASC, f.id ASC
-        LIMIT 1
         "#,
-        file_id,
-        file_id
+        current_book,
+        can_view
     )
-    .fetch_optional(pool)
+    .fetch_all(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    // Get previous file in same book
-    let previous_in_book = sqlx::query!(
+    let Some(next_id) = file_after(&book_order, file_id) else {
+        return Ok(None);
+    };
+
+    let next = sqlx::query!(
         r#"
-        SELECT 
+        SELECT
             f.id as file_id,
             f.name as file_name,
             f.location,
@@ -180,103 +197,397 @@ async fn build_file_suggestions(
         FROM tbl_files f
         JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON b.scholar_id = s.id
-        WHERE f.book = (SELECT book FROM tbl_files WHERE id = ?)
-        AND f.id < ?
-        AND f.status = 'active'
-        ORDER BY f.date DESC, f.id DESC
-        LIMIT 1
+        WHERE f.id = ?
         "#,
-        file_id,
-        file_id
+        next_id
     )
     .fetch_optional(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    // Get other files from same book
-    let same_book = sqlx::query!(
+    Ok(next.map(|row| SimpleFileInfo {
+        file_id: row.file_id,
+        file_name: row.file_name,
+        file_url: config.get_upload_url(&row.location),
+        file_duration: row.file_duration,
+        book_name: row.book_name,
+        scholar_name: row.scholar_name,
+    }))
+}
+
+/// The file after `file_id` in `order`; None at the end or when it isn't listed
+fn file_after(order: &[i32], file_id: i32) -> Option<i32> {
+    let position = order.iter().position(|&id| id == file_id)?;
+    order.get(position + 1).copied()
+}
+
+async fn next_in_playlist(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    file_id: i32,
+    playlist_id: i32,
+    can_view: bool,
+) -> Result<Option<SimpleFileInfo>, AppError> {
+    // Ties on sort_order fall back to insertion order, matching the playlist listing
+    let current = sqlx::query!(
         r#"
-        SELECT 
-            f.id as file_id,
-            f.name as file_name,
-            f.location,
-            f.duration as file_duration,
-            b.name as book_name,
-            s.name as scholar_name
-        FROM tbl_files f
-        JOIN tbl_books b ON f.book = b.id
-        JOIN tbl_scholars s ON b.scholar_id = s.id
-        WHERE f.book = (SELECT book FROM tbl_files WHERE id = ?)
-        AND f.id != ?
-        AND f.status = 'active'
-        ORDER BY f.date ASC, f.id ASC
-        LIMIT ?
+        SELECT id, sort_order, created_at
+        FROM tbl_playlist_files
+        WHERE playlist_id = ? AND file_id = ?
         "#,
-        file_id,
-        file_id,
-        limit
+        playlist_id,
+        file_id
     )
-    .fetch_all(pool)
+    .fetch_optional(pool)
     .await
-    .map_err(AppError::db_error)?;
+    .map_err(AppError::db_error)?
+    .ok_or_else(|| not_found("File is not part of this playlist"))?;
+    let sort_order = current.sort_order.unwrap_or(0);
 
-    // Get files from same scholar (different books)
-    let same_scholar = sqlx::query!(
+    let next = sqlx::query!(
         r#"
-        SELECT 
+        SELECT
             f.id as file_id,
             f.name as file_name,
             f.location,
             f.duration as file_duration,
             b.name as book_name,
             s.name as scholar_name
-        FROM tbl_files f
+        FROM tbl_playlist_files pf
+        JOIN tbl_files f ON pf.file_id = f.id
         JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON b.scholar_id = s.id
-        WHERE s.id = (SELECT s2.id FROM tbl_files f2 
-                      JOIN tbl_books b2 ON f2.book = b2.id 
-                      JOIN tbl_scholars s2 ON b2.scholar_id = s2.id 
-                      WHERE f2.id = ?)
-        AND f.book != (SELECT book FROM tbl_files WHERE id = ?)
-        AND f.id != ?
+        WHERE pf.playlist_id = ?
         AND f.status = 'active'
-        ORDER BY f.downloads DESC, f.date DESC
-        LIMIT ?
+        AND (f.restricted = FALSE OR ?)
+        AND is_published(f.publish_at, b.publish_at)
+        AND (
+            COALESCE(pf.sort_order, 0) > ?
+            OR (COALESCE(pf.sort_order, 0) = ? AND pf.created_at > ?)
+            OR (COALESCE(pf.sort_order, 0) = ? AND pf.created_at = ? AND pf.id > ?)
+        )
+        ORDER BY COALESCE(pf.sort_order, 0) ASC, pf.created_at ASC, pf.id ASC
+        LIMIT 1
         "#,
-        file_id,
-        file_id,
-        file_id,
-        limit
+        playlist_id,
+        can_view,
+        sort_order,
+        sort_order,
+        current.created_at,
+        sort_order,
+        current.created_at,
+        current.id
     )
-    .fetch_all(pool)
+    .fetch_optional(pool)
     .await
     .map_err(AppError::db_error)?;
 
-    // Get popular files (most downloaded)
-    let popular = sqlx::query!(
+    Ok(next.map(|row| SimpleFileInfo {
+        file_id: row.file_id,
+        file_name: row.file_name,
+        file_url: config.get_upload_url(&row.location),
+        file_duration: row.file_duration,
+        book_name: row.book_name,
+        scholar_name: row.scholar_name,
+    }))
+}
+
+async fn get_current_file_info(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    file_id: i32,
+    can_view: bool,
+) -> Result<Option<CurrentFileInfo>, AppError> {
+    let file_info = sqlx::query!(
         r#"
         SELECT 
             f.id as file_id,
             f.name as file_name,
             f.location,
-            f.duration as file_duration,
+            f.book as book_id,
             b.name as book_name,
+            s.id as scholar_id,
             s.name as scholar_name
         FROM tbl_files f
         JOIN tbl_books b ON f.book = b.id
         JOIN tbl_scholars s ON b.scholar_id = s.id
-        WHERE f.id != ?
-        AND f.status = 'active'
-        ORDER BY f.downloads DESC, f.date DESC
-        LIMIT ?
+        WHERE f.id = ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
         "#,
         file_id,
-        limit
+        can_view
     )
-    .fetch_all(pool)
+    .fetch_optional(pool)
     .await
     .map_err(AppError::db_error)?;
 
+    if let Some(row) = file_info {
+        // Get additional info with separate queries
+        let total_files: i64 = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            WHERE f.book = ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            "#,
+            row.book_id,
+            can_view
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+        let position: i64 = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            WHERE f.book = ? AND f.id <= ? AND f.status = 'active' AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            "#,
+            row.book_id,
+            row.file_id,
+            can_view
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::db_error)?;
+
+        Ok(Some(CurrentFileInfo {
+            file_id: row.file_id,
+            file_name: row.file_name,
+            file_url: config.get_upload_url(&row.location),
+            book_id: row.book_id,
+            book_name: row.book_name,
+            scholar_id: row.scholar_id,
+            scholar_name: row.scholar_name,
+            position_in_book: Some(position as i32),
+            total_files_in_book: total_files as i32,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
+async fn build_file_suggestions(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    file_id: i32,
+    limit: i32,
+    buckets: &SuggestionBuckets,
+    user_id: Option<i32>,
+    can_view: bool,
+) -> Result<FileSuggestions, AppError> {
+    // Unrequested buckets are skipped without a query
+
+    // Get next file in same book
+    let next_in_book = if buckets.adjacent {
+        sqlx::query!(
+            r#"
+            SELECT 
+                f.id as file_id,
+                f.name as file_name,
+                f.location,
+                f.duration as file_duration,
+                b.name as book_name,
+                s.name as scholar_name
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            JOIN tbl_scholars s ON b.scholar_id = s.id
+            WHERE f.book = (SELECT book FROM tbl_files WHERE id = ?)
+            AND f.id > ?
+            AND f.status = 'active'
+            AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            ORDER BY f.date ASC, f.id ASC
+            LIMIT 1
+            "#,
+            file_id,
+            file_id,
+            can_view
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::db_error)?
+    } else {
+        None
+    };
+
+    // Get previous file in same book
+    let previous_in_book = if buckets.adjacent {
+        sqlx::query!(
+            r#"
+            SELECT 
+                f.id as file_id,
+                f.name as file_name,
+                f.location,
+                f.duration as file_duration,
+                b.name as book_name,
+                s.name as scholar_name
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            JOIN tbl_scholars s ON b.scholar_id = s.id
+            WHERE f.book = (SELECT book FROM tbl_files WHERE id = ?)
+            AND f.id < ?
+            AND f.status = 'active'
+            AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            ORDER BY f.date DESC, f.id DESC
+            LIMIT 1
+            "#,
+            file_id,
+            file_id,
+            can_view
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::db_error)?
+    } else {
+        None
+    };
+
+    // Get other files from same book
+    let same_book = if buckets.same_book {
+        sqlx::query!(
+            r#"
+            SELECT 
+                f.id as file_id,
+                f.name as file_name,
+                f.location,
+                f.duration as file_duration,
+                b.name as book_name,
+                s.name as scholar_name
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            JOIN tbl_scholars s ON b.scholar_id = s.id
+            WHERE f.book = (SELECT book FROM tbl_files WHERE id = ?)
+            AND f.id != ?
+            AND f.status = 'active'
+            AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            ORDER BY f.date ASC, f.id ASC
+            LIMIT ?
+            "#,
+            file_id,
+            file_id,
+            can_view,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?
+    } else {
+        Vec::new()
+    };
+
+    // Get files from same scholar (different books)
+    let same_scholar = if buckets.same_scholar {
+        sqlx::query!(
+            r#"
+            SELECT 
+                f.id as file_id,
+                f.name as file_name,
+                f.location,
+                f.duration as file_duration,
+                b.name as book_name,
+                s.name as scholar_name
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            JOIN tbl_scholars s ON b.scholar_id = s.id
+            WHERE s.id = (SELECT s2.id FROM tbl_files f2 
+                          JOIN tbl_books b2 ON f2.book = b2.id 
+                          JOIN tbl_scholars s2 ON b2.scholar_id = s2.id 
+                          WHERE f2.id = ?)
+            AND f.book != (SELECT book FROM tbl_files WHERE id = ?)
+            AND f.id != ?
+            AND f.status = 'active'
+            AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            ORDER BY f.downloads DESC, f.date DESC
+            LIMIT ?
+            "#,
+            file_id,
+            file_id,
+            file_id,
+            can_view,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?
+    } else {
+        Vec::new()
+    };
+
+    // Get popular files (most downloaded)
+    let popular = if buckets.popular {
+        sqlx::query!(
+            r#"
+            SELECT 
+                f.id as file_id,
+                f.name as file_name,
+                f.location,
+                f.duration as file_duration,
+                b.name as book_name,
+                s.name as scholar_name
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            JOIN tbl_scholars s ON b.scholar_id = s.id
+            WHERE f.id != ?
+            AND f.status = 'active'
+            AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            ORDER BY f.downloads DESC, f.date DESC
+            LIMIT ?
+            "#,
+            file_id,
+            can_view,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?
+    } else {
+        Vec::new()
+    };
+
+    // Unplayed files from scholars the user has listened to; needs an authenticated user
+    let because_you_listened = if buckets.because_you_listened && user_id.is_some() {
+        sqlx::query!(
+            r#"
+            SELECT 
+                f.id as file_id,
+                f.name as file_name,
+                f.location,
+                f.duration as file_duration,
+                b.name as book_name,
+                s.name as scholar_name
+            FROM tbl_files f
+            JOIN tbl_books b ON f.book = b.id
+            JOIN tbl_scholars s ON b.scholar_id = s.id
+            WHERE b.scholar_id IN (SELECT DISTINCT b2.scholar_id FROM tbl_play_history ph
+                                   JOIN tbl_files f2 ON ph.file_id = f2.id
+                                   JOIN tbl_books b2 ON f2.book = b2.id
+                                   WHERE ph.user_id = ?)
+            AND f.id NOT IN (SELECT file_id FROM tbl_play_history WHERE user_id = ?)
+            AND f.id != ?
+            AND f.status = 'active'
+            AND (f.restricted = FALSE OR ?)
+            AND is_published(f.publish_at, b.publish_at)
+            ORDER BY f.downloads DESC, f.date DESC
+            LIMIT ?
+            "#,
+            user_id,
+            user_id,
+            file_id,
+            can_view,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::db_error)?
+    } else {
+        Vec::new()
+    };
+
     // Convert to response format
     let next_in_book = next_in_book.map(|row| SimpleFileInfo {
         file_id: row.file_id,
@@ -323,16 +634,127 @@ async fn build_file_suggestions(
         scholar_name: row.scholar_name,
     }).collect();
 
+    let because_you_listened: Vec<SimpleFileInfo> = because_you_listened.into_iter().map(|row| SimpleFileInfo {
+        file_id: row.file_id,
+        file_name: row.file_name,
+        file_url: config.get_upload_url(&row.location),
+        file_duration: row.file_duration,
+        book_name: row.book_name,
+        scholar_name: row.scholar_name,
+    }).collect();
+
     Ok(FileSuggestions {
         next_in_book,
         previous_in_book,
         same_book,
         same_scholar,
         popular,
+        because_you_listened,
     })
 }
 
+/// Which suggestion buckets to build, parsed from `include=same_book,popular,...`
+pub struct SuggestionBuckets {
+    pub adjacent: bool, // next_in_book and previous_in_book
+    pub same_book: bool,
+    pub same_scholar: bool,
+    pub popular: bool,
+    pub because_you_listened: bool,
+}
+
+impl SuggestionBuckets {
+    pub fn from_include(include: Option<&str>) -> Self {
+        let include = match include.map(str::trim).filter(|s| !s.is_empty()) {
+            Some(include) => include,
+            // Default mix when the client does not choose
+            None => {
+                return Self {
+                    adjacent: true,
+                    same_book: true,
+                    same_scholar: true,
+                    popular: true,
+                    because_you_listened: false,
+                }
+            }
+        };
+
+        let requested: Vec<&str> = include.split(',').map(str::trim).collect();
+        let has = |name: &str| requested.contains(&name);
+
+        Self {
+            adjacent: has("adjacent") || has("next") || has("previous"),
+            same_book: has("same_book"),
+            same_scholar: has("same_scholar"),
+            popular: has("popular"),
+            because_you_listened: has("because_you_listened"),
+        }
+    }
+
+    /// Without the `recommendations` flag the listening-based buckets come back
+    /// empty rather than erroring
+    pub fn with_recommendations(mut self, enabled: bool) -> Self {
+        if !enabled {
+            self.popular = false;
+            self.because_you_listened = false;
+        }
+        self
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct RelatedFilesQuery {
     pub limit: Option<i32>,
-}
\ No newline at end of file
+    pub include: Option<String>, // Comma-separated buckets, defaults to all but because_you_listened
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct NextFileQuery {
+    pub context: Option<String>, // "book" (default) or "playlist"
+    pub context_id: Option<i32>, // Book or playlist id; required for playlists
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::feature_flags::{flag_enabled, flag_states};
+    use crate::models::feature_flags::FeatureFlagOverride;
+
+    #[test]
+    fn toggling_recommendations_switches_the_listening_buckets() {
+        let requested =
+            || SuggestionBuckets::from_include(Some("same_book,popular,because_you_listened"));
+        let toggled = |enabled| {
+            flag_states(&[FeatureFlagOverride {
+                flag_key: FeatureFlag::Recommendations.key().to_string(),
+                enabled,
+                updated_by: Some(1),
+                updated_at: chrono::Utc::now().naive_utc(),
+            }])
+        };
+
+        // Off by default
+        let buckets = requested()
+            .with_recommendations(flag_enabled(&flag_states(&[]), FeatureFlag::Recommendations));
+        assert!(buckets.same_book && !buckets.popular && !buckets.because_you_listened);
+
+        let buckets = requested()
+            .with_recommendations(flag_enabled(&toggled(true), FeatureFlag::Recommendations));
+        assert!(buckets.same_book && buckets.popular && buckets.because_you_listened);
+
+        let buckets = requested()
+            .with_recommendations(flag_enabled(&toggled(false), FeatureFlag::Recommendations));
+        assert!(!buckets.popular && !buckets.because_you_listened);
+    }
+
+    #[test]
+    fn next_in_book_follows_play_all_order() {
+        // Ids as listed by date, then id
+        let book_order = [14, 9, 21, 30];
+
+        assert_eq!(file_after(&book_order, 9), Some(21));
+        assert_eq!(file_after(&book_order, 14), Some(9));
+        // The last file has nothing after it
+        assert_eq!(file_after(&book_order, 30), None);
+        assert_eq!(file_after(&book_order, 99), None);
+    }
+}
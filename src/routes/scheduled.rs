@@ -0,0 +1,172 @@
+use actix_web::{
+    delete, get, post,
+    web::{self},
+    HttpRequest, HttpResponse, Responder,
+};
+use chrono::Duration;
+use sqlx::MySqlPool;
+use tracing::instrument;
+
+use crate::{
+    core::{
+        extract_user_id_from_request, jwt_auth::JwtMiddleware, AppConfig, AppError,
+        AppSuccessResponse,
+    },
+    db::{books, files, scheduled, users},
+    models::scheduled::{CreateScheduleRequest, ScheduleListQuery, SCHEDULED_CONTENT_TYPES},
+};
+
+const DEFAULT_SCHEDULE_WINDOW_DAYS: i64 = 30;
+const MAX_SCHEDULE_WINDOW_DAYS: i64 = 366;
+const MAX_NOTE_LENGTH: usize = 255;
+
+/// Content scheduled for the current date in the platform timezone
+#[instrument(name = "Get Scheduled Today", skip(pool, config, req))]
+#[get("/today")]
+pub async fn get_scheduled_today(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let today = config.scheduling.today();
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
+
+    let items = scheduled::fetch_schedules(pool.get_ref(), &config, today, today, include_restricted).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Scheduled content retrieved successfully".to_string(),
+        data: Some(items),
+        pagination: None,
+    }))
+}
+
+/// Schedules between `from` and `to`, defaulting to the next 30 days
+#[instrument(name = "Get Schedules", skip(pool, config, auth))]
+#[get("/schedules")]
+pub async fn get_schedules(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    auth: JwtMiddleware,
+    query: web::Query<ScheduleListQuery>,
+) -> Result<impl Responder, AppError> {
+    let user = users::get_user_by_id(pool.get_ref(), auth.user_id).await?;
+    if user.role != "admin" {
+        return Err(AppError::forbidden_error("Only admins can manage schedules"));
+    }
+
+    let from = query.from.unwrap_or_else(|| config.scheduling.today());
+    let to = query
+        .to
+        .unwrap_or(from + Duration::days(DEFAULT_SCHEDULE_WINDOW_DAYS));
+    if to < from {
+        return Err(AppError::bad_request("`to` cannot be before `from`"));
+    }
+    if (to - from).num_days() > MAX_SCHEDULE_WINDOW_DAYS {
+        return Err(AppError::bad_request(format!(
+            "The range cannot be longer than {} days",
+            MAX_SCHEDULE_WINDOW_DAYS
+        )));
+    }
+
+    let items = scheduled::fetch_schedules(pool.get_ref(), &config, from, to, true).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Schedules retrieved successfully".to_string(),
+        data: Some(items),
+        pagination: None,
+    }))
+}
+
+#[instrument(name = "Create Schedule", skip(pool, config, auth))]
+#[post("/schedules")]
+pub async fn create_schedule(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    auth: JwtMiddleware,
+    request: web::Json<CreateScheduleRequest>,
+) -> Result<impl Responder, AppError> {
+    let user = users::get_user_by_id(pool.get_ref(), auth.user_id).await?;
+    if user.role != "admin" {
+        return Err(AppError::forbidden_error("Only admins can manage schedules"));
+    }
+
+    let mut request = request.into_inner();
+    request.content_type = request.content_type.trim().to_lowercase();
+    if !SCHEDULED_CONTENT_TYPES.contains(&request.content_type.as_str()) {
+        return Err(AppError::bad_request(format!(
+            "content_type must be one of: {}",
+            SCHEDULED_CONTENT_TYPES.join(", ")
+        )));
+    }
+
+    let note = request
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+    if note.is_some_and(|note| note.chars().count() > MAX_NOTE_LENGTH) {
+        return Err(AppError::bad_request(format!(
+            "Note cannot be longer than {} characters",
+            MAX_NOTE_LENGTH
+        )));
+    }
+
+    match request.content_type.as_str() {
+        "book" => {
+            books::assert_book_active(pool.get_ref(), request.content_id).await?;
+        }
+        _ => {
+            if !files::active_file_exists(pool.get_ref(), request.content_id).await? {
+                return Err(AppError::not_found("File not found"));
+            }
+        }
+    }
+
+    let publish_at = if request.hide_until_surfaced {
+        if request.surface_on <= config.scheduling.today() {
+            return Err(AppError::bad_request(
+                "Only content scheduled for a future date can be hidden until then",
+            ));
+        }
+        Some(config.scheduling.start_of_day_utc(request.surface_on))
+    } else {
+        None
+    };
+
+    let schedule_id =
+        scheduled::create_schedule(pool.get_ref(), &request, note, publish_at, auth.user_id).await?;
+
+    Ok(HttpResponse::Created().json(AppSuccessResponse {
+        success: true,
+        message: format!("Scheduled for {}", request.surface_on),
+        data: Some(serde_json::json!({ "id": schedule_id, "publish_at": publish_at })),
+        pagination: None,
+    }))
+}
+
+/// Content the schedule hid goes back into listings straight away
+#[instrument(name = "Delete Schedule", skip(pool, auth))]
+#[delete("/schedules/{schedule_id}")]
+pub async fn delete_schedule(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    schedule_id: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let user = users::get_user_by_id(pool.get_ref(), auth.user_id).await?;
+    if user.role != "admin" {
+        return Err(AppError::forbidden_error("Only admins can manage schedules"));
+    }
+
+    if !scheduled::delete_schedule(pool.get_ref(), schedule_id.into_inner()).await? {
+        return Err(AppError::not_found("Schedule not found"));
+    }
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Schedule removed".to_string(),
+        data: None::<()>,
+        pagination: None,
+    }))
+}
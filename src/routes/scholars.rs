@@ -1,6 +1,6 @@
 use crate::{
-    core::{extract_user_id_from_request, jwt_auth::JwtMiddleware, slugify, AppConfig, AppError, AppErrorType, AppSuccessResponse},
-    models::{pagination::{PaginationMeta, PaginationQuery}, scholars::{CreateScholarRequest, UpdateScholarRequest}},
+    core::{attachment_disposition, csv_field, error_codes, extract_user_id_from_request, is_valid_http_url, jwt_auth::JwtMiddleware, parse_days_window, prepare_storage_location, slugify, validate_about, validate_name, StagedFile, AppConfig, AppError, AppErrorType, AppSuccessResponse, RedisHelper, VersionConflictResponse},
+    models::{access::{CanManageResponse, ManageReason}, books::Book, files::{FilesWithStats, RecentFiles}, pagination::{PaginationMeta, PaginationQuery}, scholars::{AddScholarLinkRequest, CreateScholarRequest, ScholarDetails, ScholarCatalogQuery, ScholarDropdownQuery, ScholarFilterQuery, ScholarHome, ScholarListQuery, ScholarReportQuery, ScholarReportRow, FeaturedScholarsQuery, SetFeaturedScholarsRequest, TopFile, TopFilesQuery, TrendingScholarsQuery, UpdateScholarRequest, SCHOLAR_LINK_TYPES, TOP_FILES_METRICS}},
 };
 use actix_multipart::Multipart;
 use actix_web::{
@@ -9,11 +9,12 @@ use actix_web::{
     HttpRequest, HttpResponse, Responder,
 };
 use futures_util::TryStreamExt as _;
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
 use uuid::Uuid;
 
-use crate::db::scholars;
+use crate::db::{access, books, files, scholars};
 use sqlx::MySqlPool;
 use tracing::instrument;
 
@@ -22,12 +23,20 @@ use tracing::instrument;
 pub async fn get_scholars(
     pool: web::Data<MySqlPool>,
     config: web::Data<AppConfig>,
+    query: web::Query<ScholarListQuery>,
     pagination: web::Query<PaginationQuery>,
 ) -> Result<impl Responder, AppError> {
     let mut pagination = pagination.into_inner();
     pagination.validate();
 
-    let (data, total_items) = scholars::fetch_scholars(pool.get_ref(), &config, &pagination)
+    let sort = query.sort.as_deref().unwrap_or("priority");
+    if !matches!(sort, "priority" | "recent") {
+        return Err(AppError::bad_request(
+            "Invalid sort, expected one of: priority, recent",
+        ));
+    }
+
+    let (data, total_items) = scholars::fetch_scholars(pool.get_ref(), &config, sort, &pagination)
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch scholars: {:?}", e);
@@ -88,6 +97,235 @@ pub async fn get_scholars_by_state(
         pagination: Some(pagination_meta),
     }))
 }
+const MAX_FILTER_STATES: usize = 20;
+
+#[instrument(name = "Filter Scholars", skip(pool, config))]
+#[get("/filter")]
+pub async fn get_scholars_filtered(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    filter: web::Query<ScholarFilterQuery>,
+    pagination: web::Query<PaginationQuery>,
+) -> Result<impl Responder, AppError> {
+    let mut pagination = pagination.into_inner();
+    pagination.validate();
+
+    let state_ids = match filter.states.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(states) => states
+            .split(',')
+            .map(|id| match id.trim().parse::<i32>() {
+                Ok(id) if id > 0 => Ok(id),
+                _ => Err(AppError::bad_request(format!("Invalid state id: {}", id.trim()))),
+            })
+            .collect::<Result<Vec<i32>, AppError>>()?,
+        None => Vec::new(),
+    };
+    if state_ids.len() > MAX_FILTER_STATES {
+        return Err(AppError::bad_request(format!(
+            "Cannot filter by more than {} states",
+            MAX_FILTER_STATES
+        )));
+    }
+
+    let sort = filter.sort.as_deref().unwrap_or("priority");
+    if !matches!(sort, "priority" | "name" | "newest" | "recent") {
+        return Err(AppError::bad_request(
+            "Invalid sort, expected one of: priority, name, newest, recent",
+        ));
+    }
+
+    let name_query = filter.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    let (data, total_items) = scholars::fetch_scholars_filtered(
+        pool.get_ref(),
+        &config,
+        &state_ids,
+        name_query,
+        sort,
+        &pagination,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to filter scholars: {:?}", e);
+        AppError {
+            message: Some("Failed to filter scholars".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    })?;
+
+    let pagination_meta = PaginationMeta::new(pagination.page, pagination.per_page, total_items);
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Scholars retrieved successfully".to_string(),
+        data: Some(data),
+        pagination: Some(pagination_meta),
+    }))
+}
+
+const DEFAULT_TRENDING_LIMIT: i64 = 10;
+const MAX_TRENDING_LIMIT: i64 = 50;
+
+#[instrument(name = "Get Trending Scholars", skip(pool, config, redis_service))]
+#[get("/trending")]
+pub async fn get_trending_scholars(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    query: web::Query<TrendingScholarsQuery>,
+) -> Result<impl Responder, AppError> {
+    let window_days = match query.window.as_deref() {
+        Some(window) => parse_days_window(window, config.trending.max_window_days)?,
+        None => config.trending.default_window_days,
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TRENDING_LIMIT)
+        .clamp(1, MAX_TRENDING_LIMIT);
+
+    let cache_key = format!("cache:trending_scholars:{}d:{}", window_days, limit);
+    let ttl = std::time::Duration::from_secs(config.trending.cache_ttl_seconds);
+    let since = chrono::Utc::now() - chrono::Duration::days(window_days);
+
+    let trending = redis_service
+        .get_or_load(&cache_key, ttl, || {
+            scholars::fetch_trending_scholars(pool.get_ref(), &config, since, limit)
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch trending scholars: {:?}", e);
+            AppError {
+                message: Some("Failed to fetch trending scholars".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            }
+        })?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Trending scholars retrieved successfully".to_string(),
+        data: Some(trending),
+        pagination: None,
+    }))
+}
+
+const MAX_FEATURED_SCHOLARS: i64 = 50;
+const FEATURED_SCHOLARS_CACHE_KEY: &str = "cache:featured_scholars";
+const FEATURED_SCHOLARS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+#[instrument(name = "Get Featured Scholars", skip(pool, config, redis_service))]
+#[get("/featured")]
+pub async fn get_featured_scholars(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    query: web::Query<FeaturedScholarsQuery>,
+) -> Result<impl Responder, AppError> {
+    let limit = query
+        .limit
+        .unwrap_or(MAX_FEATURED_SCHOLARS)
+        .clamp(1, MAX_FEATURED_SCHOLARS) as usize;
+
+    let mut featured = redis_service
+        .get_or_load(FEATURED_SCHOLARS_CACHE_KEY, FEATURED_SCHOLARS_CACHE_TTL, || {
+            scholars::fetch_featured_scholars(pool.get_ref(), &config, MAX_FEATURED_SCHOLARS)
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch featured scholars: {:?}", e);
+            AppError {
+                message: Some("Failed to fetch featured scholars".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            }
+        })?;
+    featured.truncate(limit);
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Featured scholars retrieved successfully".to_string(),
+        data: Some(featured),
+        pagination: None,
+    }))
+}
+
+/// The featured list in the admin's order, repeats dropped, or an error past the cap
+fn curated_scholar_ids(requested: Vec<i32>) -> Result<Vec<i32>, AppError> {
+    let mut scholar_ids = Vec::new();
+    for scholar_id in requested {
+        if !scholar_ids.contains(&scholar_id) {
+            scholar_ids.push(scholar_id);
+        }
+    }
+    if scholar_ids.len() as i64 > MAX_FEATURED_SCHOLARS {
+        return Err(AppError::bad_request(format!(
+            "At most {} scholars can be featured",
+            MAX_FEATURED_SCHOLARS
+        )));
+    }
+    Ok(scholar_ids)
+}
+
+#[instrument(name = "Set Featured Scholars", skip(pool, redis_service, auth))]
+#[put("/featured")]
+pub async fn set_featured_scholars(
+    pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
+    auth: JwtMiddleware,
+    request: web::Json<SetFeaturedScholarsRequest>,
+) -> Result<impl Responder, AppError> {
+    crate::db::users::require_admin(pool.get_ref(), auth.user_id).await?;
+
+    let scholar_ids = curated_scholar_ids(request.into_inner().scholar_ids)?;
+    for &scholar_id in &scholar_ids {
+        scholars::assert_scholar_active(pool.get_ref(), scholar_id).await?;
+    }
+
+    scholars::set_featured_scholars(pool.get_ref(), &scholar_ids, auth.user_id).await?;
+
+    if let Err(e) = redis_service.delete(FEATURED_SCHOLARS_CACHE_KEY).await {
+        tracing::warn!("Cache delete for {} failed: {}", FEATURED_SCHOLARS_CACHE_KEY, e);
+    }
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Featured scholars updated successfully".to_string(),
+        data: Some(scholar_ids),
+        pagination: None,
+    }))
+}
+
+/// Whether the caller may add or change content under this scholar. A user
+/// without access gets `false` rather than 403 so clients can hide controls
+#[instrument(name = "Can Manage Scholar", skip(pool, auth))]
+#[get("/{scholar_id}/can-manage")]
+pub async fn can_manage_scholar(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    scholar_id: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let scholar_id = scholar_id.into_inner();
+    scholars::assert_scholar_active(pool.get_ref(), scholar_id).await?;
+
+    // Role from the database like the write paths use, since the token's may be stale
+    let user = crate::db::users::get_user_by_id(pool.get_ref(), auth.user_id).await?;
+    let reason = if user.role == "admin" {
+        Some(ManageReason::Admin)
+    } else if access::check_user_access_to_scholar(pool.get_ref(), auth.user_id, scholar_id).await? {
+        Some(ManageReason::ScholarAccess)
+    } else {
+        None
+    };
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Scholar permissions retrieved successfully".to_string(),
+        data: Some(CanManageResponse::from(reason)),
+        pagination: None,
+    }))
+}
+
 #[instrument(name = "Get Scholar Details", skip(pool, config))]
 #[get("/{scholar_id}")]
 pub async fn get_scholar_details(
@@ -125,15 +363,270 @@ pub async fn get_scholar_details(
     }))
 }
 
-#[instrument(name = "Get Scholar Statistics", skip(pool))]
+const HOME_BOOKS_PER_PAGE: i32 = 10;
+const HOME_RECENT_FILES: i32 = 10;
+
+#[instrument(name = "Get Scholar Home", skip(pool, config, req))]
+#[get("/{scholar_id}/home")]
+pub async fn get_scholar_home(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    scholar_id: web::Path<i32>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let scholar_id = scholar_id.into_inner();
+    let user_id = extract_user_id_from_request(&req, &config);
+    let books_page = PaginationQuery {
+        page: 1,
+        per_page: HOME_BOOKS_PER_PAGE,
+    };
+
+    let (scholar_res, books_res, files_res) = tokio::join!(
+        scholars::get_scholar_details(pool.get_ref(), &config, scholar_id, user_id),
+        books::fetch_books_by_scholar(pool.get_ref(), &config, scholar_id, &books_page),
+        files::fetch_recent_files_by_scholar(
+            pool.get_ref(),
+            &config,
+            scholar_id,
+            HOME_RECENT_FILES,
+            files::can_view_restricted(user_id),
+        ),
+    );
+
+    let home = assemble_scholar_home(scholar_res, books_res, files_res, &books_page)?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Scholar home retrieved successfully".to_string(),
+        data: Some(home),
+        pagination: None,
+    }))
+}
+
+fn assemble_scholar_home(
+    scholar_res: Result<ScholarDetails, AppError>,
+    books_res: Result<(Vec<Book>, i64), AppError>,
+    files_res: Result<Vec<RecentFiles>, AppError>,
+    books_page: &PaginationQuery,
+) -> Result<ScholarHome, AppError> {
+    let scholar = scholar_res.map_err(|e| {
+        tracing::error!("Failed to fetch scholar details: {:?}", e);
+        match e.error_type {
+            AppErrorType::NotFoundError => AppError {
+                message: Some("Scholar not found".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::NotFoundError,
+            },
+            _ => AppError {
+                message: Some("Failed to fetch scholar details".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            },
+        }
+    })?;
+    let (books, total_books) = books_res.map_err(|e| {
+        tracing::error!("Failed to fetch books by scholar: {:?}", e);
+        AppError {
+            message: Some("Failed to fetch books".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    })?;
+    let recent_files = files_res.map_err(|e| {
+        tracing::error!("Failed to fetch recent files by scholar: {:?}", e);
+        AppError {
+            message: Some("Failed to fetch recent files".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    })?;
+
+    Ok(ScholarHome {
+        scholar,
+        books,
+        books_pagination: PaginationMeta::new(books_page.page, books_page.per_page, total_books),
+        recent_files,
+    })
+}
+
+const MAX_REPORT_DAYS: i64 = 366;
+const REPORT_PAGE_SIZE: i32 = 500;
+const REPORT_CSV_HEADER: &str = "file_id,file_name,book,plays,downloads\n";
+
+enum ReportStage {
+    Header,
+    Rows,
+    Done,
+}
+
+struct ReportStream {
+    pool: web::Data<MySqlPool>,
+    scholar_id: i32,
+    start: chrono::NaiveDateTime,
+    end: chrono::NaiveDateTime,
+    after_file_id: i32,
+    totals: ReportTotals,
+    stage: ReportStage,
+}
+
+#[derive(Default)]
+struct ReportTotals {
+    plays: i64,
+    downloads: i64,
+}
+
+/// CSV lines for one page of the report, adding it to `totals`; the last
+/// page also carries the TOTAL line
+fn report_csv_rows(rows: &[ScholarReportRow], totals: &mut ReportTotals, is_last_page: bool) -> String {
+    let mut chunk = String::new();
+    for row in rows {
+        chunk.push_str(&format!(
+            "{},{},{},{},{}\n",
+            row.file_id,
+            csv_field(&row.file_name),
+            csv_field(&row.book_name),
+            row.plays,
+            row.downloads
+        ));
+        totals.plays += row.plays;
+        totals.downloads += row.downloads;
+    }
+    if is_last_page {
+        chunk.push_str(&format!("TOTAL,,,{},{}\n", totals.plays, totals.downloads));
+    }
+    chunk
+}
+
+/// Per-file play and download counts for a scholar as CSV. Rows are read and
+/// written a page at a time so the whole catalog is never held in memory
+#[instrument(name = "Get Scholar Report", skip(pool, auth))]
+#[get("/{scholar_id}/report.csv")]
+pub async fn get_scholar_report_csv(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    scholar_id: web::Path<i32>,
+    query: web::Query<ScholarReportQuery>,
+) -> Result<impl Responder, AppError> {
+    let scholar_id = scholar_id.into_inner();
+
+    // The stored role, so a demotion applies before the token expires
+    let role = crate::db::users::get_current_role(pool.get_ref(), auth.user_id).await?;
+    let allowed = match role.as_deref() {
+        Some("admin") => true,
+        Some("manager") => {
+            crate::db::access::check_user_access_to_scholar(
+                pool.get_ref(),
+                auth.user_id,
+                scholar_id,
+            )
+            .await?
+        }
+        _ => false,
+    };
+    if !allowed {
+        return Err(AppError::forbidden_error(
+            "You don't have permission to view reports for this scholar",
+        ));
+    }
+
+    if query.from > query.to {
+        return Err(AppError::bad_request("from must not be after to"));
+    }
+    if (query.to - query.from).num_days() + 1 > MAX_REPORT_DAYS {
+        return Err(AppError::bad_request(format!(
+            "Report range cannot exceed {} days",
+            MAX_REPORT_DAYS
+        )));
+    }
+
+    let scholar_name = scholars::fetch_scholar_name(pool.get_ref(), scholar_id)
+        .await?
+        .ok_or_else(|| AppError {
+            message: Some("Scholar not found".to_string()),
+            cause: None,
+            error_type: AppErrorType::NotFoundError,
+        })?;
+
+    let filename = format!(
+        "{}-report-{}-to-{}.csv",
+        slugify(&scholar_name),
+        query.from,
+        query.to
+    );
+    let state = ReportStream {
+        pool: pool.clone(),
+        scholar_id,
+        start: query.from.and_hms_opt(0, 0, 0).unwrap_or_default(),
+        end: (query.to + chrono::Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default(),
+        after_file_id: 0,
+        totals: ReportTotals::default(),
+        stage: ReportStage::Header,
+    };
+
+    let body = futures_util::stream::unfold(state, |mut state| async move {
+        match state.stage {
+            ReportStage::Header => {
+                state.stage = ReportStage::Rows;
+                Some((Ok(web::Bytes::from_static(REPORT_CSV_HEADER.as_bytes())), state))
+            }
+            ReportStage::Rows => {
+                let page = scholars::fetch_scholar_report_page(
+                    state.pool.get_ref(),
+                    state.scholar_id,
+                    state.start,
+                    state.end,
+                    state.after_file_id,
+                    REPORT_PAGE_SIZE,
+                )
+                .await;
+                let rows = match page {
+                    Ok(rows) => rows,
+                    Err(e) => {
+                        // Headers are already sent, so the best we can do is end the body early
+                        tracing::error!("Failed to read scholar report page: {:?}", e);
+                        state.stage = ReportStage::Done;
+                        return Some((Err(actix_web::Error::from(e)), state));
+                    }
+                };
+
+                let is_last_page = rows.len() < REPORT_PAGE_SIZE as usize;
+                if let Some(last) = rows.last() {
+                    state.after_file_id = last.file_id;
+                }
+                let chunk = report_csv_rows(&rows, &mut state.totals, is_last_page);
+                if is_last_page {
+                    state.stage = ReportStage::Done;
+                }
+                Some((Ok(web::Bytes::from(chunk)), state))
+            }
+            ReportStage::Done => None,
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(attachment_disposition(&filename))
+        .streaming(body))
+}
+
+const SCHOLAR_STATISTICS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+#[instrument(name = "Get Scholar Statistics", skip(pool, redis_service))]
 #[get("/{scholar_id}/statistics")]
 pub async fn get_scholar_statistics(
     pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
     scholar_id: web::Path<i32>,
 ) -> Result<impl Responder, AppError> {
     let scholar_id = scholar_id.into_inner();
+    let cache_key = format!("cache:scholar_statistics:{}", scholar_id);
 
-    let statistics = scholars::get_scholar_statistics(pool.get_ref(), scholar_id)
+    let statistics = redis_service
+        .get_or_load(&cache_key, SCHOLAR_STATISTICS_CACHE_TTL, || {
+            scholars::get_scholar_statistics(pool.get_ref(), scholar_id)
+        })
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch scholar statistics: {:?}", e);
@@ -151,12 +644,155 @@ pub async fn get_scholar_statistics(
         pagination: None,
     }))
 }
+
+const DEFAULT_TOP_FILES_LIMIT: i64 = 10;
+const MAX_TOP_FILES_LIMIT: i64 = 50;
+const TOP_FILES_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+#[instrument(name = "Get Scholar Top Files", skip(pool, config, redis_service, req))]
+#[get("/{scholar_id}/top-files")]
+pub async fn get_scholar_top_files(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    scholar_id: web::Path<i32>,
+    query: web::Query<TopFilesQuery>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let scholar_id = scholar_id.into_inner();
+    let metric = query.metric.as_deref().unwrap_or("plays");
+    if !TOP_FILES_METRICS.contains(&metric) {
+        return Err(AppError::bad_request(format!(
+            "Invalid metric, expected one of: {}",
+            TOP_FILES_METRICS.join(", ")
+        )));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TOP_FILES_LIMIT)
+        .clamp(1, MAX_TOP_FILES_LIMIT) as usize;
+
+    scholars::assert_scholar_active(pool.get_ref(), scholar_id).await?;
+
+    // The full ranking is cached once per metric and cut to `limit` per request
+    let cache_key = format!("cache:scholar_top_files:{}:{}", scholar_id, metric);
+    let ranking = redis_service
+        .get_or_load(&cache_key, TOP_FILES_CACHE_TTL, || {
+            scholars::fetch_scholar_top_files(pool.get_ref(), scholar_id, metric, MAX_TOP_FILES_LIMIT)
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch scholar top files: {:?}", e);
+            AppError {
+                message: Some("Failed to fetch scholar top files".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            }
+        })?;
+
+    let user_id = extract_user_id_from_request(&req, &config);
+    let include_restricted = files::can_view_restricted(user_id);
+
+    let ranking: Vec<_> = ranking
+        .into_iter()
+        .filter(|rank| include_restricted || !rank.restricted)
+        .collect();
+    let file_ids: Vec<i32> = ranking.iter().map(|rank| rank.file_id).collect();
+    let mut files_by_id: HashMap<i32, FilesWithStats> =
+        files::fetch_files_with_stats_by_ids(pool.get_ref(), &config, &file_ids, user_id)
+            .await?
+            .into_iter()
+            .map(|file| (file.file_id, file))
+            .collect();
+
+    // Files removed since the ranking was cached are skipped
+    let top_files: Vec<TopFile> = ranking
+        .into_iter()
+        .filter_map(|rank| {
+            files_by_id.remove(&rank.file_id).map(|file| TopFile {
+                file,
+                score: rank.score,
+            })
+        })
+        .take(limit)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Scholar top files retrieved successfully".to_string(),
+        data: Some(top_files),
+        pagination: None,
+    }))
+}
+
+#[instrument(name = "Get Scholar Catalog", skip(pool, config, req))]
+#[get("/{scholar_id}/catalog")]
+pub async fn get_scholar_catalog(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    scholar_id: web::Path<i32>,
+    query: web::Query<ScholarCatalogQuery>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let scholar_id = scholar_id.into_inner();
+    let files_per_book = query.files_per_book.unwrap_or(50).clamp(0, 200) as usize; // Max 200 files per book
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
+
+    let catalog = scholars::get_scholar_catalog(
+        pool.get_ref(),
+        &config,
+        scholar_id,
+        files_per_book,
+        include_restricted,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch scholar catalog: {:?}", e);
+        AppError {
+            message: Some("Failed to fetch scholar catalog".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    })?
+    .ok_or_else(|| AppError {
+        message: Some("Scholar not found".to_string()),
+        cause: None,
+        error_type: AppErrorType::NotFoundError,
+    })?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Scholar catalog retrieved successfully".to_string(),
+        data: Some(catalog),
+        pagination: None,
+    }))
+}
+
+const DEFAULT_DROPDOWN_LIMIT: i64 = 20;
+const MAX_DROPDOWN_LIMIT: i64 = 100;
+
+// None only when every match was asked for with `all=1`
+fn dropdown_limit(query: &ScholarDropdownQuery) -> Option<i64> {
+    if matches!(query.all.as_deref(), Some("1") | Some("true")) {
+        return None;
+    }
+    Some(
+        query
+            .limit
+            .unwrap_or(DEFAULT_DROPDOWN_LIMIT)
+            .clamp(1, MAX_DROPDOWN_LIMIT),
+    )
+}
+
 #[instrument(name = "Get Scholars Dropdown", skip(pool))]
 #[get("/dropdown")]
 pub async fn get_scholars_dropdown(
     pool: web::Data<MySqlPool>,
+    query: web::Query<ScholarDropdownQuery>,
 ) -> Result<impl Responder, AppError> {
-    let scholars = scholars::get_scholars_dropdown(pool.get_ref())
+    let name_query = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    let scholars = scholars::get_scholars_dropdown(pool.get_ref(), name_query, dropdown_limit(&query))
         .await
         .map_err(|e| {
             tracing::error!("Failed to fetch scholars dropdown: {:?}", e);
@@ -209,6 +845,7 @@ pub async fn create_scholar(
     let mut state_id: Option<i32> = None;
     let mut priority: Option<i32> = None;
     let mut image_filename: Option<String> = None;
+    let mut staged_image: Option<StagedFile> = None;
     let images_dir = &config.app_paths.images_dir;
 
     fs::create_dir_all(images_dir).ok();
@@ -221,12 +858,19 @@ pub async fn create_scholar(
             if field_name == "image" {
                 let file_ext = cd.get_filename().and_then(|f| std::path::Path::new(f).extension().and_then(|e| e.to_str())).unwrap_or("jpg");
                 let generated = format!("scholar_{}.{}", Uuid::new_v4(), file_ext);
-                let filepath = format!("{}/{}", images_dir, generated);
+                let (generated, filepath) = prepare_storage_location(
+                    images_dir,
+                    &generated,
+                    config.app_paths.shard_by_date,
+                )
+                .map_err(|e| AppError::internal_error(format!("Failed to prepare image path: {}", e)))?;
                 let mut f = fs::File::create(&filepath)
                     .map_err(|e| AppError::internal_error(format!("Failed to create image: {}", e)))?;
+                staged_image = Some(StagedFile::new(&filepath));
                 while let Some(chunk) = field.try_next().await.map_err(|e| AppError::internal_error(format!("Failed to read image: {}", e)))? {
                     f.write_all(&chunk).map_err(|e| AppError::internal_error(format!("Failed to write image: {}", e)))?;
                 }
+                config.image_index.insert(&generated);
                 image_filename = Some(generated);
             } else if field_name == "name" {
                 let bytes = field.try_next().await.map_err(|e| AppError::bad_request(format!("Invalid name: {}", e)))?.unwrap_or_default();
@@ -244,20 +888,12 @@ pub async fn create_scholar(
         }
     }
 
-     let scholar_name = name.ok_or_else(|| AppError::bad_request("name is required"))?;
+     let scholar_name = validate_name(&name.ok_or_else(|| AppError::bad_request("name is required"))?)?;
+     let about = validate_about(about)?;
      let scholar_state_id = state_id.ok_or_else(|| AppError::bad_request("state_id is required"))?;
      let slug_value = slugify(&scholar_name);
 
 
-    if let Some(existing_name) = scholars::check_duplicate_scholar(pool.get_ref(), &scholar_name, &slug_value).await? {
-        return Err(AppError {
-            message: Some(format!("A scholar with the name '{}' already exists", existing_name)),
-            cause: None,
-            error_type: AppErrorType::ConflictError,
-        });
-    }
-
-
     let request = CreateScholarRequest {
         name: scholar_name,
         about,
@@ -266,21 +902,35 @@ pub async fn create_scholar(
         priority,
     };
 
-    let scholar_id = scholars::create_scholar(pool.get_ref(), &request, auth.user_id, &slug_value)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to create scholar: {:?}", e);
-            AppError {
-                message: Some("Failed to create scholar".to_string()),
-                cause: Some(e.to_string()),
-                error_type: AppErrorType::InternalServerError,
-            }
-        })?;
+    let (scholar_id, slug) = scholars::create_scholar(
+        pool.get_ref(),
+        &request,
+        auth.user_id,
+        &slug_value,
+        &config.app_paths.default_scholar_image,
+    )
+    .await
+    .map_err(|e| {
+        if e.error_type == AppErrorType::ConflictError {
+            return e;
+        }
+        tracing::error!("Failed to create scholar: {:?}", e);
+        AppError {
+            message: Some("Failed to create scholar".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    })?;
+
+    // The row is committed, so the image it points at stays
+    if let Some(image) = staged_image {
+        image.keep();
+    }
 
     Ok(HttpResponse::Created().json(AppSuccessResponse {
         success: true,
         message: "Scholar created successfully".to_string(),
-        data: Some(serde_json::json!({"id": scholar_id})),
+        data: Some(serde_json::json!({"id": scholar_id, "slug": slug})),
         pagination: None,
     }))
 }
@@ -321,7 +971,9 @@ pub async fn update_scholar(
     let mut about: Option<String> = None;
     let mut state_id: Option<i32> = None;
     let mut priority: Option<i32> = None;
+    let mut version: Option<i32> = None;
     let mut image_filename: Option<String> = None;
+    let mut staged_image: Option<StagedFile> = None;
 
     let images_dir = &config.app_paths.images_dir;
     fs::create_dir_all(images_dir).ok();
@@ -334,12 +986,19 @@ pub async fn update_scholar(
             if field_name == "image" {
                 let file_ext = cd.get_filename().and_then(|f| std::path::Path::new(f).extension().and_then(|e| e.to_str())).unwrap_or("jpg");
                 let generated = format!("scholar_{}.{}", Uuid::new_v4(), file_ext);
-                let filepath = format!("{}/{}", images_dir, generated);
+                let (generated, filepath) = prepare_storage_location(
+                    images_dir,
+                    &generated,
+                    config.app_paths.shard_by_date,
+                )
+                .map_err(|e| AppError::internal_error(format!("Failed to prepare image path: {}", e)))?;
                 let mut f = fs::File::create(&filepath)
                     .map_err(|e| AppError::internal_error(format!("Failed to create image: {}", e)))?;
+                staged_image = Some(StagedFile::new(&filepath));
                 while let Some(chunk) = field.try_next().await.map_err(|e| AppError::internal_error(format!("Failed to read image: {}", e)))? {
                     f.write_all(&chunk).map_err(|e| AppError::internal_error(format!("Failed to write image: {}", e)))?;
                 }
+                config.image_index.insert(&generated);
                 image_filename = Some(generated);
             } else if field_name == "name" {
                 let bytes = field.try_next().await.map_err(|e| AppError::bad_request(format!("Invalid name: {}", e)))?.unwrap_or_default();
@@ -353,28 +1012,59 @@ pub async fn update_scholar(
             } else if field_name == "priority" {
                 let bytes = field.try_next().await.map_err(|e| AppError::bad_request(format!("Invalid priority: {}", e)))?.unwrap_or_default();
                 priority = String::from_utf8(bytes.to_vec()).ok().and_then(|s| s.parse::<i32>().ok());
+            } else if field_name == "version" {
+                let bytes = field.try_next().await.map_err(|e| AppError::bad_request(format!("Invalid version: {}", e)))?.unwrap_or_default();
+                version = Some(
+                    String::from_utf8(bytes.to_vec())
+                        .ok()
+                        .and_then(|s| s.trim().parse::<i32>().ok())
+                        .ok_or_else(|| AppError::bad_request("version must be an integer"))?,
+                );
             }
         }
     }
 
+    let name = name.as_deref().map(validate_name).transpose()?;
+    // Sent but blank clears the about text
+    let about = about
+        .map(|about| validate_about(Some(about)).map(Option::unwrap_or_default))
+        .transpose()?;
+
     let request = UpdateScholarRequest {
         name,
         about,
         state_id,
         image: image_filename,
         priority,
+        version,
     };
 
-    scholars::update_scholar(pool.get_ref(), scholar_id, &request)
-        .await
-        .map_err(|e| {
+    match scholars::update_scholar(pool.get_ref(), scholar_id, &request).await {
+        Ok(()) => {}
+        Err(e) if matches!(e.error_type, AppErrorType::ConflictError) => {
+            let current = scholars::get_scholar_details(pool.get_ref(), &config, scholar_id, Some(auth.user_id)).await?;
+            return Ok(HttpResponse::Conflict().json(VersionConflictResponse {
+                success: false,
+                code: error_codes::VERSION_CONFLICT.to_string(),
+                message: "Scholar was modified by someone else; reload and retry".to_string(),
+                current,
+            }));
+        }
+        Err(e) if matches!(e.error_type, AppErrorType::NotFoundError) => return Err(e),
+        Err(e) => {
             tracing::error!("Failed to update scholar: {:?}", e);
-            AppError {
+            return Err(AppError {
                 message: Some("Failed to update scholar".to_string()),
                 cause: Some(e.to_string()),
                 error_type: AppErrorType::InternalServerError,
-            }
-        })?;
+            });
+        }
+    }
+
+    // The row now points at the new image, so it stays
+    if let Some(image) = staged_image {
+        image.keep();
+    }
 
     // Fetch the updated scholar details
     let updated_scholar = scholars::get_scholar_details(pool.get_ref(), &config, scholar_id, Some(auth.user_id))
@@ -443,3 +1133,219 @@ pub async fn delete_scholar(
         pagination: None,
     }))
 }
+
+#[instrument(name = "Add Scholar Link", skip(pool, auth))]
+#[post("/{scholar_id}/links")]
+pub async fn add_scholar_link(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    scholar_id: web::Path<i32>,
+    request: web::Json<AddScholarLinkRequest>,
+) -> Result<impl Responder, AppError> {
+    // The `ROUTE_POLICIES` row only sees the token's role
+    crate::db::users::require_admin(pool.get_ref(), auth.user_id).await?;
+    let scholar_id = scholar_id.into_inner();
+
+    let link_type = request.link_type.trim().to_lowercase();
+    if !SCHOLAR_LINK_TYPES.contains(&link_type.as_str()) {
+        return Err(AppError::bad_request(format!(
+            "Invalid link type, expected one of: {}",
+            SCHOLAR_LINK_TYPES.join(", ")
+        )));
+    }
+
+    let url = request.url.trim();
+    if !is_valid_http_url(url) {
+        return Err(AppError::bad_request(
+            "Invalid URL, expected an absolute http or https link",
+        ));
+    }
+
+    scholars::assert_scholar_active(pool.get_ref(), scholar_id).await?;
+
+    let link = scholars::add_scholar_link(pool.get_ref(), scholar_id, &link_type, url, auth.user_id).await?;
+
+    Ok(HttpResponse::Created().json(AppSuccessResponse {
+        success: true,
+        message: "Scholar link added successfully".to_string(),
+        data: Some(link),
+        pagination: None,
+    }))
+}
+
+#[instrument(name = "Remove Scholar Link", skip(pool, auth))]
+#[actix_web::delete("/{scholar_id}/links/{link_id}")]
+pub async fn remove_scholar_link(
+    pool: web::Data<MySqlPool>,
+    auth: JwtMiddleware,
+    path: web::Path<(i32, i32)>,
+) -> Result<impl Responder, AppError> {
+    // The `ROUTE_POLICIES` row only sees the token's role
+    crate::db::users::require_admin(pool.get_ref(), auth.user_id).await?;
+    let (scholar_id, link_id) = path.into_inner();
+
+    if !scholars::remove_scholar_link(pool.get_ref(), scholar_id, link_id).await? {
+        return Err(AppError {
+            message: Some("Scholar link not found".to_string()),
+            cause: None,
+            error_type: AppErrorType::NotFoundError,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Scholar link removed successfully".to_string(),
+        data: None::<()>,
+        pagination: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn featured_scholars_keep_the_curated_order() {
+        // Stored with sort_order 0, 1, ... in this order, which is how they are listed
+        assert_eq!(curated_scholar_ids(vec![12, 5]).unwrap(), [12, 5]);
+        assert_eq!(curated_scholar_ids(vec![12, 5, 12, 3]).unwrap(), [12, 5, 3]);
+        assert!(curated_scholar_ids(Vec::new()).unwrap().is_empty());
+
+        let too_many: Vec<i32> = (1..=MAX_FEATURED_SCHOLARS as i32 + 1).collect();
+        assert!(curated_scholar_ids(too_many).is_err());
+    }
+
+    fn scholar_details() -> ScholarDetails {
+        let now = chrono::Utc::now().naive_utc();
+        ScholarDetails {
+            id: 3,
+            name: "Sheikh Ja'afar".to_string(),
+            about: None,
+            state_id: 1,
+            state: "Kano".to_string(),
+            image: None,
+            created_at: now,
+            updated_at: now,
+            created_by: 1,
+            version: 1,
+            statistics: crate::models::scholars::ScholarStatistics {
+                total_books: 12,
+                total_files: 240,
+                total_downloads: 0,
+                total_plays: 0,
+                total_likes: 0,
+                total_followers: 0,
+            },
+            links: Vec::new(),
+            is_followed_by_user: Some(true),
+            has_access: Some(false),
+        }
+    }
+
+    fn home_page() -> PaginationQuery {
+        PaginationQuery {
+            page: 1,
+            per_page: HOME_BOOKS_PER_PAGE,
+        }
+    }
+
+    #[test]
+    fn dropdown_search_is_bounded_unless_all_is_asked_for() {
+        let query = |q: &str, limit, all: Option<&str>| ScholarDropdownQuery {
+            q: Some(q.to_string()),
+            limit,
+            all: all.map(str::to_string),
+        };
+
+        assert_eq!(dropdown_limit(&query("ja", None, None)), Some(DEFAULT_DROPDOWN_LIMIT));
+        assert_eq!(dropdown_limit(&query("ja", Some(5), None)), Some(5));
+        assert_eq!(dropdown_limit(&query("ja", Some(5000), None)), Some(MAX_DROPDOWN_LIMIT));
+        assert_eq!(dropdown_limit(&query("ja", Some(5), Some("1"))), None);
+
+        // The dropdown matches with scholar search's pattern: a contains match with
+        // LIKE wildcards escaped, and nothing for a term of only harakat
+        assert_eq!(crate::core::search_like_pattern(" ibn_ ").as_deref(), Some("%ibn\\_%"));
+        assert_eq!(crate::core::search_like_pattern("\u{64e}\u{650}"), None);
+    }
+
+    #[test]
+    fn scholar_report_lists_counts_and_totals_under_the_header() {
+        assert_eq!(REPORT_CSV_HEADER, "file_id,file_name,book,plays,downloads\n");
+
+        let rows = vec![
+            ScholarReportRow {
+                file_id: 4,
+                file_name: "Lesson 1".to_string(),
+                book_name: "Umdatul Ahkam".to_string(),
+                plays: 3,
+                downloads: 2,
+            },
+            ScholarReportRow {
+                file_id: 9,
+                file_name: "Lesson 2, part 1".to_string(),
+                book_name: "Umdatul Ahkam".to_string(),
+                plays: 0,
+                downloads: 1,
+            },
+        ];
+        let mut totals = ReportTotals::default();
+
+        let first = report_csv_rows(&rows[..1], &mut totals, false);
+        assert_eq!(first, "4,Lesson 1,Umdatul Ahkam,3,2\n");
+
+        let last = report_csv_rows(&rows[1..], &mut totals, true);
+        assert_eq!(last, "9,\"Lesson 2, part 1\",Umdatul Ahkam,0,1\nTOTAL,,,3,3\n");
+    }
+
+    #[test]
+    fn scholar_home_fills_every_section() {
+        let now = chrono::Utc::now();
+        let book = Book {
+            id: 8,
+            name: "Riyadus Salihin".to_string(),
+            image: "book.jpg".to_string(),
+            created_at: now.naive_utc(),
+            created_by: 1,
+            files_count: 30,
+            downloads: 100,
+        };
+        let file = RecentFiles {
+            file_id: 21,
+            file_name: "Lesson 1".to_string(),
+            file_url: "lesson-1.mp3".to_string(),
+            file_size: "4.2 MB".to_string(),
+            file_duration: "00:30:00".to_string(),
+            downloads: 5,
+            book_id: 8,
+            scholar_id: 3,
+            scholar_name: "Sheikh Ja'afar".to_string(),
+            scholar_image: "scholar.jpg".to_string(),
+            date: now,
+        };
+
+        let home = assemble_scholar_home(Ok(scholar_details()), Ok((vec![book], 12)), Ok(vec![file]), &home_page())
+            .unwrap();
+        let body = serde_json::to_value(&home).unwrap();
+
+        assert_eq!(body["scholar"]["id"], 3);
+        assert_eq!(body["scholar"]["is_followed_by_user"], true);
+        assert_eq!(body["scholar"]["has_access"], false);
+        assert_eq!(body["books"][0]["id"], 8);
+        assert_eq!(body["books_pagination"]["total_items"], 12);
+        assert_eq!(body["books_pagination"]["has_next"], true);
+        assert_eq!(body["recent_files"][0]["file_id"], 21);
+    }
+
+    #[test]
+    fn inactive_scholar_home_is_not_found() {
+        // Lookups of an inactive scholar report not found rather than its content
+        let home = assemble_scholar_home(
+            Err(AppError::not_found("Scholar not found")),
+            Err(AppError::not_found("Scholar not found")),
+            Ok(Vec::new()),
+            &home_page(),
+        );
+        let error = home.err().expect("an inactive scholar has no home");
+        assert_eq!(error.error_type, AppErrorType::NotFoundError);
+    }
+}
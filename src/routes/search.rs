@@ -1,16 +1,22 @@
 use actix_web::{
     get,
     web::{self},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder,
 };
 use serde::Deserialize;
 use sqlx::MySqlPool;
 use tracing::instrument;
 
 use crate::{
-    core::{AppConfig, AppError, AppErrorType, AppSuccessResponse},
+    core::{
+        extract_user_id_from_request, utils::highlight_search_match, AppConfig, AppError,
+        AppErrorType, AppSuccessResponse,
+    },
     db::{books, files, scholars},
-    models::pagination::PaginationMeta,
+    models::{
+        files::FileSearchScope,
+        pagination::{PaginationMeta, PaginationQuery},
+    },
 };
 
 #[derive(Deserialize)]
@@ -18,35 +24,40 @@ pub struct SearchParams {
     pub q: String, // search query
     pub page: Option<i32>,
     pub per_page: Option<i32>,
+    /// When set to 1, each result carries the matched span of its name
+    pub highlight: Option<u8>,
 }
 
-#[instrument(name = "Search Scholars, Books, Files", skip(pool, query))]
+#[instrument(name = "Search Scholars, Books, Files", skip(pool, query, req))]
 #[get("/search")]
 pub async fn full_text_search(
     pool: web::Data<MySqlPool>,
     config: web::Data<AppConfig>,
     query: web::Query<SearchParams>,
+    req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
-    let search_term = query.q.trim();
-    if search_term.is_empty() {
-        return Err(AppError {
-            message: Some("Search query cannot be empty".to_string()),
-            cause: None,
-            error_type: AppErrorType::PayloadValidationError,
-        });
-    }
+    let search_term = search_term(&query)?;
 
     let page = query.page.unwrap_or(1);
     let per_page = query.per_page.unwrap_or(30);
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
     // Run searches concurrently
 
     let (scholars_res, books_res, files_res) = tokio::join!(
         scholars::search_scholars(pool.get_ref(), &config, search_term, page, per_page),
         books::search_books(pool.get_ref(), &config, search_term, page, per_page),
-        files::search_files(pool.get_ref(), &config, search_term, page, per_page),
+        files::search_files(
+            pool.get_ref(),
+            &config,
+            search_term,
+            FileSearchScope::All,
+            page,
+            per_page,
+            include_restricted
+        ),
     );
 
-    let (scholars, books, files) = (
+    let (mut scholars, mut books, mut files) = (
         scholars_res.map_err(|e| {
             tracing::error!("Failed to search scholars: {:?}", e);
             AppError {
@@ -73,6 +84,21 @@ pub async fn full_text_search(
         })?,
     );
 
+    if query.highlight == Some(1) {
+        for scholar in scholars.0.iter_mut() {
+            scholar.highlight = highlight_search_match(&scholar.name, search_term);
+        }
+        for book in books.0.iter_mut() {
+            book.highlight = book
+                .name
+                .as_deref()
+                .and_then(|name| highlight_search_match(name, search_term));
+        }
+        for file in files.0.iter_mut() {
+            file.highlight = highlight_search_match(&file.file_name, search_term);
+        }
+    }
+
     let scholars_pagination = PaginationMeta::new(page, per_page, scholars.1);
     let books_pagination = PaginationMeta::new(page, per_page, books.1);
     let files_pagination = PaginationMeta::new(page, per_page, files.1);
@@ -97,3 +123,88 @@ pub async fn full_text_search(
         pagination: None,
     }))
 }
+
+#[instrument(name = "Search Scholar Files", skip(pool, config, query, pagination, req))]
+#[get("/{scholar_id}/files/search")]
+pub async fn search_scholar_files(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    scholar_id: web::Path<i32>,
+    query: web::Query<SearchParams>,
+    pagination: web::Query<PaginationQuery>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let scholar_id = scholar_id.into_inner();
+    scholars::assert_scholar_active(pool.get_ref(), scholar_id).await?;
+
+    let scope = FileSearchScope::Scholar(scholar_id);
+    scoped_file_search(&pool, &config, &query, pagination.into_inner(), &req, scope).await
+}
+
+#[instrument(name = "Search Book Files", skip(pool, config, query, pagination, req))]
+#[get("/{book_id}/files/search")]
+pub async fn search_book_files(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    book_id: web::Path<i32>,
+    query: web::Query<SearchParams>,
+    pagination: web::Query<PaginationQuery>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let book_id = book_id.into_inner();
+    books::assert_book_active(pool.get_ref(), book_id).await?;
+
+    let scope = FileSearchScope::Book(book_id);
+    scoped_file_search(&pool, &config, &query, pagination.into_inner(), &req, scope).await
+}
+
+/// File half of the global search, limited to one scholar's or book's files
+async fn scoped_file_search(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    query: &SearchParams,
+    mut pagination: PaginationQuery,
+    req: &HttpRequest,
+    scope: FileSearchScope,
+) -> Result<HttpResponse, AppError> {
+    let search_term = search_term(query)?;
+    pagination.validate();
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(req, config));
+
+    let (mut results, total) = files::search_files(
+        pool,
+        config,
+        search_term,
+        scope,
+        pagination.page,
+        pagination.per_page,
+        include_restricted,
+    )
+    .await?;
+
+    if query.highlight == Some(1) {
+        for file in results.iter_mut() {
+            file.highlight = highlight_search_match(&file.file_name, search_term);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Search results retrieved successfully".to_string(),
+        data: Some(results),
+        pagination: Some(PaginationMeta::new(pagination.page, pagination.per_page, total)),
+    }))
+}
+
+/// The trimmed query; an empty one is rejected
+fn search_term(query: &SearchParams) -> Result<&str, AppError> {
+    let search_term = query.q.trim();
+    if search_term.is_empty() {
+        return Err(AppError {
+            message: Some("Search query cannot be empty".to_string()),
+            cause: None,
+            error_type: AppErrorType::PayloadValidationError,
+        });
+    }
+    Ok(search_term)
+}
@@ -2,13 +2,16 @@ use actix_web::{get, web, HttpResponse, Responder};
 use sqlx::MySqlPool;
 use tracing::instrument;
 
-use crate::core::{AppError, AppErrorType, AppSuccessResponse};
+use crate::core::{AppError, AppErrorType, AppSuccessResponse, RedisHelper};
 use crate::db::settings::fetch_site_settings;
+use crate::models::settings::SiteSettingsResponse;
+use crate::db::feature_flags::load_feature_flags_or_defaults;
 
-#[instrument(name = "Get Site Settings", skip(pool))]
+#[instrument(name = "Get Site Settings", skip(pool, redis_service))]
 #[get("/settings")]
 pub async fn get_site_settings(
     pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
 ) -> Result<impl Responder, AppError> {
     let settings = fetch_site_settings(pool.get_ref())
         .await
@@ -21,10 +24,18 @@ pub async fn get_site_settings(
             }
         })?;
 
+    // A flag read failure shouldn't take the settings down with it
+    let features = load_feature_flags_or_defaults(&pool, &redis_service)
+        .await
+        .into_iter()
+        .filter(|flag| flag.is_public && flag.enabled)
+        .map(|flag| flag.key)
+        .collect();
+
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
         message: "Site settings retrieved successfully".to_string(),
-        data: Some(settings),
+        data: Some(SiteSettingsResponse { settings, features }),
         pagination: None,
     }))
 }
@@ -0,0 +1,149 @@
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use sqlx::MySqlPool;
+use tracing::instrument;
+
+use crate::{
+    core::{
+        extract_user_id_from_request, html_escape, AppConfig, AppError, AppErrorType,
+        AppSuccessResponse,
+    },
+    db::{files, share},
+    models::share::{ShareCard, ShareCardQuery},
+};
+
+/// Long `about` texts are cut so previews stay within what chat apps display
+const SHARE_DESCRIPTION_MAX_CHARS: usize = 200;
+
+#[instrument(name = "Get File Share Card", skip(pool, config, req))]
+#[get("/file/{file_id}")]
+pub async fn get_file_share_card(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    file_id: web::Path<i32>,
+    query: web::Query<ShareCardQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
+    let card = share::fetch_file_share_card(pool.get_ref(), &config, file_id.into_inner(), include_restricted)
+        .await
+        .map_err(share_card_error)?;
+    share_card_response(card, &query)
+}
+
+#[instrument(name = "Get Book Share Card", skip(pool, config, req))]
+#[get("/book/{book_id}")]
+pub async fn get_book_share_card(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    book_id: web::Path<i32>,
+    query: web::Query<ShareCardQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
+    let card = share::fetch_book_share_card(pool.get_ref(), &config, book_id.into_inner(), include_restricted)
+        .await
+        .map_err(share_card_error)?;
+    share_card_response(card, &query)
+}
+
+#[instrument(name = "Get Scholar Share Card", skip(pool, config))]
+#[get("/scholar/{scholar_id}")]
+pub async fn get_scholar_share_card(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    scholar_id: web::Path<i32>,
+    query: web::Query<ShareCardQuery>,
+) -> Result<HttpResponse, AppError> {
+    let card = share::fetch_scholar_share_card(pool.get_ref(), &config, scholar_id.into_inner())
+        .await
+        .map_err(share_card_error)?;
+    share_card_response(card, &query)
+}
+
+fn share_card_error(e: AppError) -> AppError {
+    match e.error_type {
+        AppErrorType::NotFoundError => e,
+        _ => {
+            tracing::error!("Failed to build share card: {:?}", e);
+            AppError {
+                message: Some("Failed to build share card".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            }
+        }
+    }
+}
+
+fn share_card_response(
+    mut card: ShareCard,
+    query: &ShareCardQuery,
+) -> Result<HttpResponse, AppError> {
+    if card.description.chars().count() > SHARE_DESCRIPTION_MAX_CHARS {
+        let cut: String = card
+            .description
+            .chars()
+            .take(SHARE_DESCRIPTION_MAX_CHARS - 1)
+            .collect();
+        card.description = format!("{}…", cut.trim_end());
+    }
+
+    match query.format.as_deref() {
+        None | Some("json") => Ok(HttpResponse::Ok().json(AppSuccessResponse {
+            success: true,
+            message: "Share card retrieved successfully".to_string(),
+            data: Some(card),
+            pagination: None,
+        })),
+        Some("html") => Ok(HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(render_share_card_html(&card))),
+        Some(other) => Err(AppError::bad_request(format!(
+            "Unsupported format '{}'; use json or html",
+            other
+        ))),
+    }
+}
+
+/// Bare page carrying only the meta tags, for crawlers that don't run the frontend
+fn render_share_card_html(card: &ShareCard) -> String {
+    let mut tags = vec![
+        ("og:type", card.kind.og_type().to_string()),
+        ("og:title", card.title.clone()),
+        ("og:description", card.description.clone()),
+        ("og:image", card.image_url.clone()),
+    ];
+    if let Some(audio_url) = &card.audio_url {
+        tags.push(("og:audio", audio_url.clone()));
+    }
+    if let Some(seconds) = card.duration_seconds {
+        tags.push(("music:duration", seconds.to_string()));
+    }
+
+    let meta = tags
+        .iter()
+        .map(|(property, content)| {
+            format!(
+                r#"<meta property="{}" content="{}">"#,
+                property,
+                html_escape(content)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n    ");
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>{}</title>
+    {}
+    <meta name="twitter:card" content="summary">
+</head>
+<body></body>
+</html>
+"#,
+        html_escape(&card.title),
+        meta
+    )
+}
@@ -10,10 +10,13 @@ use tracing::instrument;
 // const BANK_NAME: &str = "009291";
 
 use crate::{
-    core::{AppError, AppErrorType, AppSuccessResponse},
+    core::{AppError, AppErrorType, AppSuccessResponse, RedisHelper},
     db::states,
 };
 
+const STATES_CACHE_KEY: &str = "cache:states";
+const STATES_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
 // #[tracing::instrument(name = "Get All Transactions", skip(db_pool, query), fields(
 //     start_date = ?query.start_date,
 //     end_date = ?query.end_date,
@@ -121,17 +124,25 @@ use crate::{
 //     }))
 // }
 
-#[instrument(name = "Get States", skip(pool))]
+#[instrument(name = "Get States", skip(pool, redis_service))]
 #[get("/states")]
-pub async fn get_states(pool: web::Data<MySqlPool>) -> Result<impl Responder, AppError> {
-    let result = states::fetch_states(pool.get_ref()).await.map_err(|e| {
-        tracing::error!("Failed to fetch states: {:?}", e);
-        AppError {
-            message: Some("Failed to fetch states".to_string()),
-            cause: Some(e.to_string()),
-            error_type: AppErrorType::InternalServerError,
-        }
-    })?;
+pub async fn get_states(
+    pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
+) -> Result<impl Responder, AppError> {
+    let result = redis_service
+        .get_or_load(STATES_CACHE_KEY, STATES_CACHE_TTL, || {
+            states::fetch_states(pool.get_ref())
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch states: {:?}", e);
+            AppError {
+                message: Some("Failed to fetch states".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            }
+        })?;
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
@@ -0,0 +1,37 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use sqlx::MySqlPool;
+use tracing::instrument;
+
+use crate::core::{AppError, AppErrorType, AppSuccessResponse, RedisHelper};
+use crate::db::stats::fetch_public_stats;
+
+const PUBLIC_STATS_CACHE_KEY: &str = "cache:public_stats";
+const PUBLIC_STATS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+#[instrument(name = "Get Public Stats", skip(pool, redis_service))]
+#[get("/stats/public")]
+pub async fn get_public_stats(
+    pool: web::Data<MySqlPool>,
+    redis_service: web::Data<RedisHelper>,
+) -> Result<impl Responder, AppError> {
+    let stats = redis_service
+        .get_or_load(PUBLIC_STATS_CACHE_KEY, PUBLIC_STATS_CACHE_TTL, || {
+            fetch_public_stats(pool.get_ref())
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch public stats: {:?}", e);
+            AppError {
+                message: Some("Failed to fetch platform statistics".to_string()),
+                cause: Some(e.to_string()),
+                error_type: AppErrorType::InternalServerError,
+            }
+        })?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "Platform statistics retrieved successfully".to_string(),
+        data: Some(stats),
+        pagination: None,
+    }))
+}
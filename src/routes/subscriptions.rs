@@ -1,19 +1,62 @@
 use crate::core::jwt_auth::JwtClaims;
-use crate::core::AppError;
-use crate::core::{AppErrorResponse, AppSuccessResponse};
-use crate::db::subscriptions;
-use crate::jobs::subscription_expiry::expire_subscriptions_now;
-use crate::models::subscriptions::{CreateSubscriptionRequest, VerifySubscriptionRequest};
+use crate::core::{constant_time_eq, format_money, AppConfig, AppError, AppErrorType, EmailService};
+use crate::core::{error_codes, AppErrorResponse, AppSuccessResponse};
+use crate::db::{subscriptions, users};
+use crate::jobs::subscription_expiry::{expire_subscriptions_now, notify_expired_subscriptions};
+use crate::models::subscriptions::{
+    BatchVerifySubscriptionItem, BatchVerifySubscriptionResult, CreateSubscriptionRequest,
+    PaymentInstructions, SubscriptionPlanWithDisplay, SubscriptionPlansQuery,
+    VerifySubscriptionRequest,
+};
 
-use actix_web::{get, post, put, web, HttpResponse, Result};
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Result};
+use secrecy::ExposeSecret;
 use sqlx::MySqlPool;
 
-#[tracing::instrument(name = "Get Subscription Plans", skip(pool))]
+const MAX_BATCH_VERIFY_ITEMS: usize = 100;
+
+#[tracing::instrument(name = "Get Subscription Plans", skip(pool, config))]
 #[get("/plans")]
 pub async fn get_subscription_plans(
     pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    query: web::Query<SubscriptionPlansQuery>,
 ) -> Result<HttpResponse, AppError> {
-    let plans = subscriptions::get_all_subscription_plans(&pool).await?;
+    let currencies = &config.currencies;
+    let target = match query.currency.as_deref().map(str::trim) {
+        Some(currency) if !currency.is_empty() => {
+            let currency = currency.to_uppercase();
+            if currencies.rate(&currency).is_none() {
+                return Err(AppError::bad_request(format!("Unsupported currency '{}'", currency)));
+            }
+            Some(currency)
+        }
+        _ => None,
+    };
+
+    let plans = subscriptions::get_all_subscription_plans(&pool)
+        .await?
+        .into_iter()
+        .map(|plan| {
+            // A plan priced in a currency without a rate is shown unconverted
+            let converted = target.as_ref().and_then(|currency| {
+                let price = currencies.convert(&plan.price, &plan.currency, currency)?;
+                Some((currency.clone(), price))
+            });
+            let (display_currency, display_price) =
+                converted.unwrap_or_else(|| (plan.currency.clone(), plan.price.clone()));
+            let display_price = display_price.round(2);
+            SubscriptionPlanWithDisplay {
+                price_display: format_money(
+                    &display_price,
+                    currencies.display_name(&display_currency),
+                ),
+                display_price,
+                display_currency,
+                plan,
+            }
+        })
+        .collect::<Vec<_>>();
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
@@ -99,9 +142,26 @@ pub async fn create_subscription(
         .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
 
     // Validate subscription plan exists
-    let _plan = subscriptions::get_subscription_plan_by_id(&pool, request.subscription_plan_id).await
+    let plan = subscriptions::get_subscription_plan_by_id(&pool, request.subscription_plan_id).await
         .map_err(|_| AppError::bad_request("Invalid subscription plan ID"))?;
 
+    let mut request = request.into_inner();
+    let currency = request.payment_currency.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    if currency.is_some_and(|currency| !currency.eq_ignore_ascii_case(&plan.currency)) {
+        return Err(AppError::bad_request(format!(
+            "Payment currency must be {} for this plan",
+            plan.currency
+        )));
+    }
+    // BigDecimal compares by value, so 1500 matches a price of 1500.00
+    if request.payment_amount != plan.price {
+        return Err(AppError::bad_request(format!(
+            "Payment amount must be {} {} for this plan",
+            plan.price, plan.currency
+        )));
+    }
+    request.payment_currency = Some(plan.currency.clone());
+
     // Check if user already has a pending subscription
     let user_subscriptions = subscriptions::get_user_subscriptions(&pool, user_id).await?;
     let has_pending = user_subscriptions.iter().any(|s| s.status == "pending");
@@ -109,6 +169,7 @@ pub async fn create_subscription(
     if has_pending {
         return Ok(HttpResponse::BadRequest().json(AppErrorResponse {
             success: false,
+            code: error_codes::SUBSCRIPTION_PENDING.to_string(),
             message: "You already have a pending subscription. Please wait for verification.".to_string(),
         }));
     }
@@ -123,7 +184,100 @@ pub async fn create_subscription(
     }))
 }
 
+// Only the owner may see instructions, and only while the subscription is pending
+async fn build_payment_instructions(
+    pool: &MySqlPool,
+    config: &AppConfig,
+    subscription_id: i32,
+    user_id: i32,
+) -> Result<PaymentInstructions, AppError> {
+    let subscription =
+        subscriptions::get_pending_subscription_for_user(pool, subscription_id, user_id)
+            .await?
+            .ok_or_else(|| AppError {
+                message: Some("Pending subscription not found".to_string()),
+                cause: None,
+                error_type: AppErrorType::NotFoundError,
+            })?;
+
+    let plan =
+        subscriptions::get_subscription_plan_by_id(pool, subscription.subscription_plan_id).await?;
+
+    Ok(PaymentInstructions {
+        subscription_id: subscription.id,
+        plan_name: plan.name,
+        amount: plan.price,
+        currency: plan.currency,
+        transaction_reference: subscription.transaction_reference,
+        channels: config.payments.channels.clone(),
+    })
+}
+
+#[tracing::instrument(name = "Get Payment Instructions", skip(pool, config, claims))]
+#[get("/{subscription_id}/payment-instructions")]
+pub async fn get_payment_instructions(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    claims: JwtClaims,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let instructions =
+        build_payment_instructions(&pool, &config, path.into_inner(), user_id).await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: instructions,
+        message: "Payment instructions retrieved successfully".to_string(),
+        pagination: None,
+    }))
+}
+
+#[tracing::instrument(name = "Resend Payment Instructions", skip(pool, config, email_service, claims))]
+#[post("/{subscription_id}/resend-instructions")]
+pub async fn resend_payment_instructions(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    email_service: web::Data<EmailService>,
+    claims: JwtClaims,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+
+    let instructions =
+        build_payment_instructions(&pool, &config, path.into_inner(), user_id).await?;
+    let user = users::get_user_by_id(&pool, user_id).await?;
+
+    email_service
+        .send_payment_instructions(&user.email, &instructions)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: instructions,
+        message: "Payment instructions sent to your email".to_string(),
+        pagination: None,
+    }))
+}
+
 // Admin endpoints
+/// Whether the signed-in user is an admin according to the database; the role
+/// in the token may predate a demotion
+async fn is_current_admin(pool: &MySqlPool, claims: &JwtClaims) -> Result<bool, AppError> {
+    let user_id: i32 = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::unauthorized("Invalid user ID in token"))?;
+    Ok(users::get_current_role(pool, user_id).await?.as_deref() == Some("admin"))
+}
+
 #[tracing::instrument(name = "Get Pending Subscriptions", skip(pool, claims))]
 #[get("/admin/pending")]
 pub async fn get_pending_subscriptions(
@@ -131,9 +285,10 @@ pub async fn get_pending_subscriptions(
     claims: JwtClaims,
 ) -> Result<HttpResponse, AppError> {
     // Check if user is admin
-    if claims.role != "admin" {
+    if !is_current_admin(&pool, &claims).await? {
         return Ok(HttpResponse::Forbidden().json(AppErrorResponse {
             success: false,
+            code: error_codes::ADMIN_REQUIRED.to_string(),
             message: "Access denied. Admin role required.".to_string(),
         }));
     }
@@ -157,9 +312,10 @@ pub async fn verify_subscription(
     request: web::Json<VerifySubscriptionRequest>,
 ) -> Result<HttpResponse, AppError> {
     // Check if user is admin
-    if claims.role != "admin" {
+    if !is_current_admin(&pool, &claims).await? {
         return Ok(HttpResponse::Forbidden().json(AppErrorResponse {
             success: false,
+            code: error_codes::ADMIN_REQUIRED.to_string(),
             message: "Access denied. Admin role required.".to_string(),
         }));
     }
@@ -170,6 +326,7 @@ pub async fn verify_subscription(
     if !["active", "cancelled"].contains(&request.status.as_str()) {
         return Ok(HttpResponse::BadRequest().json(AppErrorResponse {
             success: false,
+            code: error_codes::INVALID_STATUS.to_string(),
             message: "Invalid status. Must be 'active' or 'cancelled'.".to_string(),
         }));
     }
@@ -190,27 +347,169 @@ pub async fn verify_subscription(
     }))
 }
 
-#[tracing::instrument(name = "Expire Subscriptions Now", skip(pool, claims))]
-#[post("/admin/expire-now")]
-pub async fn expire_subscriptions(
+#[tracing::instrument(name = "Verify Subscriptions Batch", skip(pool, email_service, claims, request))]
+#[post("/admin/verify-batch")]
+pub async fn verify_subscriptions_batch(
     pool: web::Data<MySqlPool>,
+    email_service: web::Data<EmailService>,
     claims: JwtClaims,
+    request: web::Json<Vec<BatchVerifySubscriptionItem>>,
 ) -> Result<HttpResponse, AppError> {
     // Check if user is admin
-    if claims.role != "admin" {
+    if !is_current_admin(&pool, &claims).await? {
+        return Ok(HttpResponse::Forbidden().json(AppErrorResponse {
+            success: false,
+            code: error_codes::ADMIN_REQUIRED.to_string(),
+            message: "Access denied. Admin role required.".to_string(),
+        }));
+    }
+
+    let items = request.into_inner();
+    if items.is_empty() || items.len() > MAX_BATCH_VERIFY_ITEMS {
+        return Err(AppError::bad_request(format!(
+            "Provide between 1 and {} subscriptions to verify",
+            MAX_BATCH_VERIFY_ITEMS
+        )));
+    }
+
+    // Each item is verified in its own transaction so one failure doesn't undo the rest
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        if !["active", "cancelled"].contains(&item.status.as_str()) {
+            results.push(BatchVerifySubscriptionResult {
+                subscription_id: item.subscription_id,
+                success: false,
+                status: None,
+                error: Some("Invalid status. Must be 'active' or 'cancelled'.".to_string()),
+            });
+            continue;
+        }
+
+        let verify_request = VerifySubscriptionRequest {
+            status: item.status,
+            notes: item.notes,
+        };
+
+        match subscriptions::verify_user_subscription(&pool, item.subscription_id, &verify_request)
+            .await
+        {
+            Ok(subscription) => {
+                if subscription.status == "active" {
+                    notify_subscription_activated(&pool, &email_service, &subscription).await;
+                }
+                results.push(BatchVerifySubscriptionResult {
+                    subscription_id: item.subscription_id,
+                    success: true,
+                    status: Some(subscription.status),
+                    error: None,
+                });
+            }
+            Err(e) => {
+                tracing::warn!("Failed to verify subscription {}: {:?}", item.subscription_id, e);
+                results.push(BatchVerifySubscriptionResult {
+                    subscription_id: item.subscription_id,
+                    success: false,
+                    status: None,
+                    error: Some(e.message()),
+                });
+            }
+        }
+    }
+
+    let verified = results.iter().filter(|r| r.success).count();
+    let message = format!("Verified {} of {} subscriptions", verified, results.len());
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        data: results,
+        message,
+        pagination: None,
+    }))
+}
+
+// Activation emails are best-effort; the subscription is already active either way
+async fn notify_subscription_activated(
+    pool: &MySqlPool,
+    email_service: &EmailService,
+    subscription: &crate::models::subscriptions::UserSubscription,
+) {
+    let user = match users::get_user_by_id(pool, subscription.user_id).await {
+        Ok(user) => user,
+        Err(e) => {
+            tracing::warn!("Failed to load user {} for activation email: {:?}", subscription.user_id, e);
+            return;
+        }
+    };
+    let plan_name = subscriptions::get_subscription_plan_by_id(pool, subscription.subscription_plan_id)
+        .await
+        .map(|plan| plan.name)
+        .unwrap_or_else(|_| "Muryar Sunnah".to_string());
+    let end_date = subscription
+        .end_date
+        .map(|date| date.to_string())
+        .unwrap_or_default();
+
+    if let Err(e) = email_service
+        .send_subscription_activated(&user.email, &plan_name, &end_date)
+        .await
+    {
+        tracing::warn!("Failed to queue activation email for subscription {}: {:?}", subscription.id, e);
+    }
+}
+
+// A scheduler may authenticate with the configured cron token instead of an admin JWT
+fn has_valid_cron_token(req: &HttpRequest, config: &AppConfig) -> bool {
+    let Some(expected) = &config.subscriptions.cron_token else {
+        return false;
+    };
+    req.headers()
+        .get("X-Cron-Token")
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |token| {
+            !token.is_empty() && constant_time_eq(token.as_bytes(), expected.expose_secret().as_bytes())
+        })
+}
+
+#[tracing::instrument(name = "Expire Subscriptions Now", skip(pool, config, email_service, claims, req))]
+#[post("/admin/expire-now")]
+pub async fn expire_subscriptions(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    email_service: web::Data<EmailService>,
+    claims: Option<JwtClaims>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let is_admin = match &claims {
+        Some(claims) => is_current_admin(&pool, claims).await?,
+        None => false,
+    };
+
+    // Check if user is admin or the cron scheduler
+    if !is_admin && !has_valid_cron_token(&req, &config) {
         return Ok(HttpResponse::Forbidden().json(AppErrorResponse {
             success: false,
+            code: error_codes::ADMIN_REQUIRED.to_string(),
             message: "Access denied. Admin role required.".to_string(),
         }));
     }
 
-    let expired_count = expire_subscriptions_now(&pool)
+    let expired = expire_subscriptions_now(&pool, config.subscriptions.grace_days)
         .await
         .map_err(AppError::db_error)?;
 
+    if config.subscriptions.send_expiry_emails {
+        notify_expired_subscriptions(&pool, &email_service, &expired).await;
+    }
+
+    let expired_count = expired.len();
+    let subscription_ids: Vec<i32> = expired.iter().map(|s| s.subscription_id).collect();
+
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
-        data: serde_json::json!({ "expired_count": expired_count }),
+        data: serde_json::json!({
+            "expired_count": expired_count,
+            "subscription_ids": subscription_ids
+        }),
         message: format!("Successfully expired {} subscription(s)", expired_count),
         pagination: None,
     }))
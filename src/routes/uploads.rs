@@ -1,24 +1,78 @@
 use actix_files::NamedFile;
 use actix_multipart::Multipart;
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::http::header::{self, ContentDisposition, DispositionType, HeaderValue};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use futures_util::TryStreamExt;
 use sqlx::MySqlPool;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::Path;
 use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
     core::{
-        extract_mp3_metadata, jwt_auth::JwtMiddleware, AppError, AppErrorType, AppSuccessResponse,
+        attachment_disposition, client_ip, detect_audio_content_type, ensure_within_dir,
+        extract_guest_client_id,
+        extract_mp3_metadata, extract_user_id_from_request, is_safe_storage_location,
+        parse_duration,
+        jwt_auth::JwtMiddleware, prepare_storage_location, resolve_storage_path,
+        sanitize_download_filename, sha256_file, spawn_blocking_with_tracing, AppError, AppErrorType,
+        AppSuccessResponse, RedisHelper, StagedFile,
     },
-    db::{access, file_interactions, subscriptions, uploads},
+    db::{access, books, feature_flags::is_feature_enabled, file_interactions, files, subscriptions, uploads},
+    models::feature_flags::FeatureFlag,
+    routes::play_history::check_guest_rate_limit,
+    models::uploads::{DownloadZipRequest, ZipManifest, ZipManifestFile, ZipOmittedFile},
 };
 
 // const UPLOAD_DIR: &str = "./uploads";
 // const UPLOAD_DIR: &str = "/home/mubarak/Documents/my-documents/muryar_sunnah/web/uploads";
 
 const MAX_FILE_SIZE: usize = 100 * 1024 * 1024; // 100MB
+const MAX_ZIP_FILES: usize = 50;
+const MAX_ZIP_TOTAL_BYTES: u64 = 500 * 1024 * 1024; // 500MB
+// Bytes per second assumed for a preview when the stored duration is unusable (128 kbps)
+const FALLBACK_PREVIEW_BYTES_PER_SECOND: u64 = 16_000;
+
+/// An upload being streamed to disk, removed on drop unless it is persisted.
+/// Lives under a hidden `.tmp` dir so the static audio route never serves it.
+struct TempUpload {
+    path: String,
+    persisted: bool,
+}
+
+impl TempUpload {
+    fn create(upload_dir: &str) -> std::io::Result<(Self, fs::File)> {
+        let temp_dir = format!("{}/.tmp", upload_dir);
+        fs::create_dir_all(&temp_dir)?;
+
+        let path = format!("{}/{}.part", temp_dir, Uuid::new_v4());
+        let file = fs::File::create(&path)?;
+        Ok((
+            TempUpload {
+                path,
+                persisted: false,
+            },
+            file,
+        ))
+    }
+
+    /// Move the finished upload to its final location
+    fn persist(mut self, file_path: &str) -> std::io::Result<()> {
+        fs::rename(&self.path, file_path)?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for TempUpload {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
 
 #[instrument(name = "Upload File", skip(pool, payload))]
 #[post("/{book_id}/upload")]
@@ -31,7 +85,7 @@ pub async fn upload_file(
 ) -> Result<impl Responder, AppError> {
     let book_id = book_id.into_inner();
 
-    // Check if user has access to upload to this book's scholar
+    // Check if user has access to upload to this book
 
     let user = crate::db::users::get_user_by_id(pool.get_ref(), auth.user_id)
         .await
@@ -45,19 +99,11 @@ pub async fn upload_file(
         })?;
 
     if user.role != "admin" {
-        let scholar_id = uploads::get_scholar_id_from_book(pool.get_ref(), book_id)
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to get scholar_id for book {}: {:?}", book_id, e);
-                AppError {
-                    message: Some("Book not found".to_string()),
-                    cause: Some(e.to_string()),
-                    error_type: AppErrorType::NotFoundError,
-                }
-            })?;
+        books::assert_book_active(pool.get_ref(), book_id).await?;
 
+        // A grant on the book itself is enough; it need not cover the whole scholar
         let has_access =
-            access::check_user_access_to_scholar(pool.get_ref(), auth.user_id, scholar_id)
+            access::check_user_access_to_book(pool.get_ref(), auth.user_id, book_id)
                 .await
                 .map_err(|e| {
                     tracing::error!("Failed to check user access: {:?}", e);
@@ -71,7 +117,7 @@ pub async fn upload_file(
         if !has_access {
             return Err(AppError {
                 message: Some(
-                    "You don't have permission to upload to this scholar's content".to_string(),
+                    "You don't have permission to upload to this book".to_string(),
                 ),
                 cause: None,
                 error_type: AppErrorType::ForbiddenError,
@@ -91,7 +137,7 @@ pub async fn upload_file(
     })?;
 
     let mut _description: Option<String> = None;
-    let mut file_data: Option<(String, Vec<u8>, String)> = None;
+    let mut file_data: Option<(String, TempUpload, usize, String)> = None;
 
     // Process multipart form data
     while let Some(mut field) = payload.try_next().await.map_err(|e| {
@@ -148,23 +194,51 @@ pub async fn upload_file(
                     .map(|ct| ct.to_string())
                     .unwrap_or_else(|| "audio/mpeg".to_string());
 
-                let mut file_bytes = Vec::new();
+                // Stream chunks straight to a temp file instead of buffering the upload in memory
+                let (temp_upload, mut temp_file) = TempUpload::create(upload_dir).map_err(|e| {
+                    tracing::error!("Failed to create temp upload file: {:?}", e);
+                    AppError {
+                        message: Some("Failed to save file".to_string()),
+                        cause: Some(e.to_string()),
+                        error_type: AppErrorType::InternalServerError,
+                    }
+                })?;
+
+                let mut file_size: usize = 0;
                 while let Some(chunk) = field.try_next().await.map_err(|e| AppError {
                     message: Some("Failed to read file data".to_string()),
                     cause: Some(e.to_string()),
                     error_type: AppErrorType::PayloadValidationError,
                 })? {
-                    file_bytes.extend_from_slice(&chunk);
-                    if file_bytes.len() > MAX_FILE_SIZE {
+                    file_size += chunk.len();
+                    if file_size > MAX_FILE_SIZE {
                         return Err(AppError {
                             message: Some("File size exceeds maximum limit (100MB)".to_string()),
                             cause: None,
                             error_type: AppErrorType::PayloadValidationError,
                         });
                     }
+                    temp_file.write_all(&chunk).map_err(|e| {
+                        tracing::error!("Failed to write upload chunk to {}: {:?}", temp_upload.path, e);
+                        AppError {
+                            message: Some("Failed to save file".to_string()),
+                            cause: Some(e.to_string()),
+                            error_type: AppErrorType::InternalServerError,
+                        }
+                    })?;
                 }
 
-                file_data = Some((filename, file_bytes, content_type));
+                temp_file.flush().map_err(|e| {
+                    tracing::error!("Failed to flush upload {}: {:?}", temp_upload.path, e);
+                    AppError {
+                        message: Some("Failed to save file".to_string()),
+                        cause: Some(e.to_string()),
+                        error_type: AppErrorType::InternalServerError,
+                    }
+                })?;
+                drop(temp_file);
+
+                file_data = Some((filename, temp_upload, file_size, content_type));
             }
             _ => {
                 // Skip unknown fields
@@ -177,14 +251,29 @@ pub async fn upload_file(
         }
     }
 
-    let (original_filename, file_bytes, content_type) = file_data.ok_or_else(|| AppError {
-        message: Some("File is required".to_string()),
-        cause: None,
-        error_type: AppErrorType::PayloadValidationError,
-    })?;
+    let (original_filename, temp_upload, file_size, content_type) =
+        file_data.ok_or_else(|| AppError {
+            message: Some("File is required".to_string()),
+            cause: None,
+            error_type: AppErrorType::PayloadValidationError,
+        })?;
+
+    if file_size < config.uploads.min_file_bytes {
+        return Err(AppError {
+            message: Some("The audio file is too small to be valid".to_string()),
+            cause: Some(format!("{} bytes", file_size)),
+            error_type: AppErrorType::PayloadValidationError,
+        });
+    }
 
     // Extract MP3 metadata (title and duration)
-    let (title, duration) = extract_mp3_metadata(&file_bytes)?;
+    let (title, duration) =
+        extract_mp3_metadata(
+            &temp_upload.path,
+            config.uploads.min_duration_seconds,
+            config.uploads.max_file_bytes,
+        )
+        .await?;
 
     tracing::info!(
         "Extracted MP3 metadata - Title: {}, Duration: {}",
@@ -192,6 +281,13 @@ pub async fn upload_file(
         duration
     );
 
+    // Stored so clients can verify offline copies, as for bulk imports
+    let hash_path = temp_upload.path.clone();
+    let content_hash = spawn_blocking_with_tracing(move || sha256_file(Path::new(&hash_path)))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Hash task failed: {}", e)))?
+        .map_err(|e| AppError::internal_error(format!("Failed to hash upload: {}", e)))?;
+
     // Generate unique filename
     let file_stem = Path::new(&original_filename)
         .file_stem()
@@ -205,10 +301,22 @@ pub async fn upload_file(
 
     let random_id = Uuid::new_v4().to_string()[..5].to_string(); // 5 char random ID
     let unique_filename = format!("{}_{}.{}", file_stem, random_id, file_extension);
-    let file_path = format!("{}/{}", upload_dir, unique_filename);
+    let (location, file_path) = prepare_storage_location(
+        upload_dir,
+        &unique_filename,
+        config.app_paths.shard_by_date,
+    )
+    .map_err(|e| {
+        tracing::error!("Failed to prepare storage for {}: {:?}", unique_filename, e);
+        AppError {
+            message: Some("Failed to save file".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::InternalServerError,
+        }
+    })?;
 
-    fs::write(&file_path, &file_bytes).map_err(|e| {
-        tracing::error!("Failed to write file {}: {:?}", file_path, e);
+    temp_upload.persist(&file_path).map_err(|e| {
+        tracing::error!("Failed to move upload to {}: {:?}", file_path, e);
         AppError {
             message: Some("Failed to save file".to_string()),
             cause: Some(e.to_string()),
@@ -216,22 +324,25 @@ pub async fn upload_file(
         }
     })?;
 
+    // From here the stored file only stays if its row commits; that includes
+    // the request being dropped by a timeout mid-save
+    let stored_file = StagedFile::new(&file_path);
+
     // Save file metadata to database
     let upload_response = uploads::save_uploaded_file(
         pool.get_ref(),
         book_id, // Use extracted title from MP3
         &file_stem,
-        &unique_filename,
-        file_bytes.len() as i64,
+        &location,
+        file_size as i64,
         &content_type,
         &duration, // MP3 duration
+        &content_hash,
         &random_id,
         auth.user_id,
     )
     .await
     .map_err(|e| {
-        // Clean up file if database save fails
-        let _ = fs::remove_file(&file_path);
         tracing::error!("Failed to save file metadata: {:?}", e);
         AppError {
             message: Some("Failed to save file metadata".to_string()),
@@ -239,6 +350,7 @@ pub async fn upload_file(
             error_type: AppErrorType::InternalServerError,
         }
     })?;
+    stored_file.keep();
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
@@ -256,12 +368,17 @@ pub async fn upload_file(
 /// - Analytics and metrics collection
 /// - Monitoring user engagement
 ///
+/// Guests may call it when guest tracking is enabled; their downloads are
+/// keyed by the anonymous client id like guest plays.
+///
 /// POST /api/v1/files/{file_id}/track-download
-#[instrument(name = "Track Download", skip(pool, req, auth))]
+#[instrument(name = "Track Download", skip(pool, config, redis_service, req, auth))]
 #[post("/{file_id}/track-download")]
 pub async fn track_download(
     pool: web::Data<MySqlPool>,
-    auth: JwtMiddleware,
+    config: web::Data<crate::core::config::AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    auth: Option<JwtMiddleware>,
     file_id: web::Path<i32>,
     req: actix_web::HttpRequest,
 ) -> Result<impl Responder, AppError> {
@@ -284,18 +401,33 @@ pub async fn track_download(
         });
     }
 
-    // Get user's active subscription (if any)
-    let subscription_id =
-        match subscriptions::get_user_active_subscription(&pool, auth.user_id).await {
-            Ok(Some(subscription)) => Some(subscription.id),
-            _ => None,
-        };
-
     // Extract client IP and user agent
-    let client_ip = req
-        .connection_info()
-        .realip_remote_addr()
-        .map(|ip| ip.to_string());
+    let client_ip = client_ip(&req, &config);
+
+    let (user_id, anonymous_id, subscription_id) = match &auth {
+        Some(auth) => {
+            // Get user's active subscription (if any)
+            let subscription_id =
+                match subscriptions::get_user_active_subscription(&pool, auth.user_id).await {
+                    Ok(Some(subscription)) => Some(subscription.id),
+                    _ => None,
+                };
+            (Some(auth.user_id), None, subscription_id)
+        }
+        None => {
+            if !config.guest_tracking.enabled
+                || !is_feature_enabled(&pool, &redis_service, FeatureFlag::GuestTracking).await
+            {
+                return Err(AppError::unauthorized("Authentication required"));
+            }
+            let anonymous_id = extract_guest_client_id(&req).ok_or_else(|| {
+                AppError::bad_request("A valid X-Client-Id header is required for guest downloads")
+            })?;
+            check_guest_rate_limit(&redis_service, &config, &anonymous_id, client_ip.as_deref())
+                .await?;
+            (None, Some(anonymous_id), None)
+        }
+    };
 
     let user_agent = req
         .headers()
@@ -306,7 +438,8 @@ pub async fn track_download(
     // Log the download
     file_interactions::log_file_download(
         &pool,
-        auth.user_id,
+        user_id,
+        anonymous_id.as_deref(),
         subscription_id,
         file_id,
         client_ip,
@@ -322,11 +455,13 @@ pub async fn track_download(
         }
     })?;
 
-    tracing::info!(
-        "Download tracked for file {} by user {}",
-        file_id,
-        auth.user_id
-    );
+    match (user_id, &anonymous_id) {
+        (Some(user_id), _) => tracing::info!("Download tracked for file {} by user {}", file_id, user_id),
+        (None, Some(anonymous_id)) => {
+            tracing::info!("Download tracked for file {} by guest {}", file_id, anonymous_id)
+        }
+        (None, None) => {}
+    }
 
     Ok(HttpResponse::Ok().json(AppSuccessResponse {
         success: true,
@@ -349,14 +484,15 @@ pub async fn track_download(
 /// - Browser caching with Last-Modified headers
 ///
 /// GET /api/v1/files/{file_id}/download
-#[instrument(name = "Download File", skip(pool, config, auth))]
+#[instrument(name = "Download File", skip(pool, config, auth, req))]
 #[get("/{file_id}/download")]
 pub async fn download_file(
     pool: web::Data<MySqlPool>,
     config: web::Data<crate::core::config::AppConfig>,
     auth: JwtMiddleware,
+    req: HttpRequest,
     file_id: web::Path<i32>,
-) -> Result<NamedFile, AppError> {
+) -> Result<HttpResponse, AppError> {
     let file_id = file_id.into_inner();
 
     // Get file information (lightweight query)
@@ -366,31 +502,566 @@ pub async fn download_file(
             .map_err(|e| {
                 tracing::error!("Failed to get file info: {:?}", e);
                 AppError {
-                    message: Some("File not found".to_string()),
+                    message: Some("Failed to get file information".to_string()),
                     cause: Some(e.to_string()),
-                    error_type: AppErrorType::NotFoundError,
+                    error_type: AppErrorType::InternalServerError,
                 }
+            })?
+            .ok_or_else(|| AppError {
+                message: Some("File not found".to_string()),
+                cause: None,
+                error_type: AppErrorType::NotFoundError,
             })?;
 
+    // The record exists, so a missing path means storage is out of sync with the database
+    if !Path::new(&file_info.file_path).exists() {
+        tracing::error!(
+            "File {} (book {}, scholar {}) is missing on disk at {}",
+            file_id,
+            file_info.book_id,
+            file_info.scholar_id,
+            file_info.file_path
+        );
+
+        if let Err(e) =
+            uploads::flag_missing_file(pool.get_ref(), file_id, &file_info.file_path).await
+        {
+            tracing::error!("Failed to flag missing file {}: {:?}", file_id, e);
+        }
+
+        return Err(AppError {
+            message: Some(
+                "This audio is temporarily unavailable and has been reported to the administrators"
+                    .to_string(),
+            ),
+            cause: Some(format!("missing on disk: {}", file_info.file_path)),
+            error_type: AppErrorType::InternalServerError,
+        });
+    }
+
+    // Never serve a location that resolves outside the uploads dir (`..` segments or symlinks)
+    if let Err(e) = ensure_within_dir(&config.app_paths.uploads_dir, &file_info.file_path) {
+        tracing::warn!(
+            "Refusing to serve file {} from {}: {:?}",
+            file_id,
+            file_info.file_path,
+            e
+        );
+        return Err(AppError {
+            message: Some("Access to this file is denied".to_string()),
+            cause: Some(e.to_string()),
+            error_type: AppErrorType::ForbiddenError,
+        });
+    }
+
+    let stored_path = Path::new(&file_info.file_path);
+    let extension = stored_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("mp3");
+    let display_name = if config.app_paths.humanize_download_names {
+        file_info.filename.as_str()
+    } else {
+        stored_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("")
+    };
+    let download_name = sanitize_download_filename(display_name, extension);
+
     // Open file using NamedFile for efficient streaming
-    let named_file = NamedFile::open(&file_info.file_path)
+    let named_file = NamedFile::open_async(&file_info.file_path)
+        .await
         .map_err(|e| {
             tracing::error!("Failed to open file {}: {:?}", file_info.file_path, e);
             AppError {
-                message: Some("File not found on disk".to_string()),
+                message: Some("Failed to open file".to_string()),
                 cause: Some(e.to_string()),
-                error_type: AppErrorType::NotFoundError,
+                error_type: AppErrorType::InternalServerError,
             }
         })?
-        .use_last_modified(true)
-        .set_content_disposition(actix_web::http::header::ContentDisposition {
-            disposition: actix_web::http::header::DispositionType::Attachment,
-            parameters: vec![actix_web::http::header::DispositionParam::Filename(
-                file_info.filename.clone(),
-            )],
-        });
+        .set_content_disposition(attachment_disposition(&download_name));
 
     tracing::info!("File {} streamed to user {}", file_id, auth.user_id);
 
+    Ok(audio_response(named_file, stored_path, &req, &config.audio_serving).await)
+}
+
+/// Stream stored audio to authenticated users
+///
+/// Stands in for the open `/static/audio` mount unless `app_paths.public_audio` is set.
+/// NamedFile answers Range requests, so players can still seek.
+///
+/// GET /api/v1/static/audio/{location}
+#[instrument(name = "Stream Audio", skip(pool, config, _auth, req))]
+#[get("/audio/{location:.*}")]
+pub async fn stream_audio(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<crate::core::config::AppConfig>,
+    _auth: JwtMiddleware,
+    req: HttpRequest,
+    location: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    serve_stored_audio(&pool, &config, &req, &location.into_inner(), true).await
+}
+
+/// Stream stored audio to anyone, for deployments with `app_paths.public_audio`.
+/// Restricted files still need a signed-in caller, as in listings
+///
+/// GET /api/v1/static/audio/{location}
+#[instrument(name = "Stream Public Audio", skip(pool, config, req))]
+#[get("/audio/{location:.*}")]
+pub async fn stream_public_audio(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<crate::core::config::AppConfig>,
+    req: HttpRequest,
+    location: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let include_restricted = files::can_view_restricted(extract_user_id_from_request(&req, &config));
+    serve_stored_audio(&pool, &config, &req, &location.into_inner(), include_restricted).await
+}
+
+async fn serve_stored_audio(
+    pool: &MySqlPool,
+    config: &crate::core::config::AppConfig,
+    req: &HttpRequest,
+    location: &str,
+    include_restricted: bool,
+) -> Result<HttpResponse, AppError> {
+    let not_found = || AppError {
+        message: Some("File not found".to_string()),
+        cause: None,
+        error_type: AppErrorType::NotFoundError,
+    };
+
+    if !is_safe_storage_location(location)
+        || !uploads::is_published_file_location(pool, location, include_restricted).await?
+    {
+        return Err(not_found());
+    }
+
+    let uploads_dir = &config.app_paths.uploads_dir;
+    let file_path = resolve_storage_path(uploads_dir, location);
+    let file_path = ensure_within_dir(uploads_dir, &file_path).map_err(|e| {
+        tracing::warn!("Refusing to stream {}: {:?}", location, e);
+        not_found()
+    })?;
+
+    let named_file = NamedFile::open_async(&file_path)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to open audio {:?}: {:?}", file_path, e);
+            not_found()
+        })?
+        // Played in place, never saved as a download
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![],
+        });
+
+    Ok(audio_response(named_file, &file_path, req, &config.audio_serving).await)
+}
+
+/// Respond with `named_file`, typed by its detected format when enabled so a
+/// wrong or missing extension can't make players refuse it, with range
+/// support advertised and the configured caching
+async fn audio_response(
+    named_file: NamedFile,
+    path: &Path,
+    req: &HttpRequest,
+    config: &crate::core::config::AudioServingConfig,
+) -> HttpResponse {
+    let detected = if config.detect_content_type {
+        sniff_audio_content_type(path).await
+    } else {
+        None
+    };
+
+    let mut response = named_file.use_last_modified(true).into_response(req);
+    let headers = response.headers_mut();
+    if let Some(content_type) = detected {
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    let cache_control = match config.cache_max_age_seconds {
+        0 => "no-cache".to_string(),
+        max_age => format!("private, max-age={}", max_age),
+    };
+    if let Ok(value) = HeaderValue::from_str(&cache_control) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    response
+}
+
+/// Stream the first `previews.seconds` of a file without sign-in
+///
+/// The clip is the byte prefix matching that share of the stored duration,
+/// which MP3 players decode fine; files no longer than the preview are sent
+/// whole. Guests need a client id and count against the guest rate limit.
+/// Each clip is logged as a preview play, never as a play; guest previews
+/// only while guest tracking is on.
+///
+/// GET /api/v1/files/{file_id}/preview
+#[instrument(name = "Preview Audio", skip(pool, config, redis_service, req))]
+#[get("/{file_id}/preview")]
+pub async fn preview_file(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<crate::core::config::AppConfig>,
+    redis_service: web::Data<RedisHelper>,
+    req: HttpRequest,
+    file_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let preview_seconds = config.previews.seconds;
+    if preview_seconds == 0 {
+        return Err(AppError::not_found("Previews are not available"));
+    }
+
+    let user_id = extract_user_id_from_request(&req, &config);
+    let client_ip = client_ip(&req, &config);
+    let anonymous_id = match user_id {
+        Some(_) => None,
+        None => {
+            let anonymous_id = extract_guest_client_id(&req).ok_or_else(|| {
+                AppError::bad_request("A valid X-Client-Id header is required for guest previews")
+            })?;
+            check_guest_rate_limit(&redis_service, &config, &anonymous_id, client_ip.as_deref())
+                .await?;
+            Some(anonymous_id)
+        }
+    };
+
+    let uploads_dir = &config.app_paths.uploads_dir;
+    let source = uploads::get_file_preview_source(
+        pool.get_ref(),
+        uploads_dir,
+        file_id.into_inner(),
+        files::can_view_restricted(user_id),
+    )
+    .await?
+    .ok_or_else(|| AppError::not_found("File not found"))?;
+    let file_path = ensure_within_dir(uploads_dir, &source.file_path).map_err(|e| {
+        tracing::warn!("Refusing to preview {}: {:?}", source.file_path, e);
+        AppError::not_found("File not found")
+    })?;
+
+    let duration_seconds = parse_duration(&source.duration).unwrap_or(0);
+    let detect = config.audio_serving.detect_content_type;
+    let (clip, content_type) = web::block(move || {
+        let clip = read_preview_clip(&file_path, duration_seconds, preview_seconds)?;
+        let content_type = detect.then(|| detect_audio_content_type(&file_path)).flatten();
+        Ok::<_, std::io::Error>((clip, content_type))
+    })
+    .await
+    .map_err(AppError::internal_error)?
+    .map_err(|e| {
+        tracing::error!("Failed to read preview of file {}: {:?}", source.file_id, e);
+        AppError::not_found("File not found")
+    })?;
+
+    let log_preview = user_id.is_some()
+        || (config.guest_tracking.enabled
+            && is_feature_enabled(&pool, &redis_service, FeatureFlag::GuestTracking).await);
+    if log_preview {
+        if let Err(e) = uploads::log_preview_play(
+            pool.get_ref(),
+            source.file_id,
+            user_id,
+            anonymous_id.as_deref(),
+            client_ip.as_deref(),
+        )
+        .await
+        {
+            tracing::warn!("Failed to log preview of file {}: {:?}", source.file_id, e);
+        }
+    }
+
+    // Previews may be of gated files, so shared caches must not keep them
+    Ok(HttpResponse::Ok()
+        .content_type(content_type.unwrap_or("audio/mpeg"))
+        .insert_header((header::CACHE_CONTROL, "private, no-store"))
+        .body(clip))
+}
+
+/// `detect_audio_content_type` on the blocking pool, since it reads the file
+async fn sniff_audio_content_type(path: &Path) -> Option<&'static str> {
+    let path = path.to_path_buf();
+    spawn_blocking_with_tracing(move || detect_audio_content_type(&path))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Content type detection task failed: {}", e);
+            None
+        })
+}
+
+fn read_preview_clip(path: &Path, duration_seconds: u32, preview_seconds: u32) -> std::io::Result<Vec<u8>> {
+    let file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let limit = if duration_seconds == 0 {
+        preview_seconds as u64 * FALLBACK_PREVIEW_BYTES_PER_SECOND
+    } else if duration_seconds <= preview_seconds {
+        size
+    } else {
+        size * preview_seconds as u64 / duration_seconds as u64
+    };
+
+    let mut clip = Vec::with_capacity(limit.min(size) as usize);
+    file.take(limit).read_to_end(&mut clip)?;
+    Ok(clip)
+}
+
+/// Download a selection of files as a single ZIP archive
+///
+/// Files that are unknown, missing on disk or would push the archive past
+/// `MAX_ZIP_TOTAL_BYTES` are left out and listed in the archive's `manifest.json`.
+/// Every included file is logged as a download.
+///
+/// POST /api/v1/files/download-zip
+#[instrument(name = "Download Files Zip", skip(pool, config, auth, req, request))]
+#[post("/download-zip")]
+pub async fn download_files_zip(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<crate::core::config::AppConfig>,
+    auth: JwtMiddleware,
+    req: actix_web::HttpRequest,
+    request: web::Json<DownloadZipRequest>,
+) -> Result<NamedFile, AppError> {
+    let mut file_ids = request.into_inner().file_ids;
+    let mut seen = std::collections::HashSet::new();
+    file_ids.retain(|id| seen.insert(*id));
+
+    if file_ids.is_empty() {
+        return Err(AppError::bad_request("file_ids must not be empty"));
+    }
+    if file_ids.len() > MAX_ZIP_FILES {
+        return Err(AppError::bad_request(format!(
+            "At most {} files can be downloaded at once",
+            MAX_ZIP_FILES
+        )));
+    }
+
+    let uploads_dir = &config.app_paths.uploads_dir;
+    let mut included: Vec<(i32, String, String)> = Vec::new(); // (file_id, entry_name, path)
+    let mut omitted = Vec::new();
+    let mut entry_names = std::collections::HashSet::new();
+    let mut total_bytes: u64 = 0;
+
+    for file_id in file_ids {
+        let omit = |reason: &str| ZipOmittedFile {
+            file_id,
+            reason: reason.to_string(),
+        };
+
+        let file_info =
+            match uploads::get_file_download_info(pool.get_ref(), uploads_dir, file_id).await? {
+                Some(file_info) => file_info,
+                None => {
+                    omitted.push(omit("not_found"));
+                    continue;
+                }
+            };
+
+        let size = match fs::metadata(&file_info.file_path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => {
+                tracing::error!("File {} is missing on disk at {}", file_id, file_info.file_path);
+                omitted.push(omit("unavailable"));
+                continue;
+            }
+        };
+
+        if ensure_within_dir(uploads_dir, &file_info.file_path).is_err() {
+            tracing::warn!("Refusing to zip file {} from {}", file_id, file_info.file_path);
+            omitted.push(omit("access_denied"));
+            continue;
+        }
+
+        if total_bytes + size > MAX_ZIP_TOTAL_BYTES {
+            omitted.push(omit("size_limit"));
+            continue;
+        }
+        total_bytes += size;
+
+        let extension = Path::new(&file_info.file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("mp3");
+        let mut entry_name = sanitize_download_filename(&file_info.filename, extension);
+        let mut suffix = 2;
+        while !entry_names.insert(entry_name.clone()) {
+            entry_name = sanitize_download_filename(
+                &format!("{} ({})", file_info.filename, suffix),
+                extension,
+            );
+            suffix += 1;
+        }
+
+        included.push((file_id, entry_name, file_info.file_path));
+    }
+
+    if included.is_empty() {
+        return Err(AppError {
+            message: Some("None of the requested files are available for download".to_string()),
+            cause: None,
+            error_type: AppErrorType::NotFoundError,
+        });
+    }
+
+    let manifest = ZipManifest {
+        included: included
+            .iter()
+            .map(|(file_id, entry_name, _)| ZipManifestFile {
+                file_id: *file_id,
+                entry_name: entry_name.clone(),
+            })
+            .collect(),
+        omitted,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| AppError::internal_error(format!("Failed to build manifest: {}", e)))?;
+
+    // Build the archive in a temp file off the async runtime; mp3s are already compressed so entries are stored
+    let (temp_zip, temp_file) = TempUpload::create(uploads_dir).map_err(|e| {
+        tracing::error!("Failed to create temp zip file: {:?}", e);
+        AppError::internal_error("Failed to prepare download")
+    })?;
+    let entries: Vec<(String, String)> = included
+        .iter()
+        .map(|(_, entry_name, path)| (entry_name.clone(), path.clone()))
+        .collect();
+
+    let zip_file = web::block(move || -> zip::result::ZipResult<fs::File> {
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        let mut zip = zip::ZipWriter::new(temp_file);
+
+        for (entry_name, path) in entries {
+            zip.start_file(entry_name, options)?;
+            let mut source = fs::File::open(path)?;
+            std::io::copy(&mut source, &mut zip)?;
+        }
+        zip.start_file("manifest.json", options)?;
+        zip.write_all(&manifest_json)?;
+
+        let mut file = zip.finish()?;
+        file.flush()?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| AppError::internal_error(format!("Failed to build zip: {}", e)))?
+    .map_err(|e| {
+        tracing::error!("Failed to build zip: {:?}", e);
+        AppError::internal_error("Failed to build zip")
+    })?;
+    drop(zip_file);
+
+    // Reopen for reading; the temp path is unlinked when `temp_zip` drops but the open handle stays valid
+    let zip_file = fs::File::open(&temp_zip.path).map_err(|e| {
+        tracing::error!("Failed to reopen zip {}: {:?}", temp_zip.path, e);
+        AppError::internal_error("Failed to prepare download")
+    })?;
+
+    let subscription_id =
+        match subscriptions::get_user_active_subscription(&pool, auth.user_id).await {
+            Ok(Some(subscription)) => Some(subscription.id),
+            _ => None,
+        };
+    let client_ip = client_ip(&req, &config);
+    let user_agent = req
+        .headers()
+        .get("user-agent")
+        .and_then(|ua| ua.to_str().ok())
+        .map(|ua| ua.to_string());
+
+    for (file_id, _, _) in &included {
+        if let Err(e) = file_interactions::log_file_download(
+            &pool,
+            Some(auth.user_id),
+            None,
+            subscription_id,
+            *file_id,
+            client_ip.clone(),
+            user_agent.clone(),
+        )
+        .await
+        {
+            tracing::error!("Failed to log zip download of file {}: {:?}", file_id, e);
+        }
+    }
+
+    tracing::info!(
+        "Zip of {} file(s) ({} bytes) streamed to user {}",
+        included.len(),
+        total_bytes,
+        auth.user_id
+    );
+
+    let named_file = NamedFile::from_file(zip_file, "sunnah-audio.zip")
+        .map_err(|e| AppError::internal_error(format!("Failed to open zip: {}", e)))?
+        .set_content_disposition(attachment_disposition("sunnah-audio.zip"));
+
     Ok(named_file)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::AudioServingConfig;
+    use actix_web::test::TestRequest;
+
+    #[actix_web::test]
+    async fn audio_response_types_by_content_and_advertises_ranges() {
+        let dir = std::env::temp_dir().join(format!("audio_response_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        // MP3 frames behind a misleading extension
+        let path = dir.join("lecture.ogg");
+        fs::write(&path, [0xFF, 0xFB, 0x90, 0x64, 0x00, 0x00]).unwrap();
+
+        let req = TestRequest::default().to_http_request();
+        let config = AudioServingConfig {
+            detect_content_type: true,
+            cache_max_age_seconds: 60,
+        };
+        let named_file = NamedFile::open_async(&path).await.unwrap();
+        let response = audio_response(named_file, &path, &req, &config).await;
+
+        let undetected = AudioServingConfig {
+            detect_content_type: false,
+            cache_max_age_seconds: 0,
+        };
+        let named_file = NamedFile::open_async(&path).await.unwrap();
+        let by_extension = audio_response(named_file, &path, &req, &undetected).await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        let headers = response.headers();
+        assert_eq!(headers.get(header::CONTENT_TYPE).unwrap(), "audio/mpeg");
+        assert_eq!(headers.get(header::ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(headers.get(header::CACHE_CONTROL).unwrap(), "private, max-age=60");
+
+        let headers = by_extension.headers();
+        assert_eq!(headers.get(header::CONTENT_TYPE).unwrap(), "audio/ogg");
+        assert_eq!(headers.get(header::ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(headers.get(header::CACHE_CONTROL).unwrap(), "no-cache");
+    }
+
+    #[test]
+    fn preview_clip_is_bounded_by_the_preview_share_of_the_file() {
+        let dir = std::env::temp_dir().join(format!("preview_clip_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lecture.mp3");
+        let audio: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+        fs::write(&path, &audio).unwrap();
+
+        // 30s of a 300s file is the first tenth
+        let clip = read_preview_clip(&path, 300, 30).unwrap();
+        // A file no longer than the preview is sent whole
+        let short = read_preview_clip(&path, 20, 30).unwrap();
+        // Without a usable duration the cut assumes 128 kbps
+        let unknown = read_preview_clip(&path, 0, 2).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(clip, audio[..10_000]);
+        assert_eq!(short, audio);
+        assert_eq!(unknown.len() as u64, 2 * FALLBACK_PREVIEW_BYTES_PER_SECOND);
+    }
+}
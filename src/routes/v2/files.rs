@@ -0,0 +1,39 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use sqlx::MySqlPool;
+use tracing::instrument;
+
+use crate::{
+    core::{AppConfig, AppError, AppErrorType, AppSuccessResponse},
+    db::files,
+    models::v2::files::FileDetails,
+};
+
+#[instrument(name = "View File (v2)", skip(pool, config))]
+#[get("/{file_id}/view")]
+pub async fn view_file(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<AppConfig>,
+    file_id: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let file_id = file_id.into_inner();
+    let file_details = files::fetch_file_details(pool.get_ref(), &config, file_id, false)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch file details {}: {:?}", file_id, e);
+            match e.error_type {
+                AppErrorType::NotFoundError => e,
+                _ => AppError {
+                    message: Some("Failed to fetch file details".to_string()),
+                    cause: Some(e.to_string()),
+                    error_type: AppErrorType::InternalServerError,
+                },
+            }
+        })?;
+
+    Ok(HttpResponse::Ok().json(AppSuccessResponse {
+        success: true,
+        message: "File details retrieved successfully".to_string(),
+        data: Some(FileDetails::from(file_details)),
+        pagination: None,
+    }))
+}
@@ -0,0 +1,13 @@
+//! `/api/v2` handlers. Each one reuses the v1 `db` call and maps the result
+//! through `models::v2`; routes without a v2 handler exist only under v1.
+//! Access rules come from the same `ROUTE_POLICIES` rows as v1.
+use actix_web::web::scope;
+use actix_web::Scope;
+
+use files::view_file;
+
+mod files;
+
+pub fn files_routes() -> Scope {
+    scope("files").service(view_file)
+}
@@ -1,13 +1,20 @@
-use crate::core::{AppConfig, EmailService, RedisHelper};
+use crate::core::{
+    json_error_handler, path_error_handler, query_error_handler, AccessPolicy, AppConfig,
+    EmailService, LogFilterHandle, RedisHelper,
+};
 use crate::routes::sunnah_audio_routes;
-use crate::jobs::start_subscription_expiry_checker;
+use crate::jobs::{
+    start_failed_email_pruner, start_failed_email_recorder, start_image_index_refresher,
+    start_log_pruner, start_subscription_expiry_checker,
+};
 use actix_cors::Cors;
 use actix_web::http::header;
-use actix_web::{dev::Server, web::Data, App, HttpServer};
+use actix_web::{dev::Server, web, web::Data, App, HttpServer};
 use sqlx::mysql::MySqlPoolOptions;
 
 use sqlx::MySqlPool;
 use std::net::TcpListener;
+use tokio::sync::mpsc;
 
 pub struct SunnahWebServer {
     port: u16,
@@ -15,7 +22,10 @@ pub struct SunnahWebServer {
 }
 
 impl SunnahWebServer {
-    pub async fn build(configuration: AppConfig) -> Result<Self, anyhow::Error> {
+    pub async fn build(
+        configuration: AppConfig,
+        log_filter: LogFilterHandle,
+    ) -> Result<Self, anyhow::Error> {
         let address = format!(
             "{}:{}",
             configuration.sunnah_audio_server_config.host,
@@ -28,13 +38,29 @@ impl SunnahWebServer {
 
         let redis = configuration.redis.connect();
 
+        configuration.app_paths.verify_default_images();
+
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr().unwrap().port();
 
+        // Emails the workers fail to send are stored for admins to re-send
+        let (failed_emails, failed_email_receiver) = mpsc::unbounded_channel();
+        start_failed_email_recorder(mysql_pool.clone(), failed_email_receiver);
+        start_failed_email_pruner(mysql_pool.clone(), configuration.failed_emails.clone());
+
         // Start background job for subscription expiry checking
-        start_subscription_expiry_checker(mysql_pool.clone()).await;
+        start_subscription_expiry_checker(
+            mysql_pool.clone(),
+            configuration.subscriptions.clone(),
+            EmailService::with_failure_sink(configuration.smtp.clone(), Some(failed_emails.clone())),
+        )
+        .await;
+
+        // Rolled log files are removed once past the configured retention
+        start_log_pruner(configuration.logging.clone());
 
-        let server = run(listener, mysql_pool, redis, configuration.smtp).await?;
+        let email_service = EmailService::with_failure_sink(configuration.smtp, Some(failed_emails));
+        let server = run(listener, mysql_pool, redis, email_service, log_filter).await?;
 
         Ok(Self { port, server })
     }
@@ -51,13 +77,24 @@ pub async fn run(
     listener: TcpListener,
     mysql_pool: MySqlPool,
     redis_client: redis::Client,
-    smtp_config: crate::core::config::SmtpConfig,
+    email_service: EmailService,
+    log_filter: LogFilterHandle,
 ) -> Result<Server, anyhow::Error> {
     let mysql_pool = Data::new(mysql_pool);
     let redis_client = Data::new(redis_client);
-    let redis_helper = Data::new(RedisHelper::new(redis_client.clone()));
-    let email_service = Data::new(EmailService::new(smtp_config));
+    let email_service = Data::new(email_service);
+    let log_filter = Data::new(log_filter);
     let app_config = Data::new(crate::core::AppConfig::new().expect("failed to build our appConfig object"));
+    // Image URLs fall back to the placeholder for files missing from this index
+    start_image_index_refresher(
+        app_config.image_index.clone(),
+        app_config.app_paths.images_dir.clone(),
+    )
+    .await;
+    let redis_helper = Data::new(RedisHelper::new(
+        redis_client.clone(),
+        app_config.redis.rate_limit_fail_open,
+    ));
 
     let server = HttpServer::new(move || {
         let cors = Cors::default()
@@ -75,7 +112,13 @@ pub async fn run(
             .app_data(redis_client.clone())
             .app_data(redis_helper.clone())
             .app_data(email_service.clone())
+            .app_data(log_filter.clone())
             .app_data(app_config.clone())
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(path_error_handler))
+            // Inside CORS so rejected requests still carry CORS headers
+            .wrap(AccessPolicy)
             .wrap(cors)
     })
     .listen(listener)?